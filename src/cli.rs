@@ -1,4 +1,4 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -6,18 +6,32 @@ use std::path::PathBuf;
 #[command(author, version, about, long_about = None)]
 #[command(about = "A thin orchestrator for Mac bootstrap and setup")]
 pub struct Cli {
-    /// Path to config file
+    /// Path to config file (or a directory of *.toml fragments)
     #[arg(short, long, global = true)]
     pub config: Option<PathBuf>,
 
+    /// Directory of *.toml config fragments, merged in file name order
+    #[arg(long, global = true, value_name = "DIR", conflicts_with = "config")]
+    pub config_dir: Option<PathBuf>,
+
     /// Verbose output
     #[arg(short, long, global = true)]
     pub verbose: bool,
 
+    /// Only warnings and errors; install scripts show their output only if they fail
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Also write log output to this file (overrides settings.log_file)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub log_file: Option<PathBuf>,
+
     #[command(subcommand)]
     pub command: Command,
 }
 
+// Parsed once at startup: Apply's many flags make it the largest variant
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 pub enum Command {
     /// Apply configuration (install packages, apply settings)
@@ -26,19 +40,261 @@ pub enum Command {
         #[arg(long)]
         dry_run: bool,
 
+        /// Make changes even if settings.dry_run is set
+        #[arg(long, conflicts_with = "dry_run")]
+        no_dry_run: bool,
+
         /// Include system settings (defaults commands)
-        #[arg(long)]
+        #[arg(long, visible_aliases = ["system", "include-system"])]
         with_system_settings: bool,
 
+        /// Only print the final summary (suppress per-section output)
+        #[arg(long)]
+        summary_only: bool,
+
+        /// Ignore depends_on and run every phase (may fail if prerequisites are missing)
+        #[arg(long)]
+        no_deps: bool,
+
+        /// Print the computed execution plan and exit
+        #[arg(long)]
+        explain: bool,
+
+        /// Also reject package names with whitespace or illegal characters
+        #[arg(long)]
+        strict: bool,
+
+        /// Re-run the diff checks after applying and fail if anything is still missing
+        #[arg(long)]
+        verify_after: bool,
+
+        /// Force an install script to run again (repeatable)
+        #[arg(long, value_name = "SCRIPT")]
+        rerun: Vec<String>,
+
+        /// Max concurrent installs per manager (overrides config)
+        #[arg(long, value_name = "N", value_parser = parse_max_parallel)]
+        max_parallel: Option<usize>,
+
+        /// Retries for runtime installs via brew (overrides config)
+        #[arg(long, value_name = "N")]
+        max_retries: Option<u32>,
+
+        /// Abort once N packages have failed (like fail_fast, but tolerates a few)
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        max_failures: Option<u32>,
+
+        /// Only install packages whose name matches a glob (e.g. 'git*')
+        #[arg(long, value_name = "GLOB")]
+        filter: Option<String>,
+
+        /// Only install the members of a [[groups]] entry
+        #[arg(long, value_name = "NAME")]
+        group: Option<String>,
+
+        /// Only install the members of [[groups]] entries with this tag
+        #[arg(long, value_name = "TAG")]
+        tag: Option<String>,
+
+        /// Only install one manager's packages (brew, cask, npm, install, ...)
+        #[arg(long, value_name = "NAME")]
+        manager: Option<String>,
+
+        /// Only install what a `macup diff --output json` document reports missing ('-' = stdin)
+        #[arg(long, value_name = "FILE", conflicts_with = "plan_file")]
+        packages_from_diff: Option<PathBuf>,
+
+        /// Move formulae that are really casks to brew.casks and install them as casks
+        #[arg(long)]
+        auto_fix: bool,
+
+        /// Stream one JSON object per package event to stdout (other output goes to stderr)
+        #[arg(long, conflicts_with_all = ["explain", "verify_after"])]
+        json_lines: bool,
+
+        /// Preview each phase and confirm it (run, skip or abort)
+        #[arg(long, conflicts_with_all = ["explain", "json_lines"])]
+        interactive: bool,
+
+        /// Do nothing if the config hasn't changed since the last successful apply
+        #[arg(long)]
+        changed_only: bool,
+
+        /// Only run these sections, comma-separated (e.g. brew,npm)
+        #[arg(
+            long,
+            value_name = "SECTIONS",
+            value_delimiter = ',',
+            conflicts_with = "section"
+        )]
+        only: Vec<String>,
+
+        /// Don't run these sections, comma-separated (e.g. mas,system)
+        #[arg(
+            long,
+            value_name = "SECTIONS",
+            value_delimiter = ',',
+            conflicts_with = "section"
+        )]
+        skip: Vec<String>,
+
+        /// Only install missing language runtimes (node, rust, python, ...), not packages
+        #[arg(long, conflicts_with_all = ["verify_after", "interactive", "auto_fix"])]
+        only_runtimes: bool,
+
+        /// Check every phase again, even ones that completed in a recent failed apply
+        #[arg(long)]
+        recheck: bool,
+
+        /// Save the computed plan as JSON and exit (replay it with --plan-file)
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["explain", "plan_file"])]
+        save_plan: Option<PathBuf>,
+
+        /// Apply a plan saved with --save-plan instead of computing one
+        #[arg(long, value_name = "PATH")]
+        plan_file: Option<PathBuf>,
+
+        /// Replay --plan-file even if the config changed since it was saved
+        #[arg(long, requires = "plan_file")]
+        force: bool,
+
+        /// Uninstall every package a section declares (brew, npm, cargo, asdf, pnpm, yarn)
+        #[arg(
+            long,
+            value_name = "SECTION",
+            conflicts_with_all = ["explain", "save_plan", "plan_file", "interactive", "json_lines", "verify_after", "section"]
+        )]
+        reverse: Option<String>,
+
+        /// Don't ask before uninstalling (with --reverse)
+        #[arg(long, requires = "reverse")]
+        yes: bool,
+
+        /// Layer [profiles.NAME] of the config over the rest of it
+        #[arg(long, value_name = "NAME", conflicts_with = "reverse")]
+        profile: Option<String>,
+
         /// Apply only specific section (brew, mas, npm, cargo, install, system)
         section: Option<String>,
     },
 
     /// Show difference between config and current state
-    Diff,
+    Diff {
+        /// Verify cargo/npm binaries actually resolve (catches dangling symlinks)
+        #[arg(long)]
+        verify: bool,
+
+        /// Only check packages whose name matches a glob (e.g. 'git*')
+        #[arg(long, value_name = "GLOB")]
+        filter: Option<String>,
+
+        /// Only check the members of a [[groups]] entry
+        #[arg(long, value_name = "NAME")]
+        group: Option<String>,
+
+        /// Only check the members of [[groups]] entries with this tag
+        #[arg(long, value_name = "TAG")]
+        tag: Option<String>,
+
+        /// Only check one manager's packages (brew, cask, npm, install, ...)
+        #[arg(long, value_name = "NAME")]
+        manager: Option<String>,
+
+        /// Also list installed App Store apps with an update available
+        #[arg(long)]
+        outdated: bool,
+
+        /// Output format: colored table, or json/yaml for other tooling
+        #[arg(long, value_enum, default_value_t = DiffFormat::Table)]
+        output: DiffFormat,
+
+        /// Shorthand for --output json
+        #[arg(long, conflicts_with = "output")]
+        json: bool,
+
+        /// Exit with status 3 if any package is missing or broken (1 = diff failed)
+        #[arg(long)]
+        exit_code: bool,
+    },
+
+    /// List declared App Store apps with an update available
+    Outdated,
+
+    /// Run a single install script by name
+    RunScript {
+        /// Script name (as in [[install.scripts]] name = "...")
+        name: String,
+
+        /// Run it even if its binary/check/once marker says it's installed
+        #[arg(long)]
+        force: bool,
+    },
 
     /// Import packages from current system
-    Import,
+    Import {
+        /// Only offer packages that are new since the last scan
+        #[arg(long)]
+        incremental: bool,
+
+        /// Abandon a package manager scan that takes longer than this (seconds)
+        #[arg(long, value_name = "SECS", default_value_t = 30)]
+        scan_timeout: u64,
+
+        /// Import every package not yet in the config, without prompting
+        #[arg(long)]
+        yes: bool,
+
+        /// Create ~/.config/macup/macup.toml (or the --config path) if no config exists
+        #[arg(long)]
+        create_config: bool,
+
+        /// Look up the taps of selected formulae and casks and add them to brew.taps
+        #[arg(long)]
+        detect_taps: bool,
+    },
+
+    /// Print the resolved config path, settings, runtimes and required managers
+    Doctor {
+        /// Check the codegen regions of every manager instead (developer tool)
+        #[arg(long)]
+        codegen: bool,
+
+        /// Remove regions left behind by managers whose implementation is gone
+        #[arg(long, requires = "codegen")]
+        fix: bool,
+    },
+
+    /// Write a new config (./macup.toml, or the --config path)
+    Init {
+        /// Include every package found on this machine, without prompting
+        #[arg(long)]
+        from_system: bool,
+
+        /// Overwrite an existing config
+        #[arg(long)]
+        force: bool,
+
+        /// Abandon a package manager scan that takes longer than this (seconds)
+        #[arg(long, value_name = "SECS", default_value_t = 30)]
+        scan_timeout: u64,
+
+        /// Look up the taps of scanned formulae and casks and add them to brew.taps
+        #[arg(long, requires = "from_system")]
+        detect_taps: bool,
+    },
+
+    /// Upgrade config file to the current schema version
+    Migrate {
+        /// Only show the changes, don't write the config
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Read or change scalar settings in the config
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
 
     /// Add package(s) to config and install
     Add {
@@ -48,9 +304,64 @@ pub enum Command {
         /// Package name(s) or ID(s) to add
         packages: Vec<String>,
 
+        /// Read packages from a file, one per line ('-' for stdin, '#' starts a comment)
+        #[arg(long, value_name = "FILE")]
+        packages_from: Option<PathBuf>,
+
         /// Only update config, skip installation
         #[arg(long)]
         no_install: bool,
+
+        /// Show the config lines that would be added, don't write or install
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Write and install even if settings.dry_run is set
+        #[arg(long, conflicts_with = "dry_run")]
+        no_dry_run: bool,
+
+        /// Create ~/.config/macup/macup.toml (or the --config path) if no config exists
+        #[arg(long)]
+        create_config: bool,
+
+        /// App name for `macup add mas <id>` (default: looked up with `mas info`)
+        #[arg(long)]
+        name: Option<String>,
+    },
+
+    /// Uninstall packages and remove them from config
+    Rm {
+        /// Manager type: brew, cask, mas, npm, cargo, asdf, pnpm, yarn, pip
+        manager: String,
+
+        /// Package name(s) to remove (asdf: plugin@version, mas: app ID or name)
+        packages: Vec<String>,
+
+        /// Only update config, skip uninstalling
+        #[arg(long)]
+        no_uninstall: bool,
+
+        /// Show the config lines that would be removed, don't write or uninstall
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Write and uninstall even if settings.dry_run is set
+        #[arg(long, conflicts_with = "dry_run")]
+        no_dry_run: bool,
+    },
+
+    /// Upgrade installed packages from config to their latest version
+    Upgrade {
+        /// Only upgrade these packages (mas: app ID or name)
+        packages: Vec<String>,
+
+        /// Show the upgrade commands without running them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Upgrade even if settings.dry_run is set
+        #[arg(long, conflicts_with = "dry_run")]
+        no_dry_run: bool,
     },
 
     /// Create a new package manager (developer tool)
@@ -66,35 +377,79 @@ pub enum Command {
     },
 }
 
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print a setting's current value (e.g. settings.max_parallel)
+    Get {
+        /// Setting key
+        key: String,
+    },
+
+    /// Change a setting, preserving the rest of the file
+    Set {
+        /// Setting key
+        key: String,
+
+        /// New value
+        value: String,
+    },
+
+    /// Print the resolved config (fragments merged, migrated, defaults filled in)
+    Show,
+
+    /// Show packages this config adds or drops compared to another config
+    Diff {
+        /// Config file (or directory) to compare against, e.g. a shared base
+        other: PathBuf,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum NewResource {
     /// Generate boilerplate for a new package manager
     Manager {
         /// Manager name (e.g., pip, gem, go)
-        name: String,
+        #[arg(required_unless_present = "check")]
+        name: Option<String>,
 
         /// Display name (e.g., "pip packages")
-        #[arg(long)]
-        display: String,
+        #[arg(long, required_unless_present = "check")]
+        display: Option<String>,
 
         /// Icon emoji (e.g., 🐍)
-        #[arg(long)]
-        icon: String,
+        #[arg(long, required_unless_present = "check")]
+        icon: Option<String>,
 
         /// Runtime command to check (e.g., pip3)
-        #[arg(long)]
-        runtime_cmd: String,
+        #[arg(long, required_unless_present = "check")]
+        runtime_cmd: Option<String>,
 
         /// Human-readable runtime name (e.g., python)
-        #[arg(long)]
-        runtime_name: String,
+        #[arg(long, required_unless_present = "check")]
+        runtime_name: Option<String>,
 
         /// Brew formula name (e.g., python)
+        #[arg(long, required_unless_present = "check")]
+        brew_formula: Option<String>,
+
+        /// Only report which files have the codegen markers; write nothing
         #[arg(long)]
-        brew_formula: String,
+        check: bool,
+
+        /// Run cargo check afterwards and roll back every edit if it fails
+        #[arg(long, conflicts_with = "check")]
+        verify: bool,
     },
 }
 
+/// `macup diff --output` formats
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiffFormat {
+    Table,
+    Json,
+    Yaml,
+}
+
 #[derive(Subcommand)]
 pub enum RemoveResource {
     /// Remove a package manager
@@ -103,3 +458,12 @@ pub enum RemoveResource {
         name: String,
     },
 }
+
+/// `--max-parallel`: same range as settings.max_parallel
+fn parse_max_parallel(value: &str) -> Result<usize, String> {
+    let limit = crate::config::MAX_PARALLEL_LIMIT;
+    match value.parse::<usize>() {
+        Ok(n) if (1..=limit).contains(&n) => Ok(n),
+        _ => Err(format!("must be a number between 1 and {}", limit)),
+    }
+}
@@ -1,10 +1,19 @@
-use crate::config::{find_config_file, load_config};
+use crate::config::{
+    casks_location, config_write_target, find_config_file, load_config, read_config_text,
+};
+use crate::executor::list_cache;
+use crate::managers::same_package;
 use crate::managers::{
+    asdf::AsdfManager, // CODEGEN[asdf]: import
     brew::BrewManager,
     cargo_manager::CargoManager, // CODEGEN[cargo]: import
     mas::MasManager, // CODEGEN[mas]: import
     npm::NpmManager, // CODEGEN[npm]: import
+    pnpm::PnpmManager, // CODEGEN[pnpm]: import
+    yarn::YarnManager, // CODEGEN[yarn]: import
+    pip::PipManager, // CODEGEN[pip]: import
     // CODEGEN_MARKER: insert_manager_import_here
+    FailureKind,
     Manager,
     ManagerMetadata,
     PACKAGE_MANAGERS,
@@ -12,18 +21,39 @@ use crate::managers::{
 use anyhow::{Context, Result};
 use colored::Colorize;
 use std::fs;
+use std::io::Read;
 use std::path::Path;
 use toml_edit::DocumentMut;
 
+/// Names `macup add` accepts: brew, cask and every registry manager
+fn known_managers() -> Vec<&'static str> {
+    let mut names = vec!["brew", "cask"];
+    names.extend(PACKAGE_MANAGERS.iter().map(|m| m.name));
+    names
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     config_path: Option<&Path>,
     manager: &str,
     packages: Vec<String>,
     no_install: bool,
+    dry_run: bool,
+    no_dry_run: bool,
+    create_config: bool,
+    name: Option<&str>,
 ) -> Result<()> {
     if packages.is_empty() {
         anyhow::bail!("No packages specified");
     }
+    if name.is_some() && (manager != "mas" || packages.len() != 1) {
+        anyhow::bail!("--name only applies to a single mas app: macup add mas <id> --name <name>");
+    }
+    if manager == "mas" {
+        for package in &packages {
+            parse_mas_id(package)?;
+        }
+    }
 
     println!(
         "{}",
@@ -31,14 +61,26 @@ pub fn run(
     );
     println!();
 
-    // Find config file
+    // Find config file (offering to create one on a fresh machine)
+    crate::commands::import::ensure_config(config_path, create_config)?;
     let config_file = find_config_file(config_path)?;
 
     // Load config to check dependencies
     let config = load_config(&config_file)?;
+    config.settings.validate()?;
+
+    // With a config directory, new packages go to its local fragment
+    let config_file = config_write_target(&config_file);
+
+    // --dry-run (or settings.dry_run): show the config change only, no install
+    if config.effective_dry_run(dry_run, no_dry_run) {
+        return preview_config_change(&config_file, manager, &packages, name);
+    }
 
     // Determine max_parallel
-    let max_parallel = config.settings.max_parallel;
+    let max_parallel = config.effective_parallelism(manager, None);
+    crate::utils::set_install_limit(config.settings.global_max_parallel);
+    crate::utils::set_suppress_update_checks(config.settings.suppress_update_checks);
 
     // Get manager instance - check registry first, then special cases
     let mgr: Box<dyn Manager> =
@@ -54,6 +96,18 @@ pub fn run(
                 // CODEGEN_START[cargo]: match_arm
                 "cargo" => Box::new(CargoManager::new(max_parallel)),
                 // CODEGEN_END[cargo]: match_arm
+                // CODEGEN_START[asdf]: match_arm
+                "asdf" => Box::new(AsdfManager::new(max_parallel)),
+                // CODEGEN_END[asdf]: match_arm
+                // CODEGEN_START[pnpm]: match_arm
+                "pnpm" => Box::new(PnpmManager::new(max_parallel)),
+                // CODEGEN_END[pnpm]: match_arm
+                // CODEGEN_START[yarn]: match_arm
+                "yarn" => Box::new(YarnManager::new(max_parallel)),
+                // CODEGEN_END[yarn]: match_arm
+                // CODEGEN_START[pip]: match_arm
+                "pip" => Box::new(PipManager::new(max_parallel)),
+                // CODEGEN_END[pip]: match_arm
                 // CODEGEN_MARKER: insert_manager_match_arm_here
                 _ => {
                     anyhow::bail!(
@@ -71,11 +125,12 @@ pub fn run(
                 "cask" => Box::new(BrewManager::new(max_parallel)),
                 _ => {
                     // Show available managers from registry
-                    let available: Vec<_> = PACKAGE_MANAGERS.iter().map(|m| m.name).collect();
+                    let available = known_managers();
                     anyhow::bail!(
-                        "Unknown manager: '{}'. Valid: brew, cask, {}",
+                        "Unknown manager: '{}' (valid: {}){}",
                         manager,
-                        available.join(", ")
+                        available.join(", "),
+                        crate::utils::did_you_mean(manager, &available)
                     )
                 }
             }
@@ -86,6 +141,10 @@ pub fn run(
         anyhow::bail!("{} is not installed. Run 'macup apply' first.", mgr.name());
     }
 
+    if !no_install {
+        list_cache::invalidate();
+    }
+
     // Install packages first, collect successful ones
     let mut to_add = Vec::new();
     let mut errors = Vec::new();
@@ -110,6 +169,9 @@ pub fn run(
                 }
                 Err(e) => {
                     println!("{}", format!("✗ {}", e).red());
+                    if FailureKind::classify(&e, "") == FailureKind::Cask {
+                        println!("  Use 'macup add cask {}' instead", package);
+                    }
                     errors.push((package.clone(), e));
                 }
             }
@@ -124,11 +186,15 @@ pub fn run(
     if !to_add.is_empty() {
         println!();
         println!("Updating config...");
-        update_config_file(&config_file, manager, &to_add)?;
-        println!(
-            "{}",
-            format!("✓ Added {} package(s) to config", to_add.len()).green()
-        );
+        let added = update_config_file(&config_file, manager, &to_add, name)?;
+        if added == 0 {
+            println!("{}", "✓ All packages already in config".green());
+        } else {
+            println!(
+                "{}",
+                format!("✓ Added {} package(s) to config", added).green()
+            );
+        }
     }
 
     // Report errors
@@ -146,22 +212,130 @@ pub fn run(
     Ok(())
 }
 
-fn update_config_file(path: &Path, manager: &str, packages: &[String]) -> Result<()> {
-    let content =
-        fs::read_to_string(path).context(format!("Failed to read config: {}", path.display()))?;
+/// Read a package list: one package per line, blanks and `#` comments ignored
+/// A path of "-" reads from stdin
+pub fn read_packages_from(path: &Path) -> Result<Vec<String>> {
+    let content = if path == Path::new("-") {
+        let mut buf = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buf)
+            .context("Failed to read packages from stdin")?;
+        buf
+    } else {
+        fs::read_to_string(path)
+            .context(format!("Failed to read packages file: {}", path.display()))?
+    };
+
+    Ok(parse_package_list(&content))
+}
+
+fn parse_package_list(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect()
+}
+
+/// Returns how many packages were new to the config
+fn update_config_file(
+    path: &Path,
+    manager: &str,
+    packages: &[String],
+    name: Option<&str>,
+) -> Result<usize> {
+    let content = read_config_text(path)?;
+
+    let mut doc = content
+        .parse::<DocumentMut>()
+        .context("Failed to parse TOML")?;
+
+    let added = add_to_document(&mut doc, manager, packages, name)?;
+    if added > 0 {
+        fs::write(path, doc.to_string())
+            .context(format!("Failed to write config: {}", path.display()))?;
+    }
+
+    Ok(added)
+}
+
+/// Print the lines `add` would change in the config, without writing it
+fn preview_config_change(
+    path: &Path,
+    manager: &str,
+    packages: &[String],
+    name: Option<&str>,
+) -> Result<()> {
+    let content = read_config_text(path)?;
 
     let mut doc = content
         .parse::<DocumentMut>()
         .context("Failed to parse TOML")?;
 
+    if add_to_document(&mut doc, manager, packages, name)? == 0 {
+        println!("{}", "✓ All packages already in config".green());
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!("Dry run - changes to {}:", path.display()).yellow()
+    );
+    println!();
+    print_line_diff(&content, &doc.to_string());
+    println!();
+    println!("{}", "Config not written, nothing installed".yellow());
+
+    Ok(())
+}
+
+/// Minimal line diff (LCS) - configs are small, so O(n*m) is fine
+pub fn print_line_diff(old: &str, new: &str) {
+    let old: Vec<_> = old.lines().collect();
+    let new: Vec<_> = new.lines().collect();
+
+    // lcs[i][j] = length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            println!("{}", format!("- {}", old[i]).red());
+            i += 1;
+        } else {
+            println!("{}", format!("+ {}", new[j]).green());
+            j += 1;
+        }
+    }
+}
+
+/// Add packages to the manager's section, returning how many were new
+/// `name` names a single mas app instead of looking it up
+fn add_to_document(
+    doc: &mut DocumentMut,
+    manager: &str,
+    packages: &[String],
+    name: Option<&str>,
+) -> Result<usize> {
     // Determine section and key - check registry first
     let (section, key) = if let Some(meta) = ManagerMetadata::get_by_name(manager) {
         // Dynamic manager from registry - most use "packages" key
         match meta.name {
-            "mas" => {
-                // Special case: mas needs ID format
-                anyhow::bail!("Adding mas apps via CLI not yet supported. Edit config manually.");
-            }
+            "mas" => return add_mas_apps(doc, packages, name),
+            "asdf" => return add_asdf_tools(doc, packages),
             "npm" => ("npm", "global"), // npm uses "global" instead of "packages"
             _ => (meta.name, "packages"), // Default: use manager name as section, "packages" as key
         }
@@ -169,8 +343,12 @@ fn update_config_file(path: &Path, manager: &str, packages: &[String]) -> Result
         // Special cases not in registry
         match manager {
             "brew" => ("brew", "formulae"),
-            "cask" => ("brew", "casks"),
-            _ => anyhow::bail!("Unknown manager: {}", manager),
+            "cask" => casks_location(doc),
+            _ => anyhow::bail!(
+                "Unknown manager: {}{}",
+                manager,
+                crate::utils::did_you_mean(manager, &known_managers())
+            ),
         }
     };
 
@@ -181,7 +359,7 @@ fn update_config_file(path: &Path, manager: &str, packages: &[String]) -> Result
 
     // Get or create array
     if doc[section].get(key).is_none() {
-        doc[section][key] = toml_edit::array();
+        doc[section][key] = toml_edit::value(toml_edit::Array::new());
     }
 
     let array = doc[section][key]
@@ -191,17 +369,237 @@ fn update_config_file(path: &Path, manager: &str, packages: &[String]) -> Result
     // Add packages
     let mut added = 0;
     for pkg in packages {
-        // Check if already in config
-        if !array.iter().any(|v| v.as_str() == Some(pkg)) {
+        // Check if already in config (ignoring case, see same_package)
+        let exists = array
+            .iter()
+            .any(|v| v.as_str().is_some_and(|s| same_package(section, s, pkg)));
+        if !exists {
             array.push(pkg.as_str());
             added += 1;
         }
     }
 
-    if added > 0 {
-        fs::write(path, doc.to_string())
-            .context(format!("Failed to write config: {}", path.display()))?;
+    Ok(added)
+}
+
+/// asdf tools are stored as `{ plugin, version }` tables in [asdf].tools
+fn add_asdf_tools(doc: &mut DocumentMut, packages: &[String]) -> Result<usize> {
+    if doc.get("asdf").is_none() {
+        doc["asdf"] = toml_edit::table();
     }
 
-    Ok(())
+    if doc["asdf"].get("tools").is_none() {
+        doc["asdf"]["tools"] = toml_edit::value(toml_edit::Array::new());
+    }
+
+    let array = doc["asdf"]["tools"]
+        .as_array_mut()
+        .context("Expected array at [asdf.tools]")?;
+
+    let mut added = 0;
+    for pkg in packages {
+        let (plugin, version) = AsdfManager::parse_tool_spec(pkg)?;
+
+        let exists = array.iter().any(|v| {
+            v.as_inline_table().is_some_and(|t| {
+                t.get("plugin")
+                    .and_then(|p| p.as_str())
+                    .is_some_and(|p| same_package("asdf", p, plugin))
+                    && t.get("version").and_then(|v| v.as_str()) == Some(version)
+            })
+        });
+
+        if !exists {
+            let mut table = toml_edit::InlineTable::new();
+            table.insert("plugin", plugin.into());
+            table.insert("version", version.into());
+            array.push(table);
+            added += 1;
+        }
+    }
+
+    Ok(added)
+}
+
+/// App Store ids are positive integers (`mas search <name>` lists them)
+fn parse_mas_id(package: &str) -> Result<u64> {
+    match package.trim().parse::<u64>() {
+        Ok(id) if id > 0 => Ok(id),
+        _ => anyhow::bail!(
+            "Invalid App Store id '{}' - mas apps are added by numeric id (find it with 'mas search <name>')",
+            package
+        ),
+    }
+}
+
+/// mas apps are `{ name, id }` tables in [mas].apps - inline (`apps = [...]`)
+/// or `[[mas.apps]]`, whichever the config already uses
+/// Names come from `mas info` unless `name` is given
+fn add_mas_apps(doc: &mut DocumentMut, packages: &[String], name: Option<&str>) -> Result<usize> {
+    if doc.get("mas").is_none() {
+        // Only `[[mas.apps]]` headers, no empty `[mas]` above them
+        let mut mas = toml_edit::Table::new();
+        mas.set_implicit(true);
+        doc["mas"] = toml_edit::Item::Table(mas);
+    }
+    if doc["mas"].get("apps").is_none() {
+        doc["mas"]["apps"] = toml_edit::Item::ArrayOfTables(toml_edit::ArrayOfTables::new());
+    }
+
+    let mut added = 0;
+    for package in packages {
+        let id = parse_mas_id(package)? as i64;
+
+        let apps = &doc["mas"]["apps"];
+        let exists = match (apps.as_array(), apps.as_array_of_tables()) {
+            (Some(array), _) => array.iter().any(|app| {
+                app.as_inline_table()
+                    .and_then(|t| t.get("id"))
+                    .and_then(|v| v.as_integer())
+                    == Some(id)
+            }),
+            (_, Some(tables)) => tables
+                .iter()
+                .any(|app| app.get("id").and_then(|v| v.as_integer()) == Some(id)),
+            _ => anyhow::bail!("Expected an array of {{ name, id }} tables at [mas.apps]"),
+        };
+        if exists {
+            continue;
+        }
+
+        let app_name = match name {
+            Some(name) => name.to_string(),
+            None => MasManager::new(1)
+                .app_name(&id.to_string())
+                .context(format!(
+                    "Could not look up App Store app {} (pass --name to name it yourself)",
+                    id
+                ))?,
+        };
+
+        let apps = &mut doc["mas"]["apps"];
+        if let Some(array) = apps.as_array_mut() {
+            let mut table = toml_edit::InlineTable::new();
+            table.insert("name", app_name.as_str().into());
+            table.insert("id", id.into());
+
+            // One app per line when the array is laid out that way
+            let mut entry = toml_edit::Value::InlineTable(table);
+            let multiline = array
+                .iter()
+                .last()
+                .and_then(|last| last.decor().prefix())
+                .filter(|prefix| prefix.as_str().is_some_and(|p| p.contains('\n')))
+                .cloned();
+            match multiline {
+                Some(prefix) => entry.decor_mut().set_prefix(prefix),
+                None if array.is_empty() => entry.decor_mut().set_prefix(""),
+                None => entry.decor_mut().set_prefix(" "),
+            }
+            array.push_formatted(entry);
+        } else if let Some(tables) = apps.as_array_of_tables_mut() {
+            let mut table = toml_edit::Table::new();
+            table.insert("name", toml_edit::value(app_name));
+            table.insert("id", toml_edit::value(id));
+            tables.push(table);
+        }
+        added += 1;
+    }
+
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{stdout_of, FakeEnv};
+
+    #[test]
+    fn package_list_skips_blanks_and_comments() {
+        let content =
+            "# CLI tools\nripgrep\n\n  fd  \nbat # cat with wings\n   # indented comment\njq\n";
+
+        assert_eq!(parse_package_list(content), ["ripgrep", "fd", "bat", "jq"]);
+    }
+
+    #[test]
+    fn missing_packages_file_is_an_error() {
+        let err = read_packages_from(Path::new("/nonexistent/macup-tools.txt")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to read packages file: /nonexistent/macup-tools.txt"
+        );
+    }
+
+    #[test]
+    fn adding_ignores_case_and_keeps_the_configs_spelling() {
+        let mut doc: DocumentMut = "[brew]\nformulae = [\"Ripgrep:rg\"]\n".parse().unwrap();
+
+        let packages = ["ripgrep".to_string(), "FD".to_string(), "fd".to_string()];
+        let added = add_to_document(&mut doc, "brew", &packages, None).unwrap();
+
+        assert_eq!(added, 1);
+        assert_eq!(
+            doc.to_string(),
+            "[brew]\nformulae = [\"Ripgrep:rg\", \"FD\"]\n"
+        );
+    }
+
+    #[test]
+    fn dry_run_prints_the_additions_and_leaves_the_file() {
+        let original = "[cargo]\npackages = [\"bat\"]\n";
+
+        let Some(stdout) =
+            stdout_of("commands::add::tests::dry_run_prints_the_additions_and_leaves_the_file")
+        else {
+            let env = FakeEnv::new("add-dry-run");
+            env.bin("cargo", "exit 0");
+            let path = Path::new(&std::env::var("HOME").unwrap()).join("macup.toml");
+            fs::write(&path, original).unwrap();
+
+            let packages = vec!["ripgrep:rg".to_string()];
+            run(
+                Some(&path),
+                "cargo",
+                packages,
+                false,
+                true,
+                false,
+                false,
+                None,
+            )
+            .unwrap();
+
+            assert_eq!(fs::read_to_string(&path).unwrap(), original);
+            assert!(env.calls().is_empty());
+            return;
+        };
+
+        assert!(stdout.contains("- packages = [\"bat\"]\n+ packages = [\"bat\", \"ripgrep:rg\"]\n"));
+        assert!(stdout.contains("Config not written, nothing installed"));
+    }
+
+    #[test]
+    fn create_config_writes_a_new_config_then_adds_to_it() {
+        let env = FakeEnv::new("add-create-config");
+        env.bin("cargo", "exit 0");
+        let path = dirs::home_dir().unwrap().join(".config/macup/macup.toml");
+
+        let packages = vec!["ripgrep:rg".to_string()];
+        run(
+            Some(&path),
+            "cargo",
+            packages,
+            true,
+            false,
+            false,
+            true,
+            None,
+        )
+        .unwrap();
+
+        let config = crate::config::load_config(&path).unwrap();
+        assert_eq!(config.cargo.unwrap().packages, ["ripgrep:rg"]);
+        assert!(env.calls().iter().all(|call| !call.contains("install")));
+    }
 }
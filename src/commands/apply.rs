@@ -1,27 +1,304 @@
-use crate::config::{load_config_auto, validate_config};
-use crate::executor::{apply_plan, create_execution_plan};
+use crate::config::{apply_profile, load_config_auto, validate_config};
+use crate::executor::list_cache;
+use crate::executor::reconcile::missing_config;
+use crate::executor::state::{config_hash, load_state, save_state, ApplyState};
+use crate::executor::{
+    apply_plan, create_execution_plan, explain_plan, ApplyOptions, PlanFile, SelectionFilter,
+};
 use anyhow::Result;
 use std::path::Path;
 
-pub fn run(
-    config_path: Option<&Path>,
-    dry_run: bool,
-    with_system_settings: bool,
-    _section: Option<&str>,
-) -> Result<()> {
+pub fn run(config_path: Option<&Path>, opts: &ApplyOptions, section: Option<&str>) -> Result<()> {
+    // Manager progress is logged at info level - keep only warnings in summary-only mode
+    if opts.summary_only && log::max_level() <= log::LevelFilter::Info {
+        log::set_max_level(log::LevelFilter::Warn);
+    }
+
+    if opts.interactive {
+        crate::utils::require_terminal("--interactive", "run without it to apply every phase")?;
+    }
+
     // Load config
-    let (path, config) = load_config_auto(config_path)?;
+    let (path, mut config) = load_config_auto(config_path)?;
+    if let Some(profile) = &opts.profile {
+        config = apply_profile(config, profile)?;
+    }
+
+    // Durable log for unattended runs (--log-file takes precedence)
+    if let Some(log_file) = &config.settings.log_file {
+        if !crate::utils::log_file_is_set() {
+            crate::utils::set_log_file(&crate::utils::expand_tilde(log_file))?;
+        }
+    }
 
     log::info!("Loaded config from: {}", path.display());
+    if let Some(profile) = &opts.profile {
+        log::info!("Using profile: {}", profile);
+    }
 
     // Validate config
-    validate_config(&config)?;
+    validate_config(&config, opts.strict)?;
+    crate::utils::set_install_limit(config.settings.global_max_parallel);
+    crate::utils::set_suppress_update_checks(config.settings.suppress_update_checks);
+
+    // settings.dry_run turns every apply into a preview unless --no-dry-run
+    let opts = &ApplyOptions {
+        dry_run: config.effective_dry_run(opts.dry_run, opts.no_dry_run),
+        ..opts.clone()
+    };
+
+    // --rerun must name an existing install script
+    for name in &opts.rerun {
+        if config.install_script(name).is_none() {
+            anyhow::bail!("--rerun: no install script named '{}'", name);
+        }
+    }
+
+    // Narrow package lists to --manager/--filter/--group/--tag/--packages-from-diff
+    // (after validation, which needs the full config)
+    let selection = SelectionFilter {
+        filter: opts.filter.clone(),
+        group: opts.group.clone(),
+        tag: opts.tag.clone(),
+        manager: opts.manager.clone(),
+        missing: opts
+            .packages_from_diff
+            .as_deref()
+            .map(crate::commands::diff::read_missing)
+            .transpose()?,
+    };
+    let config = selection.apply(config)?;
+
+    // Fast path: nothing to do if this exact config was already applied
+    let hash = config_hash(&config, opts.with_system_settings)?;
+    if opts.changed_only && load_state().is_some_and(|state| state.config_hash == hash) {
+        // Keep stdout clean for --json-lines consumers
+        if opts.json_lines {
+            eprintln!("No changes since last apply");
+        } else {
+            println!("No changes since last apply");
+        }
+        return Ok(());
+    }
+
+    // Create execution plan, or replay a reviewed one with the package lists
+    // it was saved with
+    let (plan, config, replay) = match &opts.plan_file {
+        Some(file) => {
+            let saved = PlanFile::load(file)?;
+            if saved.config_hash != hash {
+                if !opts.force {
+                    anyhow::bail!(
+                        "Config changed since {} was saved (use --force to apply it anyway)",
+                        file.display()
+                    );
+                }
+                log::warn!("Config changed since {} was saved", file.display());
+            }
+            (saved.plan, saved.config, true)
+        }
+        None => (create_execution_plan(&config)?, config, false),
+    };
 
-    // Create execution plan
-    let plan = create_execution_plan(&config)?;
+    // `macup apply <section>` / --only / --skip: those phases, after the managers phase
+    let plan = match section {
+        Some(section) => plan.only_section(section)?,
+        None => plan.select_sections(&opts.only, &opts.skip)?,
+    };
+    let partial = section.is_some() || !opts.only.is_empty() || !opts.skip.is_empty();
+
+    if let Some(file) = &opts.save_plan {
+        let saved = PlanFile {
+            config_hash: hash,
+            plan,
+            config: missing_config(&config)?,
+        };
+        saved.save(file)?;
+        println!("✓ Plan saved to {}", file.display());
+        return Ok(());
+    }
+
+    if opts.explain {
+        explain_plan(&config, &plan, opts);
+        return Ok(());
+    }
+
+    // --auto-fix edits the config the plan came from; a real, unattended
+    // run can resume phases a recent failed run of this config completed
+    let resumable = !opts.dry_run && !opts.interactive && !opts.only_runtimes;
+    let opts = &ApplyOptions {
+        config_file: Some(path),
+        resume_key: resumable.then(|| hash.clone()),
+        replay,
+        ..opts.clone()
+    };
+
+    // Whatever happens next, cached installed lists can't be trusted afterwards
+    if !opts.dry_run {
+        list_cache::invalidate();
+    }
 
     // Apply plan
-    apply_plan(&config, &plan, dry_run, with_system_settings)?;
+    apply_plan(&config, &plan, opts)?;
+
+    // Re-run the diff checks to catch installs that reported success but didn't land
+    if opts.verify_after && !opts.dry_run {
+        crate::commands::diff::verify_after_apply(&config)?;
+    }
+
+    // Remember the applied config for --changed-only
+    // (not after --interactive, where phases may have been skipped by hand,
+    // --only-runtimes, which installs no packages, or only some sections)
+    if !opts.dry_run && !opts.interactive && !opts.only_runtimes && !partial {
+        if let Err(e) = save_state(&ApplyState::new(hash)) {
+            log::warn!("Could not record apply state: {}", e);
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::reconcile::compute_diff;
+    use crate::utils::testing::FakeEnv;
+    use std::fs;
+
+    #[test]
+    fn verify_after_flags_an_install_that_did_not_land() {
+        let env = FakeEnv::new("verify-after");
+        // cargo install "succeeds" without providing the binary
+        env.bin("brew", "exit 0").bin("cargo", "exit 0");
+        let path = Path::new(&std::env::var("HOME").unwrap()).join("macup.toml");
+        fs::write(&path, "[cargo]\npackages = [\"zz-pkg:zz-bin\"]\n").unwrap();
+
+        let opts = ApplyOptions {
+            summary_only: true,
+            ..Default::default()
+        };
+        run(Some(&path), &opts, None).unwrap();
+        assert!(env.calls().contains(&"cargo install zz-pkg".to_string()));
+
+        let opts = ApplyOptions {
+            verify_after: true,
+            ..opts
+        };
+        let err = run(Some(&path), &opts, None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Verification failed: 1 item(s) declared in config are still not installed"
+        );
+
+        // Once the binary is there, verification passes
+        env.bin("zz-bin", "exit 0").bin(
+            "cargo",
+            r#"[ "$2" = --list ] && printf 'zz-pkg v0.1.0:\n    zz-bin\n'
+exit 0"#,
+        );
+        run(Some(&path), &opts, None).unwrap();
+    }
+
+    #[test]
+    fn changed_only_skips_an_already_applied_config() {
+        let env = FakeEnv::new("changed-only");
+        env.bin("brew", "exit 0").bin("cargo", "exit 0");
+        let path = Path::new(&std::env::var("HOME").unwrap()).join("macup.toml");
+        fs::write(&path, "[cargo]\npackages = [\"zz-pkg\"]\n").unwrap();
+
+        let opts = ApplyOptions {
+            summary_only: true,
+            changed_only: true,
+            ..Default::default()
+        };
+        run(Some(&path), &opts, None).unwrap();
+        assert!(env.calls().contains(&"cargo install zz-pkg".to_string()));
+
+        // Same config: nothing is even checked
+        env.clear_calls();
+        run(Some(&path), &opts, None).unwrap();
+        assert!(env.calls().is_empty());
+
+        // Any edit invalidates the recorded hash
+        fs::write(&path, "[cargo]\npackages = [\"zz-pkg\", \"zz-other\"]\n").unwrap();
+        run(Some(&path), &opts, None).unwrap();
+        assert!(env.calls().contains(&"cargo install zz-other".to_string()));
+    }
+
+    #[test]
+    fn dry_run_setting_previews_unless_no_dry_run() {
+        let env = FakeEnv::new("settings-dry-run");
+        env.bin("brew", "exit 0").bin("cargo", "exit 0");
+        let path = Path::new(&std::env::var("HOME").unwrap()).join("macup.toml");
+        fs::write(
+            &path,
+            "[settings]\ndry_run = true\n\n[cargo]\npackages = [\"zz-pkg\"]\n",
+        )
+        .unwrap();
+
+        let opts = ApplyOptions {
+            summary_only: true,
+            ..Default::default()
+        };
+        run(Some(&path), &opts, None).unwrap();
+        assert!(!env.calls().contains(&"cargo install zz-pkg".to_string()));
+
+        let opts = ApplyOptions {
+            no_dry_run: true,
+            ..opts
+        };
+        run(Some(&path), &opts, None).unwrap();
+        assert!(env.calls().contains(&"cargo install zz-pkg".to_string()));
+    }
+
+    #[test]
+    fn packages_from_diff_installs_exactly_what_the_diff_reported_missing() {
+        let env = FakeEnv::new("packages-from-diff");
+        env.bin("brew", "exit 0")
+            .bin("cargo", "exit 0")
+            .bin("zz-bin", "exit 0");
+        let home = Path::new(&std::env::var("HOME").unwrap()).to_path_buf();
+        let path = home.join("macup.toml");
+        fs::write(
+            &path,
+            "[cargo]\npackages = [\"zz-present:zz-bin\", \"zz-absent\"]\n",
+        )
+        .unwrap();
+
+        // What `macup diff --output json` prints
+        let (_, config) = load_config_auto(Some(&path)).unwrap();
+        let diff = home.join("diff.json");
+        fs::write(
+            &diff,
+            serde_json::to_string(&compute_diff(&config, false)).unwrap(),
+        )
+        .unwrap();
+
+        // Added after the diff was taken
+        fs::write(
+            &path,
+            "[cargo]\npackages = [\"zz-present:zz-bin\", \"zz-absent\", \"zz-later\"]\n",
+        )
+        .unwrap();
+
+        let opts = ApplyOptions {
+            summary_only: true,
+            packages_from_diff: Some(diff.clone()),
+            ..Default::default()
+        };
+        run(Some(&path), &opts, None).unwrap();
+        let installs: Vec<_> = env
+            .calls()
+            .into_iter()
+            .filter(|call| call.starts_with("cargo install zz"))
+            .collect();
+        assert_eq!(installs, ["cargo install zz-absent"]);
+
+        fs::write(&diff, "{\"missing\": []}").unwrap();
+        let err = run(Some(&path), &opts, None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid --packages-from-diff input (expected the output of 'macup diff --output json')"
+        );
+    }
+}
@@ -0,0 +1,305 @@
+use crate::config::{
+    config_write_target, find_config_file, load_config, read_config_text, to_toml,
+};
+use crate::managers::ManagerMetadata;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::fs;
+use std::path::Path;
+use toml_edit::{DocumentMut, Item, TableLike};
+
+/// Value type of a scalar setting
+#[derive(Clone, Copy)]
+enum SettingType {
+    Bool,
+    /// Non-negative integer
+    Int,
+    /// Integer >= 1 (parallelism limits)
+    Positive,
+    /// Free-form string (paths)
+    Text,
+}
+
+/// Scalar `settings.*` keys editable via `macup config set`
+/// (`settings.parallelism.<manager>` is handled separately)
+const SCALAR_SETTINGS: &[(&str, SettingType)] = &[
+    ("fail_fast", SettingType::Bool),
+    ("dry_run", SettingType::Bool),
+    ("max_parallel", SettingType::Positive),
+    ("global_max_parallel", SettingType::Int),
+    ("suppress_update_checks", SettingType::Bool),
+    ("scan_cache_ttl_hours", SettingType::Int),
+    ("diff_cache_ttl_secs", SettingType::Int),
+    ("runtime_install_retries", SettingType::Int),
+    ("log_file", SettingType::Text),
+    ("brew.use_bundle", SettingType::Bool),
+    ("brew.cleanup_after", SettingType::Bool),
+];
+
+pub fn get(config_path: Option<&Path>, key: &str) -> Result<()> {
+    let setting = parse_key(key)?;
+
+    let config_file = find_config_file(config_path)?;
+    let config = load_config(&config_file)?;
+    let settings = &config.settings;
+
+    let value = match setting {
+        "config_version" => settings.config_version.to_string(),
+        "fail_fast" => settings.fail_fast.to_string(),
+        "dry_run" => settings.dry_run.to_string(),
+        "max_parallel" => settings.max_parallel.to_string(),
+        "global_max_parallel" => settings.global_max_parallel.to_string(),
+        "suppress_update_checks" => settings.suppress_update_checks.to_string(),
+        "scan_cache_ttl_hours" => settings.scan_cache_ttl_hours.to_string(),
+        "diff_cache_ttl_secs" => settings.diff_cache_ttl_secs.to_string(),
+        "runtime_install_retries" => settings.runtime_install_retries.to_string(),
+        "log_file" => settings.log_file.clone().unwrap_or_default(),
+        "brew.use_bundle" => settings.brew.use_bundle.to_string(),
+        "brew.cleanup_after" => settings.brew.cleanup_after.to_string(),
+        _ => {
+            // parse_key only lets parallelism.<manager> through here
+            let manager = setting.trim_start_matches("parallelism.");
+            config.effective_parallelism(manager, None).to_string()
+        }
+    };
+
+    println!("{}", value);
+    Ok(())
+}
+
+pub fn set(config_path: Option<&Path>, key: &str, value: &str) -> Result<()> {
+    let setting = parse_key(key)?;
+
+    if setting == "config_version" {
+        anyhow::bail!("settings.config_version is managed by 'macup migrate'");
+    }
+
+    let mut item = parse_value(key, setting_type(setting), value)?;
+
+    let config_file = config_write_target(&find_config_file(config_path)?);
+    let content = read_config_text(&config_file)?;
+
+    let mut doc = content
+        .parse::<DocumentMut>()
+        .context("Failed to parse TOML")?;
+
+    // Walk/create the tables down to the key, keeping the rest of the file untouched
+    let mut path: Vec<&str> = std::iter::once("settings")
+        .chain(setting.split('.'))
+        .collect();
+    let leaf = path.pop().unwrap_or(setting);
+
+    let mut table: &mut dyn TableLike = doc.as_table_mut();
+    for name in path {
+        if table.get(name).is_none() {
+            table.insert(name, toml_edit::table());
+        }
+        table = table
+            .get_mut(name)
+            .and_then(|item| item.as_table_like_mut())
+            .context(format!("Expected a table at '{}' in config", name))?;
+    }
+    // Keep the comment after a value that's being replaced
+    if let (Some(old), Some(new)) = (
+        table.get(leaf).and_then(Item::as_value),
+        item.as_value_mut(),
+    ) {
+        *new.decor_mut() = old.decor().clone();
+    }
+    table.insert(leaf, item);
+
+    fs::write(&config_file, doc.to_string())
+        .context(format!("Failed to write config: {}", config_file.display()))?;
+
+    println!("{}", format!("✓ Set {} = {}", key, value).green());
+    Ok(())
+}
+
+/// Print the config as macup sees it after loading
+pub fn show(config_path: Option<&Path>) -> Result<()> {
+    let config_file = find_config_file(config_path)?;
+    let config = load_config(&config_file)?;
+
+    print!("{}", to_toml(&config)?);
+    Ok(())
+}
+
+/// Compare declared packages with another config, without looking at the system
+pub fn diff(config_path: Option<&Path>, other: &Path) -> Result<()> {
+    let config_file = find_config_file(config_path)?;
+    let config = load_config(&config_file)?;
+    let other_config = load_config(other)?;
+
+    let diff = config.diff_against(&other_config)?;
+
+    if diff.lists.is_empty() {
+        println!(
+            "{}",
+            format!(
+                "✓ {} declares the same packages as {}",
+                config_file.display(),
+                other.display()
+            )
+            .green()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!("{} compared to {}:", config_file.display(), other.display()).bright_cyan()
+    );
+    println!();
+
+    for list in &diff.lists {
+        println!("{}", list.list.bold());
+        for name in &list.added {
+            println!("  {}", format!("+ {}", name).green());
+        }
+        for name in &list.removed {
+            println!("  {}", format!("- {}", name).red());
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Strip the `settings.` prefix and check the key is a known scalar setting
+fn parse_key(key: &str) -> Result<&str> {
+    let setting = key.strip_prefix("settings.").unwrap_or("");
+
+    let known = setting == "config_version"
+        || SCALAR_SETTINGS.iter().any(|(name, _)| *name == setting)
+        || setting
+            .strip_prefix("parallelism.")
+            .is_some_and(is_known_manager);
+
+    if !known {
+        let valid: Vec<_> = std::iter::once("config_version")
+            .chain(SCALAR_SETTINGS.iter().map(|(name, _)| *name))
+            .chain(std::iter::once("parallelism.<manager>"))
+            .map(|name| format!("settings.{}", name))
+            .collect();
+        anyhow::bail!(
+            "Unknown setting '{}' (valid keys: {})",
+            key,
+            valid.join(", ")
+        );
+    }
+
+    Ok(setting)
+}
+
+fn is_known_manager(name: &str) -> bool {
+    name == "brew" || ManagerMetadata::get_by_name(name).is_some()
+}
+
+fn setting_type(setting: &str) -> SettingType {
+    SCALAR_SETTINGS
+        .iter()
+        .find(|(name, _)| *name == setting)
+        .map(|(_, ty)| *ty)
+        .unwrap_or(SettingType::Positive) // parallelism.<manager>
+}
+
+fn parse_value(key: &str, ty: SettingType, value: &str) -> Result<Item> {
+    let item = match ty {
+        SettingType::Bool => {
+            let parsed: bool = value
+                .parse()
+                .ok()
+                .context(format!("{} expects true or false, got '{}'", key, value))?;
+            toml_edit::value(parsed)
+        }
+        SettingType::Int | SettingType::Positive => {
+            let parsed: i64 = value.parse().ok().filter(|n| *n >= 0).context(format!(
+                "{} expects a non-negative integer, got '{}'",
+                key, value
+            ))?;
+            if matches!(ty, SettingType::Positive) && parsed == 0 {
+                anyhow::bail!("{} must be at least 1", key);
+            }
+            toml_edit::value(parsed)
+        }
+        SettingType::Text => toml_edit::value(value),
+    };
+
+    Ok(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{stdout_of, FakeEnv};
+    use std::path::PathBuf;
+
+    fn write_config(content: &str) -> PathBuf {
+        let path = PathBuf::from(std::env::var("HOME").unwrap()).join("macup.toml");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn get_prints_the_loaded_value() {
+        let Some(stdout) = stdout_of("commands::config::tests::get_prints_the_loaded_value") else {
+            let _env = FakeEnv::new("config-get");
+            let path = write_config("[settings]\nmax_parallel = 7\n");
+            get(Some(&path), "settings.max_parallel").unwrap();
+            // Unset keys print their default
+            get(Some(&path), "settings.fail_fast").unwrap();
+            get(Some(&path), "settings.parallelism.mas").unwrap();
+            return;
+        };
+
+        // Printed right after the test harness's "test <name> ... "
+        assert!(stdout.contains(" ... 7\nfalse\n1\n"));
+    }
+
+    #[test]
+    fn set_edits_the_key_and_keeps_the_rest() {
+        let _env = FakeEnv::new("config-set");
+        let path = write_config(
+            "# my setup\n[settings]\nfail_fast = false # for now\n\n[npm]\nglobal = [\"zx\"]\n",
+        );
+
+        set(Some(&path), "settings.max_parallel", "8").unwrap();
+        set(Some(&path), "settings.fail_fast", "true").unwrap();
+        set(Some(&path), "settings.brew.cleanup_after", "true").unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# my setup\n[settings]\nfail_fast = true # for now\n"));
+        assert!(content.contains("[npm]\nglobal = [\"zx\"]\n"));
+
+        let config = load_config(&path).unwrap();
+        assert_eq!(config.settings.max_parallel, 8);
+        assert!(config.settings.fail_fast);
+        assert!(config.settings.brew.cleanup_after);
+    }
+
+    #[test]
+    fn invalid_keys_and_values_are_rejected() {
+        let _env = FakeEnv::new("config-invalid");
+        let path = write_config("[settings]\nmax_parallel = 4\n");
+
+        let err = set(Some(&path), "settings.max_paralel", "8").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Unknown setting 'settings.max_paralel' (valid keys: "));
+        assert!(get(Some(&path), "brew.formulae").is_err());
+
+        let err = set(Some(&path), "settings.fail_fast", "yes").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "settings.fail_fast expects true or false, got 'yes'"
+        );
+        let err = set(Some(&path), "settings.max_parallel", "0").unwrap_err();
+        assert_eq!(err.to_string(), "settings.max_parallel must be at least 1");
+
+        // Nothing was written
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[settings]\nmax_parallel = 4\n"
+        );
+    }
+}
@@ -1,41 +1,86 @@
-use crate::config::{load_config_auto, CargoConfig, InstallConfig, MasConfig, NpmConfig};
-use crate::managers::{
-    brew::BrewManager,
-    cargo_manager::CargoManager, // CODEGEN[cargo]: import
-    install::InstallManager,
-    mas::MasManager, // CODEGEN[mas]: import
-    npm::NpmManager, // CODEGEN[npm]: import
-    // CODEGEN_MARKER: insert_import_here
-    Manager,
-    ManagerMetadata,
-};
-use anyhow::Result;
+use crate::cli::DiffFormat;
+use crate::commands::outdated::{display_outdated, outdated_mas_apps};
+use crate::config::{load_config_auto, Config};
+use crate::executor::reconcile::{compute_diff, DiffSummary};
+use crate::executor::SelectionFilter;
+use crate::managers::mas::OutdatedApp;
+use anyhow::{Context, Result};
 use colored::Colorize;
-use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Read;
 use std::path::Path;
 
-/// Result of checking packages for a single manager
-#[derive(Debug)]
-struct DiffResult {
-    icon: String,
-    display_name: String,
-    installed: Vec<String>,
+/// `diff --output json|yaml` document
+#[derive(Serialize)]
+struct DiffReport<'a> {
+    #[serde(flatten)]
+    summary: &'a DiffSummary,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    outdated: &'a [OutdatedApp],
+}
+
+/// The parts of a `diff --output json` document `apply --packages-from-diff` reads
+#[derive(Deserialize)]
+struct DiffInput {
+    results: Vec<DiffInputResult>,
+}
+
+#[derive(Deserialize)]
+struct DiffInputResult {
+    section: String,
+    #[serde(default)]
     missing: Vec<String>,
-    skipped_reason: Option<String>, // e.g., "npm not installed"
 }
 
-/// Summary of all diff results
-#[derive(Debug)]
-struct DiffSummary {
-    results: Vec<DiffResult>,
-    total_installed: usize,
-    total_missing: usize,
-    total_skipped: usize,
+/// Missing entries per section from a `macup diff --output json` document
+/// (`-` reads stdin)
+pub fn read_missing(source: &Path) -> Result<BTreeMap<String, BTreeSet<String>>> {
+    let text = if source == Path::new("-") {
+        let mut text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .context("Failed to read diff from stdin")?;
+        text
+    } else {
+        std::fs::read_to_string(source)
+            .with_context(|| format!("Failed to read diff: {}", source.display()))?
+    };
+
+    let input: DiffInput = serde_json::from_str(&text).context(
+        "Invalid --packages-from-diff input (expected the output of 'macup diff --output json')",
+    )?;
+
+    let mut missing: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for result in input.results {
+        missing
+            .entry(result.section)
+            .or_default()
+            .extend(result.missing);
+    }
+    Ok(missing)
 }
 
-pub fn run(config_path: Option<&Path>) -> Result<()> {
+/// Exit status of `macup diff --exit-code` when packages are missing or broken
+/// (errors running the diff exit with 1, like every command, and invalid
+/// arguments with 2)
+pub const DRIFT_EXIT_CODE: i32 = 3;
+
+/// Returns whether anything declared is missing or broken
+pub fn run(
+    config_path: Option<&Path>,
+    verify: bool,
+    selection: &SelectionFilter,
+    outdated: bool,
+    output: DiffFormat,
+) -> Result<bool> {
     // Load config
     let (_config_path, config) = load_config_auto(config_path)?;
+    let config = selection.apply(config)?;
+
+    if output != DiffFormat::Table {
+        return print_report(&config, verify, outdated, output);
+    }
 
     println!("{}", "=".repeat(60).bright_blue());
     println!(
@@ -47,470 +92,130 @@ pub fn run(config_path: Option<&Path>) -> Result<()> {
     println!("{}", "=".repeat(60).bright_blue());
     println!();
 
-    // Collect all diff results
-    let mut results = Vec::new();
-
-    // Check brew sections (taps, formulae, casks)
-    if let Some(brew_config) = &config.brew {
-        results.extend(check_brew_sections(brew_config));
-    }
-
-    // Check mas
-    if let Some(mas_config) = &config.mas {
-        if let Some(result) = check_mas_section(mas_config) {
-            results.push(result);
-        }
-    }
-
-    // CODEGEN_START[npm]: check_call
-    if let Some(npm_config) = &config.npm {
-        if let Some(result) = check_npm_section(npm_config) {
-            results.push(result);
-        }
-    }
-    // CODEGEN_END[npm]: check_call
-
-    // CODEGEN_START[cargo]: check_call
-    if let Some(cargo_config) = &config.cargo {
-        if let Some(result) = check_cargo_section(cargo_config) {
-            results.push(result);
-        }
-    }
-    // CODEGEN_END[cargo]: check_call
-
-
-
-    // CODEGEN_MARKER: insert_check_call_here
-
-    // Check install scripts
-    if let Some(install_config) = &config.install {
-        if let Some(result) = check_install_scripts(install_config) {
-            results.push(result);
-        }
-    }
-
     // Calculate summary
-    let summary = calculate_summary(results);
-
-    // Display results
-    display_results(&summary);
-
-    Ok(())
-}
+    let summary = compute_diff(&config, verify);
 
-/// Check brew packages (returns multiple results for taps, formulae, casks)
-fn check_brew_sections(config: &crate::config::BrewConfig) -> Vec<DiffResult> {
-    let mut results = Vec::new();
-
-    // Check taps
-    if !config.taps.is_empty() {
-        if let Some(result) = check_brew_taps(&config.taps) {
-            results.push(result);
-        }
-    }
-
-    // Check formulae
-    if !config.formulae.is_empty() {
-        if let Some(result) = check_brew_formulae(&config.formulae) {
-            results.push(result);
-        }
-    }
+    // Installed-but-outdated apps: the only drift mas can have
+    let outdated = if outdated {
+        outdated_mas_apps(&config)?
+    } else {
+        vec![]
+    };
 
-    // Check casks
-    if !config.casks.is_empty() {
-        if let Some(result) = check_brew_casks(&config.casks) {
-            results.push(result);
-        }
+    // Display results, split under group headers unless one group was picked
+    if selection.group.is_none() && !config.groups.is_empty() {
+        display_grouped(&config, verify)?;
+    } else {
+        display_manager_results(&summary);
     }
+    display_overall(&summary, &outdated);
 
-    results
+    Ok(summary.total_missing > 0)
 }
 
-/// Check brew taps
-fn check_brew_taps(taps: &[String]) -> Option<DiffResult> {
-    if taps.is_empty() {
-        return None;
-    }
-
-    // Check if brew is installed
-    if !crate::utils::command_exists("brew") {
-        return Some(DiffResult {
-            icon: "🍺".to_string(),
-            display_name: "Homebrew Taps".to_string(),
-            installed: vec![],
-            missing: vec![],
-            skipped_reason: Some("brew not installed".to_string()),
-        });
-    }
-
-    // Get list of installed taps
-    let brew = BrewManager::new(1);
-    let installed_taps = brew.list_taps().unwrap_or_default();
-
-    // Check each tap in parallel
-    let tap_results: Vec<_> = taps
-        .par_iter()
-        .map(|tap| {
-            let is_installed = installed_taps.contains(tap);
-            (tap.clone(), is_installed)
-        })
-        .collect();
-
-    let mut installed = vec![];
-    let mut missing = vec![];
-
-    for (tap, is_installed) in tap_results {
-        if is_installed {
-            installed.push(tap);
-        } else {
-            missing.push(tap);
-        }
-    }
-
-    Some(DiffResult {
-        icon: "🍺".to_string(),
-        display_name: "Homebrew Taps".to_string(),
-        installed,
-        missing,
-        skipped_reason: None,
-    })
+/// Machine-readable diff: one flat document, without group headers or colors
+fn print_report(config: &Config, verify: bool, outdated: bool, output: DiffFormat) -> Result<bool> {
+    let summary = compute_diff(config, verify);
+    let outdated = if outdated {
+        outdated_mas_apps(config)?
+    } else {
+        vec![]
+    };
+    let report = DiffReport {
+        summary: &summary,
+        outdated: &outdated,
+    };
+
+    let text = match output {
+        DiffFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize diff")?,
+        _ => serde_json::to_string_pretty(&report).context("Failed to serialize diff")?,
+    };
+    println!("{}", text.trim_end());
+
+    Ok(summary.total_missing > 0)
 }
 
-/// Check brew formulae
-fn check_brew_formulae(formulae: &[String]) -> Option<DiffResult> {
-    if formulae.is_empty() {
-        return None;
-    }
-
-    // Check if brew is installed
-    if !crate::utils::command_exists("brew") {
-        return Some(DiffResult {
-            icon: "🍺".to_string(),
-            display_name: "Homebrew Formulae".to_string(),
-            installed: vec![],
-            missing: vec![],
-            skipped_reason: Some("brew not installed".to_string()),
-        });
-    }
-
-    // Check each formula by its binary (supports package:binary format)
-    let brew = BrewManager::new(1);
-    let formula_results: Vec<_> = formulae
-        .par_iter()
-        .map(|formula| {
-            let is_installed = brew.is_package_installed(formula).unwrap_or(false);
-            (formula.clone(), is_installed)
-        })
-        .collect();
-
-    let mut installed = vec![];
-    let mut missing = vec![];
-
-    for (formula, is_installed) in formula_results {
-        if is_installed {
-            installed.push(formula);
-        } else {
-            missing.push(formula);
+/// Print each `[[groups]]` entry under its own header, then everything else
+/// Taps belong to no group, so they only show up under "Ungrouped"
+fn display_grouped(config: &Config, verify: bool) -> Result<()> {
+    for group in &config.groups {
+        let (mut members, _) = config.group_members(&group.name)?;
+        if let Some(brew) = members.brew.as_mut() {
+            brew.taps.clear();
         }
+        display_group_header(&group.name);
+        display_manager_results(&compute_diff(&members, verify));
     }
 
-    Some(DiffResult {
-        icon: "🍺".to_string(),
-        display_name: "Homebrew Formulae".to_string(),
-        installed,
-        missing,
-        skipped_reason: None,
-    })
-}
-
-/// Check brew casks
-fn check_brew_casks(casks: &[String]) -> Option<DiffResult> {
-    if casks.is_empty() {
-        return None;
-    }
-
-    // Check if brew is installed
-    if !crate::utils::command_exists("brew") {
-        return Some(DiffResult {
-            icon: "📦".to_string(),
-            display_name: "Homebrew Casks".to_string(),
-            installed: vec![],
-            missing: vec![],
-            skipped_reason: Some("brew not installed".to_string()),
-        });
+    let ungrouped = compute_diff(&config.without_groups()?, verify);
+    if !ungrouped.results.is_empty() {
+        display_group_header("Ungrouped");
+        display_manager_results(&ungrouped);
     }
 
-    // Get list of installed casks
-    let brew = BrewManager::new(1);
-    let installed_casks = brew.list_casks().unwrap_or_default();
-
-    // Check each cask in parallel
-    let cask_results: Vec<_> = casks
-        .par_iter()
-        .map(|cask| {
-            let is_installed = installed_casks.contains(cask);
-            (cask.clone(), is_installed)
-        })
-        .collect();
-
-    let mut installed = vec![];
-    let mut missing = vec![];
-
-    for (cask, is_installed) in cask_results {
-        if is_installed {
-            installed.push(cask);
-        } else {
-            missing.push(cask);
-        }
-    }
-
-    Some(DiffResult {
-        icon: "📦".to_string(),
-        display_name: "Homebrew Casks".to_string(),
-        installed,
-        missing,
-        skipped_reason: None,
-    })
+    Ok(())
 }
 
-/// Check mas packages
-fn check_mas_section(config: &MasConfig) -> Option<DiffResult> {
-    if config.apps.is_empty() {
-        return None;
-    }
-
-    let meta = ManagerMetadata::get_by_name("mas").unwrap();
-
-    // Check if mas is installed
-    if !crate::utils::command_exists(meta.runtime_command) {
-        return Some(DiffResult {
-            icon: meta.icon.to_string(),
-            display_name: meta.display_name.to_string(),
-            installed: vec![],
-            missing: vec![],
-            skipped_reason: Some(format!("{} not installed", meta.runtime_command)),
-        });
-    }
-
-    // Check each app in parallel
-    let mas_mgr = MasManager::new(1);
-    let app_results: Vec<_> = config
-        .apps
-        .par_iter()
-        .map(|app| {
-            let display = format!("{} ({})", app.name, app.id);
-            let is_installed = mas_mgr
-                .is_package_installed(&app.id.to_string())
-                .unwrap_or(false);
-            (display, is_installed)
-        })
-        .collect();
-
-    let mut installed = vec![];
-    let mut missing = vec![];
-
-    for (display, is_installed) in app_results {
-        if is_installed {
-            installed.push(display);
-        } else {
-            missing.push(display);
-        }
-    }
-
-    Some(DiffResult {
-        icon: meta.icon.to_string(),
-        display_name: meta.display_name.to_string(),
-        installed,
-        missing,
-        skipped_reason: None,
-    })
+fn display_group_header(name: &str) {
+    println!("{}", format!("── {} ──", name).bright_magenta().bold());
+    println!();
 }
 
-// CODEGEN_START[npm]: check_function
-/// Check Npm packages
-fn check_npm_section(config: &NpmConfig) -> Option<DiffResult> {
-    if config.global.is_empty() {
-        return None;
-    }
+/// Re-check everything the config declares after `apply --verify-after`
+/// Fails if anything is still missing or broken, i.e. an install that
+/// reported success but didn't actually land
+pub fn verify_after_apply(config: &Config) -> Result<()> {
+    println!("{}", "🔎 Verifying applied config...".bright_cyan().bold());
 
-    let meta = ManagerMetadata::get_by_name("npm").unwrap();
-
-    // Check if runtime is installed
-    if !crate::utils::command_exists(meta.runtime_command) {
-        return Some(DiffResult {
-            icon: meta.icon.to_string(),
-            display_name: meta.display_name.to_string(),
-            installed: vec![],
-            missing: vec![],
-            skipped_reason: Some(format!("{} not installed", meta.runtime_command)),
-        });
-    }
+    let summary = compute_diff(config, true);
 
-    // Check each package in parallel
-    let mgr = NpmManager::new(1);
-    let pkg_results: Vec<_> = config
-        .global
-        .par_iter()
-        .map(|pkg| {
-            // Parse package:binary format - show only package name
-            let (pkg_name, _) = parse_package_name(pkg);
-            let is_installed = mgr.is_package_installed(pkg).unwrap_or(false);
-            (pkg_name.to_string(), is_installed)
-        })
-        .collect();
-
-    let mut installed = vec![];
-    let mut missing = vec![];
-
-    for (pkg, is_installed) in pkg_results {
-        if is_installed {
-            installed.push(pkg);
-        } else {
-            missing.push(pkg);
+    let mut problems = 0;
+    for result in &summary.results {
+        if let Some(reason) = &result.skipped_reason {
+            println!(
+                "  {} {}: {}",
+                "⚠️".yellow(),
+                result.display_name,
+                reason.yellow()
+            );
+            problems += 1;
+            continue;
         }
-    }
-
-    Some(DiffResult {
-        icon: meta.icon.to_string(),
-        display_name: meta.display_name.to_string(),
-        installed,
-        missing,
-        skipped_reason: None,
-    })
-}
-// CODEGEN_END[npm]: check_function
-
-// CODEGEN_START[cargo]: check_function
-/// Check Cargo packages
-fn check_cargo_section(config: &CargoConfig) -> Option<DiffResult> {
-    if config.packages.is_empty() {
-        return None;
-    }
-
-    let meta = ManagerMetadata::get_by_name("cargo").unwrap();
-
-    // Check if runtime is installed
-    if !crate::utils::command_exists(meta.runtime_command) {
-        return Some(DiffResult {
-            icon: meta.icon.to_string(),
-            display_name: meta.display_name.to_string(),
-            installed: vec![],
-            missing: vec![],
-            skipped_reason: Some(format!("{} not installed", meta.runtime_command)),
-        });
-    }
 
-    // Check each package in parallel
-    let mgr = CargoManager::new(1);
-    let pkg_results: Vec<_> = config
-        .packages
-        .par_iter()
-        .map(|pkg| {
-            // Parse package:binary format - show only package name
-            let (pkg_name, _) = parse_package_name(pkg);
-            let is_installed = mgr.is_package_installed(pkg).unwrap_or(false);
-            (pkg_name.to_string(), is_installed)
-        })
-        .collect();
-
-    let mut installed = vec![];
-    let mut missing = vec![];
-
-    for (pkg, is_installed) in pkg_results {
-        if is_installed {
-            installed.push(pkg);
-        } else {
-            missing.push(pkg);
+        for pkg in &result.missing {
+            println!("  {} {} ({})", "❌".red(), pkg.red(), result.display_name);
         }
-    }
-
-    Some(DiffResult {
-        icon: meta.icon.to_string(),
-        display_name: meta.display_name.to_string(),
-        installed,
-        missing,
-        skipped_reason: None,
-    })
-}
-// CODEGEN_END[cargo]: check_function
-
-
-
-// CODEGEN_MARKER: insert_check_function_here
-
-/// Check install scripts
-fn check_install_scripts(config: &InstallConfig) -> Option<DiffResult> {
-    if config.scripts.is_empty() {
-        return None;
-    }
-
-    let install_mgr = InstallManager::new();
-
-    // Check each script in parallel
-    let script_results: Vec<_> = config
-        .scripts
-        .par_iter()
-        .map(|script| {
-            let is_installed = install_mgr.is_installed(script).unwrap_or(false);
-            (script.name.clone(), is_installed)
-        })
-        .collect();
-
-    let mut installed = vec![];
-    let mut missing = vec![];
-
-    for (name, is_installed) in script_results {
-        if is_installed {
-            installed.push(name);
-        } else {
-            missing.push(name);
+        for pkg in &result.broken {
+            println!(
+                "  {} {} ({}) {}",
+                "❌".red(),
+                pkg.red(),
+                result.display_name,
+                "[broken binary]".dimmed()
+            );
         }
+        problems += result.missing.len() + result.broken.len();
     }
 
-    Some(DiffResult {
-        icon: "🔧".to_string(),
-        display_name: "Install Scripts".to_string(),
-        installed,
-        missing,
-        skipped_reason: None,
-    })
-}
-
-/// Parse package:binary format
-fn parse_package_name(input: &str) -> (&str, &str) {
-    if let Some((pkg, bin)) = input.split_once(':') {
-        (pkg.trim(), bin.trim())
-    } else {
-        (input.trim(), input.trim())
+    if problems > 0 {
+        anyhow::bail!(
+            "Verification failed: {} item(s) declared in config are still not installed",
+            problems
+        );
     }
-}
 
-/// Calculate summary from all results
-fn calculate_summary(results: Vec<DiffResult>) -> DiffSummary {
-    let mut total_installed = 0;
-    let mut total_missing = 0;
-    let mut total_skipped = 0;
-
-    for result in &results {
-        if result.skipped_reason.is_some() {
-            total_skipped += 1;
-        } else {
-            total_installed += result.installed.len();
-            total_missing += result.missing.len();
-        }
-    }
+    println!(
+        "  {} All {} declared package(s) present",
+        "✓".green(),
+        summary.total_installed
+    );
+    println!();
 
-    DiffSummary {
-        results,
-        total_installed,
-        total_missing,
-        total_skipped,
-    }
+    Ok(())
 }
 
-/// Display diff results with colored output
-fn display_results(summary: &DiffSummary) {
-    // Display each manager's results
+/// Display each manager's results with colored output
+fn display_manager_results(summary: &DiffSummary) {
     for result in &summary.results {
         // Show manager header
         println!(
@@ -536,8 +241,18 @@ fn display_results(summary: &DiffSummary) {
             println!("  {} {}", "❌".red(), pkg.red());
         }
 
+        // Show packages whose binary is on PATH but broken
+        for pkg in &result.broken {
+            println!(
+                "  {} {} {}",
+                "❌".red(),
+                pkg.red(),
+                "[broken binary]".dimmed()
+            );
+        }
+
         // Show summary for this manager
-        let total = result.installed.len() + result.missing.len();
+        let total = result.installed.len() + result.missing.len() + result.broken.len();
         if total > 0 {
             println!(
                 "  {}: {}/{}",
@@ -549,6 +264,11 @@ fn display_results(summary: &DiffSummary) {
 
         println!();
     }
+}
+
+/// Display outdated apps and the totals across every manager
+fn display_overall(summary: &DiffSummary, outdated: &[OutdatedApp]) {
+    display_outdated(outdated);
 
     // Overall summary
     println!("{}", "=".repeat(60).bright_blue());
@@ -585,3 +305,48 @@ fn display_results(summary: &DiffSummary) {
         println!();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{stdout_of, FakeEnv};
+
+    #[test]
+    fn yaml_output_is_the_json_document() {
+        let Some(stdout) = stdout_of("commands::diff::tests::yaml_output_is_the_json_document")
+        else {
+            let env = FakeEnv::new("diff-yaml");
+            env.bin("cargo", "exit 0").bin("zz-bin", "exit 0");
+            let path = dirs::home_dir().unwrap().join("macup.toml");
+            std::fs::write(
+                &path,
+                "[cargo]\npackages = [\"zz-present:zz-bin\", \"zz-absent\"]\n",
+            )
+            .unwrap();
+
+            let all = SelectionFilter::default();
+            run(Some(&path), false, &all, false, DiffFormat::Yaml).unwrap();
+            println!("---");
+            run(Some(&path), false, &all, false, DiffFormat::Json).unwrap();
+            return;
+        };
+
+        // The harness's "test ..." line shares the first line of output
+        let (yaml, json) = stdout.split_once("\n---\n").unwrap();
+        let yaml = &yaml[yaml.find("results:").unwrap()..];
+        let json = &json[..=json.rfind('}').unwrap()];
+
+        let from_yaml: serde_json::Value = serde_yaml::from_str(yaml).unwrap();
+        let from_json: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(from_yaml, from_json);
+        assert_eq!(
+            from_yaml["results"][0]["installed"],
+            serde_json::json!(["zz-present"])
+        );
+        assert_eq!(
+            from_yaml["results"][0]["missing"],
+            serde_json::json!(["zz-absent"])
+        );
+        assert_eq!(from_yaml["total_missing"], 1);
+    }
+}
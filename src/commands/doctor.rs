@@ -0,0 +1,153 @@
+use crate::config::{find_config_file, load_config};
+use crate::managers::PACKAGE_MANAGERS;
+use crate::utils::command_exists;
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+use std::process::Command;
+
+/// Print what macup resolved about this machine, for bug reports
+/// Read-only: nothing is installed or written
+pub fn run(config_path: Option<&Path>) -> Result<()> {
+    println!("{}", "=".repeat(60).bright_blue());
+    println!(
+        "{}",
+        "macup doctor - Resolved environment".bright_blue().bold()
+    );
+    println!("{}", "=".repeat(60).bright_blue());
+    println!();
+
+    println!("{}", "Config".bright_cyan().bold());
+    let config = match find_config_file(config_path) {
+        Ok(path) => {
+            println!("  Path: {}", path.display());
+            match load_config(&path) {
+                Ok(config) => Some(config),
+                Err(e) => {
+                    println!("  {} {:#}", "✗".red(), e);
+                    None
+                }
+            }
+        }
+        Err(e) => {
+            println!("  {} {:#}", "✗".red(), e);
+            None
+        }
+    };
+    println!();
+
+    if let Some(config) = &config {
+        println!("{}", "Settings".bright_cyan().bold());
+        // Wrapped so nested tables print as [settings.brew]
+        let mut settings = toml::Table::new();
+        settings.insert(
+            "settings".to_string(),
+            toml::Value::try_from(&config.settings)?,
+        );
+        for line in toml::to_string_pretty(&settings)?.lines() {
+            if line.is_empty() {
+                println!();
+            } else {
+                println!("  {}", line);
+            }
+        }
+        println!();
+    }
+
+    println!("{}", "Homebrew".bright_cyan().bold());
+    match brew_prefix() {
+        Some(prefix) => println!("  {} prefix: {}", "✓".green(), prefix),
+        None => println!("  {} brew not found", "✗".red()),
+    }
+    println!();
+
+    println!("{}", "Runtimes".bright_cyan().bold());
+    for meta in PACKAGE_MANAGERS {
+        if command_exists(meta.runtime_command) {
+            let version = runtime_version(meta.runtime_command)
+                .unwrap_or_else(|| "version unknown".to_string());
+            println!(
+                "  {} {:<6} {} ({})",
+                "✓".green(),
+                meta.name,
+                meta.runtime_command,
+                version.dimmed()
+            );
+        } else {
+            println!(
+                "  {} {:<6} {} not found",
+                "✗".red(),
+                meta.name,
+                meta.runtime_command
+            );
+        }
+    }
+    println!();
+
+    if let Some(config) = &config {
+        let required = config.get_required_managers();
+        println!("{}", "Required managers".bright_cyan().bold());
+        if required.is_empty() {
+            println!("  {}", "none".dimmed());
+        } else {
+            println!("  {}", required.join(", "));
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+fn brew_prefix() -> Option<String> {
+    let output = Command::new("brew").arg("--prefix").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// First line of `<command> --version`
+fn runtime_version(command: &str) -> Option<String> {
+    let output = Command::new(command).arg("--version").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{stdout_of, FakeEnv};
+
+    #[test]
+    fn dump_lists_every_managers_runtime() {
+        let Some(stdout) = stdout_of("commands::doctor::tests::dump_lists_every_managers_runtime")
+        else {
+            let env = FakeEnv::new("doctor");
+            env.bin("brew", "echo /opt/homebrew")
+                .bin("cargo", "echo 'cargo 1.80.0 (376290515 2024-07-16)'");
+            let path = dirs::home_dir().unwrap().join("macup.toml");
+            std::fs::write(&path, "[brew]\nformulae = [\"git\"]\n").unwrap();
+
+            run(Some(&path)).unwrap();
+            return;
+        };
+
+        assert!(stdout.contains("macup.toml"));
+        assert!(stdout.contains("✓ prefix: /opt/homebrew"));
+        assert!(stdout.contains("cargo (cargo 1.80.0 (376290515 2024-07-16))"));
+        for meta in PACKAGE_MANAGERS {
+            let found = format!("✓ {:<6} {} (", meta.name, meta.runtime_command);
+            let missing = format!("✗ {:<6} {} not found", meta.name, meta.runtime_command);
+            assert!(
+                stdout.contains(&found) || stdout.contains(&missing),
+                "no line for {}",
+                meta.name
+            );
+        }
+        assert!(stdout.contains("Required managers\n  brew"));
+    }
+}
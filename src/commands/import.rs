@@ -1,15 +1,23 @@
-use crate::config::{load_config_auto, Config};
+use crate::config::{
+    casks_location, config_fragments, config_write_target, find_config_file, load_config,
+    load_config_auto, migrate::CURRENT_CONFIG_VERSION, read_config_text, Config, Settings,
+};
+use crate::managers::brew::BrewManager;
+use crate::managers::same_package;
 use anyhow::{Context, Result};
 use colored::Colorize;
 use inquire::MultiSelect;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use toml_edit::{value, Array, DocumentMut};
 
 /// Represents a package manager type
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 enum PackageManager {
     BrewFormula,
     BrewCask,
@@ -20,23 +28,82 @@ enum PackageManager {
 }
 
 /// Extra data for certain package types
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 enum ExtraData {
-    MasApp { id: u64 },
+    MasApp {
+        id: u64,
+    },
+    /// Formula or cask from a tap other than homebrew/core or homebrew/cask
+    Tap {
+        tap: String,
+    },
 }
 
 /// A scanned package from the system
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct ScannedPackage {
     name: String,
     manager: PackageManager,
     manager_section: String,
     extra_data: Option<ExtraData>,
+    #[serde(skip)]
     is_existing: bool,
+    #[serde(skip)]
+    is_new: bool,
+}
+
+impl PackageManager {
+    /// Whether listing this manager's packages is cheap: brew and cargo read
+    /// local metadata, while npm and pipx start a runtime and mas queries
+    /// the App Store
+    fn lists_quickly(&self) -> bool {
+        matches!(
+            self,
+            PackageManager::BrewFormula | PackageManager::BrewCask | PackageManager::Cargo
+        )
+    }
+}
+
+/// Result of the last system scan, persisted to ~/.cache/macup/scan.json
+#[derive(Debug, Serialize, Deserialize)]
+struct ScanCache {
+    /// Unix timestamp (seconds) of the last full scan
+    scanned_at: u64,
+    packages: Vec<ScannedPackage>,
+}
+
+impl ScanCache {
+    fn new(scanned_at: u64, packages: &[ScannedPackage]) -> Self {
+        Self {
+            scanned_at,
+            packages: packages.to_vec(),
+        }
+    }
+
+    fn age_hours(&self) -> u64 {
+        now_secs().saturating_sub(self.scanned_at) / 3600
+    }
 }
 
 /// Main entry point for import command
-pub fn run(config_path: Option<&Path>) -> Result<()> {
+/// With `incremental`, only packages that are new since the last cached scan are
+/// offered, and only the quick listings run: the others come from the cache
+/// With `yes`, every package not yet in the config is imported without prompting
+/// With `detect_taps`, the taps of selected formulae and casks are looked up and added
+pub fn run(
+    config_path: Option<&Path>,
+    incremental: bool,
+    scan_timeout: u64,
+    yes: bool,
+    create_config: bool,
+    detect_taps: bool,
+) -> Result<()> {
+    if !yes {
+        crate::utils::require_terminal("macup import", "pass --yes to import every package found")?;
+    }
+
+    ensure_config(config_path, create_config)?;
+
     println!("{}", "=".repeat(60).bright_blue());
     println!(
         "{}",
@@ -45,9 +112,25 @@ pub fn run(config_path: Option<&Path>) -> Result<()> {
     println!("{}", "=".repeat(60).bright_blue());
     println!();
 
+    let (resolved_path, config) = load_config_auto(config_path)?;
+
+    // Previous scan (only needed for incremental mode)
+    let previous = if incremental {
+        load_scan_cache(config.settings.scan_cache_ttl_hours)
+    } else {
+        None
+    };
+
     // 1. Scan system
-    println!("{}", "Scanning system packages...".cyan());
-    let mut packages = scan_system()?;
+    if previous.is_some() {
+        println!(
+            "{}",
+            "Scanning system packages (npm, mas and pipx from the last scan)...".cyan()
+        );
+    } else {
+        println!("{}", "Scanning system packages...".cyan());
+    }
+    let mut packages = scan_system(Duration::from_secs(scan_timeout), previous.as_ref());
 
     if packages.is_empty() {
         println!("{}", "No packages found on system.".yellow());
@@ -55,14 +138,54 @@ pub fn run(config_path: Option<&Path>) -> Result<()> {
     }
 
     println!("  {} Found {} packages", "✓".green(), packages.len());
+
+    // An incremental scan reuses cached listings: keep the full scan's time so
+    // they still expire after the TTL
+    let scanned_at = previous
+        .as_ref()
+        .map_or_else(now_secs, |cache| cache.scanned_at);
+    if let Err(e) = save_scan_cache(&ScanCache::new(scanned_at, &packages)) {
+        log::warn!("Failed to write scan cache: {}", e);
+    }
+
+    if incremental {
+        match &previous {
+            Some(cache) => {
+                mark_new_since(&mut packages, cache);
+                packages.retain(|p| p.is_new);
+                println!(
+                    "  {} {} new since last scan ({}h ago)",
+                    "✓".green(),
+                    packages.len(),
+                    cache.age_hours()
+                );
+                if packages.is_empty() {
+                    println!();
+                    println!("{}", "No new packages since last scan.".yellow());
+                    return Ok(());
+                }
+            }
+            None => println!(
+                "  {} No recent scan cache, showing all packages",
+                "⚠".yellow()
+            ),
+        }
+    }
     println!();
 
-    // 2. Load config and detect existing
-    let (resolved_path, config) = load_config_auto(config_path)?;
-    detect_existing(&mut packages, &config)?;
+    // 2. Detect packages already in config
+    detect_existing(
+        &mut packages,
+        &config,
+        &declared_pipx_packages(&resolved_path)?,
+    )?;
 
     // 3. Interactive selection
-    let selected = interactive_select(packages)?;
+    let mut selected = if yes {
+        packages.into_iter().filter(|p| !p.is_existing).collect()
+    } else {
+        interactive_select(packages)?
+    };
 
     if selected.is_empty() {
         println!("{}", "No packages selected.".yellow());
@@ -70,7 +193,18 @@ pub fn run(config_path: Option<&Path>) -> Result<()> {
     }
 
     // 4. Auto-detect taps
-    let taps = collect_required_taps(&selected);
+    if detect_taps {
+        detect_package_taps(&mut selected, Duration::from_secs(scan_timeout));
+    }
+    let mut taps = collect_required_taps(&selected);
+    if let Some(brew) = &config.brew {
+        taps.retain(|tap| {
+            !brew
+                .taps
+                .iter()
+                .any(|entry| same_package("brew", entry.name(), tap))
+        });
+    }
 
     // 5. Generate preview
     println!();
@@ -83,9 +217,10 @@ pub fn run(config_path: Option<&Path>) -> Result<()> {
     println!("{}", preview);
 
     // 6. Confirm
-    let confirmed = inquire::Confirm::new("Add these packages to macup.toml?")
-        .with_default(true)
-        .prompt()?;
+    let confirmed = yes
+        || inquire::Confirm::new("Add these packages to macup.toml?")
+            .with_default(true)
+            .prompt()?;
 
     if !confirmed {
         println!("{}", "Import cancelled.".yellow());
@@ -95,6 +230,7 @@ pub fn run(config_path: Option<&Path>) -> Result<()> {
     // 7. Merge to config
     println!();
     println!("{}", "Writing to config...".cyan());
+    let resolved_path = config_write_target(&resolved_path);
     merge_to_config(&resolved_path, &selected, &taps)?;
 
     println!("{}", "=".repeat(60).bright_green());
@@ -113,45 +249,197 @@ pub fn run(config_path: Option<&Path>) -> Result<()> {
     println!("{}", "Next steps:".bold());
     println!("  • Run {} to verify changes", "macup diff".cyan());
     println!("  • Run {} to apply on a new machine", "macup apply".cyan());
+    if selected.iter().any(|p| p.manager == PackageManager::Pipx) {
+        println!(
+            "  • Run {} so apply installs the [pipx] packages",
+            "macup new manager pipx ...".cyan()
+        );
+    }
     println!();
 
     Ok(())
 }
 
-/// Scan all package managers on the system
-fn scan_system() -> Result<Vec<ScannedPackage>> {
-    let mut packages = Vec::new();
+/// `macup init`: write a new config with default settings
+/// With `from_system`, every scanned package goes in too, without prompting
+pub fn init(
+    path: &Path,
+    from_system: bool,
+    force: bool,
+    scan_timeout: u64,
+    detect_taps: bool,
+) -> Result<()> {
+    if path.exists() && !force {
+        anyhow::bail!(
+            "{} already exists (use --force to overwrite, or 'macup import' to add to it)",
+            path.display()
+        );
+    }
+
+    let mut content = default_settings_toml()?;
+    let mut count = 0;
 
-    // Scan each manager in parallel
-    let results: Vec<Result<Vec<ScannedPackage>>> = vec![
-        scan_brew_formulae(),
-        scan_brew_casks(),
-        scan_npm_global(),
-        scan_cargo(),
-        scan_mas(),
-        scan_pipx(),
-    ]
-    .into_par_iter()
-    .map(|f| f)
-    .collect();
+    if from_system {
+        println!("{}", "Scanning system packages...".cyan());
+        let mut packages = scan_system(Duration::from_secs(scan_timeout), None);
+        println!("  {} Found {} packages", "✓".green(), packages.len());
 
-    for result in results {
-        packages.extend(result?);
+        if detect_taps {
+            detect_package_taps(&mut packages, Duration::from_secs(scan_timeout));
+        }
+
+        let taps = collect_required_taps(&packages);
+        let sections = generate_toml_preview(&packages, &taps)?;
+        if !sections.is_empty() {
+            content.push('\n');
+            content.push_str(&sections);
+        }
+        count = packages.len();
     }
 
-    Ok(packages)
+    write_new_config(path, &content)?;
+
+    println!(
+        "{}",
+        format!("✓ Wrote {} ({} packages)", path.display(), count).green()
+    );
+    println!(
+        "  • Run {} to check it against this machine",
+        "macup diff".cyan()
+    );
+
+    Ok(())
+}
+
+/// Offer to create a minimal config when none can be found, so `add`/`import`
+/// work on a fresh machine. Goes to the --config path if one was given, else
+/// ~/.config/macup/macup.toml. With `create`, no prompt (required without a TTY)
+pub fn ensure_config(config_path: Option<&Path>, create: bool) -> Result<()> {
+    let not_found = match find_config_file(config_path) {
+        Ok(_) => return Ok(()),
+        Err(e) => e,
+    };
+
+    let path = match config_path {
+        Some(path) => path.to_path_buf(),
+        None => dirs::config_dir()
+            .context("Could not determine config directory")?
+            .join("macup/macup.toml"),
+    };
+
+    if !create {
+        if crate::utils::require_terminal("Creating a config", "pass --create-config").is_err() {
+            anyhow::bail!(
+                "{}\n(pass --create-config to create {})",
+                not_found,
+                path.display()
+            );
+        }
+        let confirmed =
+            inquire::Confirm::new(&format!("No config found. Create {}?", path.display()))
+                .with_default(true)
+                .prompt()?;
+        if !confirmed {
+            return Err(not_found);
+        }
+    }
+
+    write_new_config(&path, &default_settings_toml()?)?;
+    println!("{}", format!("✓ Created {}", path.display()).green());
+    println!();
+
+    Ok(())
+}
+
+/// Write a new config file, creating its directory
+fn write_new_config(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).context(format!("Failed to create {}", parent.display()))?;
+    }
+
+    // Write next to the target and rename, so a failed write never leaves half a config
+    let tmp = path.with_extension("toml.tmp");
+    fs::write(&tmp, content).context(format!("Failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, path).context(format!("Failed to write {}", path.display()))?;
+
+    // Catch a generated file macup can't read back
+    load_config(path).context("Generated config does not load")?;
+    Ok(())
+}
+
+/// `[settings]` for a new config: defaults at the current schema version
+fn default_settings_toml() -> Result<String> {
+    #[derive(Serialize)]
+    struct SettingsOnly {
+        settings: Settings,
+    }
+
+    let settings = Settings {
+        config_version: CURRENT_CONFIG_VERSION,
+        ..Settings::default()
+    };
+    toml::to_string_pretty(&SettingsOnly { settings }).context("Failed to serialize settings")
+}
+
+/// A system scanner: lists one manager's installed packages
+type Scanner = fn(Duration) -> Result<Vec<ScannedPackage>>;
+
+/// Scan all package managers on the system
+/// Scanners run in parallel, each under `timeout`: one that hangs (e.g. `mas list`
+/// while signed out) is abandoned with a warning and the others still contribute
+/// With a `cached` scan, only managers that list quickly are scanned again
+fn scan_system(timeout: Duration, cached: Option<&ScanCache>) -> Vec<ScannedPackage> {
+    let scanners: [(&str, PackageManager, Scanner); 6] = [
+        (
+            "brew formulae",
+            PackageManager::BrewFormula,
+            scan_brew_formulae,
+        ),
+        ("brew casks", PackageManager::BrewCask, scan_brew_casks),
+        ("npm", PackageManager::Npm, scan_npm_global),
+        ("cargo", PackageManager::Cargo, scan_cargo),
+        ("mas", PackageManager::Mas, scan_mas),
+        ("pipx", PackageManager::Pipx, scan_pipx),
+    ];
+
+    let results: Vec<_> = scanners
+        .par_iter()
+        .map(|(name, manager, scan)| match cached {
+            Some(cache) if !manager.lists_quickly() => {
+                let packages = cache
+                    .packages
+                    .iter()
+                    .filter(|pkg| pkg.manager == *manager)
+                    .cloned()
+                    .collect();
+                (*name, Ok(packages))
+            }
+            _ => (*name, scan(timeout)),
+        })
+        .collect();
+
+    let mut packages = Vec::new();
+    for (name, result) in results {
+        match result {
+            Ok(found) => packages.extend(found),
+            Err(e) => log::warn!("Skipping {} scan: {:#}", name, e),
+        }
+    }
+
+    packages
 }
 
 /// Scan Homebrew formulae
-fn scan_brew_formulae() -> Result<Vec<ScannedPackage>> {
+fn scan_brew_formulae(timeout: Duration) -> Result<Vec<ScannedPackage>> {
     if !crate::utils::command_exists("brew") {
         return Ok(vec![]);
     }
 
-    let output = Command::new("brew")
-        .args(&["list", "--formula"])
-        .output()
-        .context("Failed to run brew list")?;
+    let output = crate::utils::output_with_timeout(
+        Command::new("brew").args(["list", "--formula"]),
+        timeout,
+    )
+    .context("Failed to run brew list")?;
 
     if !output.status.success() {
         return Ok(vec![]);
@@ -160,14 +448,14 @@ fn scan_brew_formulae() -> Result<Vec<ScannedPackage>> {
     let formulae: Vec<_> = String::from_utf8_lossy(&output.stdout)
         .lines()
         .map(|line| {
-            // Skip tap detection for now (too slow)
-            // User can manually add taps if needed
+            // Taps are looked up later, for selected packages only (--detect-taps)
             ScannedPackage {
                 name: line.to_string(),
                 manager: PackageManager::BrewFormula,
                 manager_section: "brew-formulae".to_string(),
                 extra_data: None,
                 is_existing: false,
+                is_new: false,
             }
         })
         .collect();
@@ -176,15 +464,14 @@ fn scan_brew_formulae() -> Result<Vec<ScannedPackage>> {
 }
 
 /// Scan Homebrew casks
-fn scan_brew_casks() -> Result<Vec<ScannedPackage>> {
+fn scan_brew_casks(timeout: Duration) -> Result<Vec<ScannedPackage>> {
     if !crate::utils::command_exists("brew") {
         return Ok(vec![]);
     }
 
-    let output = Command::new("brew")
-        .args(&["list", "--cask"])
-        .output()
-        .context("Failed to run brew list --cask")?;
+    let output =
+        crate::utils::output_with_timeout(Command::new("brew").args(["list", "--cask"]), timeout)
+            .context("Failed to run brew list --cask")?;
 
     if !output.status.success() {
         return Ok(vec![]);
@@ -193,13 +480,14 @@ fn scan_brew_casks() -> Result<Vec<ScannedPackage>> {
     let casks: Vec<_> = String::from_utf8_lossy(&output.stdout)
         .lines()
         .map(|line| {
-            // Skip tap detection for now (too slow)
+            // Taps are looked up later, for selected packages only (--detect-taps)
             ScannedPackage {
                 name: line.to_string(),
                 manager: PackageManager::BrewCask,
                 manager_section: "brew-casks".to_string(),
                 extra_data: None,
                 is_existing: false,
+                is_new: false,
             }
         })
         .collect();
@@ -208,15 +496,16 @@ fn scan_brew_casks() -> Result<Vec<ScannedPackage>> {
 }
 
 /// Scan npm global packages
-fn scan_npm_global() -> Result<Vec<ScannedPackage>> {
+fn scan_npm_global(timeout: Duration) -> Result<Vec<ScannedPackage>> {
     if !crate::utils::command_exists("npm") {
         return Ok(vec![]);
     }
 
-    let output = Command::new("npm")
-        .args(&["list", "-g", "--depth=0", "--json"])
-        .output()
-        .context("Failed to run npm list")?;
+    let output = crate::utils::output_with_timeout(
+        Command::new("npm").args(["list", "-g", "--depth=0", "--json"]),
+        timeout,
+    )
+    .context("Failed to run npm list")?;
 
     if !output.status.success() {
         return Ok(vec![]);
@@ -237,6 +526,7 @@ fn scan_npm_global() -> Result<Vec<ScannedPackage>> {
             manager_section: "npm".to_string(),
             extra_data: None,
             is_existing: false,
+            is_new: false,
         })
         .collect();
 
@@ -244,15 +534,16 @@ fn scan_npm_global() -> Result<Vec<ScannedPackage>> {
 }
 
 /// Scan cargo installed packages
-fn scan_cargo() -> Result<Vec<ScannedPackage>> {
+fn scan_cargo(timeout: Duration) -> Result<Vec<ScannedPackage>> {
     if !crate::utils::command_exists("cargo") {
         return Ok(vec![]);
     }
 
-    let output = Command::new("cargo")
-        .args(&["install", "--list"])
-        .output()
-        .context("Failed to run cargo install --list")?;
+    let output = crate::utils::output_with_timeout(
+        Command::new("cargo").args(["install", "--list"]),
+        timeout,
+    )
+    .context("Failed to run cargo install --list")?;
 
     if !output.status.success() {
         return Ok(vec![]);
@@ -268,6 +559,7 @@ fn scan_cargo() -> Result<Vec<ScannedPackage>> {
             manager_section: "cargo".to_string(),
             extra_data: None,
             is_existing: false,
+            is_new: false,
         })
         .collect();
 
@@ -275,14 +567,12 @@ fn scan_cargo() -> Result<Vec<ScannedPackage>> {
 }
 
 /// Scan Mac App Store apps
-fn scan_mas() -> Result<Vec<ScannedPackage>> {
+fn scan_mas(timeout: Duration) -> Result<Vec<ScannedPackage>> {
     if !crate::utils::command_exists("mas") {
         return Ok(vec![]);
     }
 
-    let output = Command::new("mas")
-        .arg("list")
-        .output()
+    let output = crate::utils::output_with_timeout(Command::new("mas").arg("list"), timeout)
         .context("Failed to run mas list")?;
 
     if !output.status.success() {
@@ -303,6 +593,7 @@ fn scan_mas() -> Result<Vec<ScannedPackage>> {
                     manager_section: "mas".to_string(),
                     extra_data: Some(ExtraData::MasApp { id }),
                     is_existing: false,
+                    is_new: false,
                 })
             } else {
                 None
@@ -314,15 +605,14 @@ fn scan_mas() -> Result<Vec<ScannedPackage>> {
 }
 
 /// Scan pipx packages
-fn scan_pipx() -> Result<Vec<ScannedPackage>> {
+fn scan_pipx(timeout: Duration) -> Result<Vec<ScannedPackage>> {
     if !crate::utils::command_exists("pipx") {
         return Ok(vec![]);
     }
 
-    let output = Command::new("pipx")
-        .args(&["list", "--short"])
-        .output()
-        .context("Failed to run pipx list")?;
+    let output =
+        crate::utils::output_with_timeout(Command::new("pipx").args(["list", "--short"]), timeout)
+            .context("Failed to run pipx list")?;
 
     if !output.status.success() {
         return Ok(vec![]);
@@ -339,6 +629,7 @@ fn scan_pipx() -> Result<Vec<ScannedPackage>> {
                 manager_section: "pipx".to_string(),
                 extra_data: None,
                 is_existing: false,
+                is_new: false,
             }
         })
         .collect();
@@ -346,29 +637,123 @@ fn scan_pipx() -> Result<Vec<ScannedPackage>> {
     Ok(packages)
 }
 
+/// Cache key for a scanned package ("section/name")
+fn cache_key(pkg: &ScannedPackage) -> String {
+    format!("{}/{}", pkg.manager_section, pkg.name)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn scan_cache_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".cache/macup/scan.json"))
+}
+
+/// Load the last scan if present and younger than `ttl_hours`
+fn load_scan_cache(ttl_hours: u64) -> Option<ScanCache> {
+    let path = scan_cache_path()?;
+    let content = fs::read_to_string(&path).ok()?;
+    let cache: ScanCache = match serde_json::from_str(&content) {
+        Ok(cache) => cache,
+        Err(e) => {
+            log::warn!("Ignoring unreadable scan cache {}: {}", path.display(), e);
+            return None;
+        }
+    };
+
+    if cache.age_hours() >= ttl_hours {
+        log::debug!(
+            "Scan cache expired ({}h old, ttl {}h)",
+            cache.age_hours(),
+            ttl_hours
+        );
+        return None;
+    }
+
+    Some(cache)
+}
+
+fn save_scan_cache(cache: &ScanCache) -> Result<()> {
+    let path = scan_cache_path().context("Could not determine home directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context(format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(cache)?)
+        .context(format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Mark packages that were not present in the previous scan
+fn mark_new_since(packages: &mut [ScannedPackage], previous: &ScanCache) {
+    let known: HashSet<String> = previous.packages.iter().map(cache_key).collect();
+    for pkg in packages.iter_mut() {
+        pkg.is_new = !known.contains(&cache_key(pkg));
+    }
+}
+
+/// Whether a config list already has `name` (ignoring case and ":binary")
+fn declares(list: &[String], manager: &str, name: &str) -> bool {
+    list.iter().any(|spec| same_package(manager, spec, name))
+}
+
+/// Packages of the `[pipx]` section import writes, across every config fragment
+/// (`Config` doesn't parse it, pipx not being a built-in manager)
+fn declared_pipx_packages(config_path: &Path) -> Result<Vec<String>> {
+    let files = if config_path.is_dir() {
+        config_fragments(config_path)?
+    } else {
+        vec![config_path.to_path_buf()]
+    };
+
+    let mut packages = Vec::new();
+    for file in files {
+        let doc = read_config_text(&file)?
+            .parse::<DocumentMut>()
+            .with_context(|| format!("Failed to parse TOML config: {}", file.display()))?;
+        let list = doc
+            .get("pipx")
+            .and_then(|pipx| pipx.get("packages"))
+            .and_then(|item| item.as_array());
+        packages.extend(
+            list.into_iter()
+                .flatten()
+                .filter_map(|pkg| pkg.as_str().map(String::from)),
+        );
+    }
+    Ok(packages)
+}
+
 /// Detect which packages already exist in config
-fn detect_existing(packages: &mut [ScannedPackage], config: &Config) -> Result<()> {
+fn detect_existing(
+    packages: &mut [ScannedPackage],
+    config: &Config,
+    pipx_packages: &[String],
+) -> Result<()> {
     for pkg in packages.iter_mut() {
         let exists = match pkg.manager {
             PackageManager::BrewFormula => config
                 .brew
                 .as_ref()
-                .map(|b| b.formulae.contains(&pkg.name))
+                .map(|b| declares(&b.formulae, "brew", &pkg.name))
                 .unwrap_or(false),
             PackageManager::BrewCask => config
                 .brew
                 .as_ref()
-                .map(|b| b.casks.contains(&pkg.name))
+                .map(|b| declares(&b.casks, "brew", &pkg.name))
                 .unwrap_or(false),
             PackageManager::Npm => config
                 .npm
                 .as_ref()
-                .map(|n| n.global.contains(&pkg.name))
+                .map(|n| declares(&n.global, "npm", &pkg.name))
                 .unwrap_or(false),
             PackageManager::Cargo => config
                 .cargo
                 .as_ref()
-                .map(|c| c.packages.contains(&pkg.name))
+                .map(|c| declares(&c.packages, "cargo", &pkg.name))
                 .unwrap_or(false),
             PackageManager::Mas => {
                 if let Some(ExtraData::MasApp { id }) = pkg.extra_data {
@@ -381,7 +766,7 @@ fn detect_existing(packages: &mut [ScannedPackage], config: &Config) -> Result<(
                     false
                 }
             }
-            PackageManager::Pipx => false,
+            PackageManager::Pipx => declares(pipx_packages, "pipx", &pkg.name),
         };
 
         pkg.is_existing = exists;
@@ -419,6 +804,13 @@ fn interactive_select(packages: Vec<ScannedPackage>) -> Result<Vec<ScannedPackag
                 pkg.name,
                 "[existing]".dimmed()
             )
+        } else if pkg.is_new {
+            format!(
+                "{} {} {}",
+                section_icon(&pkg.manager_section),
+                pkg.name,
+                "[new]".green()
+            )
         } else {
             format!("{} {}", section_icon(&pkg.manager_section), pkg.name)
         };
@@ -457,13 +849,93 @@ fn section_icon(section: &str) -> &'static str {
     }
 }
 
-/// Collect required taps from selected packages
-fn collect_required_taps(_packages: &[ScannedPackage]) -> Vec<String> {
-    // Tap auto-detection disabled for performance
-    // Users can manually add taps if needed
-    Vec::new()
+/// Formulae and casks per `brew info` call
+const TAP_LOOKUP_BATCH: usize = 100;
+
+/// Record the tap of every formula and cask among `packages` that comes from a
+/// non-default tap, with batched `brew info --json=v2` calls
+/// A failed lookup is logged and leaves its batch without taps
+fn detect_package_taps(packages: &mut [ScannedPackage], timeout: Duration) {
+    if !crate::utils::command_exists("brew") {
+        return;
+    }
+
+    println!("{}", "Detecting taps...".cyan());
+    let mut found = 0;
+    for (manager, flag) in [
+        (PackageManager::BrewFormula, "--formula"),
+        (PackageManager::BrewCask, "--cask"),
+    ] {
+        let names: Vec<&str> = packages
+            .iter()
+            .filter(|pkg| pkg.manager == manager)
+            .map(|pkg| pkg.name.as_str())
+            .collect();
+
+        let mut taps = HashMap::new();
+        for batch in names.chunks(TAP_LOOKUP_BATCH) {
+            match lookup_taps(flag, batch, timeout) {
+                Ok(batch_taps) => taps.extend(batch_taps),
+                Err(e) => log::warn!(
+                    "Skipping tap detection for {} packages: {:#}",
+                    batch.len(),
+                    e
+                ),
+            }
+        }
+
+        for pkg in packages.iter_mut().filter(|pkg| pkg.manager == manager) {
+            if let Some(tap) = taps.remove(&pkg.name) {
+                pkg.extra_data = Some(ExtraData::Tap { tap });
+                found += 1;
+            }
+        }
+    }
+    println!(
+        "  {} {} package(s) from non-default taps",
+        "✓".green(),
+        found
+    );
 }
 
+/// One `brew info --json=v2` call: package -> non-default tap
+fn lookup_taps(flag: &str, names: &[&str], timeout: Duration) -> Result<HashMap<String, String>> {
+    let output = crate::utils::output_with_timeout(
+        Command::new("brew")
+            .args(["info", "--json=v2", flag])
+            .args(names),
+        timeout,
+    )
+    .context("Failed to run brew info")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "brew info failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    BrewManager::parse_package_taps(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Collect required taps from selected packages, sorted and deduplicated
+fn collect_required_taps(packages: &[ScannedPackage]) -> Vec<String> {
+    let mut taps: Vec<String> = packages
+        .iter()
+        .filter_map(|pkg| match &pkg.extra_data {
+            Some(ExtraData::Tap { tap }) => Some(tap.clone()),
+            _ => None,
+        })
+        .collect();
+    taps.sort();
+    taps.dedup();
+    taps
+}
+
+/// Comment written above a new `[pipx]` section
+const PIPX_NOTE: &str =
+    "# Note: pipx is not a built-in manager yet\n# Add support with: macup new manager pipx ...\n";
+
 /// Generate TOML preview
 fn generate_toml_preview(packages: &[ScannedPackage], taps: &[String]) -> Result<String> {
     let mut preview = String::new();
@@ -560,9 +1032,8 @@ fn generate_toml_preview(packages: &[ScannedPackage], taps: &[String]) -> Result
         if !preview.is_empty() {
             preview.push('\n');
         }
-        preview.push_str("# Note: pipx is not a built-in manager yet\n");
-        preview.push_str("# Add support with: macup new manager pipx ...\n");
-        preview.push_str("\n[pipx]\n");
+        preview.push_str(PIPX_NOTE);
+        preview.push_str("[pipx]\n");
         preview.push_str("packages = [\n");
         for pkg in &pipx_packages {
             preview.push_str(&format!("    \"{}\",\n", pkg));
@@ -576,7 +1047,7 @@ fn generate_toml_preview(packages: &[ScannedPackage], taps: &[String]) -> Result
 /// Merge selected packages into config file
 fn merge_to_config(config_path: &Path, packages: &[ScannedPackage], taps: &[String]) -> Result<()> {
     // Read existing config
-    let content = fs::read_to_string(config_path).context("Failed to read config file")?;
+    let content = read_config_text(config_path)?;
     let mut doc = content
         .parse::<DocumentMut>()
         .context("Failed to parse TOML")?;
@@ -604,21 +1075,26 @@ fn merge_to_config(config_path: &Path, packages: &[ScannedPackage], taps: &[Stri
         }
     }
 
+    // Casks go to the standalone [casks] section when the config has one
+    let (casks_section, casks_key) = casks_location(&doc);
+    let casks_in_brew = casks_section == "brew" && !brew_casks.is_empty();
+
     // Ensure [brew] section exists if needed
-    if !taps.is_empty() || !brew_formulae.is_empty() || !brew_casks.is_empty() {
+    if !taps.is_empty() || !brew_formulae.is_empty() || casks_in_brew {
         if !doc.contains_key("brew") {
             doc["brew"] = toml_edit::table();
         }
 
         // Add taps
         if !taps.is_empty() {
-            let mut tap_array = doc["brew"]["taps"]
-                .as_array()
+            let mut tap_array = doc["brew"]
+                .get("taps")
+                .and_then(|item| item.as_array())
                 .cloned()
                 .unwrap_or_else(Array::new);
 
             for tap in taps {
-                if !array_contains_str(&tap_array, tap) {
+                if !array_contains_package(&tap_array, "brew", tap) {
                     tap_array.push(tap.as_str());
                 }
             }
@@ -627,33 +1103,39 @@ fn merge_to_config(config_path: &Path, packages: &[ScannedPackage], taps: &[Stri
 
         // Merge formulae
         if !brew_formulae.is_empty() {
-            let mut array = doc["brew"]["formulae"]
-                .as_array()
+            let mut array = doc["brew"]
+                .get("formulae")
+                .and_then(|item| item.as_array())
                 .cloned()
                 .unwrap_or_else(Array::new);
 
             for formula in &brew_formulae {
-                if !array_contains_str(&array, formula) {
+                if !array_contains_package(&array, "brew", formula) {
                     array.push(formula.as_str());
                 }
             }
             doc["brew"]["formulae"] = value(array);
         }
+    }
 
-        // Merge casks
-        if !brew_casks.is_empty() {
-            let mut array = doc["brew"]["casks"]
-                .as_array()
-                .cloned()
-                .unwrap_or_else(Array::new);
+    // Merge casks
+    if !brew_casks.is_empty() {
+        if !doc.contains_key(casks_section) {
+            doc[casks_section] = toml_edit::table();
+        }
 
-            for cask in &brew_casks {
-                if !array_contains_str(&array, cask) {
-                    array.push(cask.as_str());
-                }
+        let mut array = doc[casks_section]
+            .get(casks_key)
+            .and_then(|item| item.as_array())
+            .cloned()
+            .unwrap_or_else(Array::new);
+
+        for cask in &brew_casks {
+            if !array_contains_package(&array, "brew", cask) {
+                array.push(cask.as_str());
             }
-            doc["brew"]["casks"] = value(array);
         }
+        doc[casks_section][casks_key] = value(array);
     }
 
     // Merge npm packages
@@ -662,13 +1144,14 @@ fn merge_to_config(config_path: &Path, packages: &[ScannedPackage], taps: &[Stri
             doc["npm"] = toml_edit::table();
         }
 
-        let mut array = doc["npm"]["global"]
-            .as_array()
+        let mut array = doc["npm"]
+            .get("global")
+            .and_then(|item| item.as_array())
             .cloned()
             .unwrap_or_else(Array::new);
 
         for pkg in &npm_packages {
-            if !array_contains_str(&array, pkg) {
+            if !array_contains_package(&array, "npm", pkg) {
                 array.push(pkg.as_str());
             }
         }
@@ -681,13 +1164,14 @@ fn merge_to_config(config_path: &Path, packages: &[ScannedPackage], taps: &[Stri
             doc["cargo"] = toml_edit::table();
         }
 
-        let mut array = doc["cargo"]["packages"]
-            .as_array()
+        let mut array = doc["cargo"]
+            .get("packages")
+            .and_then(|item| item.as_array())
             .cloned()
             .unwrap_or_else(Array::new);
 
         for pkg in &cargo_packages {
-            if !array_contains_str(&array, pkg) {
+            if !array_contains_package(&array, "cargo", pkg) {
                 array.push(pkg.as_str());
             }
         }
@@ -700,8 +1184,9 @@ fn merge_to_config(config_path: &Path, packages: &[ScannedPackage], taps: &[Stri
             doc["mas"] = toml_edit::table();
         }
 
-        let mut apps_array = doc["mas"]["apps"]
-            .as_array_of_tables()
+        let mut apps_array = doc["mas"]
+            .get("apps")
+            .and_then(|item| item.as_array_of_tables())
             .cloned()
             .unwrap_or_else(toml_edit::ArrayOfTables::new);
 
@@ -725,10 +1210,28 @@ fn merge_to_config(config_path: &Path, packages: &[ScannedPackage], taps: &[Stri
         doc["mas"]["apps"] = toml_edit::Item::ArrayOfTables(apps_array);
     }
 
-    // Write pipx as comment if any
+    // Merge pipx packages
+    // [pipx] isn't a built-in manager: the validator knows the section, but apply
+    // only installs it once a pipx manager is added, so it's written with a note
     if !pipx_packages.is_empty() {
-        // Just add a comment about pipx for now
-        // User would need to implement pipx manager first
+        if !doc.contains_key("pipx") {
+            let mut table = toml_edit::Table::new();
+            table.decor_mut().set_prefix(format!("\n{}", PIPX_NOTE));
+            doc["pipx"] = toml_edit::Item::Table(table);
+        }
+
+        let mut array = doc["pipx"]
+            .get("packages")
+            .and_then(|item| item.as_array())
+            .cloned()
+            .unwrap_or_else(Array::new);
+
+        for pkg in &pipx_packages {
+            if !array_contains_package(&array, "pipx", pkg) {
+                array.push(pkg.as_str());
+            }
+        }
+        doc["pipx"]["packages"] = value(array);
     }
 
     // Write back
@@ -737,7 +1240,210 @@ fn merge_to_config(config_path: &Path, packages: &[ScannedPackage], taps: &[Stri
     Ok(())
 }
 
-/// Check if array contains a string value
-fn array_contains_str(array: &Array, item: &str) -> bool {
-    array.iter().any(|v| v.as_str() == Some(item))
+/// Check if array already declares `item` (ignoring case, see same_package)
+/// Entries are strings or `{ name = ... }` tables (custom-URL taps)
+fn array_contains_package(array: &Array, manager: &str, item: &str) -> bool {
+    array.iter().any(|v| {
+        v.as_str()
+            .or_else(|| v.as_inline_table()?.get("name")?.as_str())
+            .is_some_and(|s| same_package(manager, s, item))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{stdout_of, FakeEnv};
+
+    fn scanned(manager: PackageManager, section: &str, name: &str) -> ScannedPackage {
+        ScannedPackage {
+            name: name.to_string(),
+            manager,
+            manager_section: section.to_string(),
+            extra_data: None,
+            is_existing: false,
+            is_new: false,
+        }
+    }
+
+    fn keys(packages: &[ScannedPackage]) -> Vec<String> {
+        packages.iter().map(cache_key).collect()
+    }
+
+    #[test]
+    fn scan_cache_round_trips_until_it_expires() {
+        let _env = FakeEnv::new("scan-cache");
+        let mut app = scanned(PackageManager::Mas, "mas", "Xcode");
+        app.extra_data = Some(ExtraData::MasApp { id: 497799835 });
+        let packages = vec![scanned(PackageManager::Npm, "npm", "prettier"), app];
+
+        save_scan_cache(&ScanCache::new(now_secs(), &packages)).unwrap();
+        let cache = load_scan_cache(24).unwrap();
+        assert_eq!(keys(&cache.packages), ["npm/prettier", "mas/Xcode"]);
+        assert!(matches!(
+            cache.packages[1].extra_data,
+            Some(ExtraData::MasApp { id: 497799835 })
+        ));
+
+        // A day-old scan is past a 24h TTL
+        save_scan_cache(&ScanCache::new(now_secs() - 24 * 3600, &packages)).unwrap();
+        assert!(load_scan_cache(24).is_none());
+    }
+
+    #[test]
+    fn marks_packages_missing_from_the_last_scan_as_new() {
+        let previous = ScanCache::new(
+            now_secs(),
+            &[scanned(PackageManager::Cargo, "cargo", "ripgrep")],
+        );
+        let mut packages = vec![
+            scanned(PackageManager::Cargo, "cargo", "ripgrep"),
+            scanned(PackageManager::Cargo, "cargo", "bat"),
+            // Same name, other section
+            scanned(PackageManager::BrewFormula, "brew-formulae", "ripgrep"),
+        ];
+
+        mark_new_since(&mut packages, &previous);
+        let new: Vec<_> = packages.iter().map(|pkg| pkg.is_new).collect();
+        assert_eq!(new, [false, true, true]);
+    }
+
+    #[test]
+    fn scanned_packages_match_config_entries_regardless_of_case() {
+        let config: Config = toml::from_str(
+            "[brew]\nformulae = [\"Ripgrep:rg\"]\n\n[npm]\nglobal = [\"Prettier\"]\n",
+        )
+        .unwrap();
+        let mut packages = vec![
+            scanned(PackageManager::BrewFormula, "brew-formulae", "ripgrep"),
+            scanned(PackageManager::Npm, "npm", "prettier"),
+            scanned(PackageManager::Npm, "npm", "eslint"),
+        ];
+
+        detect_existing(&mut packages, &config, &[]).unwrap();
+        let existing: Vec<_> = packages.iter().map(|pkg| pkg.is_existing).collect();
+        assert_eq!(existing, [true, true, false]);
+    }
+
+    #[test]
+    fn incremental_scan_reuses_cached_slow_listings() {
+        let env = FakeEnv::new("incremental-scan");
+        env.bin(
+            "brew",
+            r#"[ "$2" = "--formula" ] && echo wget
+exit 0"#,
+        )
+        .bin("cargo", "echo 'bat v0.24.0:'; echo '    bat'")
+        .bin("npm", "exit 1");
+
+        let cache = ScanCache::new(
+            now_secs(),
+            &[
+                scanned(PackageManager::Npm, "npm", "prettier"),
+                scanned(PackageManager::Cargo, "cargo", "uninstalled-since"),
+            ],
+        );
+
+        let mut packages = scan_system(Duration::from_secs(5), Some(&cache));
+        packages.sort_by_key(cache_key);
+        assert_eq!(
+            keys(&packages),
+            ["brew-formulae/wget", "cargo/bat", "npm/prettier"]
+        );
+        assert!(!env.calls().iter().any(|call| call.starts_with("npm")));
+    }
+
+    #[test]
+    fn hung_scanner_is_abandoned_and_the_rest_still_contribute() {
+        let env = FakeEnv::new("scan-timeout");
+        env.bin(
+            "brew",
+            r#"[ "$2" = "--formula" ] && echo wget
+exit 0"#,
+        )
+        .bin("cargo", "echo 'bat v0.24.0:'; echo '    bat'")
+        // Signed-out `mas list` that never returns
+        .bin("mas", "exec sleep 30");
+
+        let started = std::time::Instant::now();
+        let mut packages = scan_system(Duration::from_secs(1), None);
+        packages.sort_by_key(cache_key);
+
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(keys(&packages), ["brew-formulae/wget", "cargo/bat"]);
+        assert!(env.calls().contains(&"mas list".to_string()));
+    }
+
+    #[test]
+    fn init_from_system_writes_a_loadable_config_of_the_scan() {
+        let env = FakeEnv::new("init-from-system");
+        env.bin(
+            "brew",
+            r#"case "$2" in
+  --formula) echo wget ;;
+  --cask) echo firefox ;;
+esac
+exit 0"#,
+        )
+        .bin("cargo", "echo 'bat v0.24.0:'; echo '    bat'")
+        .bin(
+            "npm",
+            r#"echo '{"dependencies": {"prettier": {"version": "3.2.5"}}}'"#,
+        )
+        .bin("mas", "echo '497799835 Xcode (16.2)'");
+        let path = dirs::home_dir().unwrap().join("macup.toml");
+
+        init(&path, true, false, 5, false).unwrap();
+        let config = load_config(&path).unwrap();
+
+        assert_eq!(config.settings.config_version, CURRENT_CONFIG_VERSION);
+        let brew = config.brew.unwrap();
+        assert_eq!(brew.formulae, ["wget"]);
+        assert_eq!(brew.casks, ["firefox"]);
+        assert_eq!(config.cargo.unwrap().packages, ["bat"]);
+        assert_eq!(config.npm.unwrap().global, ["prettier"]);
+        let apps = config.mas.unwrap().apps;
+        assert_eq!((apps[0].name.as_str(), apps[0].id), ("Xcode", 497799835));
+
+        // An existing config is only replaced with --force
+        let err = init(&path, false, false, 5, false).unwrap_err();
+        assert!(err.to_string().contains("already exists (use --force"));
+        init(&path, false, true, 5, false).unwrap();
+        assert!(load_config(&path).unwrap().brew.is_none());
+    }
+
+    #[test]
+    fn prompting_without_a_terminal_is_a_clear_error() {
+        // The child's stdin is closed, like a pipe in CI
+        let Some(stdout) =
+            stdout_of("commands::import::tests::prompting_without_a_terminal_is_a_clear_error")
+        else {
+            let env = FakeEnv::new("import-no-tty");
+            env.bin("brew", "echo wget");
+            let path = dirs::home_dir().unwrap().join("macup.toml");
+            fs::write(&path, "").unwrap();
+
+            let err = run(Some(&path), false, 5, false, false, false).unwrap_err();
+            println!("import: {}", err);
+            let opts = crate::executor::ApplyOptions {
+                interactive: true,
+                ..Default::default()
+            };
+            let err = crate::commands::apply::run(Some(&path), &opts, None).unwrap_err();
+            println!("apply: {}", err);
+            // Nothing was scanned before failing
+            println!("calls: {}", env.calls().len());
+            return;
+        };
+
+        assert!(stdout.contains(
+            "import: macup import needs a terminal (stdin is not a TTY) - \
+             pass --yes to import every package found"
+        ));
+        assert!(stdout.contains(
+            "apply: --interactive needs a terminal (stdin is not a TTY) - \
+             run without it to apply every phase"
+        ));
+        assert!(stdout.contains("calls: 0"));
+    }
 }
@@ -0,0 +1,67 @@
+use crate::config::migrate::CURRENT_CONFIG_VERSION;
+use crate::config::{config_fragments, find_config_file, load_migrated_document};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::fs;
+use std::path::Path;
+
+pub fn run(config_path: Option<&Path>, dry_run: bool) -> Result<()> {
+    let config_file = find_config_file(config_path)?;
+
+    // A config directory is migrated fragment by fragment
+    if config_file.is_dir() {
+        for fragment in config_fragments(&config_file)? {
+            migrate_file(&fragment, dry_run)?;
+        }
+        return Ok(());
+    }
+
+    migrate_file(&config_file, dry_run)
+}
+
+fn migrate_file(config_file: &Path, dry_run: bool) -> Result<()> {
+    let (doc, changes) = load_migrated_document(config_file)?;
+
+    if changes.is_empty() {
+        println!(
+            "{}",
+            format!(
+                "✓ Config is already at version {} ({})",
+                CURRENT_CONFIG_VERSION,
+                config_file.display()
+            )
+            .green()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!(
+            "Migrating {} to version {}...",
+            config_file.display(),
+            CURRENT_CONFIG_VERSION
+        )
+        .bright_cyan()
+    );
+    println!();
+    for change in &changes {
+        println!("  → {}", change);
+    }
+    println!();
+
+    if dry_run {
+        println!("{}", "Dry run - config not written".yellow());
+        return Ok(());
+    }
+
+    fs::write(config_file, doc.to_string())
+        .context(format!("Failed to write config: {}", config_file.display()))?;
+
+    println!(
+        "{}",
+        format!("✓ Applied {} change(s)", changes.len()).green()
+    );
+
+    Ok(())
+}
@@ -1,6 +1,14 @@
 pub mod add;
 pub mod apply;
+pub mod config;
 pub mod diff;
+pub mod doctor;
 pub mod import;
+pub mod migrate;
 pub mod new_manager;
+pub mod outdated;
+pub mod remove;
 pub mod remove_manager;
+pub mod reverse;
+pub mod run_script;
+pub mod upgrade;
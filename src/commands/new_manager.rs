@@ -2,7 +2,9 @@ use anyhow::{Context, Result};
 use colored::Colorize;
 use std::fs;
 use std::path::Path;
+use std::process::Command;
 
+/// With `verify`, `cargo check` runs afterwards and a failure undoes every edit
 pub fn run(
     name: &str,
     display: &str,
@@ -10,6 +12,115 @@ pub fn run(
     runtime_cmd: &str,
     runtime_name: &str,
     brew_formula: &str,
+    verify: bool,
+) -> Result<()> {
+    let snapshot = Snapshot::take(name)?;
+
+    let generated = generate(name, display, icon, runtime_cmd, runtime_name, brew_formula)
+        .and_then(|()| if verify { cargo_check() } else { Ok(()) });
+
+    if let Err(e) = generated {
+        snapshot.restore()?;
+        println!("{}", "↩ Rolled back all generated edits".yellow());
+        return Err(e);
+    }
+
+    println!("{}", "=".repeat(60).bright_green());
+    println!(
+        "{}",
+        "✅ Package manager created successfully!"
+            .bright_green()
+            .bold()
+    );
+    println!("{}", "=".repeat(60).bright_green());
+    println!();
+    println!("{}", "Next steps:".bold());
+    println!(
+        "  1. Implement the Manager trait in {}",
+        format!("src/managers/{}.rs", name).cyan()
+    );
+    println!("  2. Run {} to verify compilation", "cargo build".cyan());
+    println!(
+        "  3. Test with {} in your macup.toml",
+        format!("[{}]", name).cyan()
+    );
+    println!(
+        "  4. Test with {}",
+        format!("macup add {} <package>", name).cyan()
+    );
+    println!();
+
+    Ok(())
+}
+
+/// Contents of every file `generate` edits, so a failed run can be undone
+struct Snapshot {
+    files: Vec<(&'static str, String)>,
+    /// Manager implementation file `generate` creates
+    manager_file: String,
+}
+
+impl Snapshot {
+    fn take(name: &str) -> Result<Self> {
+        let manager_file = format!("src/managers/{}.rs", name);
+        if Path::new(&manager_file).exists() {
+            anyhow::bail!("{} already exists", manager_file);
+        }
+
+        let files = CODEGEN_TARGETS
+            .iter()
+            .map(|(file, _)| {
+                fs::read_to_string(file)
+                    .map(|content| (*file, content))
+                    .context(format!("Failed to read {}", file))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            files,
+            manager_file,
+        })
+    }
+
+    fn restore(&self) -> Result<()> {
+        for (file, content) in &self.files {
+            fs::write(file, content).context(format!("Failed to restore {}", file))?;
+        }
+        if Path::new(&self.manager_file).exists() {
+            fs::remove_file(&self.manager_file)
+                .context(format!("Failed to remove {}", self.manager_file))?;
+        }
+        Ok(())
+    }
+}
+
+/// `cargo check` the crate with the generated code (`new manager --verify`)
+fn cargo_check() -> Result<()> {
+    println!("{} Running cargo check...", "9.".bold());
+
+    let output = Command::new("cargo")
+        .args(["check", "--quiet", "--message-format=short"])
+        .output()
+        .context("Failed to run cargo check")?;
+
+    if !output.status.success() {
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        anyhow::bail!("Generated code does not compile (cargo check failed)");
+    }
+
+    println!("   {} compiles", "✓".green());
+    println!();
+    Ok(())
+}
+
+/// Write every codegen edit for a new manager
+fn generate(
+    name: &str,
+    display: &str,
+    icon: &str,
+    runtime_cmd: &str,
+    runtime_name: &str,
+    brew_formula: &str,
 ) -> Result<()> {
     println!("{}", "=".repeat(60).bright_blue());
     println!(
@@ -80,40 +191,123 @@ pub fn run(
     println!("   {} {}", "✓".green(), "src/commands/add.rs".dimmed());
     println!();
 
-    // Step 8: Update diff.rs for 'macup diff' support
+    // Step 8: Update reconcile.rs for 'macup diff' support
     println!("{} Adding 'macup diff' command support...", "8.".bold());
     add_to_diff_command(name, &name_capitalized)?;
-    println!("   {} {}", "✓".green(), "src/commands/diff.rs".dimmed());
-    println!();
-
-    println!("{}", "=".repeat(60).bright_green());
     println!(
-        "{}",
-        "✅ Package manager created successfully!"
-            .bright_green()
-            .bold()
+        "   {} {}",
+        "✓".green(),
+        "src/executor/reconcile.rs".dimmed()
     );
-    println!("{}", "=".repeat(60).bright_green());
     println!();
-    println!("{}", "Next steps:".bold());
-    println!(
-        "  1. Implement the Manager trait in {}",
-        format!("src/managers/{}.rs", name).cyan()
-    );
-    println!("  2. Run {} to verify compilation", "cargo build".cyan());
-    println!(
-        "  3. Test with {} in your macup.toml",
-        format!("[{}]", name).cyan()
-    );
-    println!(
-        "  4. Test with {}",
-        format!("macup add {} <package>", name).cyan()
-    );
+
+    Ok(())
+}
+
+/// Insertion points `run` needs in each file it edits
+const CODEGEN_TARGETS: &[(&str, &[&str])] = &[
+    (
+        "src/managers/registry.rs",
+        &["// CODEGEN_MARKER: insert_manager_metadata_here"],
+    ),
+    (
+        "src/executor/planner.rs",
+        &["// CODEGEN_MARKER: insert_section_type_here"],
+    ),
+    (
+        "src/config/schema.rs",
+        &[
+            "// CODEGEN_MARKER: insert_config_field_here",
+            "// CODEGEN_MARKER: insert_config_struct_here",
+            "// CODEGEN_MARKER: insert_manager_match_arm_here",
+        ],
+    ),
+    (
+        "src/executor/apply.rs",
+        &[
+            "// CODEGEN_MARKER: insert_manager_import_here",
+            "// CODEGEN_MARKER: insert_handler_function_here",
+            "// CODEGEN_MARKER: insert_section_match_arm_here",
+        ],
+    ),
+    (
+        "src/managers/mod.rs",
+        &["// CODEGEN_MARKER: insert_module_declaration_here"],
+    ),
+    (
+        "src/commands/add.rs",
+        &[
+            "// CODEGEN_MARKER: insert_manager_import_here",
+            "// CODEGEN_MARKER: insert_manager_match_arm_here",
+        ],
+    ),
+    (
+        "src/executor/reconcile.rs",
+        &[
+            "use crate::config::{",
+            "// CODEGEN_MARKER: insert_import_here",
+            "// CODEGEN_MARKER: insert_check_call_here",
+            "// CODEGEN_MARKER: insert_check_function_here",
+        ],
+    ),
+];
+
+/// Dry-run the marker lookups `run` performs, writing nothing
+/// With a name, also checks the manager doesn't exist yet
+pub fn check(name: Option<&str>) -> Result<()> {
+    println!("{}", "Checking codegen markers...".bright_blue().bold());
     println!();
 
+    let mut not_ready = 0;
+    for (file, markers) in CODEGEN_TARGETS {
+        let missing = match fs::read_to_string(file) {
+            Ok(content) => missing_markers(&content, markers),
+            Err(e) => {
+                println!("  {} {} ({})", "✗".red(), file, e);
+                not_ready += 1;
+                continue;
+            }
+        };
+
+        if missing.is_empty() {
+            println!("  {} {}", "✓".green(), file);
+        } else {
+            println!("  {} {}", "✗".red(), file);
+            for marker in missing {
+                println!("      missing: {}", marker.yellow());
+            }
+            not_ready += 1;
+        }
+    }
+
+    if let Some(name) = name {
+        let manager_file = format!("src/managers/{}.rs", name);
+        if Path::new(&manager_file).exists() {
+            println!("  {} {} already exists", "✗".red(), manager_file);
+            not_ready += 1;
+        } else {
+            println!("  {} {} is free", "✓".green(), manager_file);
+        }
+    }
+    println!();
+
+    if not_ready > 0 {
+        anyhow::bail!("{} codegen target(s) not ready", not_ready);
+    }
+
+    println!("{}", "✓ Ready to generate".green());
     Ok(())
 }
 
+/// Markers (or patterns) not found in a file's content
+fn missing_markers<'a>(content: &str, markers: &[&'a str]) -> Vec<&'a str> {
+    markers
+        .iter()
+        .copied()
+        .filter(|marker| !content.contains(marker))
+        .collect()
+}
+
 fn capitalize(s: &str) -> String {
     let mut chars = s.chars();
     match chars.next() {
@@ -310,56 +504,38 @@ fn add_config_struct(name: &str, name_cap: &str) -> Result<()> {
     // Extract indent from the marker (usually no indent)
     let struct_indent = extract_indent(&updated_content, struct_insert_marker);
 
+    let i = &struct_indent;
     let new_struct = format!(
-        r#"{}// CODEGEN_START[{}]: config_struct
-{}#[derive(Debug, Clone, Deserialize, Serialize)]
-{}pub struct {}Config {{
-{}    #[serde(default)]
-{}    pub depends_on: Vec<String>,
-{}
-{}    #[serde(default)]
-{}    pub packages: Vec<String>,
-{}}}
-{}
-{}impl PackageManagerSection for {}Config {{
-{}    fn get_depends_on(&self) -> &Vec<String> {{
-{}        &self.depends_on
-{}    }}
-{}
-{}    fn has_packages(&self) -> bool {{
-{}        !self.packages.is_empty()
-{}    }}
-{}}}
-{}// CODEGEN_END[{}]: config_struct
-{}
-{}{}"#,
-        struct_indent,
-        name,
-        struct_indent,
-        struct_indent,
-        name_cap,
-        struct_indent,
-        struct_indent,
-        struct_indent,
-        struct_indent,
-        struct_indent,
-        struct_indent,
-        struct_indent,
-        struct_indent,
-        name_cap,
-        struct_indent,
-        struct_indent,
-        struct_indent,
-        struct_indent,
-        struct_indent,
-        struct_indent,
-        struct_indent,
-        struct_indent,
-        struct_indent,
-        name,
-        struct_indent,
-        struct_indent,
-        struct_insert_marker
+        r#"{i}// CODEGEN_START[{name}]: config_struct
+{i}#[derive(Debug, Clone, Deserialize, Serialize)]
+{i}pub struct {name_cap}Config {{
+{i}    #[serde(default)]
+{i}    pub depends_on: Vec<String>,
+{i}
+{i}    #[serde(default)]
+{i}    pub packages: Vec<String>,
+{i}
+{i}    /// `brew install` the runtime when it's missing (false = install it yourself)
+{i}    #[serde(default = "default_true")]
+{i}    pub runtime_auto_install: bool,
+{i}}}
+{i}
+{i}impl PackageManagerSection for {name_cap}Config {{
+{i}    fn get_depends_on(&self) -> &Vec<String> {{
+{i}        &self.depends_on
+{i}    }}
+{i}
+{i}    fn runtime_auto_install(&self) -> bool {{
+{i}        self.runtime_auto_install
+{i}    }}
+{i}
+{i}    fn has_packages(&self) -> bool {{
+{i}        !self.packages.is_empty()
+{i}    }}
+{i}}}
+{i}// CODEGEN_END[{name}]: config_struct
+{i}
+{i}{struct_insert_marker}"#
     );
 
     updated_content = updated_content.replace(
@@ -434,7 +610,7 @@ fn add_handler_function(name: &str, name_cap: &str) -> Result<()> {
         format!("{}/// Handler for {} package manager phase", i, name_cap),
         format!("{}fn apply_{}_phase(", i, name),
         format!("{}    config: &Config,", i),
-        format!("{}    dry_run: bool,", i),
+        format!("{}    opts: &ApplyOptions,", i),
         format!("{}    max_parallel: usize,", i),
         format!("{}    fail_fast: bool,", i),
         format!("{}    errors: &mut ApplyErrors,", i),
@@ -443,105 +619,113 @@ fn add_handler_function(name: &str, name_cap: &str) -> Result<()> {
         format!("{}        Some(cfg) if !cfg.packages.is_empty() => cfg,", i),
         format!("{}        _ => return Ok(()), // No {} config or no packages", i, name),
         format!("{}    }};", i),
-        format!("{}", i),
+        String::new(),
         format!("{}    let meta = ManagerMetadata::get_by_name(\"{}\").unwrap();", i, name),
-        format!("{}    ", i),
-        format!("{}    println!(", i),
+        String::new(),
+        format!("{}    detail!(", i),
+        format!("{}        opts,", i),
         format!("{}        \"{{}}\",", i),
         format!("{}        format!(\"{{}} Installing {{}}...\", meta.icon, meta.display_name)", i),
         format!("{}            .bright_cyan()", i),
         format!("{}            .bold()", i),
         format!("{}    );", i),
-        format!("{}", i),
+        String::new(),
         format!("{}    // Auto-install runtime if not found", i),
         format!("{}    if !crate::utils::command_exists(meta.runtime_command) {{", i),
-        format!("{}        println!(", i),
+        format!("{}        if runtime_install_disabled(config, meta, opts, fail_fast, errors, {}_config.packages.clone())? {{", i, name),
+        format!("{}            return Ok(());", i),
+        format!("{}        }}", i),
+        String::new(),
+        format!("{}        say!(opts,", i),
         format!("{}            \"  ⚠️  {{}} not found, installing {{}} via brew...\",", i),
         format!("{}            meta.runtime_command.yellow(),", i),
         format!("{}            meta.runtime_name.cyan()", i),
         format!("{}        );", i),
-        format!("{}", i),
-        format!("{}        if dry_run {{", i),
-        format!("{}            println!(\"    → Would run: brew install {{}}\", meta.brew_formula);", i),
+        String::new(),
+        format!("{}        if opts.dry_run {{", i),
+        format!("{}            detail!(opts, \"    → Would run: brew install {{}}\", meta.brew_formula);", i),
         format!("{}        }} else {{", i),
-        format!("{}            match install_runtime_via_brew(meta.brew_formula) {{", i),
+        format!("{}            match install_runtime_via_brew(meta.brew_formula, config, opts) {{", i),
         format!("{}                Ok(_) => {{", i),
-        format!("{}                    println!(\"  ✓ {{}} installed\", meta.runtime_name.green());", i),
+        format!("{}                    detail!(opts, \"  ✓ {{}} installed\", meta.runtime_name.green());", i),
         format!("{}                }}", i),
         format!("{}                Err(e) => {{", i),
-        format!("{}                    println!(\"  ❌ Failed to install {{}}: {{}}\", meta.runtime_name, e);", i),
-        format!("{}", i),
+        format!("{}                    say!(opts, \"  ❌ Failed to install {{}}: {{}}\", meta.runtime_name, e);", i),
+        String::new(),
         format!("{}                    // Record failures for all packages", i),
         format!("{}                    for pkg in &{}_config.packages {{", i, name),
-        format!("{}                        errors.package_failures.push(PackageFailure {{", i),
+        format!("{}                        errors.record(PackageFailure {{", i),
         format!("{}                            package: pkg.clone(),", i),
         format!("{}                            manager: meta.name.to_string(),", i),
         format!("{}                            reason: format!(\"{{}} installation failed: {{}}\", meta.runtime_name, e),", i),
-        format!("{}                        }});", i),
+        format!("{}                            kind: FailureKind::classify(&e, \"\"),", i),
+        format!("{}                        }})?;", i),
         format!("{}                    }}", i),
-        format!("{}", i),
+        String::new(),
         format!("{}                    if fail_fast {{", i),
         format!("{}                        bail!(\"Failed to install {{}}\", meta.runtime_name);", i),
         format!("{}                    }}", i),
-        format!("{}", i),
-        format!("{}                    println!();", i),
+        String::new(),
+        format!("{}                    detail!(opts);", i),
         format!("{}                    return Ok(());", i),
         format!("{}                }}", i),
         format!("{}            }}", i),
         format!("{}        }}", i),
         format!("{}    }}", i),
-        format!("{}", i),
+        String::new(),
         format!("{}    // Install packages - check missing first", i),
         format!("{}    let {}_mgr = {}Manager::new(max_parallel);", i, name, name_cap),
-        format!("{}    ", i),
-        format!("{}    // Filter missing packages in parallel", i),
-        format!("{}    let missing_packages: Vec<_> = {}_config", i, name),
-        format!("{}        .packages", i),
-        format!("{}        .par_iter()", i),
-        format!("{}        .filter(|pkg| !{}_mgr.is_package_installed(pkg).unwrap_or(false))", i, name),
-        format!("{}        .cloned()", i),
-        format!("{}        .collect();", i),
-        format!("{}", i),
+        String::new(),
+        format!("{}    // Filter missing packages with the check `macup diff` runs", i),
+        format!("{}    let missing_packages = missing_unless_replay(config, opts, meta.name)", i),
+        format!("{}        .{}", i, name),
+        format!("{}        .map(|{}| {}.packages)", i, name, name),
+        format!("{}        .unwrap_or_default();", i),
+        String::new(),
+        format!("{}    errors.tally(opts, meta.name, missing_packages.len());", i),
+        String::new(),
         format!("{}    if missing_packages.is_empty() {{", i),
-        format!("{}        println!(\"  ✓ All packages already installed\");", i),
-        format!("{}        println!();", i),
+        format!("{}        detail!(opts, \"  ✓ All packages already installed\");", i),
+        format!("{}        detail!(opts);", i),
         format!("{}        return Ok(());", i),
         format!("{}    }}", i),
-        format!("{}", i),
-        format!("{}    if dry_run {{", i),
-        format!("{}        println!(\"  Packages ({{}} to install):\", missing_packages.len());", i),
+        String::new(),
+        format!("{}    if opts.dry_run {{", i),
+        format!("{}        detail!(opts, \"  Packages ({{}} to install):\", missing_packages.len());", i),
         format!("{}        for pkg in &missing_packages {{", i),
-        format!("{}            println!(\"    → {{}}\", pkg);", i),
+        format!("{}            detail_command(opts, &{}_mgr.describe_install(pkg));", i, name),
         format!("{}        }}", i),
         format!("{}    }} else {{", i),
         format!("{}        match {}_mgr.install_packages(&missing_packages) {{", i, name),
         format!("{}            Ok(result) => {{", i),
-        format!("{}                print_result(\"{} packages\", &result);", i, name_cap),
-        format!("{}", i),
+        format!("{}                print_result(meta.name, &result, opts);", i),
+        String::new(),
         format!("{}                // Track failures", i),
-        format!("{}                for (pkg, reason) in &result.failed {{", i),
-        format!("{}                    errors.package_failures.push(PackageFailure {{", i),
+        format!("{}                for (pkg, reason, kind) in &result.failed {{", i),
+        format!("{}                    errors.record(PackageFailure {{", i),
         format!("{}                        package: pkg.clone(),", i),
         format!("{}                        manager: meta.name.to_string(),", i),
         format!("{}                        reason: reason.clone(),", i),
-        format!("{}                    }});", i),
+        format!("{}                        kind: *kind,", i),
+        format!("{}                    }})?;", i),
         format!("{}                }}", i),
         format!("{}            }}", i),
         format!("{}            Err(e) => {{", i),
-        format!("{}                println!(\"  ❌ {{}} installation failed: {{}}\", meta.name, e);", i),
-        format!("{}", i),
+        format!("{}                say!(opts, \"  ❌ {{}} installation failed: {{}}\", meta.name, e);", i),
+        format!("{}                errors.fail_all(meta.name, &missing_packages, &e)?;", i),
+        String::new(),
         format!("{}                if fail_fast {{", i),
         format!("{}                    bail!(\"{{}} installation failed\", meta.name);", i),
         format!("{}                }}", i),
         format!("{}            }}", i),
         format!("{}        }}", i),
         format!("{}    }}", i),
-        format!("{}", i),
-        format!("{}    println!();", i),
+        String::new(),
+        format!("{}    detail!(opts);", i),
         format!("{}    Ok(())", i),
         format!("{}}}", i),
         format!("{}// CODEGEN_END[{}]: handler_function", i, name),
-        format!("{}", i),
+        String::new(),
         format!("{}{}", i, handler_marker),
     ].join("\n");
 
@@ -560,10 +744,10 @@ fn add_handler_function(name: &str, name_cap: &str) -> Result<()> {
     let new_match_arm = format!(
         r#"{}// CODEGEN_START[{}]: match_arm
 {}SectionType::{} => {{
-{}    apply_{}_phase(config, dry_run, max_parallel, fail_fast, &mut errors)?;
+{}    apply_{}_phase(config, opts, parallelism("{}"), fail_fast, errors)?;
 {}}}
 {}// CODEGEN_END[{}]: match_arm
-{}
+
 {}{}"#,
         match_indent,
         name,
@@ -571,11 +755,11 @@ fn add_handler_function(name: &str, name_cap: &str) -> Result<()> {
         name_cap,
         match_indent,
         name,
+        name,
         match_indent,
         match_indent,
         name,
         match_indent,
-        match_indent,
         match_marker
     );
     updated_content =
@@ -591,11 +775,10 @@ fn create_manager_impl(name: &str, name_cap: &str) -> Result<()> {
     let manager_path = Path::new(&manager_file);
 
     let template = format!(
-        r#"use super::{{InstallResult, Manager}};
+        r#"use super::{{FailureKind, InstallResult, Manager}};
 use anyhow::{{Context, Result}};
 use rayon::prelude::*;
 use std::collections::HashSet;
-use std::process::Command;
 
 /// Manager for {} packages
 pub struct {}Manager {{
@@ -619,6 +802,28 @@ impl {}Manager {{
             (input.trim(), input.trim())
         }}
     }}
+
+    /// Install a single package
+    /// With `output`, the command's output is captured there instead of printed
+    fn install_one(&self, package: &str, output: Option<&mut String>) -> Result<()> {{
+        // Parse package:binary format - install using package name
+        let (pkg_name, _binary_name) = Self::parse_package_name(package);
+
+        log::info!("→ Installing {{}} ({})...", pkg_name);
+
+        let success = crate::utils::run_status(
+            &mut crate::utils::command_for(&self.describe_install(package)),
+            output,
+        )
+        .context(format!("Failed to install {{}}", pkg_name))?;
+
+        if !success {{
+            anyhow::bail!("Failed to install {{}}", pkg_name);
+        }}
+
+        log::info!("✓ {{}} installed", pkg_name);
+        Ok(())
+    }}
 }}
 
 impl Manager for {}Manager {{
@@ -643,31 +848,23 @@ impl Manager for {}Manager {{
     fn is_package_installed(&self, package: &str) -> Result<bool> {{
         // Parse package:binary format
         let (_pkg_name, binary_name) = Self::parse_package_name(package);
-        
+
         // Use `which` to check if the binary exists
         Ok(crate::utils::command_exists(binary_name))
     }}
 
-    fn install_package(&self, package: &str) -> Result<()> {{
-        // Parse package:binary format - install using package name
+    fn describe_install(&self, package: &str) -> Vec<String> {{
         let (pkg_name, _binary_name) = Self::parse_package_name(package);
-        
-        println!("  Installing {{}}...", pkg_name);
 
         // TODO: Adjust the install command for your package manager
-        // Example for npm: ["install", "--global", pkg_name]
-        // Example for cargo: ["install", pkg_name]
-        // Example for pip: ["install", pkg_name]
-        let status = Command::new("{}")
-            .args(&["install", pkg_name]) // Adjust args as needed
-            .status()
-            .context(format!("Failed to install {{}}", pkg_name))?;
-
-        if !status.success() {{
-            anyhow::bail!("Failed to install {{}}", pkg_name);
-        }}
+        // Example for npm: ["npm", "install", "--global", pkg_name]
+        // Example for cargo: ["cargo", "install", pkg_name]
+        // Example for pip: ["pip", "install", pkg_name]
+        vec!["{}".to_string(), "install".to_string(), pkg_name.to_string()]
+    }}
 
-        Ok(())
+    fn install_package(&self, package: &str) -> Result<()> {{
+        self.install_one(package, None)
     }}
 
     fn install_packages(&self, packages: &[String]) -> Result<InstallResult> {{
@@ -688,19 +885,27 @@ impl Manager for {}Manager {{
         // Collect owned strings for parallel processing
         let to_install: Vec<String> = to_install.into_iter().cloned().collect();
 
-        // Install packages in parallel
-        let install_results: Vec<_> = to_install
-            .par_iter()
-            .map(|pkg| {{
-                (pkg.clone(), self.install_package(pkg))
-            }})
-            .collect();
+        // Install packages in parallel, buffering each package's output
+        let install_results: Vec<_> = crate::utils::install_pool(self.max_parallel)?.install(|| {{
+            to_install
+                .par_iter()
+                .map(|pkg| {{
+                    let mut output = String::new();
+                    let res = self.install_one(pkg, Some(&mut output));
+                    (pkg.clone(), res, output)
+                }})
+                .collect()
+        }});
 
         // Separate successes and failures
-        for (pkg, res) in install_results {{
+        for (pkg, res, output) in install_results {{
+            crate::utils::flush_output(&pkg, &output, res.is_err());
             match res {{
                 Ok(_) => result.success.push(pkg),
-                Err(e) => result.failed.push((pkg, e.to_string())),
+                Err(e) => {{
+                    let kind = FailureKind::classify(&e, &output);
+                    result.failed.push((pkg, e.to_string(), kind))
+                }}
             }}
         }}
 
@@ -708,7 +913,7 @@ impl Manager for {}Manager {{
     }}
 }}
 "#,
-        name, name_cap, name_cap, name_cap, name, name, name
+        name, name_cap, name_cap, name, name_cap, name, name, name
     );
 
     fs::write(manager_path, template).context("Failed to create manager implementation")?;
@@ -744,13 +949,13 @@ fn update_managers_mod(name: &str) -> Result<()> {
 }
 
 fn add_to_diff_command(name: &str, name_cap: &str) -> Result<()> {
-    let diff_path = Path::new("src/commands/diff.rs");
-    let content = fs::read_to_string(diff_path).context("Failed to read diff.rs")?;
+    let diff_path = Path::new("src/executor/reconcile.rs");
+    let content = fs::read_to_string(diff_path).context("Failed to read reconcile.rs")?;
 
     // 1. Add config import at the top
-    let config_import_pattern = "use crate::config::{load_config_auto,";
+    let config_import_pattern = "use crate::config::{";
     if !content.contains(config_import_pattern) {
-        anyhow::bail!("Could not find config import in diff.rs");
+        anyhow::bail!("Could not find config import in reconcile.rs");
     }
 
     // Find the end of config imports line (after the closing })
@@ -768,7 +973,7 @@ fn add_to_diff_command(name: &str, name_cap: &str) -> Result<()> {
     // 2. Add import for manager
     let import_marker = "// CODEGEN_MARKER: insert_import_here";
     if !updated_content.contains(import_marker) {
-        anyhow::bail!("Could not find CODEGEN_MARKER: insert_import_here in diff.rs");
+        anyhow::bail!("Could not find CODEGEN_MARKER: insert_import_here in reconcile.rs");
     }
 
     let import_indent = extract_indent(&updated_content, import_marker);
@@ -782,11 +987,11 @@ fn add_to_diff_command(name: &str, name_cap: &str) -> Result<()> {
     // 3. Add check function call
     let call_marker = "// CODEGEN_MARKER: insert_check_call_here";
     if !updated_content.contains(call_marker) {
-        anyhow::bail!("Could not find CODEGEN_MARKER: insert_check_call_here in diff.rs");
+        anyhow::bail!("Could not find CODEGEN_MARKER: insert_check_call_here in reconcile.rs");
     }
 
     let call_indent = extract_indent(&updated_content, call_marker);
-    let new_call = vec![
+    let new_call = [
         format!("{}// CODEGEN_START[{}]: check_call", call_indent, name),
         format!(
             "{}if let Some({}_config) = &config.{} {{",
@@ -800,7 +1005,7 @@ fn add_to_diff_command(name: &str, name_cap: &str) -> Result<()> {
         format!("{}    }}", call_indent),
         format!("{}}}", call_indent),
         format!("{}// CODEGEN_END[{}]: check_call", call_indent, name),
-        format!(""),
+        String::new(),
         format!("{}{}", call_indent, call_marker),
     ]
     .join("\n");
@@ -811,7 +1016,7 @@ fn add_to_diff_command(name: &str, name_cap: &str) -> Result<()> {
     // 3. Add check function implementation
     let func_marker = "// CODEGEN_MARKER: insert_check_function_here";
     if !updated_content.contains(func_marker) {
-        anyhow::bail!("Could not find CODEGEN_MARKER: insert_check_function_here in diff.rs");
+        anyhow::bail!("Could not find CODEGEN_MARKER: insert_check_function_here in reconcile.rs");
     }
 
     let func_indent = extract_indent(&updated_content, func_marker);
@@ -819,7 +1024,7 @@ fn add_to_diff_command(name: &str, name_cap: &str) -> Result<()> {
     updated_content =
         updated_content.replace(&format!("{}{}", func_indent, func_marker), &check_function);
 
-    fs::write(diff_path, updated_content).context("Failed to write diff.rs")?;
+    fs::write(diff_path, updated_content).context("Failed to write reconcile.rs")?;
 
     Ok(())
 }
@@ -838,10 +1043,12 @@ fn generate_diff_check_function(name: &str, name_cap: &str, i: &str) -> String {
         format!("{}    // Check if runtime is installed", i),
         format!("{}    if !crate::utils::command_exists(meta.runtime_command) {{", i),
         format!("{}        return Some(DiffResult {{", i),
+        format!("{}            section: meta.name.to_string(),", i),
         format!("{}            icon: meta.icon.to_string(),", i),
         format!("{}            display_name: meta.display_name.to_string(),", i),
         format!("{}            installed: vec![],", i),
         format!("{}            missing: vec![],", i),
+        format!("{}            broken: vec![],", i),
         format!("{}            skipped_reason: Some(format!(\"{{}} not installed\", meta.runtime_command)),", i),
         format!("{}        }});", i),
         format!("{}    }}", i),
@@ -871,10 +1078,12 @@ fn generate_diff_check_function(name: &str, name_cap: &str, i: &str) -> String {
         format!("{}    }}", i),
         format!(""),
         format!("{}    Some(DiffResult {{", i),
+        format!("{}        section: meta.name.to_string(),", i),
         format!("{}        icon: meta.icon.to_string(),", i),
         format!("{}        display_name: meta.display_name.to_string(),", i),
         format!("{}        installed,", i),
         format!("{}        missing,", i),
+        format!("{}        broken: vec![],", i),
         format!("{}        skipped_reason: None,", i),
         format!("{}    }})", i),
         format!("{}}}", i),
@@ -883,3 +1092,81 @@ fn generate_diff_check_function(name: &str, name_cap: &str, i: &str) -> String {
         format!("{}// CODEGEN_MARKER: insert_check_function_here", i),
     ].join("\n")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{stdout_of, FakeEnv};
+
+    #[test]
+    fn this_tree_is_ready_to_generate() {
+        check(Some("zz-manager")).unwrap();
+        assert!(check(Some("brew")).is_err());
+    }
+
+    #[test]
+    fn file_with_a_removed_marker_is_not_ready() {
+        let (file, markers) = CODEGEN_TARGETS
+            .iter()
+            .find(|(file, _)| *file == "src/config/schema.rs")
+            .unwrap();
+        let content = fs::read_to_string(file).unwrap();
+        assert!(missing_markers(&content, markers).is_empty());
+
+        let edited = content.replace("// CODEGEN_MARKER: insert_config_struct_here", "");
+        assert_eq!(
+            missing_markers(&edited, markers),
+            ["// CODEGEN_MARKER: insert_config_struct_here"]
+        );
+    }
+
+    #[test]
+    fn failed_verify_rolls_back_every_edit() {
+        let Some(stdout) =
+            stdout_of("commands::new_manager::tests::failed_verify_rolls_back_every_edit")
+        else {
+            // cargo check of the generated code fails, as it would for a broken template
+            let env = FakeEnv::new("new-manager-verify");
+            env.bin(
+                "cargo",
+                "echo 'error[E0425]: cannot find value' >&2; exit 101",
+            );
+
+            let tree = dirs::home_dir().unwrap().join("tree");
+            fs::create_dir(&tree).unwrap();
+            let copied = Command::new("cp")
+                .arg("-R")
+                .arg("src")
+                .arg(&tree)
+                .status()
+                .unwrap();
+            assert!(copied.success());
+            std::env::set_current_dir(&tree).unwrap();
+
+            let before: Vec<String> = CODEGEN_TARGETS
+                .iter()
+                .map(|(file, _)| fs::read_to_string(file).unwrap())
+                .collect();
+
+            let err = run("zz", "Zz", "📦", "zz", "Zz", "zz", true).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Generated code does not compile (cargo check failed)"
+            );
+
+            for ((file, _), content) in CODEGEN_TARGETS.iter().zip(&before) {
+                assert_eq!(
+                    &fs::read_to_string(file).unwrap(),
+                    content,
+                    "{} not restored",
+                    file
+                );
+            }
+            assert!(!Path::new("src/managers/zz.rs").exists());
+            assert_eq!(env.calls(), ["cargo check --quiet --message-format=short"]);
+            return;
+        };
+
+        assert!(stdout.contains("Rolled back all generated edits"));
+    }
+}
@@ -0,0 +1,81 @@
+use crate::config::{load_config_auto, Config};
+use crate::managers::mas::{MasManager, OutdatedApp};
+use crate::managers::ManagerMetadata;
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::HashSet;
+use std::path::Path;
+
+pub fn run(config_path: Option<&Path>) -> Result<()> {
+    let (_config_path, config) = load_config_auto(config_path)?;
+
+    println!("{}", "=".repeat(60).bright_blue());
+    println!(
+        "{}",
+        "macup outdated - Checking for updates".bright_blue().bold()
+    );
+    println!("{}", "=".repeat(60).bright_blue());
+    println!();
+
+    let outdated = outdated_mas_apps(&config)?;
+    display_outdated(&outdated);
+
+    if outdated.is_empty() {
+        println!("{}", "✓ Everything is up to date".green());
+    } else {
+        println!(
+            "{}",
+            "Run 'mas upgrade' to update App Store apps.".bright_yellow()
+        );
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Declared mas apps with an update available
+/// Empty when the config has no apps or mas isn't installed
+pub fn outdated_mas_apps(config: &Config) -> Result<Vec<OutdatedApp>> {
+    let Some(mas) = config.mas.as_ref().filter(|mas| !mas.apps.is_empty()) else {
+        return Ok(vec![]);
+    };
+    if !crate::utils::command_exists("mas") {
+        return Ok(vec![]);
+    }
+
+    let declared: HashSet<String> = mas.apps.iter().map(|app| app.id.to_string()).collect();
+    let outdated = MasManager::new(1)
+        .list_outdated()?
+        .into_iter()
+        .filter(|app| declared.contains(&app.id))
+        .collect();
+
+    Ok(outdated)
+}
+
+/// Print outdated apps under the mas header (nothing if none)
+pub fn display_outdated(outdated: &[OutdatedApp]) {
+    if outdated.is_empty() {
+        return;
+    }
+
+    let meta = ManagerMetadata::get_by_name("mas").unwrap();
+    println!(
+        "{} {}",
+        meta.icon,
+        format!("{} - updates available", meta.display_name)
+            .bright_cyan()
+            .bold()
+    );
+    for app in outdated {
+        println!(
+            "  {} {} ({}) {} → {}",
+            "⬆".yellow(),
+            app.name.yellow(),
+            app.id,
+            app.installed.dimmed(),
+            app.available
+        );
+    }
+    println!();
+}
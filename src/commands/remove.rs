@@ -0,0 +1,383 @@
+use crate::config::{config_fragments, find_config_file, load_config, read_config_text, Config};
+use crate::executor::list_cache;
+use crate::managers::asdf::AsdfManager;
+use crate::managers::brew::BrewManager;
+use crate::managers::cargo_manager::CargoManager;
+use crate::managers::mas::MasManager;
+use crate::managers::npm::NpmManager;
+use crate::managers::pip::PipManager;
+use crate::managers::pnpm::PnpmManager;
+use crate::managers::same_package;
+use crate::managers::yarn::YarnManager;
+use crate::managers::Manager;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{Array, DocumentMut, Item, Value};
+
+/// Names `macup rm` accepts
+const MANAGERS: &[&str] = &[
+    "brew", "cask", "npm", "cargo", "asdf", "pnpm", "yarn", "pip", "mas",
+];
+
+/// A config file with packages taken out of it, not yet written
+struct Edit {
+    path: PathBuf,
+    before: String,
+    doc: DocumentMut,
+    removed: Vec<String>,
+}
+
+/// `macup rm <manager> <packages...>`: uninstall packages and drop them from the config
+pub fn run(
+    config_path: Option<&Path>,
+    manager: &str,
+    packages: Vec<String>,
+    no_uninstall: bool,
+    dry_run: bool,
+    no_dry_run: bool,
+) -> Result<()> {
+    if packages.is_empty() {
+        anyhow::bail!("No packages specified");
+    }
+    if !MANAGERS.contains(&manager) {
+        anyhow::bail!(
+            "Unknown manager: '{}' (valid: {}){}",
+            manager,
+            MANAGERS.join(", "),
+            crate::utils::did_you_mean(manager, MANAGERS)
+        );
+    }
+
+    let config_file = find_config_file(config_path)?;
+    let config = load_config(&config_file)?;
+    config.settings.validate()?;
+
+    // Every package must be declared somewhere before anything is touched
+    let edits = edit_config(&config_file, manager, &packages)?;
+    let missing: Vec<&String> = packages
+        .iter()
+        .filter(|pkg| !edits.iter().any(|edit| edit.removed.contains(pkg)))
+        .collect();
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "Not in the config's {} packages: {}",
+            manager,
+            missing
+                .iter()
+                .map(|pkg| pkg.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    println!(
+        "{}",
+        format!(
+            "Removing {} package(s) from [{}]...",
+            packages.len(),
+            manager
+        )
+        .bright_cyan()
+    );
+    println!();
+
+    if config.effective_dry_run(dry_run, no_dry_run) {
+        for edit in &edits {
+            println!(
+                "{}",
+                format!("Dry run - changes to {}:", edit.path.display()).yellow()
+            );
+            println!();
+            crate::commands::add::print_line_diff(&edit.before, &edit.doc.to_string());
+            println!();
+        }
+        println!("{}", "Config not written, nothing uninstalled".yellow());
+        return Ok(());
+    }
+
+    // Uninstall first: packages that fail to uninstall stay in the config
+    let mut to_remove = Vec::new();
+    let mut errors = Vec::new();
+    if no_uninstall {
+        for package in &packages {
+            println!("→ {}... skipping uninstall", package);
+        }
+        to_remove = packages.clone();
+    } else {
+        list_cache::invalidate();
+        uninstall(&config, manager, &packages, &mut to_remove, &mut errors)?;
+    }
+
+    if !to_remove.is_empty() {
+        println!();
+        println!("Updating config...");
+        let mut removed = 0;
+        for edit in edit_config(&config_file, manager, &to_remove)? {
+            fs::write(&edit.path, edit.doc.to_string())
+                .context(format!("Failed to write config: {}", edit.path.display()))?;
+            removed += edit.removed.len();
+        }
+        println!(
+            "{}",
+            format!("✓ Removed {} package(s) from config", removed).green()
+        );
+    }
+
+    if !errors.is_empty() {
+        println!();
+        println!(
+            "{}",
+            format!(
+                "⚠ {} package(s) failed to uninstall and were kept in config:",
+                errors.len()
+            )
+            .yellow()
+        );
+        for (pkg, err) in &errors {
+            println!("  - {}: {}", pkg, err);
+        }
+        anyhow::bail!("{} package(s) could not be uninstalled", errors.len());
+    }
+
+    Ok(())
+}
+
+/// Uninstall the installed ones among `packages`; those that are gone
+/// (or weren't installed) go to `removed`, failures to `errors`
+fn uninstall(
+    config: &Config,
+    manager: &str,
+    packages: &[String],
+    removed: &mut Vec<String>,
+    errors: &mut Vec<(String, anyhow::Error)>,
+) -> Result<()> {
+    let brew = BrewManager::new(1);
+    let mgr: Box<dyn Manager> = match manager {
+        "brew" | "cask" => Box::new(BrewManager::new(1)),
+        "npm" => Box::new(NpmManager::new(1)),
+        "cargo" => Box::new(CargoManager::new(1)),
+        "asdf" => Box::new(AsdfManager::new(1)),
+        "pnpm" => Box::new(PnpmManager::new(1)),
+        "yarn" => Box::new(YarnManager::new(1)),
+        "mas" => Box::new(MasManager::new(1)),
+        _ => Box::new(PipManager::new(1)),
+    };
+
+    if !mgr.is_installed() {
+        anyhow::bail!(
+            "{} is not installed - pass --no-uninstall to only edit the config",
+            mgr.name()
+        );
+    }
+
+    let casks = if manager == "cask" {
+        brew.list_casks()?
+    } else {
+        Default::default()
+    };
+
+    for package in packages {
+        print!("→ Checking {}... ", package);
+
+        // mas apps may be named by the user but install and uninstall by id
+        let target = match config.mas.as_ref().filter(|_| manager == "mas") {
+            Some(mas) => mas
+                .apps
+                .iter()
+                .find(|app| {
+                    app.id.to_string() == *package || app.name.eq_ignore_ascii_case(package)
+                })
+                .map_or_else(|| package.clone(), |app| app.id.to_string()),
+            None => package.clone(),
+        };
+
+        let installed = if manager == "cask" {
+            BrewManager::has_cask(&casks, &target)
+        } else {
+            mgr.is_package_installed(&target).unwrap_or(false)
+        };
+        if !installed {
+            println!("{}", "not installed".dimmed());
+            removed.push(package.clone());
+            continue;
+        }
+
+        print!("uninstalling... ");
+        let result = if manager == "cask" {
+            brew.uninstall_cask(&target)
+        } else {
+            mgr.uninstall_package(&target)
+        };
+        match result {
+            Ok(()) => {
+                println!("{}", "✓".green());
+                removed.push(package.clone());
+            }
+            Err(e) => {
+                println!("{}", format!("✗ {}", e).red());
+                errors.push((package.clone(), e));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Take `packages` out of every config file that declares them
+/// (each fragment of a config directory). Only changed files are returned
+fn edit_config(path: &Path, manager: &str, packages: &[String]) -> Result<Vec<Edit>> {
+    let files = if path.is_dir() {
+        config_fragments(path)?
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut edits = Vec::new();
+    for file in files {
+        let before = read_config_text(&file)?;
+        let mut doc = before
+            .parse::<DocumentMut>()
+            .with_context(|| format!("Failed to parse TOML config: {}", file.display()))?;
+
+        let removed = remove_from_document(&mut doc, manager, packages)?;
+        if !removed.is_empty() {
+            edits.push(Edit {
+                path: file,
+                before,
+                doc,
+                removed,
+            });
+        }
+    }
+
+    Ok(edits)
+}
+
+/// Remove packages from the manager's section, returning the ones found
+fn remove_from_document(
+    doc: &mut DocumentMut,
+    manager: &str,
+    packages: &[String],
+) -> Result<Vec<String>> {
+    let locations: &[(&str, &str)] = match manager {
+        "brew" => &[("brew", "formulae")],
+        // Casks live in `brew.casks` or the standalone `[casks] list`
+        "cask" => &[("brew", "casks"), ("casks", "list")],
+        "npm" => &[("npm", "global")],
+        "asdf" => return remove_asdf_tools(doc, packages),
+        "mas" => return remove_mas_apps(doc, packages),
+        _ => &[(manager, "packages")],
+    };
+    let section = if manager == "cask" { "brew" } else { manager };
+
+    let mut removed = Vec::new();
+    for (table, key) in locations {
+        let Some(array) = doc
+            .get_mut(table)
+            .and_then(|item| item.get_mut(key))
+            .and_then(|item| item.as_array_mut())
+        else {
+            continue;
+        };
+
+        remove_entries(array, &mut removed, |entry| {
+            let spec = entry.as_str().unwrap_or("");
+            packages.iter().find(|pkg| same_package(section, spec, pkg))
+        });
+    }
+
+    Ok(removed)
+}
+
+/// Drop the entries `matches` maps to a requested package, recording it in `removed`
+/// The first remaining entry keeps the layout of the array's first line
+fn remove_entries<'a>(
+    array: &mut Array,
+    removed: &mut Vec<String>,
+    mut matches: impl FnMut(&Value) -> Option<&'a String>,
+) {
+    let first_prefix = array
+        .get(0)
+        .and_then(|entry| entry.decor().prefix())
+        .cloned();
+
+    array.retain(|entry| match matches(entry) {
+        Some(pkg) => {
+            if !removed.contains(pkg) {
+                removed.push(pkg.clone());
+            }
+            false
+        }
+        None => true,
+    });
+
+    if let (Some(prefix), Some(first)) = (first_prefix, array.get_mut(0)) {
+        first.decor_mut().set_prefix(prefix);
+    }
+}
+
+/// asdf tools are `{ plugin, version }` tables in [asdf].tools
+fn remove_asdf_tools(doc: &mut DocumentMut, packages: &[String]) -> Result<Vec<String>> {
+    let specs = packages
+        .iter()
+        .map(|pkg| AsdfManager::parse_tool_spec(pkg).map(|spec| (pkg, spec)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut removed = Vec::new();
+    if let Some(array) = doc
+        .get_mut("asdf")
+        .and_then(|asdf| asdf.get_mut("tools"))
+        .and_then(|item| item.as_array_mut())
+    {
+        remove_entries(array, &mut removed, |entry| {
+            let table = entry.as_inline_table()?;
+            let plugin = table.get("plugin").and_then(|p| p.as_str()).unwrap_or("");
+            let version = table.get("version").and_then(|v| v.as_str()).unwrap_or("");
+            specs
+                .iter()
+                .find(|(_, (p, v))| same_package("asdf", plugin, p) && version == *v)
+                .map(|(pkg, _)| *pkg)
+        });
+    }
+
+    Ok(removed)
+}
+
+/// mas apps are `{ name, id }` tables in [mas].apps, matched by id or name
+fn remove_mas_apps(doc: &mut DocumentMut, packages: &[String]) -> Result<Vec<String>> {
+    let matching = |name: Option<&Value>, id: Option<&Value>| {
+        let name = name.and_then(|n| n.as_str()).unwrap_or("");
+        let id = id.and_then(|i| i.as_integer()).map(|i| i.to_string());
+        packages
+            .iter()
+            .find(|pkg| id.as_deref() == Some(pkg.as_str()) || pkg.eq_ignore_ascii_case(name))
+    };
+
+    let mut removed = Vec::new();
+    match doc.get_mut("mas").and_then(|mas| mas.get_mut("apps")) {
+        Some(Item::Value(Value::Array(array))) => {
+            remove_entries(array, &mut removed, |entry| {
+                let table = entry.as_inline_table()?;
+                matching(table.get("name"), table.get("id"))
+            });
+        }
+        // [[mas.apps]] tables
+        Some(Item::ArrayOfTables(array)) => array.retain(|table| {
+            let value = |key| table.get(key).and_then(|item: &Item| item.as_value());
+            match matching(value("name"), value("id")) {
+                Some(pkg) => {
+                    if !removed.contains(pkg) {
+                        removed.push(pkg.clone());
+                    }
+                    false
+                }
+                None => true,
+            }
+        }),
+        _ => {}
+    }
+
+    Ok(removed)
+}
@@ -1,8 +1,29 @@
 use anyhow::{Context, Result};
 use colored::Colorize;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::Path;
 
+/// Removes a manager's regions from one file (name, capitalized name)
+type RemoveStep = fn(&str, &str) -> Result<()>;
+
+/// Files `macup new manager` writes regions into, with the step that removes
+/// a manager's regions from each
+const REGION_FILES: &[(&str, RemoveStep)] = &[
+    ("src/managers/registry.rs", remove_from_registry),
+    ("src/executor/planner.rs", remove_from_section_type),
+    ("src/config/schema.rs", remove_config_struct),
+    ("src/executor/apply.rs", remove_handler_function),
+    ("src/managers/mod.rs", |name, _| {
+        remove_from_managers_mod(name)
+    }),
+    ("src/commands/add.rs", remove_from_add_command),
+    ("src/executor/reconcile.rs", remove_from_diff_command),
+];
+
+/// Region files a manager may legitimately lack (mas's diff check is hand-written)
+const OPTIONAL_REGION_FILES: &[&str] = &["src/executor/reconcile.rs"];
+
 pub fn run(name: &str) -> Result<()> {
     println!("{}", "=".repeat(60).bright_blue());
     println!(
@@ -73,10 +94,14 @@ pub fn run(name: &str) -> Result<()> {
     println!("   {} {}", "✓".green(), "src/commands/add.rs".dimmed());
     println!();
 
-    // Step 8: Remove from diff.rs
+    // Step 8: Remove from reconcile.rs
     println!("{} Removing from 'macup diff' command...", "8.".bold());
     remove_from_diff_command(name, &name_capitalized)?;
-    println!("   {} {}", "✓".green(), "src/commands/diff.rs".dimmed());
+    println!(
+        "   {} {}",
+        "✓".green(),
+        "src/executor/reconcile.rs".dimmed()
+    );
     println!();
 
     println!("{}", "=".repeat(60).bright_green());
@@ -99,6 +124,144 @@ pub fn run(name: &str) -> Result<()> {
     Ok(())
 }
 
+/// `macup doctor --codegen`: list which files hold each manager's regions and
+/// flag inconsistent managers - regions missing from some files, or a module
+/// declared without its file. With `fix`, regions of managers whose
+/// implementation is gone are removed
+pub fn audit(fix: bool) -> Result<()> {
+    println!("{}", "Checking codegen regions...".bright_blue().bold());
+    println!();
+
+    let mut contents = BTreeMap::new();
+    for (file, _) in REGION_FILES {
+        let content = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {} (run from the macup source tree)", file))?;
+        contents.insert(*file, content);
+    }
+
+    let owners: BTreeMap<&str, BTreeSet<String>> = contents
+        .iter()
+        .map(|(file, content)| (*file, region_owners(content)))
+        .collect();
+    let managers: BTreeSet<&String> = owners.values().flatten().collect();
+
+    let mut orphaned = Vec::new();
+    let mut inconsistent = 0;
+    for name in managers {
+        let missing: Vec<&str> = REGION_FILES
+            .iter()
+            .map(|(file, _)| *file)
+            .filter(|file| !OPTIONAL_REGION_FILES.contains(file) && !owners[file].contains(name))
+            .collect();
+        let module_file = declared_module(&contents["src/managers/mod.rs"], name)
+            .map(|module| format!("src/managers/{}.rs", module));
+        let impl_missing = !Path::new(
+            module_file
+                .as_deref()
+                .unwrap_or(&format!("src/managers/{}.rs", name)),
+        )
+        .exists();
+
+        if missing.is_empty() && !impl_missing {
+            println!("  {} {}", "✓".green(), name);
+            continue;
+        }
+
+        inconsistent += 1;
+        println!("  {} {}", "✗".red(), name.yellow());
+        for file in &missing {
+            println!("      no region in {}", file);
+        }
+        if let Some(file) = module_file.as_ref().filter(|_| impl_missing) {
+            println!("      module declared but {} doesn't exist", file);
+        }
+        if impl_missing {
+            orphaned.push(name.clone());
+        } else {
+            println!(
+                "      implementation exists - restore the missing regions by hand or run {}",
+                format!("macup remove manager {}", name).cyan()
+            );
+        }
+    }
+    println!();
+
+    if inconsistent == 0 {
+        println!("{}", "✓ Codegen regions are consistent".green());
+        return Ok(());
+    }
+
+    if !fix {
+        anyhow::bail!(
+            "{} manager(s) with inconsistent codegen regions ({} orphaned, --fix removes them)",
+            inconsistent,
+            orphaned.len()
+        );
+    }
+
+    let mut failed = 0;
+    for name in &orphaned {
+        println!("{} Removing orphaned regions of {}...", "→".bold(), name);
+        for (file, remove) in REGION_FILES {
+            if !owners[file].contains(name) {
+                continue;
+            }
+            match remove(name, &capitalize(name)) {
+                Ok(()) => println!("   {} {}", "✓".green(), file.dimmed()),
+                Err(e) => {
+                    println!("   {} {}: {}", "✗".red(), file, e);
+                    failed += 1;
+                }
+            }
+        }
+    }
+    println!();
+
+    if failed > 0 || inconsistent > orphaned.len() {
+        anyhow::bail!("Some codegen regions still need fixing by hand (see above)");
+    }
+
+    println!("Run {} to verify compilation", "cargo build".cyan());
+    Ok(())
+}
+
+/// Managers owning a `CODEGEN_START` region in a file
+/// Both forms: `// CODEGEN_START[name]: kind` and `// CODEGEN_START: name`
+fn region_owners(content: &str) -> BTreeSet<String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim().strip_prefix("// CODEGEN_START")?;
+            let name = match rest.strip_prefix('[') {
+                Some(tagged) => tagged.split(']').next()?,
+                None => rest.strip_prefix(':')?.trim(),
+            };
+            Some(name.to_string())
+        })
+        .collect()
+}
+
+/// Module a manager's region in managers/mod.rs declares ("cargo" -> "cargo_manager")
+fn declared_module(mod_rs: &str, name: &str) -> Option<String> {
+    let start = mod_rs.find(&format!("// CODEGEN_START[{}]: module", name))?;
+    mod_rs[start..]
+        .lines()
+        .nth(1)?
+        .trim()
+        .strip_prefix("pub mod ")?
+        .strip_suffix(';')
+        .map(str::to_string)
+}
+
+/// Skip the blank separator line `new manager` writes after a region's END marker
+fn past_separator(content: &str, pos: usize) -> usize {
+    let rest = &content[pos.min(content.len())..];
+    match rest.split_once('\n') {
+        Some((line, _)) if line.trim().is_empty() => pos + line.len() + 1,
+        _ => pos,
+    }
+}
+
 fn capitalize(s: &str) -> String {
     let mut chars = s.chars();
     match chars.next() {
@@ -166,8 +329,8 @@ fn remove_from_add_command(name: &str, name_cap: &str) -> Result<()> {
 }
 
 fn remove_from_diff_command(name: &str, name_cap: &str) -> Result<()> {
-    let diff_path = Path::new("src/commands/diff.rs");
-    let content = fs::read_to_string(diff_path).context("Failed to read diff.rs")?;
+    let diff_path = Path::new("src/executor/reconcile.rs");
+    let content = fs::read_to_string(diff_path).context("Failed to read reconcile.rs")?;
 
     // 1. Remove config import from line 1
     // Pattern: ", TestpkgConfig" in the config import
@@ -199,7 +362,7 @@ fn remove_from_diff_command(name: &str, name_cap: &str) -> Result<()> {
 
     let call_start_pos = updated_content.find(&call_start).ok_or_else(|| {
         anyhow::anyhow!(
-            "Could not find CODEGEN_START[{}]: check_call marker in diff.rs",
+            "Could not find CODEGEN_START[{}]: check_call marker in reconcile.rs",
             name
         )
     })?;
@@ -207,13 +370,16 @@ fn remove_from_diff_command(name: &str, name_cap: &str) -> Result<()> {
     let after_call_start = &updated_content[call_start_pos..];
     let call_end_offset = after_call_start.find(&call_end).ok_or_else(|| {
         anyhow::anyhow!(
-            "Could not find CODEGEN_END[{}]: check_call marker in diff.rs",
+            "Could not find CODEGEN_END[{}]: check_call marker in reconcile.rs",
             name
         )
     })?;
 
-    // Include the END marker and newlines
-    let call_end_pos = call_start_pos + call_end_offset + call_end.len() + 1; // +1 for newline
+    // Include the END marker, its newline and the blank separator line
+    let call_end_pos = past_separator(
+        &updated_content,
+        call_start_pos + call_end_offset + call_end.len() + 1,
+    );
 
     // Remove check call block
     let mut updated_content2 = String::new();
@@ -226,7 +392,7 @@ fn remove_from_diff_command(name: &str, name_cap: &str) -> Result<()> {
 
     let fn_start_pos = updated_content2.find(&fn_start).ok_or_else(|| {
         anyhow::anyhow!(
-            "Could not find CODEGEN_START[{}]: check_function marker in diff.rs",
+            "Could not find CODEGEN_START[{}]: check_function marker in reconcile.rs",
             name
         )
     })?;
@@ -234,20 +400,23 @@ fn remove_from_diff_command(name: &str, name_cap: &str) -> Result<()> {
     let after_fn_start = &updated_content2[fn_start_pos..];
     let fn_end_offset = after_fn_start.find(&fn_end).ok_or_else(|| {
         anyhow::anyhow!(
-            "Could not find CODEGEN_END[{}]: check_function marker in diff.rs",
+            "Could not find CODEGEN_END[{}]: check_function marker in reconcile.rs",
             name
         )
     })?;
 
-    // Include the END marker and newlines
-    let fn_end_pos = fn_start_pos + fn_end_offset + fn_end.len() + 1; // +1 for newline
+    // Include the END marker, its newline and the blank separator line
+    let fn_end_pos = past_separator(
+        &updated_content2,
+        fn_start_pos + fn_end_offset + fn_end.len() + 1,
+    );
 
     // Remove function block
     let mut final_content = String::new();
     final_content.push_str(&updated_content2[..fn_start_pos]);
     final_content.push_str(&updated_content2[fn_end_pos..]);
 
-    fs::write(diff_path, final_content).context("Failed to write diff.rs")?;
+    fs::write(diff_path, final_content).context("Failed to write reconcile.rs")?;
 
     Ok(())
 }
@@ -355,8 +524,11 @@ fn remove_config_struct(name: &str, _name_cap: &str) -> Result<()> {
         )
     })?;
 
-    // Include the END marker and newline
-    let field_end_pos = field_start_pos + field_end_offset + field_end.len() + 1; // +1 for newline
+    // Include the END marker, its newline and the blank separator line
+    let field_end_pos = past_separator(
+        &content,
+        field_start_pos + field_end_offset + field_end.len() + 1,
+    );
 
     // Remove field
     let mut updated_content = String::new();
@@ -382,8 +554,11 @@ fn remove_config_struct(name: &str, _name_cap: &str) -> Result<()> {
         )
     })?;
 
-    // Include the END marker and newline
-    let struct_end_pos = struct_start_pos + struct_end_offset + struct_end.len() + 1; // +1 for newline
+    // Include the END marker, its newline and the blank separator line
+    let struct_end_pos = past_separator(
+        &updated_content,
+        struct_start_pos + struct_end_offset + struct_end.len() + 1,
+    );
 
     // Remove struct
     let mut updated_content2 = String::new();
@@ -445,8 +620,8 @@ fn remove_handler_function(name: &str, _name_cap: &str) -> Result<()> {
         )
     })?;
 
-    // Include the END marker and newline
-    let end_pos = start_pos + end_offset + end_marker.len() + 1; // +1 for newline
+    // Include the END marker, its newline and the blank separator line
+    let end_pos = past_separator(&content, start_pos + end_offset + end_marker.len() + 1);
 
     // Remove from start to end
     let mut updated_content = String::new();
@@ -472,8 +647,11 @@ fn remove_handler_function(name: &str, _name_cap: &str) -> Result<()> {
         )
     })?;
 
-    // Include the END marker and newline
-    let match_end_pos = match_start_pos + match_end_offset + match_end.len() + 1; // +1 for newline
+    // Include the END marker, its newline and the blank separator line
+    let match_end_pos = past_separator(
+        &updated_content,
+        match_start_pos + match_end_offset + match_end.len() + 1,
+    );
 
     // Remove match arm
     let mut final_content = String::new();
@@ -572,3 +750,70 @@ fn remove_from_managers_mod(name: &str) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{stdout_of, FakeEnv};
+    use std::process::Command;
+
+    /// Delete the lines of `name`'s `kind` region in `file`
+    fn drop_region(file: &str, name: &str, kind: &str) {
+        let content = fs::read_to_string(file).unwrap();
+        let start = content
+            .find(&format!("// CODEGEN_START[{}]: {}", name, kind))
+            .unwrap();
+        let start = content[..start].rfind('\n').unwrap() + 1;
+        let end_marker = format!("// CODEGEN_END[{}]: {}\n", name, kind);
+        let end = start + content[start..].find(&end_marker).unwrap() + end_marker.len();
+        fs::write(file, format!("{}{}", &content[..start], &content[end..])).unwrap();
+    }
+
+    #[test]
+    fn audit_flags_and_fixes_orphaned_regions() {
+        let Some(stdout) =
+            stdout_of("commands::remove_manager::tests::audit_flags_and_fixes_orphaned_regions")
+        else {
+            // A copy of this tree to break
+            let _env = FakeEnv::new("codegen-audit");
+            let tree = dirs::home_dir().unwrap().join("tree");
+            fs::create_dir(&tree).unwrap();
+            let copied = Command::new("cp")
+                .arg("-R")
+                .arg("src")
+                .arg(&tree)
+                .status()
+                .unwrap();
+            assert!(copied.success());
+            std::env::set_current_dir(&tree).unwrap();
+
+            audit(false).unwrap();
+
+            // yarn's file is gone but its regions remain; pnpm lost one region
+            fs::remove_file("src/managers/yarn.rs").unwrap();
+            drop_region("src/commands/add.rs", "pnpm", "match_arm");
+
+            let err = audit(false).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "2 manager(s) with inconsistent codegen regions (1 orphaned, --fix removes them)"
+            );
+
+            // --fix removes yarn's regions; pnpm still needs a hand
+            let err = audit(true).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Some codegen regions still need fixing by hand (see above)"
+            );
+            for (file, _) in REGION_FILES {
+                assert!(!region_owners(&fs::read_to_string(file).unwrap()).contains("yarn"));
+            }
+            return;
+        };
+
+        assert!(stdout.contains("✗ yarn\n"));
+        assert!(stdout.contains("      module declared but src/managers/yarn.rs doesn't exist\n"));
+        assert!(stdout.contains("✗ pnpm\n      no region in src/commands/add.rs\n"));
+        assert!(stdout.contains("✓ cargo\n"));
+    }
+}
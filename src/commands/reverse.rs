@@ -0,0 +1,277 @@
+use crate::config::load_config_auto;
+use crate::executor::list_cache;
+use crate::managers::asdf::AsdfManager;
+use crate::managers::brew::BrewManager;
+use crate::managers::cargo_manager::CargoManager;
+use crate::managers::mas::MasManager;
+use crate::managers::npm::NpmManager;
+use crate::managers::pip::PipManager;
+use crate::managers::pnpm::PnpmManager;
+use crate::managers::yarn::YarnManager;
+use crate::managers::{InstallResult, Manager};
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Packages of one list, split by whether they're installed right now
+struct Target {
+    label: &'static str,
+    installed: Vec<String>,
+    not_installed: Vec<String>,
+}
+
+impl Target {
+    fn new(label: &'static str, packages: &[String], installed: &HashSet<String>) -> Self {
+        let (installed, not_installed) = packages
+            .iter()
+            .cloned()
+            .partition(|pkg| installed.contains(pkg));
+        Self {
+            label,
+            installed,
+            not_installed,
+        }
+    }
+}
+
+/// `macup apply --reverse <section>`: uninstall every package the section declares
+pub fn run(
+    config_path: Option<&Path>,
+    section: &str,
+    yes: bool,
+    dry_run: bool,
+    no_dry_run: bool,
+) -> Result<()> {
+    let (_config_path, config) = load_config_auto(config_path)?;
+    let dry_run = config.effective_dry_run(dry_run, no_dry_run);
+
+    // Casks aren't behind the Manager trait, so brew splits into two targets
+    let brew = BrewManager::new(1);
+    let manager: Box<dyn Manager> = match section {
+        "brew" => Box::new(BrewManager::new(1)),
+        "npm" => Box::new(NpmManager::new(1)),
+        "cargo" => Box::new(CargoManager::new(1)),
+        "asdf" => Box::new(AsdfManager::new(1)),
+        "pnpm" => Box::new(PnpmManager::new(1)),
+        "yarn" => Box::new(YarnManager::new(1)),
+        "pip" => Box::new(PipManager::new(1)),
+        "mas" => Box::new(MasManager::new(1)),
+        "install" | "system" => {
+            anyhow::bail!("--reverse: [{}] has no packages to uninstall", section)
+        }
+        _ => anyhow::bail!(
+            "--reverse: unknown section '{}'{}",
+            section,
+            crate::utils::did_you_mean(
+                section,
+                &["brew", "npm", "cargo", "asdf", "pnpm", "yarn", "pip", "mas"]
+            )
+        ),
+    };
+
+    let packages: Vec<String> = match section {
+        "brew" => config
+            .brew
+            .as_ref()
+            .map(|brew| brew.formulae.clone())
+            .unwrap_or_default(),
+        "npm" => config
+            .npm
+            .as_ref()
+            .map(|npm| npm.global.clone())
+            .unwrap_or_default(),
+        "cargo" => config
+            .cargo
+            .as_ref()
+            .map(|cargo| cargo.packages.clone())
+            .unwrap_or_default(),
+        "asdf" => config
+            .asdf
+            .as_ref()
+            .map(|asdf| asdf.tools.iter().map(|tool| tool.spec()).collect())
+            .unwrap_or_default(),
+        "pnpm" => config
+            .pnpm
+            .as_ref()
+            .map(|pnpm| pnpm.packages.clone())
+            .unwrap_or_default(),
+        "yarn" => config
+            .yarn
+            .as_ref()
+            .map(|yarn| yarn.packages.clone())
+            .unwrap_or_default(),
+        "mas" => config
+            .mas
+            .as_ref()
+            .map(|mas| mas.apps.iter().map(|app| app.id.to_string()).collect())
+            .unwrap_or_default(),
+        _ => config
+            .pip
+            .as_ref()
+            .map(|pip| pip.packages.clone())
+            .unwrap_or_default(),
+    };
+
+    // Formulae are matched against `brew list`, not by their binaries
+    let installed = if section == "brew" {
+        let listed = brew.list_formulae()?;
+        packages
+            .iter()
+            .filter(|formula| BrewManager::has_formula(&listed, formula))
+            .cloned()
+            .collect()
+    } else {
+        manager.installed_set(&packages)?
+    };
+    let mut targets = vec![Target::new(
+        match section {
+            "brew" => "formulae",
+            "mas" => "apps",
+            _ => "packages",
+        },
+        &packages,
+        &installed,
+    )];
+
+    let casks = config
+        .brew
+        .as_ref()
+        .filter(|_| section == "brew")
+        .map(|brew| brew.casks.clone())
+        .unwrap_or_default();
+    if !casks.is_empty() {
+        let listed = brew.list_casks()?;
+        let installed = casks
+            .iter()
+            .filter(|cask| BrewManager::has_cask(&listed, cask))
+            .cloned()
+            .collect();
+        targets.push(Target::new("casks", &casks, &installed));
+    }
+
+    let to_remove: usize = targets.iter().map(|t| t.installed.len()).sum();
+    if to_remove == 0 {
+        println!(
+            "Nothing from [{}] is installed - nothing to uninstall",
+            section
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!(
+            "Uninstalling {} package(s) declared in [{}]:",
+            to_remove, section
+        )
+        .bright_cyan()
+        .bold()
+    );
+    for target in &targets {
+        for pkg in &target.installed {
+            println!("  - {} ({})", pkg, target.label);
+        }
+    }
+    println!();
+
+    if dry_run {
+        println!("{}", "[DRY RUN] Nothing was uninstalled".yellow());
+        return Ok(());
+    }
+
+    if !yes {
+        crate::utils::require_terminal("--reverse", "pass --yes to uninstall without confirming")?;
+        let confirmed = inquire::Confirm::new(&format!("Uninstall these from [{}]?", section))
+            .with_default(false)
+            .prompt()?;
+        if !confirmed {
+            println!("{}", "Uninstall cancelled.".yellow());
+            return Ok(());
+        }
+    }
+
+    list_cache::invalidate();
+
+    let mut failed = 0;
+    for target in &targets {
+        if target.installed.is_empty() && target.not_installed.is_empty() {
+            continue;
+        }
+
+        let result = if target.label == "casks" {
+            brew.uninstall_casks(&target.installed)
+        } else {
+            manager.uninstall_packages(&target.installed)?
+        };
+        print_reverse_result(target, &result);
+        failed += result.failed.len();
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} package(s) could not be uninstalled", failed);
+    }
+
+    Ok(())
+}
+
+fn print_reverse_result(target: &Target, result: &InstallResult) {
+    println!("{}", target.label.bold());
+    if !result.success.is_empty() {
+        println!(
+            "  {} {} uninstalled: {}",
+            "✓".green(),
+            result.success.len(),
+            result.success.join(", ")
+        );
+    }
+    if !target.not_installed.is_empty() {
+        println!(
+            "  ⊘ {} not installed: {}",
+            target.not_installed.len(),
+            target.not_installed.join(", ")
+        );
+    }
+    if !result.failed.is_empty() {
+        println!("  {} {} failed:", "✗".red(), result.failed.len());
+        for (pkg, err, _) in &result.failed {
+            println!("    - {}: {}", pkg, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::FakeEnv;
+
+    #[test]
+    fn reverse_cargo_uninstalls_each_installed_crate() {
+        let env = FakeEnv::new("reverse-cargo");
+        env.bin("cargo", "exit 0")
+            .bin("rg", "exit 0")
+            .bin("bat", "exit 0");
+
+        let config = dirs::home_dir().unwrap().join("macup.toml");
+        std::fs::write(
+            &config,
+            r#"
+            [cargo]
+            packages = ["ripgrep:rg", "bat", "zz-not-installed"]
+            "#,
+        )
+        .unwrap();
+
+        run(Some(&config), "cargo", true, false, false).unwrap();
+
+        let uninstalls: Vec<_> = env
+            .calls()
+            .into_iter()
+            .filter(|call| call.starts_with("cargo uninstall"))
+            .collect();
+        assert_eq!(
+            uninstalls,
+            ["cargo uninstall ripgrep", "cargo uninstall bat"]
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use crate::config::{load_config_auto, validate_config};
+use crate::managers::install::InstallManager;
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+
+/// Run one `[[install.scripts]]` entry, outside a full apply
+/// `force` runs it even when it already looks installed (like `apply --rerun`)
+pub fn run(config_path: Option<&Path>, name: &str, force: bool) -> Result<()> {
+    let (_config_path, config) = load_config_auto(config_path)?;
+    validate_config(&config, false)?;
+
+    let Some(script) = config.install_script(name) else {
+        let names = config.install_script_names();
+        if names.is_empty() {
+            anyhow::bail!("No install script named '{}' (config has none)", name);
+        }
+        anyhow::bail!(
+            "No install script named '{}' (available: {})",
+            name,
+            names.join(", ")
+        );
+    };
+
+    let rerun = if force {
+        vec![script.name.clone()]
+    } else {
+        vec![]
+    };
+    let install_mgr = InstallManager::with_rerun(&rerun);
+
+    if !install_mgr.needs_run(script)? {
+        println!(
+            "{}",
+            format!(
+                "✓ {} already installed (use --force to run it again)",
+                script.name
+            )
+            .green()
+        );
+        return Ok(());
+    }
+
+    if !install_mgr.apply_script(script)? {
+        anyhow::bail!("{} failed", script.name);
+    }
+
+    println!("{}", format!("✓ {} done", script.name).green());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::FakeEnv;
+
+    fn write_config(home: &Path) -> std::path::PathBuf {
+        let path = home.join("macup.toml");
+        std::fs::write(
+            &path,
+            r#"
+[[install.scripts]]
+name = "setup"
+command = "zz-setup"
+once = true
+
+[[install.scripts]]
+name = "other"
+command = "true"
+check = "true"
+"#,
+        )
+        .unwrap();
+        path
+    }
+
+    #[test]
+    fn runs_the_named_script_once_unless_forced() {
+        let env = FakeEnv::new("run-script");
+        env.bin("zz-setup", "exit 0");
+        let path = write_config(&dirs::home_dir().unwrap());
+
+        run(Some(&path), "setup", false).unwrap();
+        run(Some(&path), "setup", false).unwrap();
+        assert_eq!(env.calls(), ["zz-setup "]);
+
+        run(Some(&path), "setup", true).unwrap();
+        assert_eq!(env.calls().len(), 2);
+    }
+
+    #[test]
+    fn unknown_script_lists_the_available_names() {
+        let _env = FakeEnv::new("run-script-unknown");
+        let path = write_config(&dirs::home_dir().unwrap());
+
+        let err = run(Some(&path), "stup", false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No install script named 'stup' (available: setup, other)"
+        );
+    }
+}
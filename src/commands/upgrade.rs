@@ -0,0 +1,320 @@
+use crate::config::{load_config_auto, Config};
+use crate::executor::list_cache;
+use crate::managers::brew::BrewManager;
+use crate::managers::cargo_manager::CargoManager;
+use crate::managers::mas::MasManager;
+use crate::managers::npm::NpmManager;
+use crate::managers::pnpm::PnpmManager;
+use crate::managers::yarn::YarnManager;
+use crate::managers::{same_package, FailureKind, InstallResult, Manager, ManagerMetadata};
+use crate::utils;
+use anyhow::Result;
+use colored::Colorize;
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Declared packages of one list, to upgrade with one manager
+struct Target {
+    section: &'static str,
+    label: &'static str,
+    manager: Box<dyn Manager + Sync>,
+    packages: Vec<String>,
+}
+
+/// `macup upgrade [packages...]`: bring installed, declared packages to their latest version
+pub fn run(
+    config_path: Option<&Path>,
+    packages: Vec<String>,
+    dry_run: bool,
+    no_dry_run: bool,
+) -> Result<()> {
+    let (_config_path, config) = load_config_auto(config_path)?;
+    config.settings.validate()?;
+    let dry_run = config.effective_dry_run(dry_run, no_dry_run);
+
+    let mut targets = collect_targets(&config);
+
+    // Upgrade only the named packages, all of which must be declared
+    if !packages.is_empty() {
+        let mut matched = HashSet::new();
+        for target in &mut targets {
+            target.packages.retain(|entry| {
+                match packages
+                    .iter()
+                    .find(|pkg| declares(&config, target.section, entry, pkg))
+                {
+                    Some(pkg) => {
+                        matched.insert(pkg.clone());
+                        true
+                    }
+                    None => false,
+                }
+            });
+        }
+
+        let missing: Vec<_> = packages
+            .iter()
+            .filter(|pkg| !matched.contains(*pkg))
+            .map(|pkg| pkg.as_str())
+            .collect();
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "Not declared in [brew], [npm], [pnpm], [yarn], [cargo] or [mas]: {}",
+                missing.join(", ")
+            );
+        }
+    }
+    targets.retain(|target| !target.packages.is_empty());
+
+    utils::set_install_limit(config.settings.global_max_parallel);
+    utils::set_suppress_update_checks(config.settings.suppress_update_checks);
+
+    println!("{}", "=".repeat(60).bright_blue());
+    println!("{}", "macup upgrade".bright_blue().bold());
+    println!("{}", "=".repeat(60).bright_blue());
+    println!();
+
+    if !dry_run {
+        list_cache::invalidate();
+    }
+
+    let mut failed = 0;
+    let mut upgraded = 0;
+    for target in &targets {
+        println!(
+            "{}",
+            format!("[{}] {}", target.section, target.label)
+                .bright_cyan()
+                .bold()
+        );
+
+        // Sections whose runtime is missing have nothing installed to upgrade
+        let runtime = ManagerMetadata::get_by_name(target.section)
+            .map_or(("brew", "Homebrew"), |meta| {
+                (meta.runtime_command, meta.runtime_name)
+            });
+        if !utils::command_exists(runtime.0) {
+            println!("  ⊘ {} not installed - skipping", runtime.1);
+            println!();
+            continue;
+        }
+
+        let installed = installed_packages(target)?;
+        let not_installed: Vec<_> = target
+            .packages
+            .iter()
+            .filter(|pkg| !installed.contains(*pkg))
+            .cloned()
+            .collect();
+        if !not_installed.is_empty() {
+            println!(
+                "  ⊘ {} not installed (run 'macup apply'): {}",
+                not_installed.len(),
+                not_installed.join(", ")
+            );
+        }
+
+        let (to_upgrade, pinned): (Vec<String>, Vec<String>) = target
+            .packages
+            .iter()
+            .filter(|pkg| installed.contains(*pkg))
+            .cloned()
+            .partition(|pkg| {
+                target.label == "casks" || target.manager.describe_upgrade(pkg).is_some()
+            });
+        if !pinned.is_empty() {
+            println!(
+                "  ⊘ {} pinned to a version: {}",
+                pinned.len(),
+                pinned.join(", ")
+            );
+        }
+
+        if dry_run {
+            let brew = BrewManager::new(1);
+            for pkg in &to_upgrade {
+                let argv = if target.label == "casks" {
+                    brew.describe_cask_upgrade(pkg)
+                } else {
+                    target.manager.describe_upgrade(pkg).unwrap_or_default()
+                };
+                println!("  {} {}", "would run:".dimmed(), argv.join(" "));
+            }
+            println!();
+            continue;
+        }
+
+        let max_parallel = config.effective_parallelism(target.section, None);
+        let result = upgrade_target(target, &to_upgrade, max_parallel)?;
+        print_upgrade_result(&result);
+        println!();
+        upgraded += result.success.len();
+        failed += result.failed.len();
+    }
+
+    if dry_run {
+        println!("{}", "[DRY RUN] Nothing was upgraded".yellow());
+        return Ok(());
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} package(s) could not be upgraded", failed);
+    }
+    println!("{}", format!("✓ Upgraded {} package(s)", upgraded).green());
+
+    Ok(())
+}
+
+/// Every list of the config macup knows how to upgrade
+fn collect_targets(config: &Config) -> Vec<Target> {
+    let mut targets = Vec::new();
+
+    if let Some(brew) = &config.brew {
+        // Pinned formulae stay at their installed version
+        let formulae = brew
+            .formulae
+            .iter()
+            .filter(|spec| !brew.pin.iter().any(|pin| same_package("brew", spec, pin)))
+            .cloned()
+            .collect();
+        targets.push(Target {
+            section: "brew",
+            label: "formulae",
+            manager: Box::new(BrewManager::new(1)),
+            packages: formulae,
+        });
+        // Includes the standalone [casks] list, folded in on load
+        targets.push(Target {
+            section: "brew",
+            label: "casks",
+            manager: Box::new(BrewManager::new(1)),
+            packages: brew.casks.clone(),
+        });
+    }
+    if let Some(npm) = &config.npm {
+        targets.push(Target {
+            section: "npm",
+            label: "packages",
+            manager: Box::new(NpmManager::new(1)),
+            packages: npm.global.clone(),
+        });
+    }
+    if let Some(pnpm) = &config.pnpm {
+        targets.push(Target {
+            section: "pnpm",
+            label: "packages",
+            manager: Box::new(PnpmManager::new(1)),
+            packages: pnpm.packages.clone(),
+        });
+    }
+    if let Some(yarn) = &config.yarn {
+        targets.push(Target {
+            section: "yarn",
+            label: "packages",
+            manager: Box::new(YarnManager::new(1)),
+            packages: yarn.packages.clone(),
+        });
+    }
+    if let Some(cargo) = &config.cargo {
+        targets.push(Target {
+            section: "cargo",
+            label: "packages",
+            manager: Box::new(CargoManager::new(1)),
+            packages: cargo.packages.clone(),
+        });
+    }
+    if let Some(mas) = &config.mas {
+        targets.push(Target {
+            section: "mas",
+            label: "apps",
+            manager: Box::new(MasManager::new(1)),
+            packages: mas.apps.iter().map(|app| app.id.to_string()).collect(),
+        });
+    }
+
+    targets
+}
+
+/// Whether `entry` of `section` is the package the user named
+/// mas apps can be named by id or by name
+fn declares(config: &Config, section: &str, entry: &str, requested: &str) -> bool {
+    if section == "mas" {
+        return entry == requested
+            || config.mas.as_ref().is_some_and(|mas| {
+                mas.apps.iter().any(|app| {
+                    app.id.to_string() == entry && app.name.eq_ignore_ascii_case(requested)
+                })
+            });
+    }
+    same_package(section, entry, requested)
+}
+
+/// Which of the target's packages are installed
+fn installed_packages(target: &Target) -> Result<HashSet<String>> {
+    if target.label == "casks" {
+        let listed = BrewManager::new(1).list_casks()?;
+        return Ok(target
+            .packages
+            .iter()
+            .filter(|cask| BrewManager::has_cask(&listed, cask))
+            .cloned()
+            .collect());
+    }
+    target.manager.installed_set(&target.packages)
+}
+
+/// Upgrade `packages` of the target, `max_parallel` at a time
+fn upgrade_target(
+    target: &Target,
+    packages: &[String],
+    max_parallel: usize,
+) -> Result<InstallResult> {
+    let brew = BrewManager::new(1);
+
+    let results: Vec<_> = utils::install_pool(max_parallel)?.install(|| {
+        packages
+            .par_iter()
+            .map(|pkg| {
+                let mut output = String::new();
+                let res = if target.label == "casks" {
+                    brew.upgrade_cask(pkg, Some(&mut output))
+                } else {
+                    target.manager.upgrade_package(pkg, Some(&mut output))
+                };
+                (pkg.clone(), res, output)
+            })
+            .collect()
+    });
+
+    let mut result = InstallResult::default();
+    for (pkg, res, output) in results {
+        utils::flush_output(&pkg, &output, res.is_err());
+        match res {
+            Ok(()) => result.success.push(pkg),
+            Err(e) => {
+                let kind = FailureKind::classify(&e, &output);
+                result.failed.push((pkg, e.to_string(), kind));
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+fn print_upgrade_result(result: &InstallResult) {
+    if !result.success.is_empty() {
+        println!(
+            "  {} {} upgraded: {}",
+            "✓".green(),
+            result.success.len(),
+            result.success.join(", ")
+        );
+    }
+    if !result.failed.is_empty() {
+        println!("  {} {} failed:", "✗".red(), result.failed.len());
+        for (pkg, err, _) in &result.failed {
+            println!("    - {}: {}", pkg, err);
+        }
+    }
+}
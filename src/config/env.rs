@@ -0,0 +1,212 @@
+use anyhow::Result;
+use toml_edit::{DocumentMut, Item, Value};
+
+/// Shell commands are left alone: `sh -c` expands them itself, and they may
+/// use variables that only exist inside the script
+const SHELL_FIELDS: &[&str] = &[
+    "install.scripts.command",
+    "install.scripts.check",
+    "system.commands",
+];
+
+/// Expand `$VAR`, `${VAR}` and `${VAR:-default}` in every string of the config
+/// (string arrays and inline tables included), except shell commands
+/// An undefined variable without a default is an error; `$$` is a literal `$`
+pub fn expand_env_vars(doc: &mut DocumentMut) -> Result<()> {
+    for (key, item) in doc.iter_mut() {
+        expand_item(&key.to_string(), item)?;
+    }
+    Ok(())
+}
+
+fn expand_item(path: &str, item: &mut Item) -> Result<()> {
+    match item {
+        Item::Value(value) => expand_value(path, value),
+        Item::Table(table) => {
+            for (key, item) in table.iter_mut() {
+                expand_item(&format!("{}.{}", path, key), item)?;
+            }
+            Ok(())
+        }
+        Item::ArrayOfTables(array) => {
+            for table in array.iter_mut() {
+                for (key, item) in table.iter_mut() {
+                    expand_item(&format!("{}.{}", path, key), item)?;
+                }
+            }
+            Ok(())
+        }
+        Item::None => Ok(()),
+    }
+}
+
+/// Whether `path` is one of SHELL_FIELDS, at the top level or in a profile
+fn is_shell_field(path: &str) -> bool {
+    let path = path
+        .strip_prefix("profiles.")
+        .and_then(|rest| rest.split_once('.'))
+        .map_or(path, |(_, field)| field);
+    SHELL_FIELDS.contains(&path)
+}
+
+fn expand_value(path: &str, value: &mut Value) -> Result<()> {
+    if is_shell_field(path) {
+        return Ok(());
+    }
+
+    match value {
+        Value::String(text) => {
+            let expanded = expand(text.value(), path)?;
+            if expanded != *text.value() {
+                *value = Value::from(expanded);
+            }
+            Ok(())
+        }
+        Value::Array(array) => {
+            for value in array.iter_mut() {
+                expand_value(path, value)?;
+            }
+            Ok(())
+        }
+        Value::InlineTable(table) => {
+            for (key, value) in table.iter_mut() {
+                expand_value(&format!("{}.{}", path, key), value)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Expand one string; `location` (e.g. "brew.formulae") names it in errors
+pub fn expand(text: &str, location: &str) -> Result<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(dollar) = rest.find('$') {
+        result.push_str(&rest[..dollar]);
+        rest = &rest[dollar + 1..];
+
+        if let Some(after) = rest.strip_prefix('$') {
+            result.push('$');
+            rest = after;
+        } else if let Some(braced) = rest.strip_prefix('{') {
+            let Some(end) = braced.find('}') else {
+                anyhow::bail!("Unclosed '${{' in {}: {}", location, text);
+            };
+            let (name, default) = match braced[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&braced[..end], None),
+            };
+            result.push_str(&lookup(name, default, location)?);
+            rest = &braced[end + 1..];
+        } else {
+            let len = rest
+                .char_indices()
+                .find(|(i, c)| {
+                    !(c.is_ascii_alphabetic() || *c == '_' || (*i > 0 && c.is_ascii_digit()))
+                })
+                .map_or(rest.len(), |(i, _)| i);
+            if len == 0 {
+                // Not a variable (e.g. "$5" or a trailing "$")
+                result.push('$');
+            } else {
+                result.push_str(&lookup(&rest[..len], None, location)?);
+                rest = &rest[len..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+fn lookup(name: &str, default: Option<&str>, location: &str) -> Result<String> {
+    match (std::env::var(name), default) {
+        (Ok(value), _) => Ok(value),
+        (Err(_), Some(default)) => Ok(default.to_string()),
+        (Err(_), None) => anyhow::bail!(
+            "Environment variable {} is not set (used in {}; write ${{{}:-default}} for a fallback)",
+            name,
+            location,
+            name
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_shell_fields_alone_in_profiles_too() {
+        let mut doc: DocumentMut = r#"
+            [[install.scripts]]
+            name = "top"
+            command = "echo $SCRIPT_ONLY_VAR"
+
+            [[profiles.work.install.scripts]]
+            name = "work"
+            command = "echo $SCRIPT_ONLY_VAR"
+            check = "test -d $SCRIPT_ONLY_VAR"
+            "#
+        .parse()
+        .unwrap();
+
+        expand_env_vars(&mut doc).unwrap();
+        let script = &doc["profiles"]["work"]["install"]["scripts"][0];
+        assert_eq!(script["command"].as_str(), Some("echo $SCRIPT_ONLY_VAR"));
+        assert_eq!(script["check"].as_str(), Some("test -d $SCRIPT_ONLY_VAR"));
+    }
+
+    #[test]
+    fn expands_variables_in_strings_and_arrays() {
+        std::env::set_var("MACUP_TEST_TAP", "acme/tools");
+        std::env::set_var("MACUP_TEST_CASK", "cask");
+        let mut doc: DocumentMut = r#"
+            [brew]
+            taps = ["${MACUP_TEST_TAP}", "homebrew/$MACUP_TEST_CASK"]
+            formulae = ["$MACUP_TEST_TAP-cli", "price$$5"]
+            "#
+        .parse()
+        .unwrap();
+
+        expand_env_vars(&mut doc).unwrap();
+        assert_eq!(
+            doc["brew"]["taps"].to_string().trim(),
+            r#"["acme/tools", "homebrew/cask"]"#
+        );
+        assert_eq!(
+            doc["brew"]["formulae"].to_string().trim(),
+            r#"["acme/tools-cli", "price$5"]"#
+        );
+    }
+
+    #[test]
+    fn default_applies_only_when_the_variable_is_unset() {
+        std::env::set_var("MACUP_TEST_SET", "from-env");
+        assert_eq!(
+            expand("${MACUP_TEST_SET:-fallback}", "npm.registry").unwrap(),
+            "from-env"
+        );
+        assert_eq!(
+            expand(
+                "${MACUP_TEST_NEVER_SET:-https://registry.npmjs.org}",
+                "npm.registry"
+            )
+            .unwrap(),
+            "https://registry.npmjs.org"
+        );
+    }
+
+    #[test]
+    fn undefined_variable_without_a_default_is_an_error() {
+        let err = expand("${MACUP_TEST_NEVER_SET}/x", "npm.registry").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Environment variable MACUP_TEST_NEVER_SET is not set (used in npm.registry; \
+             write ${MACUP_TEST_NEVER_SET:-default} for a fallback)"
+        );
+        assert!(expand("${MACUP_TEST_NEVER_SET", "npm.registry").is_err());
+    }
+}
@@ -1,13 +1,24 @@
+use super::env::expand_env_vars;
+use super::merge::MergePolicy;
+use super::migrate::migrate;
+use super::validator::{check_manager_wiring, check_unknown_keys};
 use super::Config;
 use anyhow::{Context, Result};
 use std::fs;
 use std::path::{Path, PathBuf};
+use toml_edit::DocumentMut;
+
+/// Fragment that mutating commands write to when the config is a directory
+pub const LOCAL_FRAGMENT: &str = "00-local.toml";
 
 /// Find config file in order of priority:
-/// 1. Explicit --config flag path
+/// 1. Explicit --config/--config-dir flag path
 /// 2. ./macup.toml (current directory)
 /// 3. ~/.config/macup/macup.toml
-/// 4. ~/.macup.toml
+/// 4. ~/.config/macup/conf.d/ (directory of fragments)
+/// 5. ~/.macup.toml
+///
+/// The result may be a directory of `*.toml` fragments (see `config_fragments`)
 pub fn find_config_file(explicit_path: Option<&Path>) -> Result<PathBuf> {
     // 1. Explicit path
     if let Some(path) = explicit_path {
@@ -29,9 +40,15 @@ pub fn find_config_file(explicit_path: Option<&Path>) -> Result<PathBuf> {
         if config_path.exists() {
             return Ok(config_path);
         }
+
+        // 4. ~/.config/macup/conf.d/
+        let fragments_dir = config_dir.join("macup/conf.d");
+        if fragments_dir.is_dir() {
+            return Ok(fragments_dir);
+        }
     }
 
-    // 4. ~/.macup.toml
+    // 5. ~/.macup.toml
     if let Some(home_dir) = dirs::home_dir() {
         let home_config = home_dir.join(".macup.toml");
         if home_config.exists() {
@@ -43,19 +60,69 @@ pub fn find_config_file(explicit_path: Option<&Path>) -> Result<PathBuf> {
         "No config file found. Searched:\n\
          - ./macup.toml\n\
          - ~/.config/macup/macup.toml\n\
+         - ~/.config/macup/conf.d/\n\
          - ~/.macup.toml"
     );
 }
 
 /// Load and parse config file
+/// Older config versions are upgraded in memory (see `macup migrate` to persist)
+/// A directory is loaded as the merge of its fragments (see `merge_fragments`)
 pub fn load_config(path: &Path) -> Result<Config> {
+    let mut config = if path.is_dir() {
+        merge_fragments(path)?
+    } else {
+        let (doc, changes) = load_migrated_document(path)?;
+        log_migration(path, &changes);
+        parse_document(doc, path)?.0
+    };
+
+    config.merge_standalone_casks();
+    Ok(config)
+}
+
+fn log_migration(path: &Path, changes: &[String]) {
+    if !changes.is_empty() {
+        log::debug!(
+            "Config {} migrated in memory ({} change(s)), run 'macup migrate' to persist",
+            path.display(),
+            changes.len()
+        );
+    }
+}
+
+/// Check, expand and parse one config document (a file or a fragment)
+/// Also returns the document's table as written, for `Config::merge`
+fn parse_document(mut doc: DocumentMut, path: &Path) -> Result<(Config, toml::Table)> {
+    check_unknown_keys(&doc).with_context(|| format!("Invalid config: {}", path.display()))?;
+    expand_env_vars(&mut doc).with_context(|| format!("Invalid config: {}", path.display()))?;
+
+    let text = doc.to_string();
+    let config: Config = toml::from_str(&text)
+        .with_context(|| format!("Failed to parse TOML config: {}", path.display()))?;
+    let table: toml::Table = toml::from_str(&text)
+        .with_context(|| format!("Failed to parse TOML config: {}", path.display()))?;
+
+    check_manager_wiring(&doc, &config)
+        .with_context(|| format!("Invalid config: {}", path.display()))?;
+
+    Ok((config, table))
+}
+
+/// Read config file as an editable document and upgrade it to the current version
+/// Returns the migrated document and the list of applied changes
+pub fn load_migrated_document(path: &Path) -> Result<(DocumentMut, Vec<String>)> {
     let content = fs::read_to_string(path)
         .with_context(|| format!("Failed to read config: {}", path.display()))?;
 
-    let config: Config = toml::from_str(&content)
+    let mut doc = content
+        .parse::<DocumentMut>()
         .with_context(|| format!("Failed to parse TOML config: {}", path.display()))?;
 
-    Ok(config)
+    let changes = migrate(&mut doc)
+        .with_context(|| format!("Failed to migrate config: {}", path.display()))?;
+
+    Ok((doc, changes))
 }
 
 /// Load config with automatic discovery
@@ -64,3 +131,335 @@ pub fn load_config_auto(explicit_path: Option<&Path>) -> Result<(PathBuf, Config
     let config = load_config(&path)?;
     Ok((path, config))
 }
+
+/// Layer the `[profiles.<name>]` overlay over the base config (see `Config::merge`):
+/// its sections add to the base's lists (or replace them, with `lists = "replace"`)
+/// and override its other values
+pub fn apply_profile(mut config: Config, name: &str) -> Result<Config> {
+    let profiles = std::mem::take(&mut config.profiles);
+    let Some(profile) = profiles.get(name) else {
+        if profiles.is_empty() {
+            anyhow::bail!(
+                "Unknown profile '{}': the config defines no [profiles]",
+                name
+            );
+        }
+        let names: Vec<&str> = profiles.keys().map(|name| name.as_str()).collect();
+        anyhow::bail!(
+            "Unknown profile '{}' (defined: {}){}",
+            name,
+            names.join(", "),
+            crate::utils::did_you_mean(name, &names)
+        );
+    };
+
+    let policy = MergePolicy {
+        arrays: profile.lists,
+    };
+    let mut merged = Config::merge(&config, &profile.sections, policy)
+        .with_context(|| format!("Failed to apply profile '{}'", name))?;
+    // The profile may bring its own standalone [casks]
+    merged.merge_standalone_casks();
+    Ok(merged)
+}
+
+/// Render a loaded config back to TOML
+/// Defaults are written out, and [casks] appears merged into brew.casks
+pub fn to_toml(config: &Config) -> Result<String> {
+    toml::to_string_pretty(config).context("Failed to serialize config")
+}
+
+/// `*.toml` fragments in a config directory, sorted by file name
+pub fn config_fragments(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut fragments: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read config directory: {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+
+    fragments.sort();
+    Ok(fragments)
+}
+
+/// File that mutating commands (add, import, config set) edit
+/// For a config directory this is its `00-local.toml` fragment, which may not
+/// exist yet - read it with `read_config_text`
+pub fn config_write_target(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        path.join(LOCAL_FRAGMENT)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Read a config file for editing; a missing file reads as empty
+pub fn read_config_text(path: &Path) -> Result<String> {
+    match fs::read_to_string(path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        result => result.with_context(|| format!("Failed to read config: {}", path.display())),
+    }
+}
+
+/// Table and key new casks are written to: the standalone `[casks] list`
+/// when the config uses one, `brew.casks` otherwise
+pub fn casks_location(doc: &DocumentMut) -> (&'static str, &'static str) {
+    if doc.contains_key("casks") {
+        ("casks", "list")
+    } else {
+        ("brew", "casks")
+    }
+}
+
+/// Move formulae that turned out to be casks from `brew.formulae` to `brew.casks`
+/// (`apply --auto-fix`). Every fragment of a config directory is checked, since
+/// the formula can be declared in any of them. Returns the number of entries moved
+pub fn move_formulae_to_casks(path: &Path, names: &[String]) -> Result<usize> {
+    let files = if path.is_dir() {
+        config_fragments(path)?
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut moved = 0;
+    for file in files {
+        let mut doc = read_config_text(&file)?
+            .parse::<DocumentMut>()
+            .with_context(|| format!("Failed to parse TOML config: {}", file.display()))?;
+
+        let Some(formulae) = doc
+            .get_mut("brew")
+            .and_then(|brew| brew.get_mut("formulae"))
+            .and_then(|item| item.as_array_mut())
+        else {
+            continue;
+        };
+
+        // Entries may be "package:binary" - the cask is the package part
+        let mut found = Vec::new();
+        formulae.retain(|entry| {
+            let spec = entry.as_str().unwrap_or("");
+            let pkg = spec.split(':').next().unwrap_or(spec).trim();
+            let is_cask = names.iter().any(|name| name == pkg);
+            if is_cask {
+                found.push(pkg.to_string());
+            }
+            !is_cask
+        });
+        if found.is_empty() {
+            continue;
+        }
+        moved += found.len();
+
+        let (section, key) = casks_location(&doc);
+        if doc[section].get(key).is_none() {
+            doc[section][key] = toml_edit::value(toml_edit::Array::new());
+        }
+        let casks = doc[section][key]
+            .as_array_mut()
+            .context(format!("Expected array at [{}.{}]", section, key))?;
+        for name in &found {
+            if !casks.iter().any(|v| v.as_str() == Some(name)) {
+                casks.push(name.as_str());
+            }
+        }
+
+        fs::write(&file, doc.to_string())
+            .with_context(|| format!("Failed to write config: {}", file.display()))?;
+    }
+
+    Ok(moved)
+}
+
+/// Merge every fragment of a config directory into one config, in file name order
+/// (see `Config::merge`): tables merge key by key, arrays are concatenated
+/// (skipping duplicates), other values from later fragments override earlier ones
+fn merge_fragments(dir: &Path) -> Result<Config> {
+    let fragments = config_fragments(dir)?;
+    if fragments.is_empty() {
+        anyhow::bail!("No *.toml fragments in config directory: {}", dir.display());
+    }
+
+    let mut merged: Config = toml::from_str("").context("Failed to build default config")?;
+
+    for fragment in &fragments {
+        let (doc, changes) = load_migrated_document(fragment)?;
+        log_migration(fragment, &changes);
+
+        let (_, table) = parse_document(doc, fragment)?;
+        merged = Config::merge(&merged, &table, MergePolicy::default())
+            .with_context(|| format!("Failed to merge config fragment: {}", fragment.display()))?;
+    }
+
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_overrides_only_the_keys_it_sets() {
+        let config: Config = toml::from_str(
+            r#"
+            [settings]
+            fail_fast = true
+            max_parallel = 2
+
+            [npm]
+            global = ["base-tool"]
+
+            [profiles.work.settings]
+            fail_fast = false
+
+            [profiles.work.npm]
+            global = ["work-tool"]
+            "#,
+        )
+        .unwrap();
+
+        let merged = apply_profile(config, "work").unwrap();
+        // Set back to its default by the profile
+        assert!(!merged.settings.fail_fast);
+        // Untouched by the profile
+        assert_eq!(merged.settings.max_parallel, 2);
+        assert_eq!(merged.npm.unwrap().global, ["base-tool", "work-tool"]);
+    }
+
+    #[test]
+    fn profile_lists_can_replace_the_base_lists() {
+        let config: Config = toml::from_str(
+            r#"
+            [brew]
+            formulae = ["git"]
+            casks = ["slack"]
+
+            [profiles.personal]
+            lists = "replace"
+            brew = { casks = ["spotify"] }
+            "#,
+        )
+        .unwrap();
+
+        let brew = apply_profile(config, "personal").unwrap().brew.unwrap();
+        assert_eq!(brew.formulae, ["git"]);
+        assert_eq!(brew.casks, ["spotify"]);
+    }
+
+    #[test]
+    fn profile_must_match_the_config_schema() {
+        let err = toml::from_str::<Config>(
+            r#"
+            [profiles.work.settings]
+            fail_fast = "no"
+            "#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("invalid type"), "{}", err);
+    }
+
+    #[test]
+    fn fragments_merge_in_file_name_order() {
+        let dir = std::env::temp_dir().join(format!("macup-test-conf-d-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("20-work.toml"),
+            "[settings]\nmax_parallel = 8\n\n[npm]\nglobal = [\"zx\", \"work-cli\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("10-base.toml"),
+            "[settings]\nmax_parallel = 2\nfail_fast = true\n\n[npm]\nglobal = [\"zx\"]\n\n[brew]\nformulae = [\"git\"]\n",
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "not a fragment").unwrap();
+
+        let config = load_config(&dir).unwrap();
+        let write_target = config_write_target(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        // The later fragment wins for scalars, lists are concatenated once
+        assert_eq!(config.settings.max_parallel, 8);
+        assert!(config.settings.fail_fast);
+        assert_eq!(config.npm.unwrap().global, ["zx", "work-cli"]);
+        assert_eq!(config.brew.unwrap().formulae, ["git"]);
+        assert_eq!(write_target, dir.join("00-local.toml"));
+    }
+
+    #[test]
+    fn standalone_casks_are_unioned_with_brew_casks() {
+        let path =
+            std::env::temp_dir().join(format!("macup-test-casks-{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            "[brew]\nformulae = [\"git\", \"docker\"]\ncasks = [\"slack\", \"iterm2\"]\n\n[casks]\nlist = [\"iterm2\", \"raycast\"]\n",
+        )
+        .unwrap();
+
+        let config = load_config(&path).unwrap();
+        // Casks found among the formulae go to the section the config already uses
+        let moved = move_formulae_to_casks(&path, &["docker".to_string()]).unwrap();
+        let moved_to = load_config(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(config.casks.is_none());
+        assert_eq!(config.brew.unwrap().casks, ["slack", "iterm2", "raycast"]);
+        assert_eq!(moved, 1);
+        let brew = moved_to.brew.unwrap();
+        assert_eq!(brew.formulae, ["git"]);
+        assert_eq!(brew.casks, ["slack", "iterm2", "raycast", "docker"]);
+    }
+
+    #[test]
+    fn standalone_casks_work_without_a_brew_section() {
+        let mut config: Config = toml::from_str("[casks]\nlist = [\"slack\"]\n").unwrap();
+        config.merge_standalone_casks();
+
+        let brew = config.brew.unwrap();
+        assert_eq!(brew.casks, ["slack"]);
+        assert!(brew.formulae.is_empty());
+    }
+
+    #[test]
+    fn configs_round_trip_through_to_toml() {
+        let representative = r#"
+            [settings]
+            fail_fast = true
+            parallelism = { npm = 2, mas = 1 }
+
+            [brew]
+            taps = ["homebrew/cask-fonts", { name = "acme/tools", url = "https://git.example.com/tools" }]
+            formulae = ["git", "ripgrep:rg"]
+            casks = ["firefox"]
+            pin = ["git"]
+
+            [npm]
+            global = ["prettier@3.2.5"]
+
+            [[install.scripts]]
+            name = "rustup"
+            command = "curl -sSf https://sh.rustup.rs | sh -s -- -y"
+            binary = "rustup"
+            on_failure = "retry"
+
+            [system]
+            commands = ["defaults write com.apple.dock autohide -bool true"]
+        "#;
+
+        for text in [include_str!("../../macup.toml"), representative, ""] {
+            let config: Config = toml::from_str(text).unwrap();
+            let rendered = to_toml(&config).unwrap();
+            let reparsed: Config = toml::from_str(&rendered).unwrap();
+
+            assert_eq!(
+                serde_json::to_value(&reparsed).unwrap(),
+                serde_json::to_value(&config).unwrap()
+            );
+            assert_eq!(to_toml(&reparsed).unwrap(), rendered);
+        }
+
+        // Defaults are written out
+        let defaults = to_toml(&toml::from_str("").unwrap()).unwrap();
+        assert!(defaults.contains("max_parallel = 4"));
+    }
+}
@@ -0,0 +1,187 @@
+use super::schema::Config;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// How arrays from an overlay combine with the base's
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArrayMerge {
+    /// Append overlay entries the base doesn't already have
+    #[default]
+    Concat,
+    /// The overlay's array replaces the base's
+    Replace,
+}
+
+/// Rules for layering one config over another (fragments, profiles)
+/// Tables always merge key by key and other values from the overlay win;
+/// only array handling varies
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergePolicy {
+    pub arrays: ArrayMerge,
+}
+
+/// Merge `overlay` into `base` following `policy`
+/// Keys missing from the overlay leave the base's value untouched
+fn merge_values(base: &mut toml::Value, overlay: toml::Value, policy: MergePolicy) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value, policy),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (toml::Value::Array(base), toml::Value::Array(overlay))
+            if policy.arrays == ArrayMerge::Concat =>
+        {
+            for value in overlay {
+                if !base.contains(&value) {
+                    base.push(value);
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+impl Config {
+    /// Layer `overlay`, a config table as written (a fragment, a profile), over `base`
+    /// - a section the overlay doesn't have leaves the base's as is; one it has
+    ///   merges into the base's key by key (or becomes the section if the base has none)
+    /// - arrays follow `policy.arrays`, other values from the overlay win
+    /// - only keys the overlay sets count, so it can also set a value back to
+    ///   its default
+    pub fn merge(base: &Config, overlay: &toml::Table, policy: MergePolicy) -> Result<Config> {
+        let mut merged = toml::Value::try_from(base).context("Failed to serialize config")?;
+        merge_values(&mut merged, toml::Value::Table(overlay.clone()), policy);
+        merged.try_into().context("Failed to rebuild merged config")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(toml: &str) -> Config {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn table(toml: &str) -> toml::Table {
+        toml::from_str(toml).unwrap()
+    }
+
+    const BASE: &str = r#"
+        [settings]
+        fail_fast = true
+        max_parallel = 2
+
+        [brew]
+        formulae = ["git", "wget"]
+        casks = ["firefox"]
+
+        [npm]
+        global = ["typescript"]
+        runtime_auto_install = false
+    "#;
+
+    #[test]
+    fn concat_appends_new_array_entries_once() {
+        let merged = Config::merge(
+            &config(BASE),
+            &table(r#"brew = { formulae = ["wget", "jq"] }"#),
+            MergePolicy::default(),
+        )
+        .unwrap();
+
+        let brew = merged.brew.unwrap();
+        assert_eq!(brew.formulae, ["git", "wget", "jq"]);
+        // Keys the overlay's section doesn't set are kept
+        assert_eq!(brew.casks, ["firefox"]);
+    }
+
+    #[test]
+    fn replace_swaps_arrays_but_still_merges_tables() {
+        let merged = Config::merge(
+            &config(BASE),
+            &table(r#"brew = { formulae = ["jq"] }"#),
+            MergePolicy {
+                arrays: ArrayMerge::Replace,
+            },
+        )
+        .unwrap();
+
+        let brew = merged.brew.unwrap();
+        assert_eq!(brew.formulae, ["jq"]);
+        assert_eq!(brew.casks, ["firefox"]);
+    }
+
+    #[test]
+    fn overlay_scalars_win_even_when_set_to_their_default() {
+        let merged = Config::merge(
+            &config(BASE),
+            &table("settings = { fail_fast = false }\nnpm = { runtime_auto_install = true }"),
+            MergePolicy::default(),
+        )
+        .unwrap();
+
+        assert!(!merged.settings.fail_fast);
+        assert_eq!(merged.settings.max_parallel, 2);
+        let npm = merged.npm.unwrap();
+        assert!(npm.runtime_auto_install);
+        assert_eq!(npm.global, ["typescript"]);
+    }
+
+    #[test]
+    fn absent_sections_keep_the_base_and_new_ones_are_added() {
+        let merged = Config::merge(
+            &config(BASE),
+            &table(r#"cargo = { packages = ["ripgrep"] }"#),
+            MergePolicy::default(),
+        )
+        .unwrap();
+
+        assert_eq!(merged.brew.unwrap().formulae, ["git", "wget"]);
+        assert_eq!(merged.npm.unwrap().global, ["typescript"]);
+        assert_eq!(merged.cargo.unwrap().packages, ["ripgrep"]);
+        assert!(merged.mas.is_none());
+    }
+
+    #[test]
+    fn nested_tables_merge_key_by_key() {
+        let merged = Config::merge(
+            &config(
+                r#"
+                [[install.scripts]]
+                name = "rustup"
+                command = "true"
+
+                [system]
+                commands = ["defaults write a b"]
+                "#,
+            ),
+            &table(
+                r#"
+                [install]
+                depends_on = ["brew"]
+
+                [system]
+                commands = ["defaults write c d"]
+                "#,
+            ),
+            MergePolicy::default(),
+        )
+        .unwrap();
+
+        let install = merged.install.unwrap();
+        assert_eq!(install.depends_on, ["brew"]);
+        assert_eq!(install.scripts.len(), 1);
+        assert_eq!(
+            merged.system.unwrap().commands,
+            ["defaults write a b", "defaults write c d"]
+        );
+    }
+}
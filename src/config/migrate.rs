@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use toml_edit::{DocumentMut, Value};
+
+/// Config schema version this binary reads and writes
+pub const CURRENT_CONFIG_VERSION: u32 = 1;
+
+/// Sections holding "package:binary" string entries
+const COLON_FORM_ARRAYS: &[(&str, &str)] = &[
+    ("brew", "formulae"),
+    ("npm", "global"),
+    ("cargo", "packages"),
+];
+
+/// Read `settings.config_version` (missing = version 0)
+pub fn config_version(doc: &DocumentMut) -> Result<u32> {
+    match doc.get("settings").and_then(|s| s.get("config_version")) {
+        Some(item) => item
+            .as_integer()
+            .and_then(|v| u32::try_from(v).ok())
+            .context("settings.config_version must be a non-negative integer"),
+        None => Ok(0),
+    }
+}
+
+/// Upgrade a config document to CURRENT_CONFIG_VERSION in place
+/// Returns a human-readable list of changes (empty if already current)
+pub fn migrate(doc: &mut DocumentMut) -> Result<Vec<String>> {
+    let version = config_version(doc)?;
+
+    if version > CURRENT_CONFIG_VERSION {
+        anyhow::bail!(
+            "Config version {} is newer than this macup supports (max {}). Please upgrade macup.",
+            version,
+            CURRENT_CONFIG_VERSION
+        );
+    }
+
+    let mut changes = Vec::new();
+
+    if version < 1 {
+        changes.extend(migrate_v0_to_v1(doc));
+    }
+
+    if version < CURRENT_CONFIG_VERSION {
+        if doc.get("settings").is_none() {
+            doc["settings"] = toml_edit::table();
+        }
+        doc["settings"]["config_version"] = toml_edit::value(i64::from(CURRENT_CONFIG_VERSION));
+        changes.push(format!(
+            "settings.config_version: {} -> {}",
+            version, CURRENT_CONFIG_VERSION
+        ));
+    }
+
+    Ok(changes)
+}
+
+/// v0 -> v1: normalize "package:binary" entries
+/// - trims whitespace around both parts ("pkg : bin" -> "pkg:bin")
+/// - drops redundant binaries ("pkg:pkg" -> "pkg", "pkg:" -> "pkg")
+fn migrate_v0_to_v1(doc: &mut DocumentMut) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    for (section, key) in COLON_FORM_ARRAYS {
+        let Some(array) = doc
+            .get_mut(section)
+            .and_then(|s| s.get_mut(key))
+            .and_then(|a| a.as_array_mut())
+        else {
+            continue;
+        };
+
+        for value in array.iter_mut() {
+            let Some(entry) = value.as_str() else {
+                continue;
+            };
+            let normalized = normalize_colon_entry(entry);
+            if normalized != entry {
+                changes.push(format!(
+                    "[{}] {}: \"{}\" -> \"{}\"",
+                    section, key, entry, normalized
+                ));
+                // Keep surrounding whitespace/comments of the array element
+                let decor = value.decor().clone();
+                *value = Value::from(normalized);
+                *value.decor_mut() = decor;
+            }
+        }
+    }
+
+    changes
+}
+
+fn normalize_colon_entry(entry: &str) -> String {
+    match entry.split_once(':') {
+        Some((pkg, bin)) => {
+            let (pkg, bin) = (pkg.trim(), bin.trim());
+            if bin.is_empty() || bin == pkg {
+                pkg.to_string()
+            } else {
+                format!("{}:{}", pkg, bin)
+            }
+        }
+        None => entry.trim().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_v0_colon_entries_to_v1() {
+        let mut doc: DocumentMut = r#"
+[npm]
+global = ["typescript : tsc", "eslint:eslint", "prettier:", "zx"] # node tools
+"#
+        .parse()
+        .unwrap();
+
+        let changes = migrate(&mut doc).unwrap();
+
+        // Three entries plus the version bump
+        assert_eq!(changes.len(), 4);
+        assert_eq!(changes[3], "settings.config_version: 0 -> 1");
+        assert_eq!(config_version(&doc).unwrap(), CURRENT_CONFIG_VERSION);
+        let global: Vec<&str> = doc["npm"]["global"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|v| v.as_str())
+            .collect();
+        assert_eq!(global, ["typescript:tsc", "eslint", "prettier", "zx"]);
+        assert!(doc.to_string().contains("# node tools"));
+
+        // A migrated config is left alone
+        assert!(migrate(&mut doc).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_configs_newer_than_this_binary() {
+        let mut doc: DocumentMut = "[settings]\nconfig_version = 99\n".parse().unwrap();
+
+        let err = migrate(&mut doc).unwrap_err();
+        assert!(err.to_string().starts_with("Config version 99 is newer"));
+    }
+}
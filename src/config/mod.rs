@@ -1,4 +1,7 @@
+pub mod env;
 pub mod loader;
+pub mod merge;
+pub mod migrate;
 pub mod schema;
 pub mod validator;
 
@@ -1,4 +1,8 @@
+use super::merge::ArrayMerge;
+use crate::managers::{normalize_package_name, ManagerMetadata};
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 /// Trait for package manager config sections (mas, npm, cargo, etc.)
 /// Allows generic iteration over different manager types
@@ -6,8 +10,10 @@ pub trait PackageManagerSection {
     /// Get the dependencies this section requires
     fn get_depends_on(&self) -> &Vec<String>;
 
+    /// Whether a missing runtime may be installed via brew
+    fn runtime_auto_install(&self) -> bool;
+
     /// Check if this section has any packages to install
-    #[allow(dead_code)]
     fn has_packages(&self) -> bool;
 }
 
@@ -16,9 +22,13 @@ pub struct Config {
     #[serde(default)]
     pub settings: Settings,
 
-    #[serde(default)]
+    #[serde(default, alias = "homebrew")]
     pub brew: Option<BrewConfig>,
 
+    /// Standalone `[casks]` section, folded into `brew.casks` on load
+    #[serde(default)]
+    pub casks: Option<CasksConfig>,
+
     // CODEGEN_START[mas]: config_field
     #[serde(default)]
     pub mas: Option<MasConfig>,
@@ -34,49 +44,278 @@ pub struct Config {
     pub cargo: Option<CargoConfig>,
     // CODEGEN_END[cargo]: config_field
 
+    // CODEGEN_START[asdf]: config_field
+    #[serde(default)]
+    pub asdf: Option<AsdfConfig>,
+    // CODEGEN_END[asdf]: config_field
+
+    // CODEGEN_START[pnpm]: config_field
+    #[serde(default)]
+    pub pnpm: Option<PnpmConfig>,
+    // CODEGEN_END[pnpm]: config_field
+
+    // CODEGEN_START[yarn]: config_field
+    #[serde(default)]
+    pub yarn: Option<YarnConfig>,
+    // CODEGEN_END[yarn]: config_field
+
+    // CODEGEN_START[pip]: config_field
+    #[serde(default)]
+    pub pip: Option<PipConfig>,
+    // CODEGEN_END[pip]: config_field
+
     // CODEGEN_MARKER: insert_config_field_here
     #[serde(default)]
     pub install: Option<InstallConfig>,
 
     #[serde(default)]
     pub system: Option<SystemConfig>,
+
+    /// Named sets of packages across managers (`[[groups]]`), for
+    /// `--group` selection and grouped diff output
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<GroupConfig>,
+
+    /// `[profiles.<name>]` overlays, layered over the rest of the config
+    /// when selected (`apply --profile <name>`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ProfileOverride>,
+}
+
+/// A `[profiles.<name>]` table: any config sections, merged into the base
+/// config when the profile is selected
+/// Kept as written, so only the keys the profile sets override the base
+/// (including ones set back to their default); checked against the config
+/// schema on load
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(try_from = "toml::Table", into = "toml::Table")]
+pub struct ProfileOverride {
+    /// `lists = "replace"`: the profile's lists replace the base's instead of
+    /// adding to them
+    pub lists: ArrayMerge,
+    pub sections: toml::Table,
+}
+
+impl TryFrom<toml::Table> for ProfileOverride {
+    type Error = toml::de::Error;
+
+    fn try_from(mut sections: toml::Table) -> Result<Self, Self::Error> {
+        let lists = match sections.remove("lists") {
+            Some(lists) => ArrayMerge::deserialize(lists)?,
+            None => ArrayMerge::default(),
+        };
+        Config::deserialize(toml::Value::Table(sections.clone()))?;
+        Ok(Self { lists, sections })
+    }
+}
+
+impl From<ProfileOverride> for toml::Table {
+    fn from(profile: ProfileOverride) -> Self {
+        let mut table = profile.sections;
+        if profile.lists == ArrayMerge::Replace {
+            table.insert("lists".to_string(), toml::Value::from("replace"));
+        }
+        table
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Settings {
+    /// Config schema version (missing = 0, upgraded on load by config::migrate)
+    #[serde(default)]
+    pub config_version: u32,
+
     #[serde(default)]
     pub fail_fast: bool,
 
+    /// Make `apply` and `add` dry runs unless `--no-dry-run` is passed
+    #[serde(default)]
+    pub dry_run: bool,
+
     #[serde(default = "default_max_parallel")]
     pub max_parallel: usize,
+
+    /// Per-manager overrides of max_parallel (e.g. `npm = 2`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub parallelism: BTreeMap<String, usize>,
+
+    /// Cap on installs running at once across all managers (0 = unbounded)
+    #[serde(default)]
+    pub global_max_parallel: usize,
+
+    /// Turn off npm/pnpm update and funding notices during installs
+    #[serde(default = "default_true")]
+    pub suppress_update_checks: bool,
+
+    /// How long `import --incremental` trusts the previous scan (hours)
+    #[serde(default = "default_scan_cache_ttl_hours")]
+    pub scan_cache_ttl_hours: u64,
+
+    /// How long `diff` reuses installed-package lists (seconds, 0 = off)
+    #[serde(default)]
+    pub diff_cache_ttl_secs: u64,
+
+    /// Extra attempts for `brew install <runtime>` (node, python, ...) before
+    /// the manager's packages are marked failed
+    #[serde(default = "default_runtime_install_retries")]
+    pub runtime_install_retries: u32,
+
+    /// Also write log output to this file (`~` is expanded)
+    #[serde(default)]
+    pub log_file: Option<String>,
+
+    /// Preferred order of sections whose dependencies allow either order
+    /// (e.g. `["brew", "cargo", "npm", "mas"]`); `depends_on` still wins
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub install_order: Vec<String>,
+
+    #[serde(default)]
+    pub brew: BrewSettings,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct BrewSettings {
+    /// Delegate the brew phase to `brew bundle` with a generated Brewfile
+    #[serde(default)]
+    pub use_bundle: bool,
+
+    /// Run `brew cleanup` after a brew phase that had no failures
+    #[serde(default)]
+    pub cleanup_after: bool,
 }
 
 fn default_max_parallel() -> usize {
     4
 }
 
+fn default_scan_cache_ttl_hours() -> u64 {
+    24
+}
+
+fn default_runtime_install_retries() -> u32 {
+    2
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
+            config_version: 0,
             fail_fast: false,
+            dry_run: false,
             max_parallel: default_max_parallel(),
+            parallelism: BTreeMap::new(),
+            global_max_parallel: 0,
+            suppress_update_checks: true,
+            scan_cache_ttl_hours: default_scan_cache_ttl_hours(),
+            diff_cache_ttl_secs: 0,
+            runtime_install_retries: default_runtime_install_retries(),
+            log_file: None,
+            install_order: vec![],
+            brew: BrewSettings::default(),
         }
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Upper bound for max_parallel, parallelism and global_max_parallel
+pub const MAX_PARALLEL_LIMIT: usize = 64;
+
+/// Upper bound for runtime_install_retries (backoff doubles per attempt)
+const MAX_RUNTIME_RETRIES: u32 = 10;
+
+impl Settings {
+    /// Reject values that would only fail (or hang) once installs start
+    pub fn validate(&self) -> Result<()> {
+        let limits = std::iter::once(("settings.max_parallel".to_string(), self.max_parallel))
+            .chain(
+                self.parallelism
+                    .iter()
+                    .map(|(manager, n)| (format!("settings.parallelism.{}", manager), *n)),
+            );
+        for (key, value) in limits {
+            if value == 0 || value > MAX_PARALLEL_LIMIT {
+                anyhow::bail!(
+                    "{} must be between 1 and {} (got {})",
+                    key,
+                    MAX_PARALLEL_LIMIT,
+                    value
+                );
+            }
+        }
+
+        if self.global_max_parallel > MAX_PARALLEL_LIMIT {
+            anyhow::bail!(
+                "settings.global_max_parallel must be at most {} (got {}, 0 = unbounded)",
+                MAX_PARALLEL_LIMIT,
+                self.global_max_parallel
+            );
+        }
+
+        if self.runtime_install_retries > MAX_RUNTIME_RETRIES {
+            anyhow::bail!(
+                "settings.runtime_install_retries must be at most {} (got {})",
+                MAX_RUNTIME_RETRIES,
+                self.runtime_install_retries
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct BrewConfig {
     #[serde(default)]
     pub depends_on: Vec<String>,
 
     #[serde(default)]
-    pub taps: Vec<String>,
+    pub taps: Vec<TapEntry>,
 
-    #[serde(default)]
+    #[serde(default, alias = "formulas")]
     pub formulae: Vec<String>,
 
     #[serde(default)]
     pub casks: Vec<String>,
+
+    /// Formulae to `brew pin` so `brew upgrade` leaves them at their installed version
+    #[serde(default)]
+    pub pin: Vec<String>,
+}
+
+/// A `brew.taps` entry: "owner/name", or `{ name, url }` for a tap whose
+/// repository isn't github.com/owner/homebrew-name (private/enterprise taps)
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum TapEntry {
+    Name(String),
+    Custom {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
+}
+
+impl TapEntry {
+    /// "owner/name" - what `brew tap` lists, so presence checks key on it
+    pub fn name(&self) -> &str {
+        match self {
+            TapEntry::Name(name) | TapEntry::Custom { name, .. } => name,
+        }
+    }
+
+    /// Git URL to clone the tap from, when not the GitHub default
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            TapEntry::Name(_) => None,
+            TapEntry::Custom { url, .. } => url.as_deref(),
+        }
+    }
+}
+
+/// GUI apps kept apart from CLI formulae: `[casks] list = [...]`
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CasksConfig {
+    #[serde(default)]
+    pub list: Vec<String>,
 }
 
 // CODEGEN_START[mas]: config_struct
@@ -87,6 +326,10 @@ pub struct MasConfig {
 
     #[serde(default)]
     pub apps: Vec<MasApp>,
+
+    /// `brew install` the runtime when it's missing (false = install it yourself)
+    #[serde(default = "default_true")]
+    pub runtime_auto_install: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -100,6 +343,10 @@ impl PackageManagerSection for MasConfig {
         &self.depends_on
     }
 
+    fn runtime_auto_install(&self) -> bool {
+        self.runtime_auto_install
+    }
+
     fn has_packages(&self) -> bool {
         !self.apps.is_empty()
     }
@@ -114,6 +361,10 @@ pub struct NpmConfig {
 
     #[serde(default)]
     pub global: Vec<String>,
+
+    /// `brew install` the runtime when it's missing (false = install it yourself)
+    #[serde(default = "default_true")]
+    pub runtime_auto_install: bool,
 }
 
 impl PackageManagerSection for NpmConfig {
@@ -121,6 +372,10 @@ impl PackageManagerSection for NpmConfig {
         &self.depends_on
     }
 
+    fn runtime_auto_install(&self) -> bool {
+        self.runtime_auto_install
+    }
+
     fn has_packages(&self) -> bool {
         !self.global.is_empty()
     }
@@ -135,6 +390,10 @@ pub struct CargoConfig {
 
     #[serde(default)]
     pub packages: Vec<String>,
+
+    /// `brew install` the runtime when it's missing (false = install it yourself)
+    #[serde(default = "default_true")]
+    pub runtime_auto_install: bool,
 }
 
 impl PackageManagerSection for CargoConfig {
@@ -142,12 +401,145 @@ impl PackageManagerSection for CargoConfig {
         &self.depends_on
     }
 
+    fn runtime_auto_install(&self) -> bool {
+        self.runtime_auto_install
+    }
+
     fn has_packages(&self) -> bool {
         !self.packages.is_empty()
     }
 }
 // CODEGEN_END[cargo]: config_struct
 
+// CODEGEN_START[asdf]: config_struct
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AsdfConfig {
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+
+    #[serde(default)]
+    pub tools: Vec<AsdfTool>,
+
+    /// `brew install` the runtime when it's missing (false = install it yourself)
+    #[serde(default = "default_true")]
+    pub runtime_auto_install: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AsdfTool {
+    pub plugin: String,
+    pub version: String,
+}
+
+impl AsdfTool {
+    /// Tool spec understood by AsdfManager ("plugin@version")
+    pub fn spec(&self) -> String {
+        format!("{}@{}", self.plugin, self.version)
+    }
+}
+
+impl PackageManagerSection for AsdfConfig {
+    fn get_depends_on(&self) -> &Vec<String> {
+        &self.depends_on
+    }
+
+    fn runtime_auto_install(&self) -> bool {
+        self.runtime_auto_install
+    }
+
+    fn has_packages(&self) -> bool {
+        !self.tools.is_empty()
+    }
+}
+// CODEGEN_END[asdf]: config_struct
+
+// CODEGEN_START[pnpm]: config_struct
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PnpmConfig {
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+
+    #[serde(default)]
+    pub packages: Vec<String>,
+
+    /// `brew install` the runtime when it's missing (false = install it yourself)
+    #[serde(default = "default_true")]
+    pub runtime_auto_install: bool,
+}
+
+impl PackageManagerSection for PnpmConfig {
+    fn get_depends_on(&self) -> &Vec<String> {
+        &self.depends_on
+    }
+
+    fn runtime_auto_install(&self) -> bool {
+        self.runtime_auto_install
+    }
+
+    fn has_packages(&self) -> bool {
+        !self.packages.is_empty()
+    }
+}
+// CODEGEN_END[pnpm]: config_struct
+
+// CODEGEN_START[yarn]: config_struct
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct YarnConfig {
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+
+    #[serde(default)]
+    pub packages: Vec<String>,
+
+    /// `brew install` the runtime when it's missing (false = install it yourself)
+    #[serde(default = "default_true")]
+    pub runtime_auto_install: bool,
+}
+
+impl PackageManagerSection for YarnConfig {
+    fn get_depends_on(&self) -> &Vec<String> {
+        &self.depends_on
+    }
+
+    fn runtime_auto_install(&self) -> bool {
+        self.runtime_auto_install
+    }
+
+    fn has_packages(&self) -> bool {
+        !self.packages.is_empty()
+    }
+}
+// CODEGEN_END[yarn]: config_struct
+
+// CODEGEN_START[pip]: config_struct
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PipConfig {
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+
+    #[serde(default)]
+    pub packages: Vec<String>,
+
+    /// `brew install` the runtime when it's missing (false = install it yourself)
+    #[serde(default = "default_true")]
+    pub runtime_auto_install: bool,
+}
+
+impl PackageManagerSection for PipConfig {
+    fn get_depends_on(&self) -> &Vec<String> {
+        &self.depends_on
+    }
+
+    fn runtime_auto_install(&self) -> bool {
+        self.runtime_auto_install
+    }
+
+    fn has_packages(&self) -> bool {
+        !self.packages.is_empty()
+    }
+}
+// CODEGEN_END[pip]: config_struct
+
 // CODEGEN_MARKER: insert_config_struct_here
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -175,14 +567,73 @@ pub struct InstallScript {
 
     pub command: String,
 
+    /// Deprecated spelling of `on_failure`: true = abort, false = skip
     #[serde(default = "default_true")]
     pub required: bool,
+
+    /// What to do when the command fails (overrides `required`)
+    #[serde(default)]
+    pub on_failure: Option<OnFailure>,
+
+    /// Extra attempts for `on_failure = "retry"`
+    #[serde(default = "default_script_retries")]
+    pub retries: u32,
+
+    /// Names of scripts in this section that must run first
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+
+    /// Run only once: a marker is recorded on success and later applies skip
+    /// the script (for steps neither `binary` nor `check` can detect)
+    #[serde(default)]
+    pub once: bool,
+
+    /// Capture the command's output and show it only if the script fails
+    /// (always the case with `--quiet`)
+    #[serde(default)]
+    pub quiet: bool,
 }
 
 fn default_true() -> bool {
     true
 }
 
+fn default_script_retries() -> u32 {
+    2
+}
+
+/// Failure policy of an install script
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnFailure {
+    /// Stop the apply
+    Abort,
+    /// Warn and continue with the next script
+    Skip,
+    /// Run again up to `retries` times, then abort (skip with `required = false`)
+    Retry,
+}
+
+impl InstallScript {
+    /// Effective policy, falling back to the legacy `required` flag
+    pub fn failure_policy(&self) -> OnFailure {
+        match self.on_failure {
+            Some(policy) => policy,
+            None if self.required => OnFailure::Abort,
+            None => OnFailure::Skip,
+        }
+    }
+
+    /// Whether a failure (after any retries) stops the apply
+    pub fn aborts_on_failure(&self) -> bool {
+        match self.failure_policy() {
+            OnFailure::Abort => true,
+            OnFailure::Skip => false,
+            OnFailure::Retry => self.required,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SystemConfig {
     #[serde(default)]
@@ -192,7 +643,55 @@ pub struct SystemConfig {
     pub commands: Vec<String>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GroupConfig {
+    pub name: String,
+
+    /// Packages as "manager:name" (e.g. "brew:node", "cask:iterm2", "npm:typescript")
+    #[serde(default)]
+    pub members: Vec<String>,
+
+    /// Labels selecting this group's members with `--tag`
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
 impl Config {
+    /// Fold the standalone `[casks]` list into `brew.casks`, skipping duplicates,
+    /// so the brew phase, diff and import only have to look at `brew.casks`
+    pub fn merge_standalone_casks(&mut self) {
+        let Some(casks) = self.casks.take() else {
+            return;
+        };
+        if casks.list.is_empty() {
+            return;
+        }
+
+        let brew = self.brew.get_or_insert_with(BrewConfig::default);
+        for cask in casks.list {
+            if !brew.casks.contains(&cask) {
+                brew.casks.push(cask);
+            }
+        }
+    }
+
+    /// Install script by name (`macup run-script`, `apply --rerun`)
+    pub fn install_script(&self, name: &str) -> Option<&InstallScript> {
+        self.install
+            .as_ref()?
+            .scripts
+            .iter()
+            .find(|script| script.name == name)
+    }
+
+    /// Names of all install scripts, in config order
+    pub fn install_script_names(&self) -> Vec<&str> {
+        self.install
+            .as_ref()
+            .map(|install| install.scripts.iter().map(|s| s.name.as_str()).collect())
+            .unwrap_or_default()
+    }
+
     /// Get package manager config by name (generic accessor)
     pub fn get_manager_config(&self, name: &str) -> Option<&dyn PackageManagerSection> {
         match name {
@@ -205,11 +704,31 @@ impl Config {
             // CODEGEN_START[cargo]: match_arm
             "cargo" => self.cargo.as_ref().map(|c| c as &dyn PackageManagerSection),
             // CODEGEN_END[cargo]: match_arm
+            // CODEGEN_START[asdf]: match_arm
+            "asdf" => self.asdf.as_ref().map(|c| c as &dyn PackageManagerSection),
+            // CODEGEN_END[asdf]: match_arm
+            // CODEGEN_START[pnpm]: match_arm
+            "pnpm" => self.pnpm.as_ref().map(|c| c as &dyn PackageManagerSection),
+            // CODEGEN_END[pnpm]: match_arm
+            // CODEGEN_START[yarn]: match_arm
+            "yarn" => self.yarn.as_ref().map(|c| c as &dyn PackageManagerSection),
+            // CODEGEN_END[yarn]: match_arm
+            // CODEGEN_START[pip]: match_arm
+            "pip" => self.pip.as_ref().map(|c| c as &dyn PackageManagerSection),
+            // CODEGEN_END[pip]: match_arm
             // CODEGEN_MARKER: insert_manager_match_arm_here
             _ => None,
         }
     }
 
+    /// Whether `manager`'s runtime may be brew-installed when missing
+    /// (`<manager>.runtime_auto_install`, default true)
+    pub fn runtime_auto_install(&self, manager: &str) -> bool {
+        self.get_manager_config(manager)
+            .map(|section| section.runtime_auto_install())
+            .unwrap_or(true)
+    }
+
     /// Auto-detect required managers from config sections
     /// Returns managers that MUST be installed based on declared packages or dependencies
     pub fn detect_required_managers(&self) -> Vec<String> {
@@ -239,11 +758,11 @@ impl Config {
             || self
                 .install
                 .as_ref()
-                .map_or(false, |i| i.depends_on.contains(&"brew".to_string()))
+                .is_some_and(|i| i.depends_on.contains(&"brew".to_string()))
             || self
                 .system
                 .as_ref()
-                .map_or(false, |s| s.depends_on.contains(&"brew".to_string()));
+                .is_some_and(|s| s.depends_on.contains(&"brew".to_string()));
 
         if needs_brew && !managers.contains(&"brew".to_string()) {
             managers.push("brew".to_string());
@@ -255,8 +774,622 @@ impl Config {
         managers
     }
 
+    /// Resolve install parallelism for a manager
+    /// Precedence: CLI override > [settings.parallelism] entry > settings.max_parallel
+    /// mas and pip fall back to 1 (App Store installs don't parallelize well, concurrent
+    /// pip installs race on the shared user site) unless set explicitly
+    pub fn effective_parallelism(&self, manager: &str, cli_override: Option<usize>) -> usize {
+        let resolved = cli_override
+            .or_else(|| self.settings.parallelism.get(manager).copied())
+            .unwrap_or(match manager {
+                "mas" | "pip" => 1,
+                _ => self.settings.max_parallel,
+            });
+
+        resolved.max(1)
+    }
+
+    /// Resolve whether a mutating command only previews its changes
+    /// Precedence: --dry-run / --no-dry-run > settings.dry_run
+    pub fn effective_dry_run(&self, dry_run: bool, no_dry_run: bool) -> bool {
+        dry_run || (!no_dry_run && self.settings.dry_run)
+    }
+
+    /// Resolve retries for runtime installs
+    /// Precedence: CLI override > settings.runtime_install_retries
+    pub fn effective_runtime_retries(&self, cli_override: Option<u32>) -> u32 {
+        cli_override.unwrap_or(self.settings.runtime_install_retries)
+    }
+
+    /// Apply an optional `--filter` glob, reporting how many entries matched
+    /// A valid glob matching nothing is not an error - there's just nothing to do
+    pub fn with_filter(self, filter: Option<&str>) -> Result<Config> {
+        let Some(filter) = filter else {
+            return Ok(self);
+        };
+
+        let pattern =
+            glob::Pattern::new(filter).context(format!("Invalid --filter glob: {}", filter))?;
+        let (config, matched) = self.filter_packages(&pattern)?;
+
+        if matched == 0 {
+            log::warn!("Filter '{}' matched 0 packages", filter);
+        } else {
+            log::info!("Filter '{}' matched {} package(s)", filter, matched);
+        }
+
+        Ok(config)
+    }
+
+    /// Keep only entries whose install name matches `pattern` (`--filter`)
+    /// Applies to every package list (formulae, casks, global, packages, apps,
+    /// tools) and install scripts; taps, depends_on and settings are kept
+    /// Returns the filtered config and the number of matching entries
+    pub fn filter_packages(&self, pattern: &glob::Pattern) -> Result<(Config, usize)> {
+        self.retain_entries(|_, name| pattern.matches(name))
+    }
+
+    /// Apply an optional `--group`, keeping only that group's members
+    pub fn with_group(self, group: Option<&str>) -> Result<Config> {
+        let Some(group) = group else {
+            return Ok(self);
+        };
+
+        let (config, matched) = self.group_members(group)?;
+        log::info!("Group '{}' selected {} package(s)", group, matched);
+        Ok(config)
+    }
+
+    /// Keep only the members of the named `[[groups]]` entry
+    /// Returns the config and the number of entries kept
+    pub fn group_members(&self, group: &str) -> Result<(Config, usize)> {
+        let Some(found) = self.groups.iter().find(|g| g.name == group) else {
+            let names: Vec<_> = self.groups.iter().map(|g| g.name.as_str()).collect();
+            if names.is_empty() {
+                anyhow::bail!("No group named '{}' (config has no [[groups]])", group);
+            }
+            anyhow::bail!(
+                "No group named '{}'{} (available: {})",
+                group,
+                crate::utils::did_you_mean(group, &names),
+                names.join(", ")
+            );
+        };
+
+        let members: HashSet<String> = found
+            .members
+            .iter()
+            .filter_map(|member| parse_member(member))
+            .collect();
+        self.retain_entries(|prefix, name| members.contains(&member_key(prefix, name)))
+    }
+
+    /// Keep only the members of the `[[groups]]` entries tagged `tag` (`--tag`)
+    pub fn with_tag(self, tag: Option<&str>) -> Result<Config> {
+        let Some(tag) = tag else {
+            return Ok(self);
+        };
+
+        let tagged: Vec<_> = self
+            .groups
+            .iter()
+            .filter(|g| g.tags.iter().any(|t| t == tag))
+            .collect();
+        if tagged.is_empty() {
+            anyhow::bail!("No [[groups]] entry is tagged '{}'", tag);
+        }
+
+        let members: HashSet<String> = tagged
+            .iter()
+            .flat_map(|g| &g.members)
+            .filter_map(|member| parse_member(member))
+            .collect();
+        let (config, matched) =
+            self.retain_entries(|prefix, name| members.contains(&member_key(prefix, name)))?;
+        log::info!("Tag '{}' selected {} package(s)", tag, matched);
+        Ok(config)
+    }
+
+    /// Keep only one manager's entries (`--manager`), named like group member
+    /// prefixes: "brew" covers formulae and casks, "cask" only casks and
+    /// "install" the install scripts
+    pub fn with_manager(self, manager: Option<&str>) -> Result<Config> {
+        let Some(manager) = manager else {
+            return Ok(self);
+        };
+
+        let mut known = vec!["brew", "cask", "install"];
+        known.extend(ManagerMetadata::all_names());
+        if !known.contains(&manager) {
+            anyhow::bail!(
+                "Unknown manager '{}'{} (available: {})",
+                manager,
+                crate::utils::did_you_mean(manager, &known),
+                known.join(", ")
+            );
+        }
+
+        let (config, matched) = self.retain_entries(|prefix, _| {
+            prefix == manager || (manager == "brew" && prefix == "cask")
+        })?;
+        log::info!("Manager '{}' selected {} package(s)", manager, matched);
+        Ok(config)
+    }
+
+    /// Keep only the entries a `macup diff` reported missing
+    /// (`apply --packages-from-diff`); `missing` maps each diff result's
+    /// section to its missing entries. Returns the config and the entries kept
+    pub fn missing_from_diff(
+        &self,
+        missing: &BTreeMap<String, BTreeSet<String>>,
+    ) -> Result<(Config, usize)> {
+        let names: BTreeMap<&str, BTreeSet<&str>> = missing
+            .iter()
+            .map(|(section, entries)| {
+                let names = entries
+                    .iter()
+                    .map(|entry| diff_entry_name(section, entry))
+                    .collect();
+                (section.as_str(), names)
+            })
+            .collect();
+
+        self.retain_entries(|prefix, name| {
+            // A `brew bundle` result lists casks under "brew"
+            let sections: &[&str] = if prefix == "cask" {
+                &["cask", "brew"]
+            } else {
+                &[prefix]
+            };
+            sections
+                .iter()
+                .any(|section| names.get(section).is_some_and(|set| set.contains(name)))
+        })
+    }
+
+    /// Entries that belong to no group (the "Ungrouped" part of `macup diff`)
+    pub fn without_groups(&self) -> Result<Config> {
+        let grouped: HashSet<String> = self
+            .groups
+            .iter()
+            .flat_map(|g| &g.members)
+            .filter_map(|member| parse_member(member))
+            .collect();
+        let (config, _) =
+            self.retain_entries(|prefix, name| !grouped.contains(&member_key(prefix, name)))?;
+        Ok(config)
+    }
+
+    /// Every entry as a normalized "manager:name" group member key
+    pub fn member_keys(&self) -> Result<HashSet<String>> {
+        let mut keys = HashSet::new();
+        self.retain_entries(|prefix, name| {
+            keys.insert(member_key(prefix, name));
+            true
+        })?;
+        Ok(keys)
+    }
+
+    /// Keep only entries `keep(member prefix, install name)` accepts
+    /// Covers every package list and install scripts; taps, depends_on,
+    /// settings and [system] are kept. Returns the config and the entry count kept
+    fn retain_entries(&self, mut keep: impl FnMut(&str, &str) -> bool) -> Result<(Config, usize)> {
+        // Work on the generic TOML form so generated manager sections are covered too
+        let mut value = toml::Value::try_from(self).context("Failed to serialize config")?;
+        let mut matched = 0;
+
+        if let Some(sections) = value.as_table_mut() {
+            for (section, item) in sections.iter_mut() {
+                if section == "settings" || section == "system" {
+                    continue;
+                }
+                let Some(table) = item.as_table_mut() else {
+                    continue;
+                };
+
+                for (key, list) in table.iter_mut() {
+                    if key == "depends_on" || key == "taps" {
+                        continue;
+                    }
+                    let Some(entries) = list.as_array_mut() else {
+                        continue;
+                    };
+
+                    let prefix = member_prefix(section, key);
+                    entries
+                        .retain(|entry| install_name(entry).is_some_and(|name| keep(prefix, name)));
+                    matched += entries.len();
+                }
+            }
+        }
+
+        let config = value
+            .try_into()
+            .context("Failed to rebuild filtered config")?;
+        Ok((config, matched))
+    }
+
     /// Get list of required managers (auto-detected only)
     pub fn get_required_managers(&self) -> Vec<String> {
         self.detect_required_managers()
     }
+
+    /// Compare declared packages with `other`, list by list (`macup config diff`)
+    /// Purely in-memory: "added" is declared here but not in `other`,
+    /// "removed" is declared in `other` but not here
+    pub fn diff_against(&self, other: &Config) -> Result<ConfigDiff> {
+        let ours = self.declared_lists()?;
+        let theirs = other.declared_lists()?;
+
+        let names: BTreeSet<&String> = ours.keys().chain(theirs.keys()).collect();
+        let empty = BTreeSet::new();
+
+        let lists = names
+            .into_iter()
+            .filter_map(|name| {
+                let ours = ours.get(name).unwrap_or(&empty);
+                let theirs = theirs.get(name).unwrap_or(&empty);
+
+                let added: Vec<_> = ours.difference(theirs).cloned().collect();
+                let removed: Vec<_> = theirs.difference(ours).cloned().collect();
+
+                (!added.is_empty() || !removed.is_empty()).then(|| ListDiff {
+                    list: name.clone(),
+                    added,
+                    removed,
+                })
+            })
+            .collect();
+
+        Ok(ConfigDiff { lists })
+    }
+
+    /// Every package list keyed by "section.key" (e.g. "brew.formulae")
+    /// Covers generated manager sections too; settings, system and depends_on are skipped
+    fn declared_lists(&self) -> Result<BTreeMap<String, BTreeSet<String>>> {
+        let value = toml::Value::try_from(self).context("Failed to serialize config")?;
+        let mut lists = BTreeMap::new();
+
+        let Some(sections) = value.as_table() else {
+            return Ok(lists);
+        };
+
+        for (section, item) in sections {
+            if section == "settings" || section == "system" {
+                continue;
+            }
+            let Some(table) = item.as_table() else {
+                continue;
+            };
+
+            for (key, list) in table {
+                if key == "depends_on" {
+                    continue;
+                }
+                let Some(entries) = list.as_array() else {
+                    continue;
+                };
+
+                let names = entries
+                    .iter()
+                    .filter_map(|entry| match entry {
+                        toml::Value::String(spec) => Some(spec.clone()),
+                        _ => install_name(entry).map(|name| name.to_string()),
+                    })
+                    .collect();
+                lists.insert(format!("{}.{}", section, key), names);
+            }
+        }
+
+        Ok(lists)
+    }
+}
+
+/// Result of `Config::diff_against`
+#[derive(Debug, Default)]
+pub struct ConfigDiff {
+    /// Lists that differ, in name order
+    pub lists: Vec<ListDiff>,
+}
+
+/// Entries of one package list (e.g. "brew.casks") that differ between two configs
+#[derive(Debug)]
+pub struct ListDiff {
+    pub list: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Manager part of a group member for entries of `[section] key`
+/// brew.casks entries are "cask:<name>"; other lists use their section name
+fn member_prefix<'a>(section: &'a str, key: &str) -> &'a str {
+    if section == "brew" && key == "casks" {
+        "cask"
+    } else {
+        section
+    }
+}
+
+/// Comparable form of a group member (case-insensitive like package names)
+fn member_key(prefix: &str, name: &str) -> String {
+    format!("{}:{}", prefix, normalize_package_name(prefix, name))
+}
+
+/// "manager:name" -> its member key; None if either part is missing
+pub fn parse_member(member: &str) -> Option<String> {
+    let (prefix, name) = member.split_once(':')?;
+    let (prefix, name) = (prefix.trim(), name.trim());
+    if prefix.is_empty() || name.is_empty() {
+        return None;
+    }
+    Some(member_key(prefix, name))
+}
+
+/// Install name of an entry as `macup diff` lists it: "Name (id)" for mas
+/// apps, "plugin@version" for asdf tools, "pkg:binary" or "pkg" otherwise
+fn diff_entry_name<'a>(section: &str, entry: &'a str) -> &'a str {
+    let name = match section {
+        "mas" => entry.rsplit_once(" (").map_or(entry, |(name, _)| name),
+        "asdf" => entry.split('@').next().unwrap_or(entry),
+        _ => entry.split(':').next().unwrap_or(entry),
+    };
+    name.trim()
+}
+
+/// Name an entry is installed by: "pkg:binary" -> "pkg", tables by their
+/// `name` (mas apps, install scripts) or `plugin` (asdf tools)
+fn install_name(entry: &toml::Value) -> Option<&str> {
+    match entry {
+        toml::Value::String(spec) => Some(spec.split(':').next().unwrap_or(spec).trim()),
+        toml::Value::Table(table) => table
+            .get("name")
+            .or_else(|| table.get("plugin"))
+            .and_then(|v| v.as_str()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(toml: &str) -> Config {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn parallelism_precedence() {
+        let config = config(
+            r#"
+            [settings]
+            max_parallel = 6
+            parallelism = { npm = 2, mas = 3 }
+            "#,
+        );
+
+        // CLI override beats everything
+        assert_eq!(config.effective_parallelism("npm", Some(8)), 8);
+        // Then the per-manager entry
+        assert_eq!(config.effective_parallelism("npm", None), 2);
+        assert_eq!(config.effective_parallelism("mas", None), 3);
+        // Then settings.max_parallel
+        assert_eq!(config.effective_parallelism("cargo", None), 6);
+        // Never below 1
+        assert_eq!(config.effective_parallelism("cargo", Some(0)), 1);
+    }
+
+    #[test]
+    fn dry_run_setting_yields_to_the_cli() {
+        let cautious = config("[settings]\ndry_run = true\n");
+        let default = config("");
+
+        assert!(cautious.effective_dry_run(false, false));
+        assert!(!cautious.effective_dry_run(false, true));
+        assert!(!default.effective_dry_run(false, false));
+        assert!(default.effective_dry_run(true, false));
+    }
+
+    #[test]
+    fn out_of_range_settings_are_rejected() {
+        let error = |settings: &str| {
+            config(&format!("[settings]\n{}\n", settings))
+                .settings
+                .validate()
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert!(config("").settings.validate().is_ok());
+        assert_eq!(
+            error("max_parallel = 0"),
+            "settings.max_parallel must be between 1 and 64 (got 0)"
+        );
+        assert_eq!(
+            error("parallelism = { npm = 1000 }"),
+            "settings.parallelism.npm must be between 1 and 64 (got 1000)"
+        );
+        assert_eq!(
+            error("global_max_parallel = 65"),
+            "settings.global_max_parallel must be at most 64 (got 65, 0 = unbounded)"
+        );
+        assert_eq!(
+            error("runtime_install_retries = 11"),
+            "settings.runtime_install_retries must be at most 10 (got 11)"
+        );
+
+        // Config validation, which runs before any install pool is built, checks them
+        let zero = config("[settings]\nmax_parallel = 0\n");
+        assert!(crate::config::validate_config(&zero, false).is_err());
+    }
+
+    #[test]
+    fn mas_and_pip_default_to_one_at_a_time() {
+        let defaults = config("");
+        assert_eq!(defaults.effective_parallelism("mas", None), 1);
+        assert_eq!(defaults.effective_parallelism("pip", None), 1);
+        assert_eq!(
+            defaults.effective_parallelism("brew", None),
+            default_max_parallel()
+        );
+
+        // An explicit CLI value still applies to mas
+        assert_eq!(defaults.effective_parallelism("mas", Some(4)), 4);
+    }
+
+    #[test]
+    fn filter_glob_matches_install_names() {
+        let config = config(
+            r#"
+            [brew]
+            taps = ["owner/tools"]
+            formulae = ["git", "git-lfs", "ripgrep:rg"]
+            casks = ["github"]
+
+            [npm]
+            depends_on = ["brew"]
+            global = ["gitmoji-cli:gitmoji", "typescript"]
+
+            [[mas.apps]]
+            name = "GitHub Desktop"
+            id = 1477385213
+
+            [[install.scripts]]
+            name = "git-hooks"
+            command = "true"
+            "#,
+        );
+
+        let (filtered, matched) = config
+            .filter_packages(&glob::Pattern::new("git*").unwrap())
+            .unwrap();
+
+        assert_eq!(matched, 5);
+        let brew = filtered.brew.unwrap();
+        assert_eq!(brew.formulae, ["git", "git-lfs"]);
+        assert_eq!(brew.casks, ["github"]);
+        // Taps and depends_on aren't packages
+        assert_eq!(brew.taps.len(), 1);
+        let npm = filtered.npm.unwrap();
+        assert_eq!(npm.global, ["gitmoji-cli:gitmoji"]);
+        assert_eq!(npm.depends_on, ["brew"]);
+        assert!(filtered.mas.unwrap().apps.is_empty());
+        assert_eq!(filtered.install.unwrap().scripts.len(), 1);
+    }
+
+    #[test]
+    fn filter_matching_nothing_is_not_an_error() {
+        let config = config("[npm]\nglobal = [\"typescript\"]");
+
+        let filtered = config.clone().with_filter(Some("zz*")).unwrap();
+        assert!(filtered.npm.unwrap().global.is_empty());
+
+        let err = config.with_filter(Some("[unclosed")).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid --filter glob: [unclosed");
+    }
+
+    #[test]
+    fn diff_against_lists_added_and_removed_entries() {
+        let base = config(
+            r#"
+            [brew]
+            depends_on = ["mas"]
+            formulae = ["git", "wget"]
+            casks = ["slack"]
+
+            [npm]
+            global = ["prettier"]
+            "#,
+        );
+        let work = config(
+            r#"
+            [brew]
+            formulae = ["git", "jq"]
+            casks = ["slack"]
+
+            [cargo]
+            packages = ["ripgrep"]
+            "#,
+        );
+
+        let diff = work.diff_against(&base).unwrap();
+        let lists: Vec<_> = diff
+            .lists
+            .iter()
+            .map(|list| (list.list.as_str(), list.added.clone(), list.removed.clone()))
+            .collect();
+
+        assert_eq!(
+            lists,
+            [
+                (
+                    "brew.formulae",
+                    vec!["jq".to_string()],
+                    vec!["wget".to_string()]
+                ),
+                ("cargo.packages", vec!["ripgrep".to_string()], vec![]),
+                ("npm.global", vec![], vec!["prettier".to_string()]),
+            ]
+        );
+        assert!(work.diff_against(&work).unwrap().lists.is_empty());
+    }
+
+    #[test]
+    fn aliases_parse_like_the_canonical_names() {
+        let canonical = config("[brew]\nformulae = [\"git\"]\ncasks = [\"slack\"]\n");
+        let canonical = serde_json::to_value(&canonical).unwrap();
+
+        for aliased in [
+            "[homebrew]\nformulae = [\"git\"]\ncasks = [\"slack\"]\n",
+            "[brew]\nformulas = [\"git\"]\ncasks = [\"slack\"]\n",
+            "[homebrew]\nformulas = [\"git\"]\ncasks = [\"slack\"]\n",
+        ] {
+            assert_eq!(serde_json::to_value(config(aliased)).unwrap(), canonical);
+        }
+    }
+
+    fn grouped_config() -> Config {
+        config(
+            r#"
+            [brew]
+            formulae = ["node", "git"]
+            casks = ["iterm2", "slack"]
+
+            [npm]
+            global = ["typescript", "prettier"]
+
+            [cargo]
+            packages = ["wasm-pack:wasm-pack"]
+
+            [[groups]]
+            name = "web-dev"
+            members = ["brew:Node", "npm:typescript", "cargo:wasm-pack", "cask:iterm2"]
+            "#,
+        )
+    }
+
+    #[test]
+    fn group_keeps_only_its_members() {
+        let (web, matched) = grouped_config().group_members("web-dev").unwrap();
+
+        assert_eq!(matched, 4);
+        let brew = web.brew.unwrap();
+        assert_eq!(brew.formulae, ["node"]);
+        assert_eq!(brew.casks, ["iterm2"]);
+        assert_eq!(web.npm.unwrap().global, ["typescript"]);
+        assert_eq!(web.cargo.unwrap().packages, ["wasm-pack:wasm-pack"]);
+
+        let rest = grouped_config().without_groups().unwrap();
+        let brew = rest.brew.unwrap();
+        assert_eq!(brew.formulae, ["git"]);
+        assert_eq!(brew.casks, ["slack"]);
+        assert_eq!(rest.npm.unwrap().global, ["prettier"]);
+        assert!(rest.cargo.unwrap().packages.is_empty());
+    }
+
+    #[test]
+    fn unknown_group_lists_the_defined_ones() {
+        let err = grouped_config().with_group(Some("ops")).unwrap_err();
+        assert_eq!(err.to_string(), "No group named 'ops' (available: web-dev)");
+        assert!(grouped_config().with_group(None).is_ok());
+    }
 }
@@ -1,34 +1,392 @@
-use super::Config;
-use crate::managers::PACKAGE_MANAGERS;
+use super::{parse_member, Config};
+use crate::managers::{install::InstallManager, PACKAGE_MANAGERS};
 use anyhow::Result;
 use std::collections::{HashMap, HashSet};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+/// `[brew]` keys, including the `formulas` alias
+const BREW_KEYS: &[&str] = &["depends_on", "taps", "formulae", "formulas", "casks", "pin"];
+
+/// Known keys per top-level section (registry managers not listed here
+/// use the generated `depends_on` + `packages` layout)
+const SECTION_KEYS: &[(&str, &[&str])] = &[
+    (
+        "settings",
+        &[
+            "config_version",
+            "fail_fast",
+            "dry_run",
+            "max_parallel",
+            "parallelism",
+            "global_max_parallel",
+            "suppress_update_checks",
+            "scan_cache_ttl_hours",
+            "diff_cache_ttl_secs",
+            "runtime_install_retries",
+            "log_file",
+            "install_order",
+            "brew",
+        ],
+    ),
+    ("brew", BREW_KEYS),
+    ("homebrew", BREW_KEYS),
+    ("casks", &["list"]),
+    ("mas", &["depends_on", "apps", "runtime_auto_install"]),
+    ("asdf", &["depends_on", "tools", "runtime_auto_install"]),
+    ("npm", &["depends_on", "global", "runtime_auto_install"]),
+    ("install", &["depends_on", "scripts"]),
+    // Written by `macup import`; kept in the generated layout for `new manager pipx`
+    ("pipx", GENERATED_MANAGER_KEYS),
+    ("system", &["depends_on", "commands"]),
+];
+
+/// Known keys for nested tables (e.g. `[settings.brew]`, `[[install.scripts]]`)
+const NESTED_KEYS: &[(&str, &str, &[&str])] = &[
+    ("settings", "brew", &["use_bundle", "cleanup_after"]),
+    ("brew", "taps", &["name", "url"]),
+    ("homebrew", "taps", &["name", "url"]),
+    ("mas", "apps", &["name", "id"]),
+    ("asdf", "tools", &["plugin", "version"]),
+    (
+        "install",
+        "scripts",
+        &[
+            "name",
+            "binary",
+            "check",
+            "command",
+            "required",
+            "on_failure",
+            "retries",
+            "depends_on",
+            "once",
+            "quiet",
+        ],
+    ),
+];
+
+/// Keys of each `[[groups]]` entry
+const GROUP_KEYS: &[&str] = &["name", "members", "tags"];
+
+const GENERATED_MANAGER_KEYS: &[&str] = &["depends_on", "packages", "runtime_auto_install"];
+
+/// App Store ids are 9-10 digits; anything longer is a typo
+const MAX_MAS_APP_ID: u64 = 9_999_999_999;
 
 /// Validate config for correctness
-pub fn validate_config(config: &Config) -> Result<()> {
+/// `strict` adds package name checks that may reject unusual but valid names
+pub fn validate_config(config: &Config, strict: bool) -> Result<()> {
+    // Out-of-range settings (e.g. max_parallel = 0)
+    config.settings.validate()?;
+
     // Check for dependency cycles
     check_dependency_cycles(config)?;
 
     // Validate install scripts have binary OR check
     validate_install_scripts(config)?;
 
+    // Catch ids/names that would only fail deep inside an install
+    validate_mas_apps(config)?;
+    validate_package_names(config, strict)?;
+
+    // Group members must point at declared packages
+    validate_groups(config)?;
+
+    Ok(())
+}
+
+/// Reject keys that serde would silently ignore (e.g. `[brew] formula = [...]`)
+/// Unknown top-level sections only warn, so configs written for newer
+/// managers still load. Each `[profiles.<name>]` is checked the same way
+pub fn check_unknown_keys(doc: &DocumentMut) -> Result<()> {
+    check_sections(doc.as_table(), "")
+}
+
+/// Check the sections of the config, or of one profile (`prefix` = "profiles.<name>.")
+fn check_sections(sections: &dyn TableLike, prefix: &str) -> Result<()> {
+    for (section, item) in sections.iter() {
+        // A profile's `lists` setting, checked when the profile is parsed
+        if section == "lists" && !prefix.is_empty() {
+            continue;
+        }
+
+        // [[groups]] is an array of tables, not a section
+        if section == "groups" {
+            check_nested_keys(&format!("{}groups", prefix), item, GROUP_KEYS)?;
+            continue;
+        }
+
+        if section == "profiles" {
+            if !prefix.is_empty() {
+                anyhow::bail!("[{}profiles]: profiles can't be nested", prefix);
+            }
+            let Some(profiles) = item.as_table_like() else {
+                anyhow::bail!("Config section [profiles] must be a table");
+            };
+            for (name, profile) in profiles.iter() {
+                let Some(profile) = profile.as_table_like() else {
+                    anyhow::bail!("Profile [profiles.{}] must be a table", name);
+                };
+                check_sections(profile, &format!("profiles.{}.", name))?;
+            }
+            continue;
+        }
+
+        let Some(known) = known_section_keys(section) else {
+            log::warn!("Ignoring unknown config section [{}{}]", prefix, section);
+            continue;
+        };
+
+        let Some(table) = item.as_table_like() else {
+            anyhow::bail!("Config section [{}{}] must be a table", prefix, section);
+        };
+
+        for (key, value) in table.iter() {
+            if !known.contains(&key) {
+                anyhow::bail!(
+                    "Unknown key '{}' in [{}{}] (valid keys: {})",
+                    key,
+                    prefix,
+                    section,
+                    known.join(", ")
+                );
+            }
+
+            if let Some((_, _, nested)) = NESTED_KEYS
+                .iter()
+                .find(|(s, k, _)| *s == section && *k == key)
+            {
+                check_nested_keys(&format!("{}{}.{}", prefix, section, key), value, nested)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn known_section_keys(section: &str) -> Option<&'static [&'static str]> {
+    if let Some((_, keys)) = SECTION_KEYS.iter().find(|(name, _)| *name == section) {
+        return Some(keys);
+    }
+    PACKAGE_MANAGERS
+        .iter()
+        .any(|m| m.name == section)
+        .then_some(GENERATED_MANAGER_KEYS)
+}
+
+/// Catch registry managers whose section doesn't reach the rest of macup:
+/// a `[name]` table that `Config::get_manager_config` can't return (a
+/// `new manager` edit missing from schema.rs), or one whose listed packages
+/// `has_packages` doesn't see. Either way the section would be silently skipped
+pub fn check_manager_wiring(doc: &DocumentMut, config: &Config) -> Result<()> {
+    for meta in PACKAGE_MANAGERS {
+        let Some(table) = doc.get(meta.name).and_then(Item::as_table_like) else {
+            continue;
+        };
+
+        let Some(section) = config.get_manager_config(meta.name) else {
+            anyhow::bail!(
+                "[{}] is a registered manager but Config::get_manager_config has no arm for it \
+                 (src/config/schema.rs) - its packages would be ignored",
+                meta.name
+            );
+        };
+
+        let listed = table.iter().find(|(key, value)| {
+            let non_empty = value.as_array().is_some_and(|array| !array.is_empty())
+                || value
+                    .as_array_of_tables()
+                    .is_some_and(|array| !array.is_empty());
+            *key != "depends_on" && non_empty
+        });
+        if let Some((key, _)) = listed {
+            if !section.has_packages() {
+                anyhow::bail!(
+                    "[{}] lists packages under '{}' but its has_packages() reports none \
+                     (src/config/schema.rs)",
+                    meta.name,
+                    key
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check a nested table, or each table in an array of tables / inline tables
+fn check_nested_keys(location: &str, value: &Item, known: &[&str]) -> Result<()> {
+    let tables: Vec<&dyn TableLike> = match value {
+        Item::Table(table) => vec![table as &dyn TableLike],
+        Item::Value(toml_edit::Value::InlineTable(table)) => vec![table as &dyn TableLike],
+        Item::ArrayOfTables(array) => array.iter().map(|t| t as &dyn TableLike).collect(),
+        Item::Value(toml_edit::Value::Array(array)) => array
+            .iter()
+            .filter_map(|v| v.as_inline_table())
+            .map(|t| t as &dyn TableLike)
+            .collect(),
+        _ => return Ok(()),
+    };
+
+    for table in tables {
+        for (nested_key, _) in table.iter() {
+            if !known.contains(&nested_key) {
+                anyhow::bail!(
+                    "Unknown key '{}' in [{}] (valid keys: {})",
+                    nested_key,
+                    location,
+                    known.join(", ")
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Group names must be unique and every member a declared "manager:name"
+fn validate_groups(config: &Config) -> Result<()> {
+    if config.groups.is_empty() {
+        return Ok(());
+    }
+
+    let declared = config.member_keys()?;
+    let mut names = HashSet::new();
+
+    for group in &config.groups {
+        if !names.insert(group.name.as_str()) {
+            anyhow::bail!("Duplicate group name '{}'", group.name);
+        }
+
+        for member in &group.members {
+            let Some(key) = parse_member(member) else {
+                anyhow::bail!(
+                    "Group '{}': member '{}' must be written as manager:name (e.g. brew:node)",
+                    group.name,
+                    member
+                );
+            };
+            if !declared.contains(&key) {
+                anyhow::bail!(
+                    "Group '{}': member '{}' is not declared in the config",
+                    group.name,
+                    member
+                );
+            }
+        }
+    }
+
     Ok(())
 }
 
-/// Validate that install scripts have at least binary or check defined
+/// Validate that install scripts have binary, check or once defined,
+/// and that their depends_on names existing scripts without cycles
 fn validate_install_scripts(config: &Config) -> Result<()> {
     if let Some(install) = &config.install {
+        let names: HashSet<_> = install.scripts.iter().map(|s| s.name.as_str()).collect();
+
         for script in &install.scripts {
-            if script.binary.is_none() && script.check.is_none() {
+            if script.binary.is_none() && script.check.is_none() && !script.once {
                 anyhow::bail!(
-                    "Install script '{}' must have either 'binary' or 'check' field defined",
+                    "Install script '{}' must have 'binary', 'check' or 'once = true' defined",
                     script.name
                 );
             }
+
+            if let Some(dep) = script
+                .depends_on
+                .iter()
+                .find(|dep| !names.contains(dep.as_str()))
+            {
+                anyhow::bail!(
+                    "Install script '{}' depends on unknown script '{}'",
+                    script.name,
+                    dep
+                );
+            }
+        }
+
+        let scripts: Vec<_> = install.scripts.iter().collect();
+        InstallManager::order_scripts(&scripts)?;
+    }
+    Ok(())
+}
+
+/// Validate mas app ids are plausible App Store ids
+fn validate_mas_apps(config: &Config) -> Result<()> {
+    if let Some(mas) = &config.mas {
+        for app in &mas.apps {
+            if app.id == 0 || app.id > MAX_MAS_APP_ID {
+                anyhow::bail!(
+                    "Invalid App Store id {} for mas app '{}' (find the id with 'mas search')",
+                    app.id,
+                    app.name
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Validate brew/cask/npm/pnpm/yarn/cargo package names
+/// Empty names are always rejected; whitespace and characters no registry
+/// allows only in strict mode
+fn validate_package_names(config: &Config, strict: bool) -> Result<()> {
+    let mut lists: Vec<(&str, &Vec<String>)> = Vec::new();
+    if let Some(brew) = &config.brew {
+        lists.push(("brew.formulae", &brew.formulae));
+        lists.push(("brew.casks", &brew.casks));
+    }
+    if let Some(npm) = &config.npm {
+        lists.push(("npm.global", &npm.global));
+    }
+    if let Some(cargo) = &config.cargo {
+        lists.push(("cargo.packages", &cargo.packages));
+    }
+    if let Some(pnpm) = &config.pnpm {
+        lists.push(("pnpm.packages", &pnpm.packages));
+    }
+    if let Some(yarn) = &config.yarn {
+        lists.push(("yarn.packages", &yarn.packages));
+    }
+
+    for (key, packages) in lists {
+        for package in packages {
+            // npm/cargo entries may carry a binary name ("pkg:binary")
+            let name = package
+                .split_once(':')
+                .map_or(package.as_str(), |(pkg, _)| pkg);
+
+            if name.trim().is_empty() {
+                anyhow::bail!("Empty package name in [{}]", key);
+            }
+
+            if strict {
+                if let Some(c) = name.chars().find(|c| !is_package_name_char(*c)) {
+                    anyhow::bail!(
+                        "Invalid package name '{}' in [{}]: unexpected {}",
+                        package,
+                        key,
+                        if c.is_whitespace() {
+                            "whitespace".to_string()
+                        } else {
+                            format!("character '{}'", c)
+                        }
+                    );
+                }
+            }
         }
     }
+
     Ok(())
 }
 
+/// Characters allowed across brew (tap/formula@version), npm (@scope/name@version)
+/// and cargo package names
+fn is_package_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@' | '/' | '+' | '~')
+}
+
 /// Check for circular dependencies in depends_on
 fn check_dependency_cycles(config: &Config) -> Result<()> {
     let mut deps = HashMap::new();
@@ -91,3 +449,149 @@ fn has_cycle(
     stack.remove(node);
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(toml: &str) -> Result<()> {
+        check_unknown_keys(&toml.parse::<DocumentMut>().unwrap())
+    }
+
+    #[test]
+    fn pipx_section_written_by_import_is_known() {
+        assert!(check("[pipx]\npackages = [\"black\"]").is_ok());
+
+        let err = check("[pipx]\npakages = [\"black\"]").unwrap_err();
+        assert!(err.to_string().contains("pakages"));
+    }
+
+    #[test]
+    fn typod_key_names_the_key_and_section() {
+        let err = check("[brew]\nformula = [\"git\"]").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Unknown key 'formula' in [brew] (valid keys: "));
+
+        let err = check("[[mas.apps]]\nname = \"Xcode\"\nidd = 497799835").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Unknown key 'idd' in [mas.apps]"));
+
+        let err = check("[profiles.work.npm]\nglobals = []").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Unknown key 'globals' in [profiles.work.npm]"));
+    }
+
+    #[test]
+    fn known_keys_and_unknown_sections_pass() {
+        assert!(check(
+            r#"
+            [brew]
+            formulae = ["git"]
+            taps = [{ name = "foo/bar", url = "https://example.com/bar" }]
+
+            [[groups]]
+            name = "dev"
+            members = ["brew:git"]
+
+            [some-future-manager]
+            packages = ["x"]
+            "#
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn aliased_section_and_keys_pass() {
+        assert!(check("[homebrew]\nformulas = [\"git\"]\npin = [\"git\"]").is_ok());
+        assert!(check("[homebrew]\nformula = [\"git\"]").is_err());
+    }
+
+    fn validate(toml: &str, strict: bool) -> Result<()> {
+        validate_config(&toml::from_str(toml).unwrap(), strict)
+    }
+
+    #[test]
+    fn group_members_must_be_declared_packages() {
+        let base = "[brew]\nformulae = [\"node\"]\n\n[[groups]]\nname = \"web\"\n";
+
+        assert!(validate(&format!("{}members = [\"brew:node\"]", base), false).is_ok());
+        let err = validate(&format!("{}members = [\"npm:node\"]", base), false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Group 'web': member 'npm:node' is not declared in the config"
+        );
+        let err = validate(&format!("{}members = [\"node\"]", base), false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Group 'web': member 'node' must be written as manager:name (e.g. brew:node)"
+        );
+    }
+
+    #[test]
+    fn empty_package_name_is_always_rejected() {
+        let err = validate("[npm]\nglobal = [\"zx\", \" :bin\"]", false).unwrap_err();
+        assert_eq!(err.to_string(), "Empty package name in [npm.global]");
+    }
+
+    #[test]
+    fn whitespace_in_a_name_is_rejected_in_strict_mode() {
+        let toml = "[brew]\nformulae = [\"ripgrep\", \"git lfs\"]";
+
+        assert!(validate(toml, false).is_ok());
+        let err = validate(toml, true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid package name 'git lfs' in [brew.formulae]: unexpected whitespace"
+        );
+
+        // Scoped, versioned and tapped names are fine
+        assert!(validate(
+            "[npm]\nglobal = [\"@scope/pkg@1.2.0\"]\n[brew]\nformulae = [\"owner/tap/python@3.12\"]",
+            true
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn mas_ids_must_be_plausible() {
+        let app = |id: u64| format!("[[mas.apps]]\nname = \"Xcode\"\nid = {}", id);
+
+        let err = validate(&app(0), false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid App Store id 0 for mas app 'Xcode' (find the id with 'mas search')"
+        );
+        assert!(validate(&app(MAX_MAS_APP_ID + 1), false).is_err());
+        assert!(validate(&app(497799835), false).is_ok());
+    }
+
+    #[test]
+    fn unwired_manager_section_is_rejected() {
+        let wiring = |doc: &str, seen: &str| {
+            let config: Config = toml::from_str(seen).unwrap();
+            check_manager_wiring(&doc.parse::<DocumentMut>().unwrap(), &config)
+        };
+        let doc = "[pnpm]\npackages = [\"zz-pnpm\"]\n";
+
+        assert!(wiring(doc, doc).is_ok());
+
+        // The section is in the file but never reaches Config (no schema.rs arm)
+        let err = wiring(doc, "").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "[pnpm] is a registered manager but Config::get_manager_config has no arm for it \
+             (src/config/schema.rs) - its packages would be ignored"
+        );
+
+        // The packages are listed but has_packages doesn't see them
+        let err = wiring(doc, "[pnpm]\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "[pnpm] lists packages under 'packages' but its has_packages() reports none \
+             (src/config/schema.rs)"
+        );
+    }
+}
@@ -1,12 +1,17 @@
 use crate::config::Config;
-use crate::executor::{ExecutionPlan, SectionType};
+use crate::executor::{reconcile, state, ExecutionPlan, SectionType};
 use crate::managers::{
+    asdf::AsdfManager, // CODEGEN[asdf]: import
     brew::BrewManager,
     cargo_manager::CargoManager, // CODEGEN[cargo]: import
     install::InstallManager,
     mas::MasManager, // CODEGEN[mas]: import
     npm::NpmManager, // CODEGEN[npm]: import
+    pnpm::PnpmManager, // CODEGEN[pnpm]: import
+    yarn::YarnManager, // CODEGEN[yarn]: import
+    pip::PipManager, // CODEGEN[pip]: import
     // CODEGEN_MARKER: insert_manager_import_here
+    FailureKind,
     Manager,
     ManagerMetadata,
 };
@@ -15,14 +20,173 @@ use anyhow::{bail, Context, Result};
 use colored::Colorize;
 use rayon::prelude::*;
 use std::collections::HashSet;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Print a per-section detail line, unless `--summary-only` was requested.
+/// Warnings and errors use `say!` so they stay visible.
+macro_rules! detail {
+    ($opts:expr) => {
+        if !$opts.summary_only {
+            println!();
+        }
+    };
+    ($opts:expr, $($arg:tt)*) => {
+        if !$opts.summary_only {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Print a line that stays visible in `--summary-only` (warnings, errors).
+/// With `--json-lines` it goes to stderr, keeping stdout for JSON events.
+macro_rules! say {
+    ($opts:expr) => {
+        if $opts.json_lines {
+            eprintln!();
+        } else {
+            println!();
+        }
+    };
+    ($opts:expr, $($arg:tt)*) => {
+        if $opts.json_lines {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Options controlling how a plan is applied
+#[derive(Debug, Default, Clone)]
+pub struct ApplyOptions {
+    /// Only show what would be done, don't make changes
+    pub dry_run: bool,
+
+    /// Ignore settings.dry_run (`--no-dry-run`)
+    pub no_dry_run: bool,
+
+    /// Run the system settings phase
+    pub with_system_settings: bool,
+
+    /// Suppress per-section output, keeping warnings and the final summary
+    pub summary_only: bool,
+
+    /// Override install parallelism for every manager (`--max-parallel`)
+    pub max_parallel: Option<usize>,
+
+    /// Print the execution plan instead of running it
+    pub explain: bool,
+
+    /// Also reject package names with whitespace or illegal characters
+    pub strict: bool,
+
+    /// Re-check the config against the system after applying
+    pub verify_after: bool,
+
+    /// Install scripts to run even if already installed
+    pub rerun: Vec<String>,
+
+    /// Override retries for runtime installs via brew (`--max-retries`)
+    pub max_retries: Option<u32>,
+
+    /// Abort once this many packages have failed (`--max-failures`)
+    pub max_failures: Option<u32>,
+
+    /// Only handle packages whose install name matches this glob
+    pub filter: Option<String>,
+
+    /// Only handle the members of this `[[groups]]` entry
+    pub group: Option<String>,
+
+    /// Only handle the members of `[[groups]]` entries with this tag
+    pub tag: Option<String>,
+
+    /// Only handle this manager's packages (`--manager`)
+    pub manager: Option<String>,
+
+    /// Only handle what this `macup diff --output json` document reports
+    /// missing (`-` = stdin)
+    pub packages_from_diff: Option<PathBuf>,
+
+    /// Run every phase even when its `depends_on` managers are unavailable
+    pub no_deps: bool,
+
+    /// Move formulae that are really casks to `brew.casks` and install them as casks
+    pub auto_fix: bool,
+
+    /// Config file or directory the plan was loaded from (edited by `--auto-fix`)
+    pub config_file: Option<PathBuf>,
+
+    /// Stream one JSON object per package event to stdout (implies `summary_only`)
+    pub json_lines: bool,
+
+    /// Preview each phase and ask whether to run, skip or abort it
+    pub interactive: bool,
+
+    /// Exit early when the config is unchanged since the last successful apply
+    pub changed_only: bool,
+
+    /// Only run these sections' phases (`--only`, empty = all)
+    pub only: Vec<String>,
+
+    /// Don't run these sections' phases (`--skip`)
+    pub skip: Vec<String>,
+
+    /// Run the managers phase, then only install each section's missing runtime
+    pub only_runtimes: bool,
+
+    /// Don't skip phases that completed in a recent failed apply
+    pub recheck: bool,
+
+    /// Config hash to resume by: phases that completed in a recent failed
+    /// apply of it are skipped, and this run's progress is recorded
+    pub resume_key: Option<String>,
+
+    /// Write the computed plan here and exit instead of applying
+    pub save_plan: Option<PathBuf>,
+
+    /// Apply a plan saved with `save_plan` instead of computing one
+    pub plan_file: Option<PathBuf>,
+
+    /// Replay `plan_file` even though the config changed since it was saved
+    pub force: bool,
+
+    /// `[profiles.<name>]` layered over the config (`--profile`)
+    pub profile: Option<String>,
+
+    /// Install the package lists of a replayed plan as saved, without
+    /// checking what's installed (`--plan-file`)
+    pub replay: bool,
+}
+
 /// Tracks execution context and state
 #[derive(Debug, Default)]
 struct ExecutionContext {
     available_managers: HashSet<String>,
     skipped_phases: Vec<SkippedPhase>,
+    /// Index of the phase currently running
+    current_phase: Option<usize>,
+    /// Set when a phase error aborts the run
+    aborted: bool,
+    /// Phases never reached because the run aborted
+    not_attempted: Vec<String>,
+    /// Package phases a recent failed apply completed (skipped this time)
+    resumed: HashSet<String>,
+    /// Package phases that finished without failures in this run
+    completed_phases: Vec<String>,
+    /// Set by a phase that failed without recording `package_failures`
+    phase_failed: bool,
+}
+
+impl ExecutionContext {
+    /// Remember a resumable phase that ran without failures, for a re-run
+    /// after this apply fails elsewhere
+    fn finish_phase(&mut self, phase: &str, resumable: bool, failed: bool) {
+        if resumable && !failed && !self.phase_failed {
+            self.completed_phases.push(phase.to_string());
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -36,6 +200,10 @@ struct SkippedPhase {
 struct ApplyErrors {
     manager_failures: Vec<ManagerFailure>,
     package_failures: Vec<PackageFailure>,
+    /// Dry run only: (section, packages it would install), in phase order
+    would_install: Vec<(String, usize)>,
+    /// --max-failures: abort as soon as this many packages have failed
+    max_failures: Option<u32>,
 }
 
 #[derive(Debug)]
@@ -49,19 +217,56 @@ struct PackageFailure {
     package: String,
     manager: String,
     reason: String,
+    kind: FailureKind,
 }
 
 impl ApplyErrors {
     fn has_failures(&self) -> bool {
         !self.manager_failures.is_empty() || !self.package_failures.is_empty()
     }
+
+    /// Record a package failure, aborting once --max-failures is reached
+    fn record(&mut self, failure: PackageFailure) -> Result<()> {
+        self.package_failures.push(failure);
+        if let Some(max) = self.max_failures {
+            if self.package_failures.len() >= max as usize {
+                bail!(
+                    "Aborting: {} package failures reached --max-failures {}",
+                    self.package_failures.len(),
+                    max
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Record every package of a manager call that failed as a whole
+    /// (e.g. signed out of the App Store), so none of them counts as installed
+    fn fail_all(&mut self, manager: &str, packages: &[String], e: &anyhow::Error) -> Result<()> {
+        for package in packages {
+            self.record(PackageFailure {
+                package: package.clone(),
+                manager: manager.to_string(),
+                reason: e.to_string(),
+                kind: FailureKind::classify(e, ""),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Count what a section would install, for the dry-run summary
+    fn tally(&mut self, opts: &ApplyOptions, section: &str, count: usize) {
+        if opts.dry_run {
+            self.would_install.push((section.to_string(), count));
+        }
+    }
 }
 
 // CODEGEN_START[mas]: handler_function
 /// Handler for Mas package manager phase
 fn apply_mas_phase(
     config: &Config,
-    dry_run: bool,
+    opts: &ApplyOptions,
     max_parallel: usize,
     fail_fast: bool,
     errors: &mut ApplyErrors,
@@ -73,7 +278,8 @@ fn apply_mas_phase(
 
     let meta = ManagerMetadata::get_by_name("mas").unwrap();
 
-    println!(
+    detail!(
+        opts,
         "{}",
         format!("{} Installing {}...", meta.icon, meta.display_name)
             .bright_cyan()
@@ -82,36 +288,52 @@ fn apply_mas_phase(
 
     // Auto-install mas if not found
     if !crate::utils::command_exists(meta.runtime_command) {
-        println!(
+        if runtime_install_disabled(
+            config,
+            meta,
+            opts,
+            fail_fast,
+            errors,
+            mas_config
+                .apps
+                .iter()
+                .map(|app| format!("{} ({})", app.name, app.id)),
+        )? {
+            return Ok(());
+        }
+
+        say!(
+            opts,
             "  ⚠️  {} not found, installing {} via brew...",
             meta.runtime_command.yellow(),
             meta.runtime_name.cyan()
         );
 
-        if dry_run {
-            println!("    → Would run: brew install {}", meta.brew_formula);
+        if opts.dry_run {
+            detail!(opts, "    → Would run: brew install {}", meta.brew_formula);
         } else {
-            match install_runtime_via_brew(meta.brew_formula) {
+            match install_runtime_via_brew(meta.brew_formula, config, opts) {
                 Ok(_) => {
-                    println!("  ✓ {} installed", meta.runtime_name.green());
+                    detail!(opts, "  ✓ {} installed", meta.runtime_name.green());
                 }
                 Err(e) => {
-                    println!("  ❌ Failed to install {}: {}", meta.runtime_name, e);
+                    say!(opts, "  ❌ Failed to install {}: {}", meta.runtime_name, e);
 
                     // Record failures for all apps
                     for app in &mas_config.apps {
-                        errors.package_failures.push(PackageFailure {
+                        errors.record(PackageFailure {
                             package: format!("{} ({})", app.name, app.id),
                             manager: meta.name.to_string(),
                             reason: format!("{} installation failed: {}", meta.runtime_name, e),
-                        });
+                            kind: FailureKind::classify(&e, ""),
+                        })?;
                     }
 
                     if fail_fast {
                         bail!("Failed to install {}", meta.runtime_name);
                     }
 
-                    println!();
+                    detail!(opts);
                     return Ok(());
                 }
             }
@@ -121,45 +343,46 @@ fn apply_mas_phase(
     // Install apps - check missing first
     let mas = MasManager::new(max_parallel);
 
-    // Filter missing apps in parallel
-    let missing_apps: Vec<_> = mas_config
-        .apps
-        .par_iter()
-        .filter(|app| {
-            !mas.is_package_installed(&app.id.to_string())
-                .unwrap_or(false)
-        })
-        .collect();
+    // Filter missing apps with the check `macup diff` runs
+    let missing_apps = missing_unless_replay(config, opts, meta.name)
+        .mas
+        .map(|mas| mas.apps)
+        .unwrap_or_default();
+
+    errors.tally(opts, meta.name, missing_apps.len());
 
     if missing_apps.is_empty() {
-        println!("  ✓ All apps already installed");
-        println!();
+        detail!(opts, "  ✓ All apps already installed");
+        detail!(opts);
         return Ok(());
     }
 
-    if dry_run {
-        println!("  Apps ({} to install):", missing_apps.len());
+    if opts.dry_run {
+        detail!(opts, "  Apps ({} to install):", missing_apps.len());
         for app in &missing_apps {
-            println!("    → {} ({})", app.name, app.id);
+            let command = mas.describe_install(&app.id.to_string());
+            detail!(opts, "    → {} ({})", command.join(" "), app.name);
         }
     } else {
         let app_ids: Vec<String> = missing_apps.iter().map(|app| app.id.to_string()).collect();
 
         match mas.install_packages(&app_ids) {
             Ok(result) => {
-                print_result("Apps", &result);
+                print_result(meta.name, &result, opts);
 
                 // Track failures
-                for (pkg, reason) in &result.failed {
-                    errors.package_failures.push(PackageFailure {
+                for (pkg, reason, kind) in &result.failed {
+                    errors.record(PackageFailure {
                         package: pkg.clone(),
                         manager: meta.name.to_string(),
                         reason: reason.clone(),
-                    });
+                        kind: *kind,
+                    })?;
                 }
             }
             Err(e) => {
-                println!("  ❌ {} installation failed: {}", meta.name, e);
+                say!(opts, "  ❌ {} installation failed: {}", meta.name, e);
+                errors.fail_all(meta.name, &app_ids, &e)?;
 
                 if fail_fast {
                     bail!("{} installation failed", meta.name);
@@ -168,7 +391,7 @@ fn apply_mas_phase(
         }
     }
 
-    println!();
+    detail!(opts);
     Ok(())
 }
 // CODEGEN_END[mas]: handler_function
@@ -177,7 +400,7 @@ fn apply_mas_phase(
 /// Handler for Npm package manager phase
 fn apply_npm_phase(
     config: &Config,
-    dry_run: bool,
+    opts: &ApplyOptions,
     max_parallel: usize,
     fail_fast: bool,
     errors: &mut ApplyErrors,
@@ -189,7 +412,8 @@ fn apply_npm_phase(
 
     let meta = ManagerMetadata::get_by_name("npm").unwrap();
 
-    println!(
+    detail!(
+        opts,
         "{}",
         format!("{} Installing {}...", meta.icon, meta.display_name)
             .bright_cyan()
@@ -198,36 +422,49 @@ fn apply_npm_phase(
 
     // Auto-install node if npm not found
     if !crate::utils::command_exists(meta.runtime_command) {
-        println!(
+        if runtime_install_disabled(
+            config,
+            meta,
+            opts,
+            fail_fast,
+            errors,
+            npm_config.global.clone(),
+        )? {
+            return Ok(());
+        }
+
+        say!(
+            opts,
             "  ⚠️  {} not found, installing {} via brew...",
             meta.runtime_command.yellow(),
             meta.runtime_name.cyan()
         );
 
-        if dry_run {
-            println!("    → Would run: brew install {}", meta.brew_formula);
+        if opts.dry_run {
+            detail!(opts, "    → Would run: brew install {}", meta.brew_formula);
         } else {
-            match install_runtime_via_brew(meta.brew_formula) {
+            match install_runtime_via_brew(meta.brew_formula, config, opts) {
                 Ok(_) => {
-                    println!("  ✓ {} installed", meta.runtime_name.green());
+                    detail!(opts, "  ✓ {} installed", meta.runtime_name.green());
                 }
                 Err(e) => {
-                    println!("  ❌ Failed to install {}: {}", meta.runtime_name, e);
+                    say!(opts, "  ❌ Failed to install {}: {}", meta.runtime_name, e);
 
                     // Record failures for all packages
                     for pkg in &npm_config.global {
-                        errors.package_failures.push(PackageFailure {
+                        errors.record(PackageFailure {
                             package: pkg.clone(),
                             manager: meta.name.to_string(),
                             reason: format!("{} installation failed: {}", meta.runtime_name, e),
-                        });
+                            kind: FailureKind::classify(&e, ""),
+                        })?;
                     }
 
                     if fail_fast {
                         bail!("Failed to install {}", meta.runtime_name);
                     }
 
-                    println!();
+                    detail!(opts);
                     return Ok(());
                 }
             }
@@ -237,41 +474,47 @@ fn apply_npm_phase(
     // Install packages - check missing first
     let npm = NpmManager::new(max_parallel);
 
-    // Filter missing packages in parallel
-    let missing_packages: Vec<_> = npm_config
-        .global
-        .par_iter()
-        .filter(|pkg| !npm.is_package_installed(pkg).unwrap_or(false))
-        .cloned()
-        .collect();
+    // Filter missing packages with the check `macup diff` runs
+    let missing_packages = missing_unless_replay(config, opts, meta.name)
+        .npm
+        .map(|npm| npm.global)
+        .unwrap_or_default();
+
+    errors.tally(opts, meta.name, missing_packages.len());
 
     if missing_packages.is_empty() {
-        println!("  ✓ All packages already installed");
-        println!();
+        detail!(opts, "  ✓ All packages already installed");
+        detail!(opts);
         return Ok(());
     }
 
-    if dry_run {
-        println!("  Global packages ({} to install):", missing_packages.len());
+    if opts.dry_run {
+        detail!(
+            opts,
+            "  Global packages ({} to install):",
+            missing_packages.len()
+        );
         for pkg in &missing_packages {
-            println!("    → {}", pkg);
+            detail_command(opts, &npm.describe_install(pkg));
         }
     } else {
         match npm.install_packages(&missing_packages) {
             Ok(result) => {
-                print_result("NPM packages", &result);
+                print_result(meta.name, &result, opts);
 
                 // Track failures
-                for (pkg, reason) in &result.failed {
-                    errors.package_failures.push(PackageFailure {
+                for (pkg, reason, kind) in &result.failed {
+                    errors.record(PackageFailure {
                         package: pkg.clone(),
                         manager: meta.name.to_string(),
                         reason: reason.clone(),
-                    });
+                        kind: *kind,
+                    })?;
                 }
             }
             Err(e) => {
-                println!("  ❌ {} installation failed: {}", meta.name, e);
+                say!(opts, "  ❌ {} installation failed: {}", meta.name, e);
+                errors.fail_all(meta.name, &missing_packages, &e)?;
 
                 if fail_fast {
                     bail!("{} installation failed", meta.name);
@@ -280,16 +523,16 @@ fn apply_npm_phase(
         }
     }
 
-    println!();
+    detail!(opts);
     Ok(())
 }
 // CODEGEN_END[npm]: handler_function
 
 // CODEGEN_START[cargo]: handler_function
-/// Handler for Cargo package manager phase  
+/// Handler for Cargo package manager phase
 fn apply_cargo_phase(
     config: &Config,
-    dry_run: bool,
+    opts: &ApplyOptions,
     max_parallel: usize,
     fail_fast: bool,
     errors: &mut ApplyErrors,
@@ -301,7 +544,8 @@ fn apply_cargo_phase(
 
     let meta = ManagerMetadata::get_by_name("cargo").unwrap();
 
-    println!(
+    detail!(
+        opts,
         "{}",
         format!("{} Installing {}...", meta.icon, meta.display_name)
             .bright_cyan()
@@ -310,68 +554,82 @@ fn apply_cargo_phase(
 
     // Auto-install rust if cargo not found
     if !crate::utils::command_exists(meta.runtime_command) {
+        if runtime_install_disabled(
+            config,
+            meta,
+            opts,
+            fail_fast,
+            errors,
+            cargo_config.packages.clone(),
+        )? {
+            return Ok(());
+        }
+
         // Check if rustup exists first
         if crate::utils::command_exists("rustup") {
-            println!("  ⚠️  cargo not found, installing via rustup...");
+            say!(opts, "  ⚠️  cargo not found, installing via rustup...");
 
-            if !dry_run {
+            if !opts.dry_run {
                 match Command::new("rustup")
                     .args(["toolchain", "install", "stable"])
                     .status()
                 {
                     Ok(status) if status.success() => {
-                        println!("  ✓ {} installed", "rust".green());
+                        detail!(opts, "  ✓ {} installed", "rust".green());
                     }
                     _ => {
-                        println!("  ❌ Failed to install rust via rustup");
+                        say!(opts, "  ❌ Failed to install rust via rustup");
 
                         for pkg in &cargo_config.packages {
-                            errors.package_failures.push(PackageFailure {
+                            errors.record(PackageFailure {
                                 package: pkg.clone(),
                                 manager: meta.name.to_string(),
                                 reason: "rust installation via rustup failed".to_string(),
-                            });
+                                kind: FailureKind::Other,
+                            })?;
                         }
 
                         if fail_fast {
                             bail!("Failed to install rust via rustup");
                         }
 
-                        println!();
+                        detail!(opts);
                         return Ok(());
                     }
                 }
             }
         } else {
-            println!(
+            say!(
+                opts,
                 "  ⚠️  {} not found, installing {} via brew...",
                 meta.runtime_command.yellow(),
                 meta.runtime_name.cyan()
             );
 
-            if dry_run {
-                println!("    → Would run: brew install {}", meta.brew_formula);
+            if opts.dry_run {
+                detail!(opts, "    → Would run: brew install {}", meta.brew_formula);
             } else {
-                match install_runtime_via_brew(meta.brew_formula) {
+                match install_runtime_via_brew(meta.brew_formula, config, opts) {
                     Ok(_) => {
-                        println!("  ✓ {} installed", meta.runtime_name.green());
+                        detail!(opts, "  ✓ {} installed", meta.runtime_name.green());
                     }
                     Err(e) => {
-                        println!("  ❌ Failed to install {}: {}", meta.runtime_name, e);
+                        say!(opts, "  ❌ Failed to install {}: {}", meta.runtime_name, e);
 
                         for pkg in &cargo_config.packages {
-                            errors.package_failures.push(PackageFailure {
+                            errors.record(PackageFailure {
                                 package: pkg.clone(),
                                 manager: meta.name.to_string(),
                                 reason: format!("{} installation failed: {}", meta.runtime_name, e),
-                            });
+                                kind: FailureKind::classify(&e, ""),
+                            })?;
                         }
 
                         if fail_fast {
                             bail!("Failed to install {}", meta.runtime_name);
                         }
 
-                        println!();
+                        detail!(opts);
                         return Ok(());
                     }
                 }
@@ -382,41 +640,43 @@ fn apply_cargo_phase(
     // Install packages - check missing first
     let cargo_mgr = CargoManager::new(max_parallel);
 
-    // Filter missing packages in parallel
-    let missing_packages: Vec<_> = cargo_config
-        .packages
-        .par_iter()
-        .filter(|pkg| !cargo_mgr.is_package_installed(pkg).unwrap_or(false))
-        .cloned()
-        .collect();
+    // Filter missing packages with the check `macup diff` runs
+    let missing_packages = missing_unless_replay(config, opts, meta.name)
+        .cargo
+        .map(|cargo| cargo.packages)
+        .unwrap_or_default();
+
+    errors.tally(opts, meta.name, missing_packages.len());
 
     if missing_packages.is_empty() {
-        println!("  ✓ All packages already installed");
-        println!();
+        detail!(opts, "  ✓ All packages already installed");
+        detail!(opts);
         return Ok(());
     }
 
-    if dry_run {
-        println!("  Packages ({} to install):", missing_packages.len());
+    if opts.dry_run {
+        detail!(opts, "  Packages ({} to install):", missing_packages.len());
         for pkg in &missing_packages {
-            println!("    → {}", pkg);
+            detail_command(opts, &cargo_mgr.describe_install(pkg));
         }
     } else {
         match cargo_mgr.install_packages(&missing_packages) {
             Ok(result) => {
-                print_result("Cargo packages", &result);
+                print_result(meta.name, &result, opts);
 
                 // Track failures
-                for (pkg, reason) in &result.failed {
-                    errors.package_failures.push(PackageFailure {
+                for (pkg, reason, kind) in &result.failed {
+                    errors.record(PackageFailure {
                         package: pkg.clone(),
                         manager: meta.name.to_string(),
                         reason: reason.clone(),
-                    });
+                        kind: *kind,
+                    })?;
                 }
             }
             Err(e) => {
-                println!("  ❌ {} installation failed: {}", meta.name, e);
+                say!(opts, "  ❌ {} installation failed: {}", meta.name, e);
+                errors.fail_all(meta.name, &missing_packages, &e)?;
 
                 if fail_fast {
                     bail!("{} installation failed", meta.name);
@@ -425,325 +685,1207 @@ fn apply_cargo_phase(
         }
     }
 
-    println!();
+    detail!(opts);
     Ok(())
 }
 // CODEGEN_END[cargo]: handler_function
 
-
-
-// CODEGEN_MARKER: insert_handler_function_here
-
-pub fn apply_plan(
+// CODEGEN_START[asdf]: handler_function
+/// Handler for Asdf package manager phase
+fn apply_asdf_phase(
     config: &Config,
-    plan: &ExecutionPlan,
-    dry_run: bool,
-    with_system_settings: bool,
+    opts: &ApplyOptions,
+    max_parallel: usize,
+    fail_fast: bool,
+    errors: &mut ApplyErrors,
 ) -> Result<()> {
-    let max_parallel = config.settings.max_parallel;
-    let fail_fast = config.settings.fail_fast;
-    let mut errors = ApplyErrors::default();
-    let mut ctx = ExecutionContext::default();
-
-    println!("{}", "=".repeat(50).bright_blue());
-    println!("{}", "Starting macup apply".bright_blue().bold());
-    println!("{}", "=".repeat(50).bright_blue());
-    println!();
-
-    if dry_run {
-        println!("{}", "[DRY RUN MODE]".yellow().bold());
-        println!();
-    }
-
-    for phase in &plan.phases {
-        // Check if dependencies are satisfied
-        if !can_execute_phase(phase, &ctx.available_managers) {
-            let missing_deps: Vec<_> = phase
-                .depends_on
-                .iter()
-                .filter(|dep| !ctx.available_managers.contains(*dep))
-                .collect();
+    let asdf_config = match &config.asdf {
+        Some(cfg) if !cfg.tools.is_empty() => cfg,
+        _ => return Ok(()), // No asdf config or no tools
+    };
 
-            let reason = format!(
-                "Missing dependencies: {}",
-                missing_deps
-                    .iter()
-                    .map(|s| s.as_str())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            );
+    let meta = ManagerMetadata::get_by_name("asdf").unwrap();
 
-            ctx.skipped_phases.push(SkippedPhase {
-                name: phase.name.clone(),
-                reason: reason.clone(),
-            });
+    detail!(
+        opts,
+        "{}",
+        format!("{} Installing {}...", meta.icon, meta.display_name)
+            .bright_cyan()
+            .bold()
+    );
 
-            println!(
-                "  ⚠️  Skipping {} phase: {}",
-                phase.name.yellow(),
-                reason.yellow()
-            );
-            println!();
-            continue;
+    // Auto-install runtime if not found
+    if !crate::utils::command_exists(meta.runtime_command) {
+        if runtime_install_disabled(
+            config,
+            meta,
+            opts,
+            fail_fast,
+            errors,
+            asdf_config.tools.iter().map(|tool| tool.spec()),
+        )? {
+            return Ok(());
         }
 
-        match &phase.section_type {
-            SectionType::Managers => {
-                println!(
-                    "{}",
-                    format!("📦 Checking package managers...")
-                        .bright_cyan()
-                        .bold()
-                );
-
-                // Get required managers (auto-detected)
-                let required_managers = config.get_required_managers();
-
-                if required_managers.is_empty() {
-                    println!("  (No package managers required)");
-                } else {
-                    for manager_name in &required_managers {
-                        match check_and_install_manager(manager_name, dry_run) {
-                            Ok(_) => {
-                                // Track successfully installed/available manager
-                                ctx.available_managers.insert(manager_name.clone());
-                            }
-                            Err(e) => {
-                                println!("  ❌ Failed to install {}: {}", manager_name.red(), e);
-
-                                errors.manager_failures.push(ManagerFailure {
-                                    name: manager_name.clone(),
-                                    reason: e.to_string(),
-                                });
+        say!(
+            opts,
+            "  ⚠️  {} not found, installing {} via brew...",
+            meta.runtime_command.yellow(),
+            meta.runtime_name.cyan()
+        );
 
-                                if fail_fast {
-                                    bail!("Manager installation failed: {}", manager_name);
-                                }
-                            }
-                        }
-                    }
+        if opts.dry_run {
+            detail!(opts, "    → Would run: brew install {}", meta.brew_formula);
+        } else {
+            match install_runtime_via_brew(meta.brew_formula, config, opts) {
+                Ok(_) => {
+                    detail!(opts, "  ✓ {} installed", meta.runtime_name.green());
                 }
+                Err(e) => {
+                    say!(opts, "  ❌ Failed to install {}: {}", meta.runtime_name, e);
 
-                println!();
-            }
-
-            SectionType::Install => {
-                if let Some(install_config) = &config.install {
-                    println!(
-                        "{}",
-                        format!("🔧 Running install scripts...")
-                            .bright_cyan()
-                            .bold()
-                    );
-
-                    let install_mgr = InstallManager::new();
-
-                    // Filter missing scripts in parallel
-                    let missing_scripts: Vec<_> = install_config
-                        .scripts
-                        .par_iter()
-                        .filter(|script| !install_mgr.is_installed(script).unwrap_or(false))
-                        .collect();
+                    // Record failures for all tools
+                    for tool in &asdf_config.tools {
+                        errors.record(PackageFailure {
+                            package: tool.spec(),
+                            manager: meta.name.to_string(),
+                            reason: format!("{} installation failed: {}", meta.runtime_name, e),
+                            kind: FailureKind::classify(&e, ""),
+                        })?;
+                    }
 
-                    if missing_scripts.is_empty() {
-                        println!("  ✓ All scripts already installed");
-                        println!();
-                    } else {
-                        if dry_run {
-                            println!("  Scripts ({} to run):", missing_scripts.len());
-                            for script in &missing_scripts {
-                                println!("    → {}", script.name);
-                            }
-                            println!();
-                        } else {
-                            // Convert back to owned for apply_scripts
-                            let scripts_to_run: Vec<_> =
-                                missing_scripts.into_iter().cloned().collect();
-                            install_mgr.apply_scripts(&scripts_to_run)?;
-                            println!();
-                        }
+                    if fail_fast {
+                        bail!("Failed to install {}", meta.runtime_name);
                     }
+
+                    detail!(opts);
+                    return Ok(());
                 }
             }
+        }
+    }
 
-            SectionType::Brew => {
-                if let Some(brew_config) = &config.brew {
-                    println!(
-                        "{}",
-                        format!("🍺 Installing Homebrew packages...")
-                            .bright_cyan()
-                            .bold()
-                    );
+    // Install tools - check missing first
+    let asdf_mgr = AsdfManager::new(max_parallel);
 
-                    let brew = BrewManager::new(max_parallel);
-
-                    // Check and install taps
-                    if !brew_config.taps.is_empty() {
-                        let installed_taps = brew.list_taps().unwrap_or_default();
-                        let missing_taps: Vec<_> = brew_config
-                            .taps
-                            .par_iter()
-                            .filter(|tap| !installed_taps.contains(*tap))
-                            .cloned()
-                            .collect();
-
-                        if !missing_taps.is_empty() {
-                            if dry_run {
-                                println!("  Taps ({} to add):", missing_taps.len());
-                                for tap in &missing_taps {
-                                    println!("    → {}", tap);
-                                }
-                            } else {
-                                let result = brew.add_taps(&missing_taps)?;
-                                print_result("Taps", &result);
-                            }
-                        }
-                    }
+    // Filter missing tools with the check `macup diff` runs
+    let missing_tools: Vec<String> = missing_unless_replay(config, opts, meta.name)
+        .asdf
+        .map(|asdf| asdf.tools.iter().map(|tool| tool.spec()).collect())
+        .unwrap_or_default();
 
-                    // Check and install formulae
-                    if !brew_config.formulae.is_empty() {
-                        let missing_formulae: Vec<_> = brew_config
-                            .formulae
-                            .par_iter()
-                            .filter(|pkg| !brew.is_package_installed(pkg).unwrap_or(false))
-                            .cloned()
-                            .collect();
-
-                        if !missing_formulae.is_empty() {
-                            if dry_run {
-                                println!("  Formulae ({} to install):", missing_formulae.len());
-                                for pkg in &missing_formulae {
-                                    println!("    → {}", pkg);
-                                }
-                            } else {
-                                let result = brew.install_formulae(&missing_formulae)?;
-                                print_result("Formulae", &result);
-                            }
-                        }
-                    }
+    errors.tally(opts, meta.name, missing_tools.len());
 
-                    // Check and install casks
-                    if !brew_config.casks.is_empty() {
-                        let installed_casks = brew.list_casks().unwrap_or_default();
-                        let missing_casks: Vec<_> = brew_config
-                            .casks
-                            .par_iter()
-                            .filter(|pkg| !installed_casks.contains(*pkg))
-                            .cloned()
-                            .collect();
-
-                        if !missing_casks.is_empty() {
-                            if dry_run {
-                                println!("  Casks ({} to install):", missing_casks.len());
-                                for pkg in &missing_casks {
-                                    println!("    → {}", pkg);
-                                }
-                            } else {
-                                let result = brew.install_casks(&missing_casks)?;
-                                print_result("Casks", &result);
-                            }
-                        }
-                    }
+    if missing_tools.is_empty() {
+        detail!(opts, "  ✓ All tools already installed");
+        detail!(opts);
+        return Ok(());
+    }
+
+    if opts.dry_run {
+        detail!(opts, "  Tools ({} to install):", missing_tools.len());
+        for spec in &missing_tools {
+            detail_command(opts, &asdf_mgr.describe_install(spec));
+        }
+    } else {
+        match asdf_mgr.install_packages(&missing_tools) {
+            Ok(result) => {
+                print_result(meta.name, &result, opts);
 
-                    println!();
+                // Track failures
+                for (pkg, reason, kind) in &result.failed {
+                    errors.record(PackageFailure {
+                        package: pkg.clone(),
+                        manager: meta.name.to_string(),
+                        reason: reason.clone(),
+                        kind: *kind,
+                    })?;
                 }
             }
+            Err(e) => {
+                say!(opts, "  ❌ {} installation failed: {}", meta.name, e);
+                errors.fail_all(meta.name, &missing_tools, &e)?;
 
-            // CODEGEN_START[mas]: match_arm
-            SectionType::Mas => {
-                apply_mas_phase(config, dry_run, max_parallel, fail_fast, &mut errors)?;
+                if fail_fast {
+                    bail!("{} installation failed", meta.name);
+                }
             }
-            // CODEGEN_END[mas]: match_arm
+        }
+    }
 
-            // CODEGEN_START[npm]: match_arm
-            SectionType::Npm => {
-                apply_npm_phase(config, dry_run, max_parallel, fail_fast, &mut errors)?;
-            }
-            // CODEGEN_END[npm]: match_arm
+    detail!(opts);
+    Ok(())
+}
+// CODEGEN_END[asdf]: handler_function
 
-            // CODEGEN_START[cargo]: match_arm
-            SectionType::Cargo => {
-                apply_cargo_phase(config, dry_run, max_parallel, fail_fast, &mut errors)?;
+// CODEGEN_START[pnpm]: handler_function
+/// Handler for Pnpm package manager phase
+fn apply_pnpm_phase(
+    config: &Config,
+    opts: &ApplyOptions,
+    max_parallel: usize,
+    fail_fast: bool,
+    errors: &mut ApplyErrors,
+) -> Result<()> {
+    let pnpm_config = match &config.pnpm {
+        Some(cfg) if !cfg.packages.is_empty() => cfg,
+        _ => return Ok(()), // No pnpm config or no packages
+    };
+
+    let meta = ManagerMetadata::get_by_name("pnpm").unwrap();
+
+    detail!(
+        opts,
+        "{}",
+        format!("{} Installing {}...", meta.icon, meta.display_name)
+            .bright_cyan()
+            .bold()
+    );
+
+    // Auto-install runtime if not found
+    if !crate::utils::command_exists(meta.runtime_command) {
+        if runtime_install_disabled(
+            config,
+            meta,
+            opts,
+            fail_fast,
+            errors,
+            pnpm_config.packages.clone(),
+        )? {
+            return Ok(());
+        }
+
+        say!(
+            opts,
+            "  ⚠️  {} not found, installing {} via brew...",
+            meta.runtime_command.yellow(),
+            meta.runtime_name.cyan()
+        );
+
+        if opts.dry_run {
+            detail!(opts, "    → Would run: brew install {}", meta.brew_formula);
+        } else {
+            match install_runtime_via_brew(meta.brew_formula, config, opts) {
+                Ok(_) => {
+                    detail!(opts, "  ✓ {} installed", meta.runtime_name.green());
+                }
+                Err(e) => {
+                    say!(opts, "  ❌ Failed to install {}: {}", meta.runtime_name, e);
+
+                    // Record failures for all packages
+                    for pkg in &pnpm_config.packages {
+                        errors.record(PackageFailure {
+                            package: pkg.clone(),
+                            manager: meta.name.to_string(),
+                            reason: format!("{} installation failed: {}", meta.runtime_name, e),
+                            kind: FailureKind::classify(&e, ""),
+                        })?;
+                    }
+
+                    if fail_fast {
+                        bail!("Failed to install {}", meta.runtime_name);
+                    }
+
+                    detail!(opts);
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    // Install packages - check missing first
+    let pnpm_mgr = PnpmManager::new(max_parallel);
+
+    // Filter missing packages with the check `macup diff` runs
+    let missing_packages = missing_unless_replay(config, opts, meta.name)
+        .pnpm
+        .map(|pnpm| pnpm.packages)
+        .unwrap_or_default();
+
+    errors.tally(opts, meta.name, missing_packages.len());
+
+    if missing_packages.is_empty() {
+        detail!(opts, "  ✓ All packages already installed");
+        detail!(opts);
+        return Ok(());
+    }
+
+    if opts.dry_run {
+        detail!(opts, "  Packages ({} to install):", missing_packages.len());
+        for pkg in &missing_packages {
+            detail_command(opts, &pnpm_mgr.describe_install(pkg));
+        }
+    } else {
+        match pnpm_mgr.install_packages(&missing_packages) {
+            Ok(result) => {
+                print_result(meta.name, &result, opts);
+
+                // Track failures
+                for (pkg, reason, kind) in &result.failed {
+                    errors.record(PackageFailure {
+                        package: pkg.clone(),
+                        manager: meta.name.to_string(),
+                        reason: reason.clone(),
+                        kind: *kind,
+                    })?;
+                }
+            }
+            Err(e) => {
+                say!(opts, "  ❌ {} installation failed: {}", meta.name, e);
+                errors.fail_all(meta.name, &missing_packages, &e)?;
+
+                if fail_fast {
+                    bail!("{} installation failed", meta.name);
+                }
+            }
+        }
+    }
+
+    detail!(opts);
+    Ok(())
+}
+// CODEGEN_END[pnpm]: handler_function
+
+// CODEGEN_START[yarn]: handler_function
+/// Handler for Yarn package manager phase
+fn apply_yarn_phase(
+    config: &Config,
+    opts: &ApplyOptions,
+    max_parallel: usize,
+    fail_fast: bool,
+    errors: &mut ApplyErrors,
+) -> Result<()> {
+    let yarn_config = match &config.yarn {
+        Some(cfg) if !cfg.packages.is_empty() => cfg,
+        _ => return Ok(()), // No yarn config or no packages
+    };
+
+    let meta = ManagerMetadata::get_by_name("yarn").unwrap();
+
+    detail!(
+        opts,
+        "{}",
+        format!("{} Installing {}...", meta.icon, meta.display_name)
+            .bright_cyan()
+            .bold()
+    );
+
+    // Auto-install runtime if not found
+    if !crate::utils::command_exists(meta.runtime_command) {
+        if runtime_install_disabled(
+            config,
+            meta,
+            opts,
+            fail_fast,
+            errors,
+            yarn_config.packages.clone(),
+        )? {
+            return Ok(());
+        }
+
+        say!(
+            opts,
+            "  ⚠️  {} not found, installing {} via brew...",
+            meta.runtime_command.yellow(),
+            meta.runtime_name.cyan()
+        );
+
+        if opts.dry_run {
+            detail!(opts, "    → Would run: brew install {}", meta.brew_formula);
+        } else {
+            match install_runtime_via_brew(meta.brew_formula, config, opts) {
+                Ok(_) => {
+                    detail!(opts, "  ✓ {} installed", meta.runtime_name.green());
+                }
+                Err(e) => {
+                    say!(opts, "  ❌ Failed to install {}: {}", meta.runtime_name, e);
+
+                    // Record failures for all packages
+                    for pkg in &yarn_config.packages {
+                        errors.record(PackageFailure {
+                            package: pkg.clone(),
+                            manager: meta.name.to_string(),
+                            reason: format!("{} installation failed: {}", meta.runtime_name, e),
+                            kind: FailureKind::classify(&e, ""),
+                        })?;
+                    }
+
+                    if fail_fast {
+                        bail!("Failed to install {}", meta.runtime_name);
+                    }
+
+                    detail!(opts);
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    // Install packages - check missing first
+    let yarn_mgr = YarnManager::new(max_parallel);
+
+    // Filter missing packages with the check `macup diff` runs
+    let missing_packages = missing_unless_replay(config, opts, meta.name)
+        .yarn
+        .map(|yarn| yarn.packages)
+        .unwrap_or_default();
+
+    errors.tally(opts, meta.name, missing_packages.len());
+
+    if missing_packages.is_empty() {
+        detail!(opts, "  ✓ All packages already installed");
+        detail!(opts);
+        return Ok(());
+    }
+
+    if opts.dry_run {
+        detail!(opts, "  Packages ({} to install):", missing_packages.len());
+        for pkg in &missing_packages {
+            detail_command(opts, &yarn_mgr.describe_install(pkg));
+        }
+    } else {
+        match yarn_mgr.install_packages(&missing_packages) {
+            Ok(result) => {
+                print_result(meta.name, &result, opts);
+
+                // Track failures
+                for (pkg, reason, kind) in &result.failed {
+                    errors.record(PackageFailure {
+                        package: pkg.clone(),
+                        manager: meta.name.to_string(),
+                        reason: reason.clone(),
+                        kind: *kind,
+                    })?;
+                }
+            }
+            Err(e) => {
+                say!(opts, "  ❌ {} installation failed: {}", meta.name, e);
+                errors.fail_all(meta.name, &missing_packages, &e)?;
+
+                if fail_fast {
+                    bail!("{} installation failed", meta.name);
+                }
+            }
+        }
+    }
+
+    detail!(opts);
+    Ok(())
+}
+// CODEGEN_END[yarn]: handler_function
+
+// CODEGEN_START[pip]: handler_function
+/// Handler for Pip package manager phase
+fn apply_pip_phase(
+    config: &Config,
+    opts: &ApplyOptions,
+    max_parallel: usize,
+    fail_fast: bool,
+    errors: &mut ApplyErrors,
+) -> Result<()> {
+    let pip_config = match &config.pip {
+        Some(cfg) if !cfg.packages.is_empty() => cfg,
+        _ => return Ok(()), // No pip config or no packages
+    };
+
+    let meta = ManagerMetadata::get_by_name("pip").unwrap();
+
+    detail!(
+        opts,
+        "{}",
+        format!("{} Installing {}...", meta.icon, meta.display_name)
+            .bright_cyan()
+            .bold()
+    );
+
+    // Auto-install runtime if not found
+    if !crate::utils::command_exists(meta.runtime_command) {
+        if runtime_install_disabled(
+            config,
+            meta,
+            opts,
+            fail_fast,
+            errors,
+            pip_config.packages.clone(),
+        )? {
+            return Ok(());
+        }
+
+        say!(
+            opts,
+            "  ⚠️  {} not found, installing {} via brew...",
+            meta.runtime_command.yellow(),
+            meta.runtime_name.cyan()
+        );
+
+        if opts.dry_run {
+            detail!(opts, "    → Would run: brew install {}", meta.brew_formula);
+        } else {
+            match install_runtime_via_brew(meta.brew_formula, config, opts) {
+                Ok(_) => {
+                    detail!(opts, "  ✓ {} installed", meta.runtime_name.green());
+                }
+                Err(e) => {
+                    say!(opts, "  ❌ Failed to install {}: {}", meta.runtime_name, e);
+
+                    // Record failures for all packages
+                    for pkg in &pip_config.packages {
+                        errors.record(PackageFailure {
+                            package: pkg.clone(),
+                            manager: meta.name.to_string(),
+                            reason: format!("{} installation failed: {}", meta.runtime_name, e),
+                            kind: FailureKind::classify(&e, ""),
+                        })?;
+                    }
+
+                    if fail_fast {
+                        bail!("Failed to install {}", meta.runtime_name);
+                    }
+
+                    detail!(opts);
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    // Install packages - check missing first
+    let pip_mgr = PipManager::new(max_parallel);
+
+    // Filter missing packages with the check `macup diff` runs
+    let missing_packages = missing_unless_replay(config, opts, meta.name)
+        .pip
+        .map(|pip| pip.packages)
+        .unwrap_or_default();
+
+    errors.tally(opts, meta.name, missing_packages.len());
+
+    if missing_packages.is_empty() {
+        detail!(opts, "  ✓ All packages already installed");
+        detail!(opts);
+        return Ok(());
+    }
+
+    if opts.dry_run {
+        detail!(opts, "  Packages ({} to install):", missing_packages.len());
+        for pkg in &missing_packages {
+            detail_command(opts, &pip_mgr.describe_install(pkg));
+        }
+    } else {
+        match pip_mgr.install_packages(&missing_packages) {
+            Ok(result) => {
+                print_result(meta.name, &result, opts);
+
+                // Track failures
+                for (pkg, reason, kind) in &result.failed {
+                    errors.record(PackageFailure {
+                        package: pkg.clone(),
+                        manager: meta.name.to_string(),
+                        reason: reason.clone(),
+                        kind: *kind,
+                    })?;
+                }
+            }
+            Err(e) => {
+                say!(opts, "  ❌ {} installation failed: {}", meta.name, e);
+                errors.fail_all(meta.name, &missing_packages, &e)?;
+
+                if fail_fast {
+                    bail!("{} installation failed", meta.name);
+                }
+            }
+        }
+    }
+
+    detail!(opts);
+    Ok(())
+}
+// CODEGEN_END[pip]: handler_function
+
+// CODEGEN_MARKER: insert_handler_function_here
+
+pub fn apply_plan(config: &Config, plan: &ExecutionPlan, opts: &ApplyOptions) -> Result<()> {
+    let mut errors = ApplyErrors {
+        max_failures: opts.max_failures,
+        ..Default::default()
+    };
+    let mut ctx = ExecutionContext::default();
+
+    detail!(opts, "{}", "=".repeat(50).bright_blue());
+    detail!(opts, "{}", "Starting macup apply".bright_blue().bold());
+    detail!(opts, "{}", "=".repeat(50).bright_blue());
+    detail!(opts);
+
+    if opts.dry_run {
+        say!(opts, "{}", "[DRY RUN MODE]".yellow().bold());
+        say!(opts);
+    }
+
+    if let Some(key) = opts.resume_key.as_deref().filter(|_| !opts.recheck) {
+        ctx.resumed = state::load_completed_phases(key);
+    }
+
+    let ran = run_phases(config, plan, opts, &mut errors, &mut ctx);
+
+    // Remember finished phases so fixing a failure and re-running is quick
+    if let Some(key) = &opts.resume_key {
+        if ran.is_err() || errors.has_failures() {
+            if let Err(e) = state::save_completed_phases(key, &ctx.completed_phases) {
+                log::warn!("Could not record completed phases: {}", e);
+            }
+        } else {
+            state::clear_completed_phases();
+        }
+    }
+
+    if let Err(e) = ran {
+        // Aborted mid-run (fail_fast) - show what was never attempted
+        if let Some(current) = ctx.current_phase {
+            ctx.not_attempted = plan.phases[current + 1..]
+                .iter()
+                .map(|phase| phase.name.clone())
+                .collect();
+        }
+        ctx.aborted = true;
+        if opts.json_lines {
+            emit_summary("aborted", &errors, &ctx);
+        } else {
+            print_summary(&errors, &ctx);
+        }
+        return Err(e);
+    }
+
+    // --json-lines: the summary event replaces the decorative report
+    if opts.json_lines {
+        let status = if errors.has_failures() {
+            "failed"
+        } else {
+            "ok"
+        };
+        emit_summary(status, &errors, &ctx);
+
+        if errors.has_failures() {
+            bail!("macup completed with errors");
+        }
+        return Ok(());
+    }
+
+    // Print summary
+    let has_issues = errors.has_failures() || !ctx.skipped_phases.is_empty();
+
+    if has_issues {
+        print_summary(&errors, &ctx);
+
+        if errors.has_failures() {
+            bail!("macup completed with errors");
+        } else {
+            // Only skipped phases, not a hard error
+            say!(
+                opts,
+                "\n{}",
+                "⚠️  Some phases were skipped (see above)".yellow()
+            );
+        }
+    }
+
+    if opts.dry_run {
+        print_dry_run_summary(&errors, opts);
+    }
+
+    say!(opts, "{}", "=".repeat(50).bright_green());
+    say!(opts, "{}", "✓ macup apply completed!".bright_green().bold());
+    say!(opts, "{}", "=".repeat(50).bright_green());
+
+    Ok(())
+}
+
+/// Run phases in plan order, recording failures and skipped phases
+fn run_phases(
+    config: &Config,
+    plan: &ExecutionPlan,
+    opts: &ApplyOptions,
+    errors: &mut ApplyErrors,
+    ctx: &mut ExecutionContext,
+) -> Result<()> {
+    let parallelism = |manager: &str| config.effective_parallelism(manager, opts.max_parallel);
+    let fail_fast = config.settings.fail_fast;
+
+    for (idx, phase) in plan.phases.iter().enumerate() {
+        ctx.current_phase = Some(idx);
+
+        // Only package phases resume; managers must run to know what's available
+        let resumable = !matches!(
+            phase.section_type,
+            SectionType::Managers | SectionType::Install | SectionType::System
+        );
+        if resumable && ctx.resumed.contains(&phase.name) {
+            say!(
+                opts,
+                "{} {} completed in the previous run - skipped (--recheck to check it again)",
+                "✓".green(),
+                phase.name
+            );
+            say!(opts);
+            ctx.completed_phases.push(phase.name.clone());
+            continue;
+        }
+        let failures_before = errors.package_failures.len();
+        ctx.phase_failed = false;
+
+        // Check if dependencies are satisfied (--no-deps bypasses the gate)
+        if !opts.no_deps && !can_execute_phase(phase, &ctx.available_managers) {
+            let missing_deps: Vec<_> = phase
+                .depends_on
+                .iter()
+                .filter(|dep| !ctx.available_managers.contains(*dep))
+                .collect();
+
+            let reason = format!(
+                "Missing dependencies: {}",
+                missing_deps
+                    .iter()
+                    .map(|s| s.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+
+            ctx.skipped_phases.push(SkippedPhase {
+                name: phase.name.clone(),
+                reason: reason.clone(),
+            });
+
+            say!(
+                opts,
+                "  ⚠️  Skipping {} phase: {}",
+                phase.name.yellow(),
+                reason.yellow()
+            );
+            say!(opts);
+            continue;
+        }
+
+        // --interactive: preview the phase, then run, skip or abort
+        let system_skipped =
+            matches!(phase.section_type, SectionType::System) && !opts.with_system_settings;
+        if opts.interactive && !system_skipped {
+            match confirm_phase(config, phase, opts, ctx)? {
+                PhaseChoice::Run => {}
+                PhaseChoice::Skip => {
+                    ctx.skipped_phases.push(SkippedPhase {
+                        name: phase.name.clone(),
+                        reason: "user skipped".to_string(),
+                    });
+                    continue;
+                }
+                PhaseChoice::Abort => bail!("Aborted by user before the {} phase", phase.name),
+            }
+        }
+
+        // --only-runtimes: each package section just gets its runtime
+        if opts.only_runtimes && !matches!(phase.section_type, SectionType::Managers) {
+            if let Some(meta) = ManagerMetadata::get_by_section_type(&phase.section_type) {
+                ensure_runtime(config, meta, opts, fail_fast, errors)?;
+            }
+            continue;
+        }
+
+        match &phase.section_type {
+            SectionType::Managers => {
+                detail!(
+                    opts,
+                    "{}",
+                    "📦 Checking package managers...".bright_cyan().bold()
+                );
+
+                // Get required managers (auto-detected)
+                let required_managers = config.get_required_managers();
+
+                if required_managers.is_empty() {
+                    detail!(opts, "  (No package managers required)");
+                } else {
+                    for manager_name in &required_managers {
+                        match check_and_install_manager(manager_name, opts) {
+                            Ok(_) => {
+                                // Track successfully installed/available manager
+                                ctx.available_managers.insert(manager_name.clone());
+                            }
+                            Err(e) => {
+                                say!(opts, "  ❌ Failed to install {}: {}", manager_name.red(), e);
+
+                                errors.manager_failures.push(ManagerFailure {
+                                    name: manager_name.clone(),
+                                    reason: e.to_string(),
+                                });
+
+                                if fail_fast {
+                                    bail!("Manager installation failed: {}", manager_name);
+                                }
+                            }
+                        }
+                    }
+                }
+
+                detail!(opts);
+            }
+
+            SectionType::Install => {
+                if let Some(install_config) = &config.install {
+                    detail!(
+                        opts,
+                        "{}",
+                        "🔧 Running install scripts...".bright_cyan().bold()
+                    );
+
+                    let install_mgr = InstallManager::with_rerun(&opts.rerun);
+
+                    // Filter missing scripts in parallel
+                    let missing_scripts: Vec<_> = install_config
+                        .scripts
+                        .par_iter()
+                        .filter(|script| install_mgr.needs_run(script).unwrap_or(true))
+                        .collect();
+                    errors.tally(opts, "install", missing_scripts.len());
+
+                    if missing_scripts.is_empty() {
+                        detail!(opts, "  ✓ All scripts already installed");
+                        detail!(opts);
+                    } else {
+                        if opts.dry_run {
+                            detail!(opts, "  Scripts ({} to run):", missing_scripts.len());
+                            for script in InstallManager::order_scripts(&missing_scripts)? {
+                                detail!(opts, "    → {}", script.name);
+                            }
+                            detail!(opts);
+                        } else {
+                            // Convert back to owned for apply_scripts
+                            let scripts_to_run: Vec<_> =
+                                missing_scripts.into_iter().cloned().collect();
+                            install_mgr.apply_scripts(&scripts_to_run)?;
+                            detail!(opts);
+                        }
+                    }
+                }
+            }
+
+            SectionType::Brew => {
+                if let Some(brew_config) = &config.brew {
+                    detail!(
+                        opts,
+                        "{}",
+                        "🍺 Installing Homebrew packages...".bright_cyan().bold()
+                    );
+
+                    let brew = BrewManager::new(parallelism("brew"));
+                    let cleanup = config.settings.brew.cleanup_after && !opts.dry_run;
+
+                    // Delegate to `brew bundle` - brew handles idempotency itself
+                    if config.settings.brew.use_bundle {
+                        if opts.dry_run {
+                            detail!(opts, "  Would run brew bundle with Brewfile:");
+                            for line in BrewManager::brewfile_contents(brew_config).lines() {
+                                detail!(opts, "    {}", line);
+                            }
+                        } else if let Err(e) = brew.bundle_install(brew_config) {
+                            say!(opts, "  ❌ {}", e.to_string().red());
+
+                            errors.record(PackageFailure {
+                                package: "brew bundle".to_string(),
+                                manager: "brew".to_string(),
+                                reason: e.to_string(),
+                                kind: FailureKind::classify(&e, ""),
+                            })?;
+
+                            if fail_fast {
+                                bail!("brew bundle failed");
+                            }
+                        } else {
+                            detail!(opts, "  ✓ brew bundle completed");
+                        }
+
+                        let bundle_failed = errors.package_failures.len() > failures_before;
+                        if cleanup && !bundle_failed {
+                            run_brew_cleanup(&brew, opts);
+                        }
+
+                        detail!(opts);
+                        ctx.finish_phase(&phase.name, resumable, bundle_failed);
+                        continue;
+                    }
+
+                    // Taps, formulae and casks are reported as one brew result
+                    let mut results = Vec::new();
+                    let mut would_install = 0;
+
+                    // Missing entries come from the check `macup diff` runs
+                    let missing = missing_unless_replay(config, opts, "brew")
+                        .brew
+                        .unwrap_or_default();
+
+                    // Add missing taps
+                    if !missing.taps.is_empty() {
+                        if opts.dry_run {
+                            detail!(opts, "  Taps ({} to add):", missing.taps.len());
+                            for tap in &missing.taps {
+                                detail!(opts, "    → {}", tap.name());
+                            }
+                        } else {
+                            results.push(brew.add_taps(&missing.taps)?);
+                        }
+                    }
+
+                    // Install missing formulae
+                    let missing_formulae = &missing.formulae;
+                    would_install += missing_formulae.len();
+                    if !missing_formulae.is_empty() {
+                        if opts.dry_run {
+                            detail!(opts, "  Formulae ({} to install):", missing_formulae.len());
+                            for pkg in missing_formulae {
+                                detail_command(opts, &brew.describe_install(pkg));
+                            }
+                        } else {
+                            let mut result = brew.install_formulae(missing_formulae)?;
+
+                            if opts.auto_fix {
+                                fix_cask_formulae(&brew, &mut result, opts)?;
+                            }
+
+                            // Track failures
+                            for (pkg, reason, kind) in &result.failed {
+                                errors.record(PackageFailure {
+                                    package: pkg.clone(),
+                                    manager: "brew".to_string(),
+                                    reason: reason.clone(),
+                                    kind: *kind,
+                                })?;
+                            }
+                            results.push(result);
+                        }
+                    }
+
+                    // Install missing casks
+                    let missing_casks = &missing.casks;
+                    would_install += missing_casks.len();
+                    if !missing_casks.is_empty() {
+                        if opts.dry_run {
+                            detail!(opts, "  Casks ({} to install):", missing_casks.len());
+                            for pkg in missing_casks {
+                                detail_command(opts, &BrewManager::describe_cask_install(pkg));
+                            }
+                        } else {
+                            results.push(brew.install_casks(missing_casks)?);
+                        }
+                    }
+
+                    errors.tally(opts, "brew", would_install);
+
+                    let result = crate::managers::InstallResult::aggregate(&results);
+                    if !result.is_empty() {
+                        print_result("brew", &result, opts);
+                    }
+
+                    // Keep `brew list --pinned` in line with brew.pin
+                    if !brew_config.pin.is_empty() || !brew_config.formulae.is_empty() {
+                        let changes = brew
+                            .pin_changes(&brew_config.pin, &brew_config.formulae)
+                            .unwrap_or_default();
+
+                        if opts.dry_run {
+                            for name in &changes.to_pin {
+                                detail!(opts, "  → Would pin {}", name);
+                            }
+                            for name in &changes.to_unpin {
+                                detail!(opts, "  → Would unpin {}", name);
+                            }
+                        } else if !changes.is_empty() {
+                            let mut pinned = Vec::new();
+                            let mut unpinned = Vec::new();
+                            let wanted = changes.to_pin.iter().map(|name| (name, true));
+                            let unwanted = changes.to_unpin.iter().map(|name| (name, false));
+
+                            for (name, pin) in wanted.chain(unwanted) {
+                                match brew.set_pinned(name, pin) {
+                                    Ok(()) if pin => pinned.push(name.as_str()),
+                                    Ok(()) => unpinned.push(name.as_str()),
+                                    Err(e) => {
+                                        say!(opts, "  {} {}", "✗".red(), e);
+                                        errors.record(PackageFailure {
+                                            package: name.clone(),
+                                            manager: "brew".to_string(),
+                                            reason: e.to_string(),
+                                            kind: FailureKind::classify(&e, ""),
+                                        })?;
+                                    }
+                                }
+                            }
+
+                            if !pinned.is_empty() {
+                                detail!(opts, "  📌 Pinned: {}", pinned.join(", "));
+                            }
+                            if !unpinned.is_empty() {
+                                detail!(opts, "  📌 Unpinned: {}", unpinned.join(", "));
+                            }
+                        }
+                    }
+
+                    // Cask and tap failures aren't in package_failures
+                    let brew_failed = !result.failed.is_empty()
+                        || errors.package_failures.len() > failures_before;
+                    ctx.phase_failed |= brew_failed;
+                    if cleanup && !brew_failed {
+                        run_brew_cleanup(&brew, opts);
+                    }
+
+                    detail!(opts);
+                }
+            }
+
+            // CODEGEN_START[mas]: match_arm
+            SectionType::Mas => {
+                apply_mas_phase(config, opts, parallelism("mas"), fail_fast, errors)?;
+            }
+            // CODEGEN_END[mas]: match_arm
+
+            // CODEGEN_START[npm]: match_arm
+            SectionType::Npm => {
+                apply_npm_phase(config, opts, parallelism("npm"), fail_fast, errors)?;
+            }
+            // CODEGEN_END[npm]: match_arm
+
+            // CODEGEN_START[cargo]: match_arm
+            SectionType::Cargo => {
+                apply_cargo_phase(config, opts, parallelism("cargo"), fail_fast, errors)?;
             }
             // CODEGEN_END[cargo]: match_arm
 
-            
-            
+            // CODEGEN_START[asdf]: match_arm
+            SectionType::Asdf => {
+                apply_asdf_phase(config, opts, parallelism("asdf"), fail_fast, errors)?;
+            }
+            // CODEGEN_END[asdf]: match_arm
+
+            // CODEGEN_START[pnpm]: match_arm
+            SectionType::Pnpm => {
+                apply_pnpm_phase(config, opts, parallelism("pnpm"), fail_fast, errors)?;
+            }
+            // CODEGEN_END[pnpm]: match_arm
+
+            // CODEGEN_START[yarn]: match_arm
+            SectionType::Yarn => {
+                apply_yarn_phase(config, opts, parallelism("yarn"), fail_fast, errors)?;
+            }
+            // CODEGEN_END[yarn]: match_arm
+
+            // CODEGEN_START[pip]: match_arm
+            SectionType::Pip => {
+                apply_pip_phase(config, opts, parallelism("pip"), fail_fast, errors)?;
+            }
+            // CODEGEN_END[pip]: match_arm
+
             // CODEGEN_MARKER: insert_section_match_arm_here
             SectionType::System => {
                 // Skip system settings unless explicitly requested
-                if !with_system_settings {
-                    if config.system.is_some() {
-                        println!(
+                if !opts.with_system_settings {
+                    if let Some(system_config) = &config.system {
+                        say!(
+                            opts,
                             "{}",
-                            "⊘ Skipping system settings (use --with-system-settings to apply)"
-                                .yellow()
+                            format!(
+                                "⊘ Skipping system settings ({} command(s) in [system])",
+                                system_config.commands.len()
+                            )
+                            .yellow()
+                            .bold()
+                        );
+                        say!(
+                            opts,
+                            "   They change macOS preferences, so they only run when asked:"
                         );
-                        println!();
+                        say!(
+                            opts,
+                            "   {}  (or {})",
+                            "macup apply --system".bright_cyan(),
+                            "macup apply system".bright_cyan()
+                        );
+                        say!(opts);
                     }
                     continue;
                 }
 
                 if let Some(system_config) = &config.system {
-                    println!(
+                    detail!(
+                        opts,
                         "{}",
-                        format!("⚙️  Applying system settings...")
-                            .bright_cyan()
-                            .bold()
+                        "⚙️  Applying system settings...".bright_cyan().bold()
                     );
 
-                    if dry_run {
+                    if opts.dry_run {
                         for cmd in &system_config.commands {
-                            println!("  → Would run: {}", cmd);
+                            detail!(opts, "  → Would run: {}", cmd);
                         }
                     } else {
                         let system = SystemManager::new();
                         system.apply_commands(&system_config.commands)?;
                     }
 
-                    println!();
+                    detail!(opts);
                 }
             }
         }
+
+        let failed = errors.package_failures.len() > failures_before;
+        ctx.finish_phase(&phase.name, resumable, failed);
     }
 
-    // Print summary
-    let has_issues = errors.has_failures() || !ctx.skipped_phases.is_empty();
+    Ok(())
+}
 
-    if has_issues {
-        print_summary(&errors, &ctx);
+/// Print the ordered phases of a plan without executing anything
+/// Availability is predicted from managers already on PATH - the managers phase
+/// may still install the rest during a real run
+pub fn explain_plan(config: &Config, plan: &ExecutionPlan, opts: &ApplyOptions) {
+    let available: HashSet<String> = config
+        .get_required_managers()
+        .into_iter()
+        .filter(|name| crate::utils::command_exists(name))
+        .collect();
 
-        if errors.has_failures() {
-            bail!("macup completed with errors");
+    println!("{}", "=".repeat(50).bright_blue());
+    println!(
+        "{}",
+        format!("Execution plan ({} phases)", plan.phases.len())
+            .bright_blue()
+            .bold()
+    );
+    println!("{}", "=".repeat(50).bright_blue());
+    println!();
+
+    for (idx, phase) in plan.phases.iter().enumerate() {
+        let depends_on = if phase.depends_on.is_empty() {
+            "-".to_string()
         } else {
-            // Only skipped phases, not a hard error
+            phase.depends_on.join(", ")
+        };
+
+        println!(
+            "  {}. {} {}",
+            idx + 1,
+            phase.name.bold(),
+            format!("[{:?}]", phase.section_type).dimmed()
+        );
+        println!("     depends_on: {}", depends_on);
+
+        if matches!(phase.section_type, SectionType::System) && !opts.with_system_settings {
             println!(
-                "\n{}",
-                "⚠️  Some phases were skipped due to missing dependencies".yellow()
+                "     {}",
+                "⊘ skipped without --with-system-settings (--system)".yellow()
+            );
+        } else if !opts.no_deps && !can_execute_phase(phase, &available) {
+            let missing: Vec<_> = phase
+                .depends_on
+                .iter()
+                .filter(|dep| !available.contains(*dep))
+                .map(|dep| dep.as_str())
+                .collect();
+            println!(
+                "     {}",
+                format!(
+                    "⚠️  would skip unless the managers phase installs: {}",
+                    missing.join(", ")
+                )
+                .yellow()
             );
         }
     }
 
-    println!("{}", "=".repeat(50).bright_green());
-    println!("{}", "✓ macup apply completed!".bright_green().bold());
-    println!("{}", "=".repeat(50).bright_green());
+    println!();
+}
 
-    Ok(())
+/// Answer to the `--interactive` prompt before a phase
+enum PhaseChoice {
+    Run,
+    Skip,
+    Abort,
+}
+
+/// Show what a phase would do (a dry run of just that phase) and ask how to proceed
+/// Cancelling the prompt (Esc / Ctrl-C) aborts
+fn confirm_phase(
+    config: &Config,
+    phase: &crate::executor::Phase,
+    opts: &ApplyOptions,
+    ctx: &ExecutionContext,
+) -> Result<PhaseChoice> {
+    let preview_opts = ApplyOptions {
+        dry_run: true,
+        interactive: false,
+        summary_only: false,
+        ..opts.clone()
+    };
+    let preview_plan = ExecutionPlan {
+        phases: vec![phase.clone()],
+    };
+    let mut preview_ctx = ExecutionContext {
+        available_managers: ctx.available_managers.clone(),
+        ..Default::default()
+    };
+
+    // Dry run: nothing is installed, failures can only come from detection
+    run_phases(
+        config,
+        &preview_plan,
+        &preview_opts,
+        &mut ApplyErrors::default(),
+        &mut preview_ctx,
+    )?;
+
+    let choice = ask_phase_choice(&phase.name);
+    println!();
+
+    Ok(match choice {
+        "Run" => PhaseChoice::Run,
+        "Skip" => PhaseChoice::Skip,
+        _ => PhaseChoice::Abort,
+    })
+}
+
+/// The `--interactive` prompt itself
+#[cfg(not(test))]
+fn ask_phase_choice(phase_name: &str) -> &'static str {
+    let options = vec!["Run", "Skip", "Abort"];
+    inquire::Select::new(&format!("Run the {} phase?", phase_name), options)
+        .prompt()
+        .unwrap_or("Abort")
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Answers given to the `--interactive` prompt in tests, in phase order
+    static PHASE_ANSWERS: std::cell::RefCell<Vec<&'static str>> =
+        const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// Tests answer from `PHASE_ANSWERS` (running out aborts, like Esc would)
+#[cfg(test)]
+fn ask_phase_choice(_phase_name: &str) -> &'static str {
+    PHASE_ANSWERS.with(|answers| {
+        let mut answers = answers.borrow_mut();
+        if answers.is_empty() {
+            "Abort"
+        } else {
+            answers.remove(0)
+        }
+    })
 }
 
 /// Check if a phase can execute based on satisfied dependencies
 fn can_execute_phase(phase: &crate::executor::Phase, available_managers: &HashSet<String>) -> bool {
-    // Managers phase can always run
-    if matches!(phase.section_type, SectionType::Managers) {
-        return true;
-    }
-
-    // Package manager phases: Always run, they handle dependencies internally
-    // They check actual runtime availability (mas/node/cargo), not brew dependency
+    // Managers and package manager phases: Always run, they handle dependencies internally
+    // They check actual runtime availability (mas/node/cargo/asdf/python), not brew dependency
     // This allows flexibility: if user has node installed manually, npm phase still works
-    if matches!(
+    // (Matching the strict phases keeps generated managers on this side too)
+    if !matches!(
         phase.section_type,
-        SectionType::Brew | SectionType::Mas | SectionType::Npm | SectionType::Cargo
+        SectionType::Install | SectionType::System
     ) {
         return true;
     }
@@ -759,19 +1901,19 @@ fn can_execute_phase(phase: &crate::executor::Phase, available_managers: &HashSe
     true
 }
 
-fn check_and_install_manager(name: &str, dry_run: bool) -> Result<()> {
+fn check_and_install_manager(name: &str, opts: &ApplyOptions) -> Result<()> {
     let exists = crate::utils::command_exists(name);
 
     if exists {
-        println!("  ✓ {} is installed", name.green());
+        detail!(opts, "  ✓ {} is installed", name.green());
         return Ok(());
     }
 
     // Not installed
-    println!("  → Installing {}...", name.yellow());
+    detail!(opts, "  → Installing {}...", name.yellow());
 
-    if dry_run {
-        println!("    → Would install {}", name);
+    if opts.dry_run {
+        detail!(opts, "    → Would install {}", name);
         return Ok(());
     }
 
@@ -793,11 +1935,15 @@ fn check_and_install_manager(name: &str, dry_run: bool) -> Result<()> {
                 std::env::set_var("PATH", format!("/opt/homebrew/bin:{}", current_path));
             }
 
-            println!("  ✓ {} installed", name.green());
+            detail!(opts, "  ✓ {} installed", name.green());
         }
         _ => {
             // Other managers (mas, npm, cargo) are auto-installed inline in their sections
-            println!("  ℹ️  {} will be auto-installed when needed", name.cyan());
+            detail!(
+                opts,
+                "  ℹ️  {} will be auto-installed when needed",
+                name.cyan()
+            );
             return Ok(());
         }
     }
@@ -805,50 +1951,282 @@ fn check_and_install_manager(name: &str, dry_run: bool) -> Result<()> {
     Ok(())
 }
 
-fn print_result(_label: &str, result: &crate::managers::InstallResult) {
-    if !result.success.is_empty() {
-        println!(
-            "  ✓ {} installed: {}",
-            result.success.len(),
-            result.success.len()
+/// The part of config section `key` its phase still has to install, from the
+/// check `macup diff` runs. A replayed plan's lists were resolved when it was
+/// saved, so they're used as they are
+fn missing_unless_replay(config: &Config, opts: &ApplyOptions, key: &str) -> Config {
+    if opts.replay {
+        return config.clone();
+    }
+    reconcile::missing_in_section(config, key).unwrap_or_else(|e| {
+        log::warn!("Could not check [{}] against the system: {}", key, e);
+        config.clone()
+    })
+}
+
+/// Dry-run line for one package: the exact command an apply would run
+fn detail_command(opts: &ApplyOptions, command: &[String]) {
+    detail!(opts, "    → {}", command.join(" "));
+}
+
+fn print_result(phase: &str, result: &crate::managers::InstallResult, opts: &ApplyOptions) {
+    crate::executor::events::emit_result(phase, result, opts);
+
+    if opts.summary_only {
+        return;
+    }
+
+    print!("{}", result.summary());
+}
+
+/// `settings.brew.cleanup_after`: run `brew cleanup` and report reclaimed space
+/// A failing cleanup only warns - the packages are installed at this point
+fn run_brew_cleanup(brew: &BrewManager, opts: &ApplyOptions) {
+    match brew.cleanup() {
+        Ok(Some(freed)) => detail!(opts, "  🧹 brew cleanup freed {}", freed),
+        Ok(None) => detail!(opts, "  🧹 brew cleanup: nothing to remove"),
+        Err(e) => say!(opts, "  {} {}", "⚠️".yellow(), e),
+    }
+}
+
+/// `--auto-fix`: move formulae that brew says are casks to `brew.casks` in the
+/// config, then install them as casks in place of the failed formula installs
+fn fix_cask_formulae(
+    brew: &BrewManager,
+    result: &mut crate::managers::InstallResult,
+    opts: &ApplyOptions,
+) -> Result<()> {
+    let Some(config_file) = &opts.config_file else {
+        return Ok(());
+    };
+
+    // "package:binary" entries - the cask is the package part
+    let casks: Vec<String> = result
+        .failed
+        .iter()
+        .filter(|(_, _, kind)| *kind == FailureKind::Cask)
+        .map(|(pkg, _, _)| pkg.split(':').next().unwrap_or(pkg).trim().to_string())
+        .collect();
+
+    if casks.is_empty() {
+        return Ok(());
+    }
+
+    let moved = crate::config::move_formulae_to_casks(config_file, &casks)?;
+    say!(
+        opts,
+        "  🔧 Moved {} formula(e) to brew.casks in {}: {}",
+        moved,
+        config_file.display(),
+        casks.join(", ")
+    );
+
+    let cask_result = brew.install_casks(&casks)?;
+
+    result
+        .failed
+        .retain(|(_, _, kind)| *kind != FailureKind::Cask);
+    result.success.extend(cask_result.success);
+    result.skipped.extend(cask_result.skipped);
+    result.failed.extend(cask_result.failed);
+
+    Ok(())
+}
+
+/// Handle a missing runtime when `<manager>.runtime_auto_install = false`:
+/// every package of the phase fails with a pointer to a manual install
+/// Returns true when the phase should stop there
+fn runtime_install_disabled(
+    config: &Config,
+    meta: &ManagerMetadata,
+    opts: &ApplyOptions,
+    fail_fast: bool,
+    errors: &mut ApplyErrors,
+    packages: impl IntoIterator<Item = String>,
+) -> Result<bool> {
+    if config.runtime_auto_install(meta.name) {
+        return Ok(false);
+    }
+
+    let reason = format!(
+        "{} not found and {}.runtime_auto_install = false - install {} yourself and re-run",
+        meta.runtime_command, meta.name, meta.runtime_name
+    );
+    say!(opts, "  ❌ {}", reason.red());
+
+    for package in packages {
+        errors.record(PackageFailure {
+            package,
+            manager: meta.name.to_string(),
+            reason: reason.clone(),
+            kind: FailureKind::Other,
+        })?;
+    }
+
+    if fail_fast {
+        bail!("{} not found", meta.runtime_command);
+    }
+
+    detail!(opts);
+    Ok(true)
+}
+
+/// Install a section's runtime via brew if it's missing (`--only-runtimes`)
+/// A failed install is a manager failure - no packages were attempted
+fn ensure_runtime(
+    config: &Config,
+    meta: &ManagerMetadata,
+    opts: &ApplyOptions,
+    fail_fast: bool,
+    errors: &mut ApplyErrors,
+) -> Result<()> {
+    let populated = config
+        .get_manager_config(meta.name)
+        .is_some_and(|section| section.has_packages());
+    if !populated {
+        return Ok(());
+    }
+
+    if crate::utils::command_exists(meta.runtime_command) {
+        detail!(
+            opts,
+            "{} {} {} already installed",
+            meta.icon,
+            "✓".green(),
+            meta.runtime_name
+        );
+        return Ok(());
+    }
+
+    if !config.runtime_auto_install(meta.name) {
+        say!(
+            opts,
+            "{} ⊘ {} not found ({}.runtime_auto_install = false) - skipped",
+            meta.icon,
+            meta.runtime_command.yellow(),
+            meta.name
         );
+        return Ok(());
     }
-    if !result.skipped.is_empty() {
-        println!("  ⊘ {} skipped (already installed)", result.skipped.len());
+
+    if opts.dry_run {
+        say!(
+            opts,
+            "{} → Would run: brew install {}",
+            meta.icon,
+            meta.brew_formula
+        );
+        return Ok(());
     }
-    if !result.failed.is_empty() {
-        println!("  ✗ {} failed:", result.failed.len());
-        for (pkg, err) in &result.failed {
-            println!("    - {}: {}", pkg, err);
+
+    say!(
+        opts,
+        "{} Installing {} via brew...",
+        meta.icon,
+        meta.runtime_name.cyan()
+    );
+    match install_runtime_via_brew(meta.brew_formula, config, opts) {
+        Ok(()) => {
+            say!(opts, "  ✓ {} installed", meta.runtime_name.green());
+            Ok(())
+        }
+        Err(e) => {
+            say!(opts, "  ❌ Failed to install {}: {}", meta.runtime_name, e);
+            errors.manager_failures.push(ManagerFailure {
+                name: meta.runtime_name.to_string(),
+                reason: e.to_string(),
+            });
+
+            if fail_fast {
+                bail!("Failed to install {}", meta.runtime_name);
+            }
+            Ok(())
         }
     }
 }
 
 /// Install a runtime (node, rust, python, etc.) via brew
-fn install_runtime_via_brew(formula: &str) -> Result<()> {
+/// Retried with backoff (settings.runtime_install_retries / --max-retries)
+fn install_runtime_via_brew(formula: &str, config: &Config, opts: &ApplyOptions) -> Result<()> {
     // Check brew exists first
     if !crate::utils::command_exists("brew") {
         bail!("{} requires brew, but brew is not installed", formula);
     }
 
-    let status = Command::new("brew")
-        .env("HOMEBREW_NO_AUTO_UPDATE", "1")
-        .args(["install", formula])
-        .status()
-        .context(format!("Failed to execute brew install {}", formula))?;
+    let retries = config.effective_runtime_retries(opts.max_retries);
+    let label = format!("brew install {}", formula);
 
-    if !status.success() {
-        bail!("brew install {} failed", formula);
-    }
+    crate::utils::retry_with_backoff(retries, &label, || {
+        let status = Command::new("brew")
+            .env("HOMEBREW_NO_AUTO_UPDATE", "1")
+            .args(["install", formula])
+            .status()
+            .context(format!("Failed to execute brew install {}", formula))?;
 
-    Ok(())
+        if !status.success() {
+            bail!("brew install {} failed", formula);
+        }
+
+        Ok(())
+    })
+}
+
+fn emit_summary(status: &str, errors: &ApplyErrors, ctx: &ExecutionContext) {
+    let failed_managers: Vec<_> = errors
+        .manager_failures
+        .iter()
+        .map(|failure| failure.name.as_str())
+        .collect();
+    let skipped_phases: Vec<_> = ctx
+        .skipped_phases
+        .iter()
+        .map(|skipped| skipped.name.as_str())
+        .collect();
+
+    crate::executor::events::emit_summary(
+        status,
+        errors.package_failures.len(),
+        &failed_managers,
+        &skipped_phases,
+    );
+}
+
+/// Print the per-section tally of a dry run, e.g. "5 brew, 2 npm, 0 cargo"
+fn print_dry_run_summary(errors: &ApplyErrors, opts: &ApplyOptions) {
+    say!(opts, "{}", "=".repeat(50).yellow());
+    say!(opts, "{}", "Dry run summary".yellow().bold());
+    say!(opts, "{}", "=".repeat(50).yellow());
+
+    if errors.would_install.is_empty() {
+        say!(opts, "  Nothing to install");
+    } else {
+        let total: usize = errors.would_install.iter().map(|(_, count)| count).sum();
+        let tally: Vec<String> = errors
+            .would_install
+            .iter()
+            .map(|(section, count)| format!("{} {}", count, section))
+            .collect();
+        say!(
+            opts,
+            "  Would install: {} ({} total)",
+            tally.join(", "),
+            total
+        );
+    }
+    say!(opts);
 }
 
 /// Print comprehensive summary at end of apply
 fn print_summary(errors: &ApplyErrors, ctx: &ExecutionContext) {
+    let title = if ctx.aborted {
+        "⚠️  macup aborted"
+    } else {
+        "⚠️  macup completed with issues"
+    };
+
     println!();
     println!("{}", "=".repeat(50).yellow());
-    println!("{}", "⚠️  macup completed with issues".yellow().bold());
+    println!("{}", title.yellow().bold());
     println!("{}", "=".repeat(50).yellow());
     println!();
 
@@ -865,7 +2243,7 @@ fn print_summary(errors: &ApplyErrors, ctx: &ExecutionContext) {
     if !errors.manager_failures.is_empty() {
         println!("{}", "Failed manager installations:".red().bold());
         for failure in &errors.manager_failures {
-            println!("  ❌ {} ({})", failure.name.red(), "manager");
+            println!("  ❌ {} (manager)", failure.name.red());
             println!("     Reason: {}", failure.reason);
             println!(
                 "     Fix: Install {} manually and re-run macup apply",
@@ -878,27 +2256,35 @@ fn print_summary(errors: &ApplyErrors, ctx: &ExecutionContext) {
     if !errors.package_failures.is_empty() {
         println!("{}", "Failed package installations:".red().bold());
 
-        // Group by manager for cleaner output
-        let mut by_manager: std::collections::HashMap<String, Vec<&PackageFailure>> =
-            std::collections::HashMap::new();
+        // Group by failure kind so each group gets one suggested fix
+        let mut by_kind: std::collections::BTreeMap<FailureKind, Vec<&PackageFailure>> =
+            std::collections::BTreeMap::new();
 
         for failure in &errors.package_failures {
-            by_manager
-                .entry(failure.manager.clone())
-                .or_insert_with(Vec::new)
-                .push(failure);
+            by_kind.entry(failure.kind).or_default().push(failure);
         }
 
-        for (manager, failures) in by_manager {
-            println!("  {} via {}:", "Packages".red(), manager);
+        for (kind, failures) in by_kind {
+            println!("  {}:", kind.label().red());
             for failure in failures {
-                println!("    ❌ {}", failure.package);
+                println!("    ❌ {} (via {})", failure.package, failure.manager);
                 println!("       Reason: {}", failure.reason);
             }
+            if let Some(fix) = kind.remediation() {
+                println!("     Fix: {}", fix);
+            }
             println!();
         }
     }
 
+    if !ctx.not_attempted.is_empty() {
+        println!("{}", "Not attempted (run aborted):".yellow().bold());
+        for name in &ctx.not_attempted {
+            println!("  ⊘ {} phase", name.yellow());
+        }
+        println!();
+    }
+
     println!(
         "💡 {}",
         "Run 'macup apply' again after fixing the issues.".bright_yellow()
@@ -906,3 +2292,662 @@ fn print_summary(errors: &ApplyErrors, ctx: &ExecutionContext) {
     println!("   Already installed packages will be skipped automatically.");
     println!();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::create_execution_plan;
+    use crate::utils::testing::{stdout_of, FakeEnv};
+
+    fn config(toml: &str) -> Config {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn ran(env: &FakeEnv, command: &str) -> bool {
+        env.calls().iter().any(|call| call.starts_with(command))
+    }
+
+    #[test]
+    fn resumes_phases_completed_by_a_failed_apply() {
+        let env = FakeEnv::new("resume");
+        env.bin("brew", "exit 0")
+            .bin("cargo", "exit 0")
+            .bin("npm", "exit 1");
+
+        let config = config(
+            r#"
+            [cargo]
+            packages = ["zz-crate"]
+
+            [npm]
+            global = ["zz-npm"]
+            "#,
+        );
+        let plan = create_execution_plan(&config).unwrap();
+        let mut opts = ApplyOptions {
+            summary_only: true,
+            resume_key: Some("hash".to_string()),
+            ..Default::default()
+        };
+
+        // npm fails, cargo completes
+        assert!(apply_plan(&config, &plan, &opts).is_err());
+        assert!(ran(&env, "cargo install zz-crate"));
+        assert_eq!(
+            state::load_completed_phases("hash"),
+            HashSet::from(["cargo".to_string()])
+        );
+
+        // The re-run skips cargo and retries npm
+        env.clear_calls();
+        assert!(apply_plan(&config, &plan, &opts).is_err());
+        assert!(!ran(&env, "cargo install"));
+        assert!(ran(&env, "npm install"));
+
+        // --recheck runs every phase again
+        env.clear_calls();
+        opts.recheck = true;
+        assert!(apply_plan(&config, &plan, &opts).is_err());
+        assert!(ran(&env, "cargo install zz-crate"));
+
+        // Another config doesn't resume this one's phases
+        assert!(state::load_completed_phases("other").is_empty());
+    }
+
+    #[test]
+    fn signed_out_mas_apply_fails() {
+        let env = FakeEnv::new("failed-call");
+        // Signed out: `mas account` fails, so install_packages fails as a whole
+        env.bin("brew", "exit 0").bin(
+            "mas",
+            r#"case "$1" in
+  list) exit 0 ;;
+  account) echo "Not signed in"; exit 1 ;;
+esac
+exit 0"#,
+        );
+
+        let config = config(
+            r#"
+            [[mas.apps]]
+            name = "Xcode"
+            id = 497799835
+            "#,
+        );
+        let plan = create_execution_plan(&config).unwrap();
+        let opts = ApplyOptions {
+            summary_only: true,
+            resume_key: Some("hash".to_string()),
+            ..Default::default()
+        };
+
+        let err = apply_plan(&config, &plan, &opts).unwrap_err();
+        assert_eq!(err.to_string(), "macup completed with errors");
+        assert!(!env
+            .calls()
+            .iter()
+            .any(|call| call.starts_with("mas install")));
+        // Nor is the phase resumed as completed
+        assert!(state::load_completed_phases("hash").is_empty());
+    }
+
+    #[test]
+    fn third_failure_aborts_with_max_failures_3() {
+        let env = FakeEnv::new("max-failures");
+        env.bin("brew", "exit 0").bin(
+            "npm",
+            r#"[ "$1" = "install" ] && exit 1
+exit 0"#,
+        );
+
+        let config = config(
+            r#"
+            [npm]
+            global = ["zz-a", "zz-b", "zz-c", "zz-d"]
+            "#,
+        );
+        let plan = create_execution_plan(&config).unwrap();
+        let opts = ApplyOptions {
+            summary_only: true,
+            max_failures: Some(3),
+            ..Default::default()
+        };
+
+        // Aborts while recording the npm phase's failures, not after them
+        let err = apply_plan(&config, &plan, &opts).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Aborting: 3 package failures reached --max-failures 3"
+        );
+    }
+
+    #[test]
+    fn diff_and_apply_agree_on_the_missing_set() {
+        let env = FakeEnv::new("diff-apply");
+        env.bin("brew", "exit 0")
+            .bin("zz-tool", "exit 0")
+            .bin("cargo", "exit 0")
+            .bin(
+                "npm",
+                r#"[ "$1" = "list" ] && echo /usr/lib/node_modules/zz-listed
+exit 0"#,
+            );
+
+        let config = config(
+            r#"
+            [npm]
+            global = ["zz-listed", "zz-tool", "zz-missing", "zz-pkg:zz-bin"]
+
+            [cargo]
+            packages = ["zz-tool", "zz-crate"]
+            "#,
+        );
+
+        let mut diff_missing: Vec<String> = reconcile::compute_diff(&config, false)
+            .results
+            .into_iter()
+            .flat_map(|result| result.missing)
+            .collect();
+        diff_missing.sort();
+
+        let plan = create_execution_plan(&config).unwrap();
+        let opts = ApplyOptions {
+            summary_only: true,
+            ..Default::default()
+        };
+        env.clear_calls();
+        let _ = apply_plan(&config, &plan, &opts);
+
+        let mut installed: Vec<String> = env
+            .calls()
+            .iter()
+            .filter(|call| call.starts_with("npm install") || call.starts_with("cargo install"))
+            .filter_map(|call| call.split_whitespace().last().map(String::from))
+            .collect();
+        installed.sort();
+
+        assert_eq!(diff_missing, ["zz-crate", "zz-missing", "zz-pkg"]);
+        assert_eq!(installed, diff_missing);
+    }
+
+    #[test]
+    fn package_phases_run_without_their_managers_phase_dependencies() {
+        let phase = |section_type| crate::executor::Phase {
+            name: "phase".to_string(),
+            section_type,
+            depends_on: vec!["node".to_string()],
+        };
+        let available = HashSet::new();
+
+        for section_type in [
+            SectionType::Brew,
+            SectionType::Mas,
+            SectionType::Npm,
+            SectionType::Cargo,
+            SectionType::Asdf,
+            SectionType::Pnpm,
+            SectionType::Yarn,
+            SectionType::Pip,
+        ] {
+            assert!(can_execute_phase(&phase(section_type), &available));
+        }
+        assert!(!can_execute_phase(&phase(SectionType::Install), &available));
+        assert!(!can_execute_phase(&phase(SectionType::System), &available));
+    }
+
+    #[test]
+    fn summary_only_prints_just_the_summary() {
+        let Some(stdout) =
+            stdout_of("executor::apply::tests::summary_only_prints_just_the_summary")
+        else {
+            let env = FakeEnv::new("summary-only");
+            env.bin("brew", "exit 0").bin("cargo", "exit 0");
+
+            let config = config(
+                r#"
+                [cargo]
+                packages = ["zz-crate"]
+                "#,
+            );
+            let plan = create_execution_plan(&config).unwrap();
+            let opts = ApplyOptions {
+                summary_only: true,
+                ..Default::default()
+            };
+            apply_plan(&config, &plan, &opts).unwrap();
+            return;
+        };
+
+        assert!(stdout.contains("✓ macup apply completed!"));
+        assert!(!stdout.contains("Starting macup apply"));
+        assert!(!stdout.contains("Installing"));
+        assert!(!stdout.contains("zz-crate"));
+    }
+
+    #[test]
+    fn json_lines_streams_one_event_per_package() {
+        let Some(stdout) =
+            stdout_of("executor::apply::tests::json_lines_streams_one_event_per_package")
+        else {
+            let env = FakeEnv::new("json-lines");
+            env.bin("brew", "exit 0").bin(
+                "cargo",
+                r#"[ "$2" = zz-broken ] && exit 1
+exit 0"#,
+            );
+
+            let config = config(
+                r#"
+                [cargo]
+                packages = ["zz-crate", "zz-broken"]
+                "#,
+            );
+            let plan = create_execution_plan(&config).unwrap();
+            let opts = ApplyOptions {
+                // As `main` sets it: --json-lines implies --summary-only
+                json_lines: true,
+                summary_only: true,
+                ..Default::default()
+            };
+            let _ = apply_plan(&config, &plan, &opts);
+            return;
+        };
+
+        // The harness's "test ... " prefix shares the first line
+        let events: Vec<serde_json::Value> = stdout
+            .lines()
+            .filter_map(|line| line.find('{').map(|start| &line[start..]))
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(
+            events,
+            [
+                serde_json::json!({ "phase": "cargo", "package": "zz-crate", "status": "installed" }),
+                serde_json::json!({
+                    "phase": "cargo",
+                    "package": "zz-broken",
+                    "status": "failed",
+                    "error": "cargo install zz-broken failed",
+                    "kind": "Other failures",
+                }),
+                serde_json::json!({
+                    "phase": "summary",
+                    "status": "failed",
+                    "failed_packages": 1,
+                    "failed_managers": [],
+                    "skipped_phases": [],
+                }),
+            ]
+        );
+        assert!(!stdout.contains("Installing cargo packages"));
+        assert!(!stdout.contains("1 installed"));
+    }
+
+    #[test]
+    fn aborting_in_brew_lists_later_phases_as_not_attempted() {
+        let Some(stdout) = stdout_of(
+            "executor::apply::tests::aborting_in_brew_lists_later_phases_as_not_attempted",
+        ) else {
+            let env = FakeEnv::new("not-attempted");
+            env.bin(
+                "brew",
+                r#"[ "$1" = bundle ] && exit 1
+exit 0"#,
+            )
+            .bin("npm", "exit 0")
+            .bin("cargo", "exit 0");
+
+            let config = config(
+                r#"
+                [settings]
+                fail_fast = true
+                brew = { use_bundle = true }
+                # Independent phases may otherwise run in any order
+                install_order = ["brew", "npm", "cargo"]
+
+                [brew]
+                formulae = ["zz-formula"]
+
+                [npm]
+                global = ["zz-npm"]
+
+                [cargo]
+                packages = ["zz-crate"]
+                "#,
+            );
+            let plan = create_execution_plan(&config).unwrap();
+            let opts = ApplyOptions {
+                summary_only: true,
+                ..Default::default()
+            };
+            assert!(apply_plan(&config, &plan, &opts).is_err());
+            assert!(!ran(&env, "npm install"));
+            assert!(!ran(&env, "cargo install"));
+            return;
+        };
+
+        let (_, not_attempted) = stdout.split_once("Not attempted (run aborted):").unwrap();
+        assert!(not_attempted.contains("⊘ npm phase"));
+        assert!(not_attempted.contains("⊘ cargo phase"));
+        assert!(!not_attempted.contains("⊘ brew phase"));
+    }
+
+    fn explained_config() -> Config {
+        config(
+            r#"
+            [npm]
+            global = ["zz-npm"]
+
+            [cargo]
+            depends_on = ["npm"]
+            packages = ["zz-crate"]
+
+            [install]
+            depends_on = ["cargo"]
+
+            [[install.scripts]]
+            name = "zz-script"
+            binary = "zz-script"
+            command = "true"
+            "#,
+        )
+    }
+
+    #[test]
+    fn interactive_apply_skips_and_aborts_phases() {
+        let env = FakeEnv::new("interactive");
+        env.bin("brew", "exit 0")
+            .bin("npm", "exit 0")
+            .bin("cargo", "exit 0")
+            .bin("zz-script-installer", "exit 0");
+
+        let config = explained_config();
+        let plan = create_execution_plan(&config).unwrap();
+        let opts = ApplyOptions {
+            interactive: true,
+            ..Default::default()
+        };
+        let answer = |given: Vec<&'static str>| {
+            PHASE_ANSWERS.with(|answers| *answers.borrow_mut() = given);
+        };
+
+        // Phases in order: managers, npm, cargo
+        answer(vec!["Run", "Skip", "Run"]);
+        apply_plan(&config, &plan, &opts).unwrap();
+        assert!(!ran(&env, "npm install"));
+        assert!(ran(&env, "cargo install zz-crate"));
+
+        env.clear_calls();
+        answer(vec!["Run", "Run", "Abort"]);
+        let err = apply_plan(&config, &plan, &opts).unwrap_err();
+        assert_eq!(err.to_string(), "Aborted by user before the cargo phase");
+        assert!(ran(&env, "npm install -g zz-npm"));
+        assert!(!ran(&env, "cargo install"));
+    }
+
+    #[test]
+    fn brew_pins_follow_brew_pin() {
+        let env = FakeEnv::new("brew-pin");
+        env.bin(
+            "brew",
+            r#"case "$*" in
+  "list --pinned") printf 'wget\njq\nopenssl@3\n' ;;
+  "list --formula --versions") printf 'git 2.44.0\nwget 1.24.5\njq 1.7.1\nopenssl@3 3.3.0\n' ;;
+esac
+exit 0"#,
+        );
+
+        let config = config(
+            r#"
+            [brew]
+            formulae = ["git", "wget", "jq"]
+            pin = ["git", "wget"]
+            "#,
+        );
+        let plan = create_execution_plan(&config).unwrap();
+        let opts = ApplyOptions {
+            summary_only: true,
+            ..Default::default()
+        };
+        apply_plan(&config, &plan, &opts).unwrap();
+
+        let changes: Vec<_> = env
+            .calls()
+            .into_iter()
+            .filter(|call| call.starts_with("brew pin") || call.starts_with("brew unpin"))
+            .collect();
+        // wget is already pinned; openssl@3 was pinned outside macup
+        assert_eq!(changes, ["brew pin git", "brew unpin jq"]);
+    }
+
+    #[test]
+    fn brew_cleanup_runs_only_after_a_clean_brew_phase() {
+        let env = FakeEnv::new("brew-cleanup-after");
+        env.bin(
+            "brew",
+            r#"[ "$2" = zz-broken ] && exit 1
+[ "$1" = cleanup ] && echo "This operation has freed approximately 1.2GB of disk space."
+exit 0"#,
+        );
+        let opts = ApplyOptions {
+            summary_only: true,
+            ..Default::default()
+        };
+        let apply = |toml: &str| {
+            let config = config(toml);
+            let plan = create_execution_plan(&config).unwrap();
+            env.clear_calls();
+            let _ = apply_plan(&config, &plan, &opts);
+            ran(&env, "brew cleanup")
+        };
+
+        assert!(apply(
+            r#"
+            [settings.brew]
+            cleanup_after = true
+
+            [brew]
+            formulae = ["zz-formula"]
+            "#
+        ));
+        assert!(!apply(
+            r#"
+            [settings.brew]
+            cleanup_after = true
+
+            [brew]
+            formulae = ["zz-formula", "zz-broken"]
+            "#
+        ));
+        assert!(!apply(
+            r#"
+            [brew]
+            formulae = ["zz-formula"]
+            "#
+        ));
+    }
+
+    #[test]
+    fn explain_prints_phases_in_plan_order() {
+        let Some(stdout) = stdout_of("executor::apply::tests::explain_prints_phases_in_plan_order")
+        else {
+            let config = explained_config();
+            let plan = create_execution_plan(&config).unwrap();
+            explain_plan(&config, &plan, &ApplyOptions::default());
+            return;
+        };
+
+        let plan = create_execution_plan(&explained_config()).unwrap();
+        let printed: Vec<&str> = stdout
+            .lines()
+            .filter_map(|line| line.trim_start().split_once(". "))
+            .filter(|(number, _)| number.parse::<usize>().is_ok())
+            .filter_map(|(_, rest)| rest.split_whitespace().next())
+            .collect();
+        let planned: Vec<&str> = plan.phases.iter().map(|p| p.name.as_str()).collect();
+
+        assert_eq!(printed, planned);
+        assert_eq!(printed, ["managers", "npm", "cargo", "install"]);
+        assert!(stdout.contains("3. cargo [Cargo]\n     depends_on: npm\n"));
+    }
+
+    #[test]
+    fn no_deps_runs_a_phase_whose_dependency_failed() {
+        let env = FakeEnv::new("no-deps");
+        // brew isn't installed and its install script fails
+        env.bin("curl", "echo 'exit 1'").bin("zz-runner", "exit 0");
+
+        let config = config(
+            r#"
+            [install]
+            depends_on = ["brew"]
+
+            [[install.scripts]]
+            name = "zz-script"
+            binary = "zz-script"
+            command = "zz-runner"
+            "#,
+        );
+        let plan = create_execution_plan(&config).unwrap();
+        let mut opts = ApplyOptions {
+            summary_only: true,
+            ..Default::default()
+        };
+
+        // The failed brew install skips the install phase
+        assert!(apply_plan(&config, &plan, &opts).is_err());
+        assert!(ran(&env, "curl"));
+        assert!(!ran(&env, "zz-runner"));
+
+        // --no-deps runs it anyway
+        env.clear_calls();
+        opts.no_deps = true;
+        assert!(apply_plan(&config, &plan, &opts).is_err());
+        assert!(ran(&env, "zz-runner"));
+    }
+
+    #[test]
+    fn runtime_install_is_retried_the_configured_times() {
+        let env = FakeEnv::new("runtime-retries");
+        env.bin(
+            "brew",
+            r#"[ "$1" = install ] && exit 1
+exit 0"#,
+        );
+
+        let config = config("[settings]\nruntime_install_retries = 2");
+        let err =
+            install_runtime_via_brew("zz-node", &config, &ApplyOptions::default()).unwrap_err();
+        assert_eq!(err.to_string(), "brew install zz-node failed");
+        assert_eq!(env.calls(), ["brew install zz-node"; 3]);
+
+        // --max-retries overrides the setting
+        env.clear_calls();
+        let opts = ApplyOptions {
+            max_retries: Some(0),
+            ..Default::default()
+        };
+        assert!(install_runtime_via_brew("zz-node", &config, &opts).is_err());
+        assert_eq!(env.calls().len(), 1);
+    }
+
+    #[test]
+    fn dry_run_tallies_the_missing_packages_per_section() {
+        let Some(stdout) =
+            stdout_of("executor::apply::tests::dry_run_tallies_the_missing_packages_per_section")
+        else {
+            let env = FakeEnv::new("dry-run-tally");
+            // A package counts as installed when its binary is on PATH
+            env.bin("brew", "exit 0")
+                .bin("npm", "exit 0")
+                .bin("cargo", "exit 0")
+                .bin("zz-npm-bin", "exit 0")
+                .bin("zz-cargo-bin", "exit 0");
+
+            let config = config(
+                r#"
+                [settings]
+                install_order = ["npm", "cargo"]
+
+                [npm]
+                global = ["zz-a", "zz-b:zz-npm-bin", "zz-c"]
+
+                [cargo]
+                packages = ["zz-installed:zz-cargo-bin", "zz-crate"]
+                "#,
+            );
+            let plan = create_execution_plan(&config).unwrap();
+            let opts = ApplyOptions {
+                dry_run: true,
+                summary_only: true,
+                ..Default::default()
+            };
+            apply_plan(&config, &plan, &opts).unwrap();
+
+            assert!(!ran(&env, "npm install"));
+            assert!(!ran(&env, "cargo install zz"));
+            return;
+        };
+
+        assert!(stdout.contains("Would install: 2 npm, 1 cargo (3 total)"));
+    }
+
+    #[test]
+    fn runtime_auto_install_false_leaves_a_missing_runtime_alone() {
+        let env = FakeEnv::new("runtime-auto-install");
+        env.bin("brew", "exit 0");
+
+        let manual = config(
+            r#"
+            [pnpm]
+            runtime_auto_install = false
+            packages = ["zz-pnpm"]
+            "#,
+        );
+        let plan = create_execution_plan(&manual).unwrap();
+        let opts = ApplyOptions {
+            summary_only: true,
+            ..Default::default()
+        };
+
+        // pnpm's packages fail instead of brew installing pnpm
+        assert!(apply_plan(&manual, &plan, &opts).is_err());
+        assert!(!ran(&env, "brew install pnpm"));
+
+        // By default the missing runtime is brew-installed
+        env.clear_calls();
+        let default = config("[pnpm]\npackages = [\"zz-pnpm\"]\n");
+        let plan = create_execution_plan(&default).unwrap();
+        let _ = apply_plan(&default, &plan, &opts);
+        assert!(ran(&env, "brew install pnpm"));
+    }
+
+    #[test]
+    fn only_runtimes_installs_missing_runtimes_but_no_packages() {
+        let env = FakeEnv::new("only-runtimes");
+        env.bin("brew", "exit 0").bin("cargo", "exit 0");
+
+        let config = config(
+            r#"
+            [pnpm]
+            packages = ["zz-pnpm"]
+
+            [cargo]
+            packages = ["zz-crate"]
+            "#,
+        );
+        let plan = create_execution_plan(&config).unwrap();
+        let opts = ApplyOptions {
+            summary_only: true,
+            only_runtimes: true,
+            ..Default::default()
+        };
+        apply_plan(&config, &plan, &opts).unwrap();
+
+        // pnpm is missing, cargo isn't
+        assert!(ran(&env, "brew install pnpm"));
+        assert!(!ran(&env, "brew install rust"));
+        assert!(!ran(&env, "cargo install"));
+    }
+}
@@ -0,0 +1,47 @@
+use super::ApplyOptions;
+use crate::managers::InstallResult;
+use serde_json::{json, Value};
+
+/// Emit one `--json-lines` event per package of a finished install batch
+pub fn emit_result(phase: &str, result: &InstallResult, opts: &ApplyOptions) {
+    if !opts.json_lines {
+        return;
+    }
+
+    for pkg in &result.success {
+        emit(json!({ "phase": phase, "package": pkg, "status": "installed" }));
+    }
+    for pkg in &result.skipped {
+        emit(json!({ "phase": phase, "package": pkg, "status": "skipped" }));
+    }
+    for (pkg, reason, kind) in &result.failed {
+        emit(json!({
+            "phase": phase,
+            "package": pkg,
+            "status": "failed",
+            "error": reason,
+            "kind": kind.label(),
+        }));
+    }
+}
+
+/// Final `--json-lines` event, emitted once per run
+pub fn emit_summary(
+    status: &str,
+    failed_packages: usize,
+    failed_managers: &[&str],
+    skipped_phases: &[&str],
+) {
+    emit(json!({
+        "phase": "summary",
+        "status": status,
+        "failed_packages": failed_packages,
+        "failed_managers": failed_managers,
+        "skipped_phases": skipped_phases,
+    }));
+}
+
+/// One event per line on stdout (line-buffered, so consumers see it immediately)
+fn emit(event: Value) {
+    println!("{}", event);
+}
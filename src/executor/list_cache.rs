@@ -0,0 +1,162 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Installed-package lists reused by `macup diff` (~/.cache/macup/lists.json)
+/// Opt-in via `settings.diff_cache_ttl_secs`; dropped whenever macup
+/// installs or uninstalls something
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ListCache {
+    /// List name (e.g. "brew-casks") -> cached entry
+    lists: BTreeMap<String, CachedList>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedList {
+    /// Unix timestamp (seconds) the list was queried
+    saved_at: u64,
+    items: Vec<String>,
+}
+
+/// Return the list `name`, reusing a cached copy younger than `ttl_secs`
+/// With `ttl_secs == 0` the cache is bypassed and `fetch` always runs
+pub fn cached_list(
+    name: &str,
+    ttl_secs: u64,
+    fetch: impl FnOnce() -> Result<HashSet<String>>,
+) -> Result<HashSet<String>> {
+    if ttl_secs == 0 {
+        return fetch();
+    }
+
+    let mut cache = load_cache().unwrap_or_default();
+    if let Some(entry) = cache.lists.get(name) {
+        let age = now_secs().saturating_sub(entry.saved_at);
+        if age < ttl_secs {
+            log::debug!("Using cached {} list ({}s old)", name, age);
+            return Ok(entry.items.iter().cloned().collect());
+        }
+        log::debug!(
+            "Cached {} list expired ({}s old, ttl {}s)",
+            name,
+            age,
+            ttl_secs
+        );
+    }
+
+    let items = fetch()?;
+    cache.lists.insert(
+        name.to_string(),
+        CachedList {
+            saved_at: now_secs(),
+            items: items.iter().cloned().collect(),
+        },
+    );
+    if let Err(e) = save_cache(&cache) {
+        log::warn!("Could not write list cache: {}", e);
+    }
+
+    Ok(items)
+}
+
+/// Forget every cached list (installed state is about to change)
+pub fn invalidate() {
+    let Some(path) = cache_path() else {
+        return;
+    };
+    if path.exists() {
+        if let Err(e) = fs::remove_file(&path) {
+            log::warn!("Could not remove list cache {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn cache_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".cache/macup/lists.json"))
+}
+
+fn load_cache() -> Option<ListCache> {
+    let path = cache_path()?;
+    let content = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(cache) => Some(cache),
+        Err(e) => {
+            log::warn!("Ignoring unreadable list cache {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn save_cache(cache: &ListCache) -> Result<()> {
+    let path = cache_path().context("Could not determine home directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context(format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(cache)?)
+        .context(format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::FakeEnv;
+    use std::cell::Cell;
+
+    fn fetch_counting(calls: &Cell<u32>) -> impl FnOnce() -> Result<HashSet<String>> + '_ {
+        move || {
+            calls.set(calls.get() + 1);
+            Ok(HashSet::from(["git".to_string()]))
+        }
+    }
+
+    #[test]
+    fn reuses_a_list_within_its_ttl() {
+        let _env = FakeEnv::new("list-cache-hit");
+        let calls = Cell::new(0);
+
+        let first = cached_list("brew-taps", 60, fetch_counting(&calls)).unwrap();
+        let second = cached_list("brew-taps", 60, fetch_counting(&calls)).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn queries_again_after_expiry_or_invalidation() {
+        let _env = FakeEnv::new("list-cache-miss");
+        let calls = Cell::new(0);
+
+        // Saved two minutes ago: stale for a 60s ttl
+        let mut cache = ListCache::default();
+        cache.lists.insert(
+            "brew-taps".to_string(),
+            CachedList {
+                saved_at: now_secs() - 120,
+                items: vec!["old".to_string()],
+            },
+        );
+        save_cache(&cache).unwrap();
+        let listed = cached_list("brew-taps", 60, fetch_counting(&calls)).unwrap();
+        assert!(listed.contains("git"));
+        assert_eq!(calls.get(), 1);
+
+        invalidate();
+        cached_list("brew-taps", 60, fetch_counting(&calls)).unwrap();
+        assert_eq!(calls.get(), 2);
+
+        // A ttl of 0 never reads the cache
+        cached_list("brew-taps", 0, fetch_counting(&calls)).unwrap();
+        assert_eq!(calls.get(), 3);
+    }
+}
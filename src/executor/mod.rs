@@ -1,5 +1,11 @@
 pub mod apply;
+pub mod events;
+pub mod list_cache;
 pub mod planner;
+pub mod reconcile;
+pub mod selection;
+pub mod state;
 
 pub use apply::*;
 pub use planner::*;
+pub use selection::SelectionFilter;
@@ -1,22 +1,49 @@
 use crate::config::Config;
 use crate::managers::{ManagerMetadata, PACKAGE_MANAGERS};
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutionPlan {
     pub phases: Vec<Phase>,
 }
 
-#[derive(Debug, Clone)]
+/// A reviewed plan saved with `apply --save-plan`, replayed with `--plan-file`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanFile {
+    /// Hash of the resolved config the plan was computed from
+    pub config_hash: String,
+    pub plan: ExecutionPlan,
+    /// The config narrowed to what was missing when the plan was saved:
+    /// each section's package list is installed as is on replay
+    pub config: Config,
+}
+
+impl PlanFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .context(format!("Failed to read plan file: {}", path.display()))?;
+        serde_json::from_str(&content)
+            .context(format!("Failed to parse plan file: {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .context(format!("Failed to write plan file: {}", path.display()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Phase {
-    #[allow(dead_code)]
     pub name: String,
     pub section_type: SectionType,
     pub depends_on: Vec<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SectionType {
     Managers,
     Brew,
@@ -30,10 +57,70 @@ pub enum SectionType {
     Cargo,
     // CODEGEN_END: cargo
     Install,
+    // CODEGEN_START: asdf
+    Asdf,
+    // CODEGEN_END: asdf
+    // CODEGEN_START: pnpm
+    Pnpm,
+    // CODEGEN_END: pnpm
+    // CODEGEN_START: yarn
+    Yarn,
+    // CODEGEN_END: yarn
+    // CODEGEN_START: pip
+    Pip,
+    // CODEGEN_END: pip
     // CODEGEN_MARKER: insert_section_type_here
     System,
 }
 
+impl ExecutionPlan {
+    /// Keep only the `section` phase, plus the managers phase it needs
+    /// (`macup apply brew`). The section's own depends_on aren't run
+    pub fn only_section(self, section: &str) -> Result<ExecutionPlan> {
+        check_section(section)?;
+        if !self.phases.iter().any(|phase| phase.name == section) {
+            anyhow::bail!("[{}] is not in the config - nothing to apply", section);
+        }
+
+        self.select_sections(&[section.to_string()], &[])
+    }
+
+    /// Keep the phases of `only` (all when empty) that aren't in `skip`
+    /// (`--only brew,npm`, `--skip system`). The managers phase always stays,
+    /// everything else depends on it
+    pub fn select_sections(self, only: &[String], skip: &[String]) -> Result<ExecutionPlan> {
+        for section in only.iter().chain(skip) {
+            check_section(section)?;
+        }
+
+        let phases = self
+            .phases
+            .into_iter()
+            .filter(|phase| {
+                phase.section_type == SectionType::Managers
+                    || ((only.is_empty() || only.contains(&phase.name))
+                        && !skip.contains(&phase.name))
+            })
+            .collect();
+        Ok(ExecutionPlan { phases })
+    }
+}
+
+/// Error (listing the valid names) unless `section` can have a phase
+fn check_section(section: &str) -> Result<()> {
+    let mut sections = vec!["brew", "install", "system"];
+    sections.extend(PACKAGE_MANAGERS.iter().map(|meta| meta.name));
+    if !sections.contains(&section) {
+        anyhow::bail!(
+            "Unknown section '{}' (valid: {}){}",
+            section,
+            sections.join(", "),
+            crate::utils::did_you_mean(section, &sections)
+        );
+    }
+    Ok(())
+}
+
 pub fn create_execution_plan(config: &Config) -> Result<ExecutionPlan> {
     let mut phases = vec![];
 
@@ -66,55 +153,233 @@ pub fn create_execution_plan(config: &Config) -> Result<ExecutionPlan> {
         deps_map.insert("system", system.depends_on.clone());
     }
 
+    let order = install_order_ranks(config, &deps_map)?;
+    let rank = |name: &str| order.get(name).copied().unwrap_or(usize::MAX);
+
     // Topological sort to determine execution order
+    // Among sections whose dependencies are met, settings.install_order decides
     let mut satisfied = HashSet::new();
     satisfied.insert("brew".to_string()); // Assume brew always available after managers
 
     let mut remaining: Vec<&str> = deps_map.keys().copied().collect();
 
     while !remaining.is_empty() {
-        let before_len = remaining.len();
-
-        remaining.retain(|&name| {
-            let deps = deps_map.get(name).map(|v| v.as_slice()).unwrap_or(&[]);
-
-            if deps.iter().all(|d| satisfied.contains(d)) {
-                // All dependencies satisfied, add to phases
-                let section_type = match name {
-                    "install" => SectionType::Install,
-                    "brew" => SectionType::Brew,
-                    "system" => SectionType::System,
-                    // Try registry for package managers
-                    _ => {
-                        if let Some(meta) = ManagerMetadata::get_by_name(name) {
-                            meta.section_type.clone()
-                        } else {
-                            return true; // Unknown section, skip
-                        }
-                    }
-                };
-
-                phases.push(Phase {
-                    name: name.to_string(),
-                    section_type,
-                    depends_on: deps.to_vec(),
-                });
-
-                satisfied.insert(name.to_string());
-                false // Remove from remaining
-            } else {
-                true // Keep in remaining
-            }
-        });
+        let next = remaining
+            .iter()
+            .enumerate()
+            .filter(|(_, &name)| {
+                deps_map
+                    .get(name)
+                    .map(|v| v.as_slice())
+                    .unwrap_or(&[])
+                    .iter()
+                    .all(|d| satisfied.contains(d))
+            })
+            .min_by_key(|(_, &name)| rank(name))
+            .map(|(i, _)| i);
 
         // Check for cycles
-        if remaining.len() == before_len && !remaining.is_empty() {
+        let Some(index) = next else {
             anyhow::bail!(
                 "Dependency cycle or unsatisfied dependencies: {:?}",
                 remaining
             );
-        }
+        };
+
+        let name = remaining.remove(index);
+        let deps = deps_map.get(name).map(|v| v.as_slice()).unwrap_or(&[]);
+
+        let section_type = match name {
+            "install" => SectionType::Install,
+            "brew" => SectionType::Brew,
+            "system" => SectionType::System,
+            // Try registry for package managers
+            _ => match ManagerMetadata::get_by_name(name) {
+                Some(meta) => meta.section_type.clone(),
+                None => continue, // Unknown section, skip
+            },
+        };
+
+        phases.push(Phase {
+            name: name.to_string(),
+            section_type,
+            depends_on: deps.to_vec(),
+        });
+
+        satisfied.insert(name.to_string());
     }
 
     Ok(ExecutionPlan { phases })
 }
+
+/// Sort key for each section from `settings.install_order`
+/// A section inherits the earliest position of anything that depends on it,
+/// so `install_order = ["cargo", "npm"]` also pulls cargo's dependencies
+/// ahead of npm. Unlisted sections keep no key and go last.
+fn install_order_ranks<'a>(
+    config: &'a Config,
+    deps_map: &HashMap<&'a str, Vec<String>>,
+) -> Result<HashMap<&'a str, usize>> {
+    let order = &config.settings.install_order;
+    let mut ranks = HashMap::new();
+
+    for (position, name) in order.iter().enumerate() {
+        let known = matches!(name.as_str(), "brew" | "install" | "system")
+            || ManagerMetadata::get_by_name(name).is_some();
+        if !known {
+            let mut sections = vec!["brew", "install", "system"];
+            sections.extend(PACKAGE_MANAGERS.iter().map(|meta| meta.name));
+            anyhow::bail!(
+                "settings.install_order: unknown section '{}'{}",
+                name,
+                crate::utils::did_you_mean(name, &sections)
+            );
+        }
+
+        // Listing a section after one of its own dependencies is the only
+        // order that can be honored
+        for later in &order[position + 1..] {
+            if depends_on(deps_map, name, later) {
+                anyhow::bail!(
+                    "settings.install_order puts '{}' before '{}', but '{}' depends on '{}'",
+                    name,
+                    later,
+                    name,
+                    later
+                );
+            }
+        }
+
+        // Walk up the dependencies, keeping the earliest position seen
+        let mut stack = vec![name.as_str()];
+        while let Some(section) = stack.pop() {
+            let Some((&key, deps)) = deps_map.get_key_value(section) else {
+                continue;
+            };
+            if ranks.contains_key(key) {
+                continue;
+            }
+            ranks.insert(key, position);
+            stack.extend(deps.iter().map(String::as_str));
+        }
+    }
+
+    Ok(ranks)
+}
+
+/// Whether `section` depends on `target`, directly or transitively
+fn depends_on(deps_map: &HashMap<&str, Vec<String>>, section: &str, target: &str) -> bool {
+    let mut seen = HashSet::new();
+    let mut stack = vec![section];
+    while let Some(current) = stack.pop() {
+        if !seen.insert(current) {
+            continue;
+        }
+        if let Some(deps) = deps_map.get(current) {
+            if deps.iter().any(|d| d == target) {
+                return true;
+            }
+            stack.extend(deps.iter().map(String::as_str));
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_file_round_trips() {
+        let config: Config = toml::from_str(
+            r#"
+            [brew]
+            taps = ["hashicorp/tap"]
+            formulae = ["terraform"]
+
+            [npm]
+            depends_on = ["brew"]
+            global = ["typescript"]
+
+            [[mas.apps]]
+            name = "Xcode"
+            id = 497799835
+            "#,
+        )
+        .unwrap();
+        let saved = PlanFile {
+            config_hash: "abc123".to_string(),
+            plan: create_execution_plan(&config).unwrap(),
+            config,
+        };
+
+        let path = std::env::temp_dir().join(format!("macup-plan-{}.json", std::process::id()));
+        saved.save(&path).unwrap();
+        let loaded = PlanFile::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&saved).unwrap()
+        );
+        let phases: Vec<_> = loaded.plan.phases.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(phases.first(), Some(&"managers"));
+        assert!(phases.contains(&"npm"));
+        assert_eq!(loaded.config.npm.unwrap().global, ["typescript"]);
+        assert_eq!(loaded.config.mas.unwrap().apps[0].id, 497799835);
+    }
+
+    fn phase_names(config: &str) -> Result<Vec<String>> {
+        let config: Config = toml::from_str(config).unwrap();
+        let plan = create_execution_plan(&config)?;
+        Ok(plan.phases.into_iter().map(|p| p.name).collect())
+    }
+
+    #[test]
+    fn install_order_breaks_ties_between_independent_sections() {
+        let sections = r#"
+            [brew]
+            formulae = ["jq"]
+
+            [npm]
+            global = ["typescript"]
+
+            [cargo]
+            depends_on = ["pip"]
+            packages = ["ripgrep"]
+
+            [pip]
+            packages = ["black"]
+        "#;
+
+        let phases = phase_names(&format!(
+            "[settings]\ninstall_order = [\"cargo\", \"npm\"]\n{}",
+            sections
+        ))
+        .unwrap();
+        // cargo pulls its dependency pip ahead of npm; unlisted brew goes last
+        assert_eq!(phases, ["managers", "pip", "cargo", "npm", "brew"]);
+    }
+
+    #[test]
+    fn install_order_against_a_dependency_is_rejected() {
+        let err = phase_names(
+            r#"
+            [settings]
+            install_order = ["npm", "cargo"]
+
+            [npm]
+            depends_on = ["cargo"]
+            global = ["typescript"]
+
+            [cargo]
+            packages = ["ripgrep"]
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "settings.install_order puts 'npm' before 'cargo', but 'npm' depends on 'cargo'"
+        );
+    }
+}
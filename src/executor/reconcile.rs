@@ -0,0 +1,1092 @@
+use crate::config::{CargoConfig, Config, InstallConfig, MasConfig, NpmConfig, PipConfig};
+use crate::executor::list_cache::cached_list;
+use crate::managers::{
+    asdf::AsdfManager, // CODEGEN[asdf]: import
+    brew::BrewManager,
+    cargo_manager::CargoManager, // CODEGEN[cargo]: import
+    install::InstallManager,
+    mas::MasManager, // CODEGEN[mas]: import
+    node_globals::parse_package_name,
+    npm::NpmManager, // CODEGEN[npm]: import
+    pnpm::PnpmManager, // CODEGEN[pnpm]: import
+    yarn::YarnManager, // CODEGEN[yarn]: import
+    pip::PipManager, // CODEGEN[pip]: import
+    // CODEGEN_MARKER: insert_import_here
+    Manager,
+    ManagerMetadata,
+};
+use crate::utils::BinaryStatus;
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::PathBuf;
+
+/// Result of checking packages for a single manager
+#[derive(Debug, Serialize)]
+pub struct DiffResult {
+    /// Config section the entries come from, as in group members
+    /// ("brew", "cask", "npm", "install", ...)
+    pub section: String,
+    pub icon: String,
+    pub display_name: String,
+    pub installed: Vec<String>,
+    pub missing: Vec<String>,
+    pub broken: Vec<String>, // on PATH but the binary doesn't resolve (--verify)
+    pub skipped_reason: Option<String>, // e.g., "npm not installed"
+}
+
+/// Per-package outcome of a diff check
+enum PackageStatus {
+    Installed,
+    Missing,
+    Broken(PathBuf),
+}
+
+/// Summary of all diff results
+#[derive(Debug, Serialize)]
+pub struct DiffSummary {
+    pub results: Vec<DiffResult>,
+    pub total_installed: usize,
+    pub total_missing: usize,
+    pub total_skipped: usize,
+}
+
+/// Check every configured section against the system
+/// This is the single source of truth for "what's missing" - `macup diff`,
+/// the apply phases and `apply --verify-after` all work from it
+pub fn compute_diff(config: &Config, verify: bool) -> DiffSummary {
+    calculate_summary(collect_results(config, verify))
+}
+
+/// `config` narrowed to what the diff finds missing now (saved plans, apply phases)
+/// Sections the diff can't check yet (their runtime isn't installed) are kept
+/// whole, since apply would install all of them
+pub fn missing_config(config: &Config) -> Result<Config> {
+    let mut missing: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let mut unchecked = HashSet::new();
+    for result in compute_diff(config, false).results {
+        if result.skipped_reason.is_some() {
+            unchecked.insert(result.section.clone());
+        }
+        missing
+            .entry(result.section)
+            .or_default()
+            .extend(result.missing);
+    }
+
+    let (narrowed, _) = config.missing_from_diff(&missing)?;
+    let mut narrowed = toml::Value::try_from(&narrowed).context("Failed to serialize config")?;
+    let full = toml::Value::try_from(config).context("Failed to serialize config")?;
+    for section in &unchecked {
+        // Taps and casks are part of [brew]
+        let table = match section.as_str() {
+            "tap" | "cask" => "brew",
+            other => other,
+        };
+        if let (Some(narrowed), Some(entries)) = (narrowed.as_table_mut(), full.get(table)) {
+            narrowed.insert(table.to_string(), entries.clone());
+        }
+    }
+    let mut narrowed: Config = narrowed
+        .try_into()
+        .context("Failed to rebuild narrowed config")?;
+
+    // Taps are never narrowed by missing_from_diff
+    if let Some(brew) = narrowed
+        .brew
+        .as_mut()
+        .filter(|_| !unchecked.contains("tap"))
+    {
+        let taps = missing.get("tap");
+        brew.taps
+            .retain(|tap| taps.is_some_and(|taps| taps.contains(tap.name())));
+    }
+
+    Ok(narrowed)
+}
+
+/// What the apply phase of config section `key` installs: `missing_config`
+/// of just that section, checked when the phase starts so it sees what
+/// earlier phases (and the phase's own runtime install) changed
+pub fn missing_in_section(config: &Config, key: &str) -> Result<Config> {
+    let mut section = toml::Value::try_from(config).context("Failed to serialize config")?;
+    if let Some(table) = section.as_table_mut() {
+        table.retain(|name, _| name == "settings" || name == key);
+    }
+    let section: Config = section
+        .try_into()
+        .context("Failed to rebuild config section")?;
+    missing_config(&section)
+}
+
+fn collect_results(config: &Config, verify: bool) -> Vec<DiffResult> {
+    let mut results = Vec::new();
+    let cache_ttl = config.settings.diff_cache_ttl_secs;
+
+    // Check brew sections (taps, formulae, casks)
+    if let Some(brew_config) = &config.brew {
+        if config.settings.brew.use_bundle {
+            results.extend(check_brew_bundle(brew_config));
+        } else {
+            results.extend(check_brew_sections(brew_config, cache_ttl));
+        }
+    }
+
+    // Check mas
+    if let Some(mas_config) = &config.mas {
+        if let Some(result) = check_mas_section(mas_config) {
+            results.push(result);
+        }
+    }
+
+    // CODEGEN_START[npm]: check_call
+    if let Some(npm_config) = &config.npm {
+        if let Some(result) = check_npm_section(npm_config, verify, cache_ttl) {
+            results.push(result);
+        }
+    }
+    // CODEGEN_END[npm]: check_call
+
+    // CODEGEN_START[cargo]: check_call
+    if let Some(cargo_config) = &config.cargo {
+        if let Some(result) = check_cargo_section(cargo_config, verify) {
+            results.push(result);
+        }
+    }
+    // CODEGEN_END[cargo]: check_call
+
+    // CODEGEN_START[asdf]: check_call
+    if let Some(asdf_config) = &config.asdf {
+        if let Some(result) = check_asdf_section(asdf_config) {
+            results.push(result);
+        }
+    }
+    // CODEGEN_END[asdf]: check_call
+
+    // CODEGEN_START[pnpm]: check_call
+    if let Some(pnpm_config) = &config.pnpm {
+        if let Some(result) = check_pnpm_section(pnpm_config) {
+            results.push(result);
+        }
+    }
+    // CODEGEN_END[pnpm]: check_call
+
+    // CODEGEN_START[yarn]: check_call
+    if let Some(yarn_config) = &config.yarn {
+        if let Some(result) = check_yarn_section(yarn_config) {
+            results.push(result);
+        }
+    }
+    // CODEGEN_END[yarn]: check_call
+
+    // CODEGEN_START[pip]: check_call
+    if let Some(pip_config) = &config.pip {
+        if let Some(result) = check_pip_section(pip_config) {
+            results.push(result);
+        }
+    }
+    // CODEGEN_END[pip]: check_call
+
+    // CODEGEN_MARKER: insert_check_call_here
+
+    // Check install scripts
+    if let Some(install_config) = &config.install {
+        if let Some(result) = check_install_scripts(install_config) {
+            results.push(result);
+        }
+    }
+
+    results
+}
+
+/// Check brew packages (returns multiple results for taps, formulae, casks)
+/// `cache_ttl` is `settings.diff_cache_ttl_secs` (0 = always query brew)
+fn check_brew_sections(config: &crate::config::BrewConfig, cache_ttl: u64) -> Vec<DiffResult> {
+    let mut results = Vec::new();
+
+    // Check taps
+    if !config.taps.is_empty() {
+        let names: Vec<String> = config.taps.iter().map(|t| t.name().to_string()).collect();
+        if let Some(result) = check_brew_taps(&names, cache_ttl) {
+            results.push(result);
+        }
+    }
+
+    // Check formulae
+    if !config.formulae.is_empty() {
+        if let Some(result) = check_brew_formulae(&config.formulae) {
+            results.push(result);
+        }
+    }
+
+    // Check casks
+    if !config.casks.is_empty() {
+        if let Some(result) = check_brew_casks(&config.casks, cache_ttl) {
+            results.push(result);
+        }
+    }
+
+    results
+}
+
+/// Check the brew section via `brew bundle check` (settings.brew.use_bundle)
+fn check_brew_bundle(config: &crate::config::BrewConfig) -> Option<DiffResult> {
+    let entries: Vec<String> = config
+        .taps
+        .iter()
+        .map(|tap| tap.name())
+        .chain(config.formulae.iter().map(|s| s.as_str()))
+        .chain(config.casks.iter().map(|s| s.as_str()))
+        .map(|s| s.to_string())
+        .collect();
+
+    if entries.is_empty() {
+        return None;
+    }
+
+    let skipped = |reason: String| DiffResult {
+        section: "brew".to_string(),
+        icon: "🍺".to_string(),
+        display_name: "Homebrew (brew bundle)".to_string(),
+        installed: vec![],
+        missing: vec![],
+        broken: vec![],
+        skipped_reason: Some(reason),
+    };
+
+    if !crate::utils::command_exists("brew") {
+        return Some(skipped("brew not installed".to_string()));
+    }
+
+    let missing_names = match BrewManager::new(1).bundle_check(config) {
+        Ok(missing) => missing,
+        Err(e) => return Some(skipped(e.to_string())),
+    };
+
+    let (missing, installed): (Vec<_>, Vec<_>) = entries.into_iter().partition(|entry| {
+        let (pkg_name, _) = parse_package_name(entry);
+        missing_names.contains(pkg_name)
+    });
+
+    Some(DiffResult {
+        section: "brew".to_string(),
+        icon: "🍺".to_string(),
+        display_name: "Homebrew (brew bundle)".to_string(),
+        installed,
+        missing,
+        broken: vec![],
+        skipped_reason: None,
+    })
+}
+
+/// Check brew taps
+fn check_brew_taps(taps: &[String], cache_ttl: u64) -> Option<DiffResult> {
+    if taps.is_empty() {
+        return None;
+    }
+
+    // Check if brew is installed
+    if !crate::utils::command_exists("brew") {
+        return Some(DiffResult {
+            section: "tap".to_string(),
+            icon: "🍺".to_string(),
+            display_name: "Homebrew Taps".to_string(),
+            installed: vec![],
+            missing: vec![],
+            broken: vec![],
+            skipped_reason: Some("brew not installed".to_string()),
+        });
+    }
+
+    // Get list of installed taps
+    let brew = BrewManager::new(1);
+    let installed_taps =
+        cached_list("brew-taps", cache_ttl, || brew.list_taps()).unwrap_or_default();
+
+    // Check each tap in parallel
+    let tap_results: Vec<_> = taps
+        .par_iter()
+        .map(|tap| {
+            let is_installed = BrewManager::has_tap(&installed_taps, tap);
+            (tap.clone(), is_installed)
+        })
+        .collect();
+
+    let mut installed = vec![];
+    let mut missing = vec![];
+
+    for (tap, is_installed) in tap_results {
+        if is_installed {
+            installed.push(tap);
+        } else {
+            missing.push(tap);
+        }
+    }
+
+    Some(DiffResult {
+        section: "tap".to_string(),
+        icon: "🍺".to_string(),
+        display_name: "Homebrew Taps".to_string(),
+        installed,
+        missing,
+        broken: vec![],
+        skipped_reason: None,
+    })
+}
+
+/// Check brew formulae
+fn check_brew_formulae(formulae: &[String]) -> Option<DiffResult> {
+    if formulae.is_empty() {
+        return None;
+    }
+
+    // Check if brew is installed
+    if !crate::utils::command_exists("brew") {
+        return Some(DiffResult {
+            section: "brew".to_string(),
+            icon: "🍺".to_string(),
+            display_name: "Homebrew Formulae".to_string(),
+            installed: vec![],
+            missing: vec![],
+            broken: vec![],
+            skipped_reason: Some("brew not installed".to_string()),
+        });
+    }
+
+    // One `brew list` covers every formula (tap-qualified and package:binary too)
+    let listed = BrewManager::new(1)
+        .installed_set(formulae)
+        .unwrap_or_default();
+    let (installed, missing): (Vec<_>, Vec<_>) = formulae
+        .iter()
+        .cloned()
+        .partition(|formula| listed.contains(formula));
+
+    Some(DiffResult {
+        section: "brew".to_string(),
+        icon: "🍺".to_string(),
+        display_name: "Homebrew Formulae".to_string(),
+        installed,
+        missing,
+        broken: vec![],
+        skipped_reason: None,
+    })
+}
+
+/// Check brew casks
+fn check_brew_casks(casks: &[String], cache_ttl: u64) -> Option<DiffResult> {
+    if casks.is_empty() {
+        return None;
+    }
+
+    // Check if brew is installed
+    if !crate::utils::command_exists("brew") {
+        return Some(DiffResult {
+            section: "cask".to_string(),
+            icon: "📦".to_string(),
+            display_name: "Homebrew Casks".to_string(),
+            installed: vec![],
+            missing: vec![],
+            broken: vec![],
+            skipped_reason: Some("brew not installed".to_string()),
+        });
+    }
+
+    // Get list of installed casks
+    let brew = BrewManager::new(1);
+    let installed_casks =
+        cached_list("brew-casks", cache_ttl, || brew.list_casks()).unwrap_or_default();
+
+    // Token match, falling back to app names ("Visual Studio Code")
+    let missing = brew.missing_casks(casks, &installed_casks);
+    let installed = casks
+        .iter()
+        .filter(|cask| !missing.contains(cask))
+        .cloned()
+        .collect();
+
+    Some(DiffResult {
+        section: "cask".to_string(),
+        icon: "📦".to_string(),
+        display_name: "Homebrew Casks".to_string(),
+        installed,
+        missing,
+        broken: vec![],
+        skipped_reason: None,
+    })
+}
+
+/// Check mas packages
+fn check_mas_section(config: &MasConfig) -> Option<DiffResult> {
+    if config.apps.is_empty() {
+        return None;
+    }
+
+    let meta = ManagerMetadata::get_by_name("mas").unwrap();
+
+    // Check if mas is installed
+    if !crate::utils::command_exists(meta.runtime_command) {
+        return Some(DiffResult {
+            section: meta.name.to_string(),
+            icon: meta.icon.to_string(),
+            display_name: meta.display_name.to_string(),
+            installed: vec![],
+            missing: vec![],
+            broken: vec![],
+            skipped_reason: Some(format!("{} not installed", meta.runtime_command)),
+        });
+    }
+
+    // Signed out, `mas list` can't be trusted - don't report every app as missing
+    let mas_mgr = MasManager::new(1);
+    if mas_mgr.is_signed_out() {
+        return Some(DiffResult {
+            section: meta.name.to_string(),
+            icon: meta.icon.to_string(),
+            display_name: meta.display_name.to_string(),
+            installed: vec![],
+            missing: vec![],
+            broken: vec![],
+            skipped_reason: Some("not signed in to App Store".to_string()),
+        });
+    }
+
+    // One `mas list` covers every app
+    let ids: Vec<String> = config.apps.iter().map(|app| app.id.to_string()).collect();
+    let listed = mas_mgr.installed_set(&ids).unwrap_or_default();
+    let app_results: Vec<_> = config
+        .apps
+        .iter()
+        .map(|app| {
+            let display = format!("{} ({})", app.name, app.id);
+            (display, listed.contains(&app.id.to_string()))
+        })
+        .collect();
+
+    let mut installed = vec![];
+    let mut missing = vec![];
+
+    for (display, is_installed) in app_results {
+        if is_installed {
+            installed.push(display);
+        } else {
+            missing.push(display);
+        }
+    }
+
+    Some(DiffResult {
+        section: meta.name.to_string(),
+        icon: meta.icon.to_string(),
+        display_name: meta.display_name.to_string(),
+        installed,
+        missing,
+        broken: vec![],
+        skipped_reason: None,
+    })
+}
+
+// CODEGEN_START[npm]: check_function
+/// Check Npm packages
+fn check_npm_section(config: &NpmConfig, verify: bool, cache_ttl: u64) -> Option<DiffResult> {
+    if config.global.is_empty() {
+        return None;
+    }
+
+    let meta = ManagerMetadata::get_by_name("npm").unwrap();
+
+    // Check if runtime is installed
+    if !crate::utils::command_exists(meta.runtime_command) {
+        return Some(DiffResult {
+            section: meta.name.to_string(),
+            icon: meta.icon.to_string(),
+            display_name: meta.display_name.to_string(),
+            installed: vec![],
+            missing: vec![],
+            broken: vec![],
+            skipped_reason: Some(format!("{} not installed", meta.runtime_command)),
+        });
+    }
+
+    // With the diff cache on, one cached `npm list -g` replaces per-package `npm ls`
+    let mgr = NpmManager::new(1);
+    let cached_globals = (cache_ttl > 0)
+        .then(|| cached_list("npm-global", cache_ttl, || mgr.list_global_packages()).ok())
+        .flatten();
+    let listed = match &cached_globals {
+        Some(globals) => NpmManager::installed_among(&config.global, globals),
+        None => mgr.installed_set(&config.global).unwrap_or_default(),
+    };
+
+    // Check each package in parallel
+    let pkg_results: Vec<_> = config
+        .global
+        .par_iter()
+        .map(|pkg| {
+            // Parse package:binary format - show only package name
+            let (pkg_name, binary_name) = parse_package_name(pkg);
+            let status = if !listed.contains(pkg) {
+                PackageStatus::Missing
+            } else if verify {
+                verify_binary(binary_name)
+            } else {
+                PackageStatus::Installed
+            };
+            (pkg_name.to_string(), status)
+        })
+        .collect();
+
+    let mut installed = vec![];
+    let mut missing = vec![];
+    let mut broken = vec![];
+
+    for (pkg, status) in pkg_results {
+        match status {
+            PackageStatus::Installed => installed.push(pkg),
+            PackageStatus::Missing => missing.push(pkg),
+            PackageStatus::Broken(path) => broken.push(format!("{} ({})", pkg, path.display())),
+        }
+    }
+
+    Some(DiffResult {
+        section: meta.name.to_string(),
+        icon: meta.icon.to_string(),
+        display_name: meta.display_name.to_string(),
+        installed,
+        missing,
+        broken,
+        skipped_reason: None,
+    })
+}
+// CODEGEN_END[npm]: check_function
+
+// CODEGEN_START[cargo]: check_function
+/// Check Cargo packages
+fn check_cargo_section(config: &CargoConfig, verify: bool) -> Option<DiffResult> {
+    if config.packages.is_empty() {
+        return None;
+    }
+
+    let meta = ManagerMetadata::get_by_name("cargo").unwrap();
+
+    // Check if runtime is installed
+    if !crate::utils::command_exists(meta.runtime_command) {
+        return Some(DiffResult {
+            section: meta.name.to_string(),
+            icon: meta.icon.to_string(),
+            display_name: meta.display_name.to_string(),
+            installed: vec![],
+            missing: vec![],
+            broken: vec![],
+            skipped_reason: Some(format!("{} not installed", meta.runtime_command)),
+        });
+    }
+
+    // Check each package in parallel
+    let mgr = CargoManager::new(1);
+    let pkg_results: Vec<_> = config
+        .packages
+        .par_iter()
+        .map(|pkg| {
+            // Parse package:binary format - show only package name
+            let (pkg_name, binary_name) = parse_package_name(pkg);
+            let status = if !mgr.is_package_installed(pkg).unwrap_or(false) {
+                PackageStatus::Missing
+            } else if verify {
+                verify_binary(binary_name)
+            } else {
+                PackageStatus::Installed
+            };
+            (pkg_name.to_string(), status)
+        })
+        .collect();
+
+    let mut installed = vec![];
+    let mut missing = vec![];
+    let mut broken = vec![];
+
+    for (pkg, status) in pkg_results {
+        match status {
+            PackageStatus::Installed => installed.push(pkg),
+            PackageStatus::Missing => missing.push(pkg),
+            PackageStatus::Broken(path) => broken.push(format!("{} ({})", pkg, path.display())),
+        }
+    }
+
+    Some(DiffResult {
+        section: meta.name.to_string(),
+        icon: meta.icon.to_string(),
+        display_name: meta.display_name.to_string(),
+        installed,
+        missing,
+        broken,
+        skipped_reason: None,
+    })
+}
+// CODEGEN_END[cargo]: check_function
+
+// CODEGEN_START[asdf]: check_function
+/// Check asdf tools (plugin@version)
+fn check_asdf_section(config: &crate::config::AsdfConfig) -> Option<DiffResult> {
+    if config.tools.is_empty() {
+        return None;
+    }
+
+    let meta = ManagerMetadata::get_by_name("asdf").unwrap();
+
+    // Check if runtime is installed
+    if !crate::utils::command_exists(meta.runtime_command) {
+        return Some(DiffResult {
+            section: meta.name.to_string(),
+            icon: meta.icon.to_string(),
+            display_name: meta.display_name.to_string(),
+            installed: vec![],
+            missing: vec![],
+            broken: vec![],
+            skipped_reason: Some(format!("{} not installed", meta.runtime_command)),
+        });
+    }
+
+    // One `asdf list <plugin>` per plugin
+    let specs: Vec<String> = config.tools.iter().map(|tool| tool.spec()).collect();
+    let listed = AsdfManager::new(1)
+        .installed_set(&specs)
+        .unwrap_or_default();
+    let tool_results: Vec<_> = specs
+        .into_iter()
+        .map(|spec| {
+            let is_installed = listed.contains(&spec);
+            (spec, is_installed)
+        })
+        .collect();
+
+    let mut installed = vec![];
+    let mut missing = vec![];
+
+    for (spec, is_installed) in tool_results {
+        if is_installed {
+            installed.push(spec);
+        } else {
+            missing.push(spec);
+        }
+    }
+
+    Some(DiffResult {
+        section: meta.name.to_string(),
+        icon: meta.icon.to_string(),
+        display_name: meta.display_name.to_string(),
+        installed,
+        missing,
+        broken: vec![],
+        skipped_reason: None,
+    })
+}
+// CODEGEN_END[asdf]: check_function
+
+// CODEGEN_START[pnpm]: check_function
+/// Check Pnpm packages
+fn check_pnpm_section(config: &crate::config::PnpmConfig) -> Option<DiffResult> {
+    if config.packages.is_empty() {
+        return None;
+    }
+
+    let meta = ManagerMetadata::get_by_name("pnpm").unwrap();
+
+    // Check if runtime is installed
+    if !crate::utils::command_exists(meta.runtime_command) {
+        return Some(DiffResult {
+            section: meta.name.to_string(),
+            icon: meta.icon.to_string(),
+            display_name: meta.display_name.to_string(),
+            installed: vec![],
+            missing: vec![],
+            broken: vec![],
+            skipped_reason: Some(format!("{} not installed", meta.runtime_command)),
+        });
+    }
+
+    // Check each package in parallel
+    let mgr = PnpmManager::new(1);
+    let pkg_results: Vec<_> = config
+        .packages
+        .par_iter()
+        .map(|pkg| {
+            // Parse package:binary format - show only package name
+            let (pkg_name, _) = parse_package_name(pkg);
+            let is_installed = mgr.is_package_installed(pkg).unwrap_or(false);
+            (pkg_name.to_string(), is_installed)
+        })
+        .collect();
+
+    let mut installed = vec![];
+    let mut missing = vec![];
+
+    for (pkg, is_installed) in pkg_results {
+        if is_installed {
+            installed.push(pkg);
+        } else {
+            missing.push(pkg);
+        }
+    }
+
+    Some(DiffResult {
+        section: meta.name.to_string(),
+        icon: meta.icon.to_string(),
+        display_name: meta.display_name.to_string(),
+        installed,
+        missing,
+        broken: vec![],
+        skipped_reason: None,
+    })
+}
+// CODEGEN_END[pnpm]: check_function
+
+// CODEGEN_START[yarn]: check_function
+/// Check Yarn packages
+fn check_yarn_section(config: &crate::config::YarnConfig) -> Option<DiffResult> {
+    if config.packages.is_empty() {
+        return None;
+    }
+
+    let meta = ManagerMetadata::get_by_name("yarn").unwrap();
+
+    // Check if runtime is installed
+    if !crate::utils::command_exists(meta.runtime_command) {
+        return Some(DiffResult {
+            section: meta.name.to_string(),
+            icon: meta.icon.to_string(),
+            display_name: meta.display_name.to_string(),
+            installed: vec![],
+            missing: vec![],
+            broken: vec![],
+            skipped_reason: Some(format!("{} not installed", meta.runtime_command)),
+        });
+    }
+
+    // Check each package in parallel
+    let mgr = YarnManager::new(1);
+    let pkg_results: Vec<_> = config
+        .packages
+        .par_iter()
+        .map(|pkg| {
+            // Parse package:binary format - show only package name
+            let (pkg_name, _) = parse_package_name(pkg);
+            let is_installed = mgr.is_package_installed(pkg).unwrap_or(false);
+            (pkg_name.to_string(), is_installed)
+        })
+        .collect();
+
+    let mut installed = vec![];
+    let mut missing = vec![];
+
+    for (pkg, is_installed) in pkg_results {
+        if is_installed {
+            installed.push(pkg);
+        } else {
+            missing.push(pkg);
+        }
+    }
+
+    Some(DiffResult {
+        section: meta.name.to_string(),
+        icon: meta.icon.to_string(),
+        display_name: meta.display_name.to_string(),
+        installed,
+        missing,
+        broken: vec![],
+        skipped_reason: None,
+    })
+}
+// CODEGEN_END[yarn]: check_function
+
+// CODEGEN_START[pip]: check_function
+/// Check Pip packages
+fn check_pip_section(config: &PipConfig) -> Option<DiffResult> {
+    if config.packages.is_empty() {
+        return None;
+    }
+
+    let meta = ManagerMetadata::get_by_name("pip").unwrap();
+
+    // Check if runtime is installed
+    if !crate::utils::command_exists(meta.runtime_command) {
+        return Some(DiffResult {
+            section: meta.name.to_string(),
+            icon: meta.icon.to_string(),
+            display_name: meta.display_name.to_string(),
+            installed: vec![],
+            missing: vec![],
+            broken: vec![],
+            skipped_reason: Some(format!("{} not installed", meta.runtime_command)),
+        });
+    }
+
+    // Packages are checked by name against a single `pip3 list`
+    let listed = PipManager::new(1)
+        .installed_set(&config.packages)
+        .unwrap_or_default();
+    let (installed, missing): (Vec<_>, Vec<_>) = config
+        .packages
+        .iter()
+        .cloned()
+        .partition(|pkg| listed.contains(pkg));
+
+    Some(DiffResult {
+        section: meta.name.to_string(),
+        icon: meta.icon.to_string(),
+        display_name: meta.display_name.to_string(),
+        installed,
+        missing,
+        broken: vec![],
+        skipped_reason: None,
+    })
+}
+// CODEGEN_END[pip]: check_function
+
+// CODEGEN_MARKER: insert_check_function_here
+
+/// Check install scripts
+fn check_install_scripts(config: &InstallConfig) -> Option<DiffResult> {
+    if config.scripts.is_empty() {
+        return None;
+    }
+
+    let install_mgr = InstallManager::new();
+
+    // Check each script in parallel
+    let script_results: Vec<_> = config
+        .scripts
+        .par_iter()
+        .map(|script| {
+            let is_installed = install_mgr.is_installed(script).unwrap_or(false);
+            (script.name.clone(), is_installed)
+        })
+        .collect();
+
+    let mut installed = vec![];
+    let mut missing = vec![];
+
+    for (name, is_installed) in script_results {
+        if is_installed {
+            installed.push(name);
+        } else {
+            missing.push(name);
+        }
+    }
+
+    Some(DiffResult {
+        section: "install".to_string(),
+        icon: "🔧".to_string(),
+        display_name: "Install Scripts".to_string(),
+        installed,
+        missing,
+        broken: vec![],
+        skipped_reason: None,
+    })
+}
+
+/// Deep check used by `--verify` on a package the fast check found installed:
+/// its binary must resolve to an executable, not just have an entry on PATH
+fn verify_binary(binary_name: &str) -> PackageStatus {
+    match crate::utils::check_binary(binary_name) {
+        BinaryStatus::Broken(path) => PackageStatus::Broken(path),
+        // No binary at all: found through the package list (e.g. an npm library)
+        BinaryStatus::Found | BinaryStatus::Missing => PackageStatus::Installed,
+    }
+}
+
+/// Calculate summary from all results
+fn calculate_summary(results: Vec<DiffResult>) -> DiffSummary {
+    let mut total_installed = 0;
+    let mut total_missing = 0;
+    let mut total_skipped = 0;
+
+    for result in &results {
+        if result.skipped_reason.is_some() {
+            total_skipped += 1;
+        } else {
+            total_installed += result.installed.len();
+            total_missing += result.missing.len() + result.broken.len();
+        }
+    }
+
+    DiffSummary {
+        results,
+        total_installed,
+        total_missing,
+        total_skipped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::FakeEnv;
+
+    fn result<'a>(summary: &'a DiffSummary, section: &str) -> &'a DiffResult {
+        summary
+            .results
+            .iter()
+            .find(|result| result.section == section)
+            .unwrap()
+    }
+
+    #[test]
+    fn verify_reports_dangling_binaries_of_installed_packages() {
+        let env = FakeEnv::new("verify");
+        env.bin("cargo", "exit 0")
+            .bin(
+                "npm",
+                r#"[ "$1" = "list" ] && printf '/usr/lib/node_modules/zz-dangling\n/usr/lib/node_modules/zz-lib\n'
+exit 0"#,
+            )
+            .link("zz-dangling", "/nonexistent/zz-dangling")
+            .link("zz-stale", "/nonexistent/zz-stale");
+
+        let config: Config = toml::from_str(
+            r#"
+            [npm]
+            global = ["zz-dangling", "zz-lib", "zz-absent"]
+
+            [cargo]
+            packages = ["zz-stale"]
+            "#,
+        )
+        .unwrap();
+
+        // The fast check trusts the package list
+        let fast = compute_diff(&config, false);
+        assert_eq!(result(&fast, "npm").installed, ["zz-dangling", "zz-lib"]);
+        assert!(result(&fast, "npm").broken.is_empty());
+
+        // --verify only re-checks what the fast check found installed
+        let verified = compute_diff(&config, true);
+        let npm = result(&verified, "npm");
+        assert_eq!(npm.installed, ["zz-lib"]);
+        assert_eq!(npm.missing, ["zz-absent"]);
+        assert_eq!(npm.broken.len(), 1);
+        assert!(npm.broken[0].starts_with("zz-dangling ("));
+
+        let cargo = result(&verified, "cargo");
+        assert_eq!(cargo.missing, ["zz-stale"]);
+        assert!(cargo.broken.is_empty());
+    }
+
+    #[test]
+    fn cached_npm_globals_match_versioned_entries() {
+        let env = FakeEnv::new("npm-cached");
+        env.bin(
+            "npm",
+            r#"[ "$1" = list ] && printf '/usr/lib\n/usr/lib/node_modules/@scope/tool\n'
+exit 0"#,
+        );
+
+        let config: Config = toml::from_str(
+            r#"
+            [settings]
+            diff_cache_ttl_secs = 60
+
+            [npm]
+            global = ["@scope/tool@1.2.0", "zz-absent"]
+            "#,
+        )
+        .unwrap();
+
+        let first = compute_diff(&config, false);
+        assert_eq!(result(&first, "npm").installed, ["@scope/tool@1.2.0"]);
+        assert_eq!(result(&first, "npm").missing, ["zz-absent"]);
+
+        // The second diff answers from the cached list
+        env.clear_calls();
+        let second = compute_diff(&config, false);
+        assert_eq!(result(&second, "npm").installed, ["@scope/tool@1.2.0"]);
+        assert!(env.calls().is_empty());
+    }
+
+    #[test]
+    fn brew_names_match_regardless_of_case() {
+        let env = FakeEnv::new("diff-mixed-case");
+        env.bin(
+            "brew",
+            r#"case "$*" in
+  "list --formula --versions") echo "ripgrep 14.1.0" ;;
+  "list --cask") echo firefox ;;
+  tap) echo homebrew/cask-fonts ;;
+esac
+exit 0"#,
+        );
+
+        let config: Config = toml::from_str(
+            r#"
+            [brew]
+            taps = ["Homebrew/Cask-Fonts"]
+            formulae = ["Ripgrep:rg", "zz-absent"]
+            casks = ["Firefox"]
+            "#,
+        )
+        .unwrap();
+
+        let diff = compute_diff(&config, false);
+        // The config's casing is what gets reported
+        assert_eq!(result(&diff, "tap").installed, ["Homebrew/Cask-Fonts"]);
+        assert_eq!(result(&diff, "brew").installed, ["Ripgrep:rg"]);
+        assert_eq!(result(&diff, "brew").missing, ["zz-absent"]);
+        assert_eq!(result(&diff, "cask").installed, ["Firefox"]);
+    }
+
+    #[test]
+    fn signed_out_mas_is_skipped_instead_of_all_missing() {
+        let env = FakeEnv::new("diff-mas-signed-out");
+        env.bin(
+            "mas",
+            r#"[ "$1" = account ] && { echo "Error: Not signed in" >&2; exit 1; }
+exit 0"#,
+        );
+
+        let config: Config = toml::from_str(
+            r#"
+            [mas]
+            apps = [
+                { name = "Xcode", id = 497799835 },
+                { name = "Keynote", id = 409183694 },
+            ]
+            "#,
+        )
+        .unwrap();
+
+        let diff = compute_diff(&config, false);
+        let mas = result(&diff, "mas");
+        assert_eq!(
+            mas.skipped_reason.as_deref(),
+            Some("not signed in to App Store")
+        );
+        assert!(mas.missing.is_empty());
+        assert_eq!(env.calls(), ["mas account"]);
+    }
+
+    #[test]
+    fn cask_written_by_app_name_is_reported_installed() {
+        let env = FakeEnv::new("diff-cask-app-name");
+        env.bin(
+            "brew",
+            r#"case "$*" in
+  "list --cask") echo visual-studio-code ;;
+  "info --cask --json=v2 --installed")
+    echo '{"casks": [{"token": "visual-studio-code", "name": ["Microsoft Visual Studio Code", "Visual Studio Code"]}]}' ;;
+esac
+exit 0"#,
+        );
+
+        let config: Config = toml::from_str(
+            r#"
+            [brew]
+            casks = ["Visual Studio Code", "Slack"]
+            "#,
+        )
+        .unwrap();
+
+        let diff = compute_diff(&config, false);
+        assert_eq!(result(&diff, "cask").installed, ["Visual Studio Code"]);
+        assert_eq!(result(&diff, "cask").missing, ["Slack"]);
+    }
+}
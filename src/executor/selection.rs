@@ -0,0 +1,152 @@
+use crate::config::Config;
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Which packages a command works on, built from its selection flags
+/// A package is kept only if it passes every active filter; taps,
+/// depends_on, settings and [system] are never filtered out
+#[derive(Debug, Default, Clone)]
+pub struct SelectionFilter {
+    /// `--filter`: glob over install names
+    pub filter: Option<String>,
+    /// `--group`: members of a `[[groups]]` entry
+    pub group: Option<String>,
+    /// `--tag`: members of every `[[groups]]` entry with this tag
+    pub tag: Option<String>,
+    /// `--manager`: one manager's entries ("brew", "cask", "npm", ...)
+    pub manager: Option<String>,
+    /// `--packages-from-diff`: entries a `macup diff` reported missing, by section
+    pub missing: Option<BTreeMap<String, BTreeSet<String>>>,
+}
+
+impl SelectionFilter {
+    /// Narrow `config` to the selected packages
+    /// Every filter keeps a subset of what it's given, so the order doesn't matter
+    pub fn apply(&self, config: Config) -> Result<Config> {
+        let config = config
+            .with_manager(self.manager.as_deref())?
+            .with_filter(self.filter.as_deref())?
+            .with_group(self.group.as_deref())?
+            .with_tag(self.tag.as_deref())?;
+
+        let Some(missing) = &self.missing else {
+            return Ok(config);
+        };
+        let (config, matched) = config.missing_from_diff(missing)?;
+        log::info!("Diff selected {} missing package(s)", matched);
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        toml::from_str(
+            r#"
+            [brew]
+            taps = ["owner/tools"]
+            formulae = ["typst", "git"]
+            casks = ["typora"]
+
+            [npm]
+            global = ["typescript", "typeorm", "prettier"]
+
+            [cargo]
+            packages = ["typos-cli"]
+
+            [[groups]]
+            name = "core"
+            tags = ["essential"]
+            members = ["npm:typescript", "npm:prettier", "brew:typst", "cargo:typos-cli"]
+
+            [[groups]]
+            name = "extra"
+            members = ["npm:typeorm", "cask:typora"]
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn manager_tag_and_filter_must_all_match() {
+        let selection = SelectionFilter {
+            manager: Some("npm".to_string()),
+            tag: Some("essential".to_string()),
+            filter: Some("type*".to_string()),
+            ..Default::default()
+        };
+
+        let config = selection.apply(config()).unwrap();
+
+        assert_eq!(config.npm.unwrap().global, ["typescript"]);
+        let brew = config.brew.unwrap();
+        assert!(brew.formulae.is_empty());
+        assert!(brew.casks.is_empty());
+        // Taps are never filtered out
+        assert_eq!(brew.taps.len(), 1);
+        assert!(config.cargo.unwrap().packages.is_empty());
+    }
+
+    #[test]
+    fn group_and_diff_selection_combine_with_the_others() {
+        let selection = SelectionFilter {
+            group: Some("extra".to_string()),
+            filter: Some("typ*".to_string()),
+            missing: Some(BTreeMap::from([(
+                "cask".to_string(),
+                BTreeSet::from(["typora".to_string()]),
+            )])),
+            ..Default::default()
+        };
+
+        let config = selection.apply(config()).unwrap();
+
+        let brew = config.brew.unwrap();
+        assert_eq!(brew.casks, ["typora"]);
+        assert!(brew.formulae.is_empty());
+        // typeorm is in the group and matches, but the diff didn't report it
+        assert!(config.npm.unwrap().global.is_empty());
+    }
+
+    #[test]
+    fn brew_manager_covers_casks_and_cask_only_casks() {
+        let brew = SelectionFilter {
+            manager: Some("brew".to_string()),
+            ..Default::default()
+        };
+        let selected = brew.apply(config()).unwrap();
+        let brew = selected.brew.unwrap();
+        assert_eq!(brew.formulae, ["typst", "git"]);
+        assert_eq!(brew.casks, ["typora"]);
+        assert!(selected.npm.unwrap().global.is_empty());
+
+        let cask = SelectionFilter {
+            manager: Some("cask".to_string()),
+            ..Default::default()
+        };
+        let selected = cask.apply(config()).unwrap().brew.unwrap();
+        assert!(selected.formulae.is_empty());
+        assert_eq!(selected.casks, ["typora"]);
+    }
+
+    #[test]
+    fn unknown_tag_or_manager_is_an_error() {
+        let tag = SelectionFilter {
+            tag: Some("nightly".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            tag.apply(config()).unwrap_err().to_string(),
+            "No [[groups]] entry is tagged 'nightly'"
+        );
+
+        let manager = SelectionFilter {
+            manager: Some("nmp".to_string()),
+            ..Default::default()
+        };
+        let err = manager.apply(config()).unwrap_err().to_string();
+        assert!(err.starts_with("Unknown manager 'nmp' - did you mean 'npm'?"));
+    }
+}
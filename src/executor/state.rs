@@ -0,0 +1,142 @@
+use crate::config::Config;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Record of the last successful apply (~/.cache/macup/state.json)
+/// Backs `apply --changed-only`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApplyState {
+    /// Hash of the resolved config the apply ran with
+    pub config_hash: String,
+    /// Unix timestamp (seconds) of the apply
+    pub applied_at: u64,
+}
+
+impl ApplyState {
+    pub fn new(config_hash: String) -> Self {
+        Self {
+            config_hash,
+            applied_at: now_secs(),
+        }
+    }
+}
+
+/// Phases that fully succeeded during an apply that failed elsewhere
+/// (~/.cache/macup/resume.json). A re-run of the same config shortly after
+/// skips them instead of repeating their checks
+#[derive(Debug, Serialize, Deserialize)]
+struct ApplyProgress {
+    config_hash: String,
+    saved_at: u64,
+    completed: Vec<String>,
+}
+
+/// How long a failed apply's completed phases are trusted (seconds)
+const RESUME_WINDOW_SECS: u64 = 60 * 60;
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Hash of the resolved config (after merging fragments and --filter)
+/// `with_system_settings` is part of it: an apply that skipped [system]
+/// doesn't cover one that includes it
+pub fn config_hash(config: &Config, with_system_settings: bool) -> Result<String> {
+    // Going through Value sorts map keys, so HashMap order doesn't leak in
+    let value = serde_json::to_value(config).context("Failed to serialize config")?;
+    let text = format!("{}\0{}", value, with_system_settings);
+
+    // FNV-1a: stable across Rust versions, unlike DefaultHasher
+    let hash = text.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+
+    Ok(format!("{:016x}", hash))
+}
+
+fn state_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".cache/macup/state.json"))
+}
+
+fn progress_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".cache/macup/resume.json"))
+}
+
+/// Load the last apply state, if any
+pub fn load_state() -> Option<ApplyState> {
+    let path = state_path()?;
+    let content = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            log::warn!("Ignoring unreadable apply state {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+pub fn save_state(state: &ApplyState) -> Result<()> {
+    let path = state_path().context("Could not determine home directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context(format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(state)?)
+        .context(format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Phases a recent failed apply of this exact config completed
+/// Empty once the config changed or the resume window passed
+pub fn load_completed_phases(config_hash: &str) -> HashSet<String> {
+    let Some(content) = progress_path().and_then(|path| fs::read_to_string(path).ok()) else {
+        return HashSet::new();
+    };
+    let progress: ApplyProgress = match serde_json::from_str(&content) {
+        Ok(progress) => progress,
+        Err(e) => {
+            log::warn!("Ignoring unreadable resume state: {}", e);
+            return HashSet::new();
+        }
+    };
+
+    let age = now_secs().saturating_sub(progress.saved_at);
+    if progress.config_hash != config_hash || age >= RESUME_WINDOW_SECS {
+        return HashSet::new();
+    }
+    progress.completed.into_iter().collect()
+}
+
+/// Remember which phases completed in an apply that didn't fully succeed
+pub fn save_completed_phases(config_hash: &str, completed: &[String]) -> Result<()> {
+    let path = progress_path().context("Could not determine home directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context(format!("Failed to create {}", parent.display()))?;
+    }
+    let progress = ApplyProgress {
+        config_hash: config_hash.to_string(),
+        saved_at: now_secs(),
+        completed: completed.to_vec(),
+    };
+    fs::write(&path, serde_json::to_string_pretty(&progress)?)
+        .context(format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Forget completed phases (the apply succeeded, nothing left to resume)
+pub fn clear_completed_phases() {
+    let Some(path) = progress_path() else {
+        return;
+    };
+    if path.exists() {
+        if let Err(e) = fs::remove_file(&path) {
+            log::warn!("Could not remove resume state {}: {}", path.display(), e);
+        }
+    }
+}
@@ -8,46 +8,258 @@ mod utils;
 
 use anyhow::Result;
 use clap::Parser;
-use cli::{Cli, Command, NewResource, RemoveResource};
+use cli::{Cli, Command, ConfigAction, DiffFormat, NewResource, RemoveResource};
+use colored::Colorize;
+use executor::{ApplyOptions, SelectionFilter};
 
-fn main() -> Result<()> {
-    // Setup logging
+fn main() {
+    // Setup logging (records are also copied to the log file, once one is set)
     env_logger::Builder::from_default_env()
         .filter_level(log::LevelFilter::Info)
+        .target(env_logger::Target::Pipe(Box::new(utils::TeeWriter)))
         .init();
 
     let cli = Cli::parse();
+    let verbose = cli.verbose;
+
+    exit_on_error(run(cli), verbose);
+}
+
+/// Report a failed command and exit with status 1
+fn exit_on_error(result: Result<()>, verbose: bool) {
+    if let Err(e) = result {
+        report_error(&e, verbose);
+        std::process::exit(1);
+    }
+}
+
+/// Print a failed command's error without Rust's `Debug` formatting
+/// `--verbose` keeps the full report, including a backtrace when enabled
+fn report_error(error: &anyhow::Error, verbose: bool) {
+    if verbose {
+        eprintln!("{} {:?}", "Error:".red().bold(), error);
+        return;
+    }
+
+    eprintln!("{} {}", "Error:".red().bold(), error.to_string().red());
+    let mut causes = error.chain().skip(1).peekable();
+    if causes.peek().is_some() {
+        eprintln!();
+        eprintln!("Caused by:");
+        for cause in causes {
+            eprintln!("    {}", cause);
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    if let Some(path) = &cli.log_file {
+        utils::set_log_file(path)?;
+    }
 
     // Set verbose logging if requested
     if cli.verbose {
         log::set_max_level(log::LevelFilter::Debug);
+    } else if cli.quiet {
+        log::set_max_level(log::LevelFilter::Warn);
+    }
+
+    // --config-dir is --config restricted to a directory
+    if let Some(dir) = &cli.config_dir {
+        if !dir.is_dir() {
+            anyhow::bail!("Config directory not found: {}", dir.display());
+        }
     }
+    let config_path = cli.config_dir.as_deref().or(cli.config.as_deref());
 
     match cli.command {
         Command::Apply {
             dry_run,
+            no_dry_run,
             with_system_settings,
+            summary_only,
+            no_deps,
+            explain,
+            strict,
+            verify_after,
+            rerun,
+            max_parallel,
+            max_retries,
+            max_failures,
+            filter,
+            group,
+            tag,
+            manager,
+            packages_from_diff,
+            auto_fix,
+            json_lines,
+            interactive,
+            changed_only,
+            only,
+            skip,
+            only_runtimes,
+            recheck,
+            save_plan,
+            plan_file,
+            force,
+            reverse,
+            yes,
+            profile,
             section,
         } => {
-            commands::apply::run(
-                cli.config.as_deref(),
+            if let Some(section) = reverse {
+                commands::reverse::run(config_path, &section, yes, dry_run, no_dry_run)?;
+                return Ok(());
+            }
+
+            let opts = ApplyOptions {
                 dry_run,
-                with_system_settings,
-                section.as_deref(),
+                no_dry_run,
+                // `macup apply system` (or `--only system`) is an explicit request for system settings
+                with_system_settings: with_system_settings
+                    || section.as_deref() == Some("system")
+                    || only.iter().any(|s| s == "system"),
+                summary_only: summary_only || json_lines,
+                max_parallel,
+                explain,
+                strict,
+                verify_after,
+                rerun,
+                max_retries,
+                max_failures,
+                filter,
+                group,
+                tag,
+                manager,
+                packages_from_diff,
+                no_deps,
+                auto_fix,
+                config_file: None,
+                json_lines,
+                interactive,
+                changed_only,
+                only,
+                skip,
+                only_runtimes,
+                recheck,
+                resume_key: None,
+                save_plan,
+                plan_file,
+                force,
+                profile,
+                replay: false,
+            };
+            commands::apply::run(config_path, &opts, section.as_deref())?;
+        }
+        Command::Diff {
+            verify,
+            filter,
+            group,
+            tag,
+            manager,
+            outdated,
+            output,
+            json,
+            exit_code,
+        } => {
+            let drifted = commands::diff::run(
+                config_path,
+                verify,
+                &SelectionFilter {
+                    filter,
+                    group,
+                    tag,
+                    manager,
+                    missing: None,
+                },
+                outdated,
+                if json { DiffFormat::Json } else { output },
             )?;
+            if exit_code && drifted {
+                std::process::exit(commands::diff::DRIFT_EXIT_CODE);
+            }
+        }
+        Command::Outdated => {
+            commands::outdated::run(config_path)?;
         }
-        Command::Diff => {
-            commands::diff::run(cli.config.as_deref())?;
+        Command::RunScript { name, force } => {
+            commands::run_script::run(config_path, &name, force)?;
+        }
+        Command::Import {
+            incremental,
+            scan_timeout,
+            yes,
+            create_config,
+            detect_taps,
+        } => {
+            commands::import::run(
+                config_path,
+                incremental,
+                scan_timeout,
+                yes,
+                create_config,
+                detect_taps,
+            )?;
+        }
+        Command::Doctor { codegen, fix } => {
+            if codegen {
+                commands::remove_manager::audit(fix)?;
+            } else {
+                commands::doctor::run(config_path)?;
+            }
+        }
+        Command::Init {
+            from_system,
+            force,
+            scan_timeout,
+            detect_taps,
+        } => {
+            let path = cli
+                .config
+                .as_deref()
+                .unwrap_or(std::path::Path::new("macup.toml"));
+            commands::import::init(path, from_system, force, scan_timeout, detect_taps)?;
         }
-        Command::Import => {
-            commands::import::run(cli.config.as_deref())?;
+        Command::Migrate { dry_run } => {
+            commands::migrate::run(config_path, dry_run)?;
         }
+        Command::Config { action } => match action {
+            ConfigAction::Get { key } => {
+                commands::config::get(config_path, &key)?;
+            }
+            ConfigAction::Show => {
+                commands::config::show(config_path)?;
+            }
+            ConfigAction::Diff { other } => {
+                commands::config::diff(config_path, &other)?;
+            }
+            ConfigAction::Set { key, value } => {
+                commands::config::set(config_path, &key, &value)?;
+            }
+        },
         Command::Add {
             manager,
-            packages,
+            mut packages,
+            packages_from,
             no_install,
+            dry_run,
+            no_dry_run,
+            create_config,
+            name,
         } => {
-            commands::add::run(cli.config.as_deref(), &manager, packages, no_install)?;
+            if let Some(path) = &packages_from {
+                packages.extend(commands::add::read_packages_from(path)?);
+            }
+            commands::add::run(
+                config_path,
+                &manager,
+                packages,
+                no_install,
+                dry_run,
+                no_dry_run,
+                create_config,
+                name.as_deref(),
+            )?;
         }
         Command::New { resource } => match resource {
             NewResource::Manager {
@@ -57,17 +269,48 @@ fn main() -> Result<()> {
                 runtime_cmd,
                 runtime_name,
                 brew_formula,
+                check,
+                verify,
             } => {
-                commands::new_manager::run(
-                    &name,
-                    &display,
-                    &icon,
-                    &runtime_cmd,
-                    &runtime_name,
-                    &brew_formula,
-                )?;
+                if check {
+                    commands::new_manager::check(name.as_deref())?;
+                } else {
+                    // clap requires all of these unless --check is given
+                    commands::new_manager::run(
+                        &name.unwrap_or_default(),
+                        &display.unwrap_or_default(),
+                        &icon.unwrap_or_default(),
+                        &runtime_cmd.unwrap_or_default(),
+                        &runtime_name.unwrap_or_default(),
+                        &brew_formula.unwrap_or_default(),
+                        verify,
+                    )?;
+                }
             }
         },
+        Command::Rm {
+            manager,
+            packages,
+            no_uninstall,
+            dry_run,
+            no_dry_run,
+        } => {
+            commands::remove::run(
+                config_path,
+                &manager,
+                packages,
+                no_uninstall,
+                dry_run,
+                no_dry_run,
+            )?;
+        }
+        Command::Upgrade {
+            packages,
+            dry_run,
+            no_dry_run,
+        } => {
+            commands::upgrade::run(config_path, packages, dry_run, no_dry_run)?;
+        }
         Command::Remove { resource } => match resource {
             RemoveResource::Manager { name } => {
                 commands::remove_manager::run(&name)?;
@@ -77,3 +320,65 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{output_of, FakeEnv};
+
+    #[test]
+    fn failure_prints_a_clean_error_and_exits_1() {
+        let Some(output) = output_of("tests::failure_prints_a_clean_error_and_exits_1") else {
+            let _env = FakeEnv::new("main-error");
+            let path = dirs::home_dir().unwrap().join("macup.toml");
+            std::fs::write(&path, "[brew\n").unwrap();
+
+            let cli = Cli::parse_from(["macup", "--config", path.to_str().unwrap(), "apply"]);
+            exit_on_error(run(cli), false);
+            unreachable!("run should have failed");
+        };
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(stderr.contains("Error: Failed to parse TOML config: "));
+        assert!(stderr.contains("\nCaused by:\n    TOML parse error at line 1"));
+        assert!(!stderr.contains("Stack backtrace"));
+        assert!(!stderr.contains("panicked"));
+    }
+
+    #[test]
+    fn apply_system_runs_system_settings_without_the_flag() {
+        let env = FakeEnv::new("main-apply-system");
+        env.bin("brew", "exit 0").bin("defaults", "exit 0");
+        let path = dirs::home_dir().unwrap().join("macup.toml");
+        std::fs::write(
+            &path,
+            "[system]\ncommands = [\"defaults write com.apple.dock autohide -bool true\"]\n",
+        )
+        .unwrap();
+        let apply = |args: &[&str]| {
+            let cli = Cli::parse_from(
+                [
+                    "macup",
+                    "--config",
+                    path.to_str().unwrap(),
+                    "apply",
+                    "--summary-only",
+                ]
+                .iter()
+                .chain(args),
+            );
+            run(cli).unwrap();
+        };
+
+        // A plain apply skips them
+        apply(&[]);
+        assert!(env.calls().iter().all(|call| !call.starts_with("defaults")));
+
+        apply(&["system"]);
+        assert_eq!(
+            env.calls().last().map(String::as_str),
+            Some("defaults write com.apple.dock autohide -bool true")
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use crate::managers::{FailureKind, InstallResult, Manager};
+use crate::utils;
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::process::Command;
+
+pub struct AsdfManager {
+    max_parallel: usize,
+}
+
+impl AsdfManager {
+    pub fn new(max_parallel: usize) -> Self {
+        Self { max_parallel }
+    }
+
+    /// Parse a tool spec
+    /// Format: "plugin@version"
+    /// Examples:
+    ///   - "nodejs@20.11.0" -> plugin "nodejs", version "20.11.0"
+    pub fn parse_tool_spec(input: &str) -> Result<(&str, &str)> {
+        match input.split_once('@') {
+            Some((plugin, version)) if !plugin.trim().is_empty() && !version.trim().is_empty() => {
+                Ok((plugin.trim(), version.trim()))
+            }
+            _ => anyhow::bail!("Invalid asdf tool '{}', expected plugin@version", input),
+        }
+    }
+
+    /// List added plugins
+    pub fn list_plugins(&self) -> Result<HashSet<String>> {
+        let output = Command::new("asdf")
+            .args(["plugin", "list"])
+            .output()
+            .context("Failed to run asdf plugin list")?;
+
+        // asdf exits non-zero when no plugins are installed
+        let plugins = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        Ok(plugins)
+    }
+
+    /// List installed versions of a plugin
+    pub fn list_versions(&self, plugin: &str) -> Result<HashSet<String>> {
+        let output = Command::new("asdf")
+            .args(["list", plugin])
+            .output()
+            .context(format!("Failed to run asdf list {}", plugin))?;
+
+        if !output.status.success() {
+            return Ok(HashSet::new());
+        }
+
+        // Format: "  20.11.0" or " *20.11.0" (current version)
+        let versions = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|s| s.trim().trim_start_matches('*').to_string())
+            .filter(|s| !s.is_empty() && !s.starts_with("No versions"))
+            .collect();
+
+        Ok(versions)
+    }
+
+    /// Add a plugin
+    pub fn add_plugin(&self, plugin: &str) -> Result<()> {
+        log::info!("→ Adding asdf plugin {}...", plugin);
+
+        let success = utils::run_status(Command::new("asdf").args(["plugin", "add", plugin]), None)
+            .context(format!("Failed to add asdf plugin: {}", plugin))?;
+
+        if !success {
+            anyhow::bail!("asdf plugin add {} failed", plugin);
+        }
+
+        log::info!("✓ Plugin {} added", plugin);
+        Ok(())
+    }
+
+    /// Command `install_version` runs
+    fn describe_version_install(plugin: &str, version: &str) -> Vec<String> {
+        vec![
+            "asdf".to_string(),
+            "install".to_string(),
+            plugin.to_string(),
+            version.to_string(),
+        ]
+    }
+
+    /// Install a tool version (plugin must already be added)
+    /// With `output`, asdf's output is captured there instead of printed
+    pub fn install_version(
+        &self,
+        plugin: &str,
+        version: &str,
+        output: Option<&mut String>,
+    ) -> Result<()> {
+        log::info!("→ Installing {} {} (asdf)...", plugin, version);
+
+        let success = utils::run_status(
+            &mut utils::command_for(&Self::describe_version_install(plugin, version)),
+            output,
+        )
+        .context(format!("Failed to install {} {}", plugin, version))?;
+
+        if !success {
+            anyhow::bail!("asdf install {} {} failed", plugin, version);
+        }
+
+        log::info!("✓ {} {} installed", plugin, version);
+        Ok(())
+    }
+}
+
+impl Manager for AsdfManager {
+    fn name(&self) -> &str {
+        "asdf"
+    }
+
+    fn is_installed(&self) -> bool {
+        utils::command_exists("asdf")
+    }
+
+    fn install_self(&self) -> Result<()> {
+        // Runtime is installed via brew in apply phase
+        Ok(())
+    }
+
+    fn list_installed(&self) -> Result<HashSet<String>> {
+        let mut installed = HashSet::new();
+        for plugin in self.list_plugins()? {
+            for version in self.list_versions(&plugin)? {
+                installed.insert(format!("{}@{}", plugin, version));
+            }
+        }
+        Ok(installed)
+    }
+
+    fn is_package_installed(&self, package: &str) -> Result<bool> {
+        let (plugin, version) = Self::parse_tool_spec(package)?;
+        Ok(self.list_versions(plugin)?.contains(version))
+    }
+
+    fn installed_set(&self, candidates: &[String]) -> Result<HashSet<String>> {
+        // One `asdf list <plugin>` per plugin, not per version
+        let mut versions: HashMap<&str, HashSet<String>> = HashMap::new();
+        let mut installed = HashSet::new();
+
+        for spec in candidates {
+            let (plugin, version) = Self::parse_tool_spec(spec)?;
+            if !versions.contains_key(plugin) {
+                versions.insert(plugin, self.list_versions(plugin)?);
+            }
+            if versions[plugin].contains(version) {
+                installed.insert(spec.clone());
+            }
+        }
+
+        Ok(installed)
+    }
+
+    /// A malformed spec is described as-is; installing it fails with the parse error
+    fn describe_install(&self, package: &str) -> Vec<String> {
+        match Self::parse_tool_spec(package) {
+            Ok((plugin, version)) => Self::describe_version_install(plugin, version),
+            Err(_) => vec![
+                "asdf".to_string(),
+                "install".to_string(),
+                package.to_string(),
+            ],
+        }
+    }
+
+    fn install_package(&self, package: &str) -> Result<()> {
+        let (plugin, version) = Self::parse_tool_spec(package)?;
+
+        if self.is_package_installed(package)? {
+            log::info!("✓ {} {} already installed", plugin, version);
+            return Ok(());
+        }
+
+        if !self.list_plugins()?.contains(plugin) {
+            self.add_plugin(plugin)?;
+        }
+
+        self.install_version(plugin, version, None)
+    }
+
+    fn uninstall_package(&self, package: &str) -> Result<()> {
+        let (plugin, version) = Self::parse_tool_spec(package)?;
+
+        log::info!("→ Uninstalling {} {} (asdf)...", plugin, version);
+
+        let success = utils::run_status(
+            Command::new("asdf").args(["uninstall", plugin, version]),
+            None,
+        )
+        .context(format!("Failed to uninstall {} {}", plugin, version))?;
+
+        if !success {
+            anyhow::bail!("asdf uninstall {} {} failed", plugin, version);
+        }
+
+        log::info!("✓ {} {} uninstalled", plugin, version);
+        Ok(())
+    }
+
+    fn install_packages(&self, packages: &[String]) -> Result<InstallResult> {
+        if packages.is_empty() {
+            return Ok(InstallResult::default());
+        }
+
+        let mut result = InstallResult::default();
+        let mut to_install = Vec::new();
+
+        for pkg in packages {
+            match self.is_package_installed(pkg) {
+                Ok(true) => result.skipped.push(pkg.clone()),
+                Ok(false) => to_install.push(pkg.clone()),
+                Err(e) => {
+                    let kind = FailureKind::classify(&e, "");
+                    result.failed.push((pkg.clone(), e.to_string(), kind))
+                }
+            }
+        }
+
+        if !result.skipped.is_empty() {
+            log::info!("✓ {} tools already installed", result.skipped.len());
+        }
+
+        if to_install.is_empty() {
+            return Ok(result);
+        }
+
+        // Plugins are added sequentially (shared asdf state), versions in parallel
+        let plugins = self.list_plugins()?;
+        let mut failed_plugins = HashSet::new();
+        for pkg in &to_install {
+            let (plugin, _) = Self::parse_tool_spec(pkg)?;
+            if plugins.contains(plugin) || failed_plugins.contains(plugin) {
+                continue;
+            }
+            if let Err(e) = self.add_plugin(plugin) {
+                log::warn!("{}", e);
+                failed_plugins.insert(plugin.to_string());
+            }
+        }
+
+        log::info!("Installing {} tools...", to_install.len());
+
+        let results: Vec<_> = utils::install_pool(self.max_parallel)?.install(|| {
+            to_install
+                .par_iter()
+                .map(|pkg| {
+                    let mut output = String::new();
+                    let res = Self::parse_tool_spec(pkg).and_then(|(plugin, version)| {
+                        if failed_plugins.contains(plugin) {
+                            anyhow::bail!("asdf plugin add {} failed", plugin);
+                        }
+                        self.install_version(plugin, version, Some(&mut output))
+                    });
+                    (pkg.clone(), res, output)
+                })
+                .collect()
+        });
+
+        for (pkg, res, output) in results {
+            utils::flush_output(&pkg, &output, res.is_err());
+            match res {
+                Ok(_) => result.success.push(pkg),
+                Err(e) => {
+                    let kind = FailureKind::classify(&e, &output);
+                    result.failed.push((pkg, e.to_string(), kind))
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
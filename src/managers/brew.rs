@@ -1,17 +1,43 @@
-use crate::managers::{InstallResult, Manager};
+use crate::config::{BrewConfig, TapEntry};
+use crate::managers::{normalize_package_name, FailureKind, InstallResult, Manager};
 use crate::utils;
 use anyhow::{Context, Result};
 use rayon::prelude::*;
-use std::collections::HashSet;
-use std::process::Command;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
 
 pub struct BrewManager {
     max_parallel: usize,
+    /// `brew list --versions` result, fetched once and dropped on (un)install
+    formula_versions: Mutex<Option<HashMap<String, String>>>,
+    /// Installed casks by lowercased app name, fetched once when a
+    /// `brew.casks` entry looks like an app name rather than a token
+    cask_app_names: Mutex<Option<HashMap<String, String>>>,
+}
+
+/// Pins to add and remove so `brew list --pinned` matches `brew.pin`
+#[derive(Debug, Default)]
+pub struct PinChanges {
+    pub to_pin: Vec<String>,
+    pub to_unpin: Vec<String>,
+}
+
+impl PinChanges {
+    pub fn is_empty(&self) -> bool {
+        self.to_pin.is_empty() && self.to_unpin.is_empty()
+    }
 }
 
 impl BrewManager {
     pub fn new(max_parallel: usize) -> Self {
-        Self { max_parallel }
+        Self {
+            max_parallel,
+            formula_versions: Mutex::new(None),
+            cask_app_names: Mutex::new(None),
+        }
     }
 
     /// Create brew command with HOMEBREW_NO_AUTO_UPDATE=1
@@ -26,33 +52,194 @@ impl BrewManager {
     /// Examples:
     ///   - "httpie:http" -> install "httpie", check binary "http"
     ///   - "neovim" -> install "neovim", check binary "neovim"
+    ///   - "owner/repo/tool" -> install "owner/repo/tool", check binary "tool"
     fn parse_package_name(input: &str) -> (&str, &str) {
         if let Some((pkg, bin)) = input.split_once(':') {
             (pkg.trim(), bin.trim())
         } else {
-            (input.trim(), input.trim())
+            (input.trim(), Self::short_name(input.trim()))
         }
     }
 
-    /// List installed formulae
-    pub fn list_formulae(&self) -> Result<HashSet<String>> {
+    /// Name brew reports for a tap-qualified formula or cask:
+    /// "homebrew/cask-versions/firefox@beta" -> "firefox@beta"
+    /// `brew list` only shows the short name, so presence checks must use it
+    pub fn short_name(name: &str) -> &str {
+        name.rsplit('/').next().unwrap_or(name)
+    }
+
+    /// Whether `formula` (possibly tap-qualified, "name:binary", any casing) is
+    /// in a `list_formulae` result
+    pub fn has_formula(installed: &HashSet<String>, formula: &str) -> bool {
+        let (name, _) = Self::parse_package_name(formula);
+        installed.contains(&normalize_package_name("brew", Self::short_name(name)))
+    }
+
+    /// Whether `cask` (possibly tap-qualified, any casing) is in a `list_casks` result
+    pub fn has_cask(installed: &HashSet<String>, cask: &str) -> bool {
+        installed.contains(&normalize_package_name("brew", Self::short_name(cask)))
+    }
+
+    /// Entries of `casks` that aren't installed
+    /// Matched by token first; entries that look like an app's name instead
+    /// ("Visual Studio Code") are then looked up among the names of installed
+    /// casks, with a warning suggesting the token
+    pub fn missing_casks(&self, casks: &[String], installed: &HashSet<String>) -> Vec<String> {
+        let unmatched: Vec<String> = casks
+            .iter()
+            .filter(|cask| !Self::has_cask(installed, cask))
+            .cloned()
+            .collect();
+
+        // Tokens are lowercase without spaces - don't ask brew about those
+        let looks_like_app_name =
+            |cask: &str| cask.chars().any(|c| c.is_whitespace() || c.is_uppercase());
+        if !unmatched.iter().any(|cask| looks_like_app_name(cask)) {
+            return unmatched;
+        }
+
+        let mut cached = self
+            .cask_app_names
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let app_names = cached.get_or_insert_with(|| {
+            self.installed_cask_app_names().unwrap_or_else(|e| {
+                log::debug!("Could not read installed cask names: {}", e);
+                HashMap::new()
+            })
+        });
+
+        unmatched
+            .into_iter()
+            .filter(|cask| match app_names.get(&cask.trim().to_lowercase()) {
+                Some(token) => {
+                    log::warn!(
+                        "brew.casks: '{}' is an app name - write its cask token '{}' instead",
+                        cask,
+                        token
+                    );
+                    false
+                }
+                None => true,
+            })
+            .collect()
+    }
+
+    /// Parse `brew info --cask --json=v2` output into lowercased app name -> token
+    pub fn parse_cask_app_names(json: &str) -> Result<HashMap<String, String>> {
+        let info: serde_json::Value =
+            serde_json::from_str(json).context("Failed to parse brew info output")?;
+
+        let mut names = HashMap::new();
+        for cask in info["casks"].as_array().into_iter().flatten() {
+            let Some(token) = cask["token"].as_str() else {
+                continue;
+            };
+            for name in cask["name"].as_array().into_iter().flatten() {
+                if let Some(name) = name.as_str() {
+                    names.insert(name.to_lowercase(), token.to_string());
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    /// Parse `brew info --json=v2` output into formula or cask -> tap, for
+    /// packages outside the default homebrew/core and homebrew/cask taps
+    pub fn parse_package_taps(json: &str) -> Result<HashMap<String, String>> {
+        let info: serde_json::Value =
+            serde_json::from_str(json).context("Failed to parse brew info output")?;
+
+        let formulae = info["formulae"].as_array().into_iter().flatten();
+        let casks = info["casks"].as_array().into_iter().flatten();
+
+        let mut taps = HashMap::new();
+        for (package, key) in formulae
+            .map(|formula| (formula, "name"))
+            .chain(casks.map(|cask| (cask, "token")))
+        {
+            let (Some(name), Some(tap)) = (package[key].as_str(), package["tap"].as_str()) else {
+                continue;
+            };
+            if !matches!(tap, "homebrew/core" | "homebrew/cask") {
+                taps.insert(name.to_string(), tap.to_string());
+            }
+        }
+        Ok(taps)
+    }
+
+    /// App names of every installed cask (`brew info --cask --json=v2 --installed`)
+    fn installed_cask_app_names(&self) -> Result<HashMap<String, String>> {
         let output = self
             .brew_command()
-            .args(["list", "--formula"])
+            .args(["info", "--cask", "--json=v2", "--installed"])
             .output()
-            .context("Failed to list brew formulae")?;
+            .context("Failed to run brew info")?;
 
         if !output.status.success() {
-            anyhow::bail!("brew list --formula failed");
+            anyhow::bail!("brew info --cask --installed failed");
         }
 
-        let installed = String::from_utf8_lossy(&output.stdout)
+        Self::parse_cask_app_names(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Whether `tap` (any casing) is in a `list_taps` result
+    pub fn has_tap(installed: &HashSet<String>, tap: &str) -> bool {
+        installed.contains(&normalize_package_name("brew", tap))
+    }
+
+    /// Parse `brew list --versions` output into formula -> version
+    /// Lines look like "git 2.43.0" or "python@3.12 3.12.1 3.12.2" when
+    /// several versions are kept; the last (newest) one wins
+    pub fn parse_versions(output: &str) -> HashMap<String, String> {
+        output
             .lines()
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let name = fields.next()?;
+                let version = fields.last().unwrap_or_default();
+                Some((name.to_string(), version.to_string()))
+            })
+            .collect()
+    }
 
-        Ok(installed)
+    /// Installed formulae with their versions, from a single `brew list --versions`
+    /// The result is cached for the lifetime of this manager
+    pub fn list_formulae_with_versions(&self) -> Result<HashMap<String, String>> {
+        let mut cached = self
+            .formula_versions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(versions) = cached.as_ref() {
+            return Ok(versions.clone());
+        }
+
+        let output = self
+            .brew_command()
+            .args(["list", "--formula", "--versions"])
+            .output()
+            .context("Failed to list brew formulae")?;
+
+        if !output.status.success() {
+            anyhow::bail!("brew list --formula --versions failed");
+        }
+
+        let versions = Self::parse_versions(&String::from_utf8_lossy(&output.stdout));
+        *cached = Some(versions.clone());
+        Ok(versions)
+    }
+
+    /// List installed formulae
+    pub fn list_formulae(&self) -> Result<HashSet<String>> {
+        Ok(self.list_formulae_with_versions()?.into_keys().collect())
+    }
+
+    /// Drop the cached formula list after something was installed or removed
+    fn forget_formulae(&self) {
+        *self
+            .formula_versions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = None;
     }
 
     /// List installed casks
@@ -99,37 +286,89 @@ impl BrewManager {
 
     /// Install a formula
     /// Accepts "package:binary" format but only uses package name for installation
-    pub fn install_formula(&self, package_spec: &str) -> Result<()> {
+    /// With `output`, brew's output is captured there instead of printed
+    pub fn install_formula(
+        &self,
+        package_spec: &str,
+        mut output: Option<&mut String>,
+    ) -> Result<()> {
         // Parse package:binary format - install using package name only
         let (pkg_name, _binary_name) = Self::parse_package_name(package_spec);
 
         log::info!("→ Installing {} (formula)...", pkg_name);
 
-        let status = self
-            .brew_command()
-            .args(["install", pkg_name])
-            .status()
-            .context(format!("Failed to install formula: {}", pkg_name))?;
+        let success = utils::run_status(
+            utils::command_for(&self.describe_install(package_spec))
+                .env("HOMEBREW_NO_AUTO_UPDATE", "1"),
+            output.as_deref_mut(),
+        )
+        .context(format!("Failed to install formula: {}", pkg_name))?;
+
+        if !success {
+            // Listed under formulae but only exists as a cask (e.g. "docker")
+            let is_cask = match output.as_deref() {
+                Some(text) => Self::output_suggests_cask(text, pkg_name),
+                None => self.cask_exists(pkg_name),
+            };
+            if is_cask {
+                anyhow::bail!(
+                    "{} is a cask, not a formula - move it to brew.casks",
+                    pkg_name
+                );
+            }
 
-        if !status.success() {
             anyhow::bail!("brew install {} failed", pkg_name);
         }
 
+        self.forget_formulae();
         log::info!("✓ {} installed", pkg_name);
         Ok(())
     }
 
+    /// Whether a failed `brew install <name>` points at a cask of that name
+    /// e.g. "No available formula with the name "docker". ... brew install --cask docker"
+    pub fn output_suggests_cask(output: &str, name: &str) -> bool {
+        let output = output.to_lowercase();
+        let name = name.to_lowercase();
+
+        output.contains(&format!("brew install --cask {}", name))
+            || output.contains(&format!("found a cask named \"{}\"", name))
+            || output.contains("did you mean the cask")
+    }
+
+    /// Whether a cask with this name exists (used when brew's output wasn't captured)
+    fn cask_exists(&self, name: &str) -> bool {
+        self.brew_command()
+            .args(["info", "--cask", name])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    /// Command `install_cask` runs for `name`
+    pub fn describe_cask_install(name: &str) -> Vec<String> {
+        vec![
+            "brew".to_string(),
+            "install".to_string(),
+            "--cask".to_string(),
+            name.to_string(),
+        ]
+    }
+
     /// Install a cask
-    pub fn install_cask(&self, name: &str) -> Result<()> {
+    /// With `output`, brew's output is captured there instead of printed
+    pub fn install_cask(&self, name: &str, output: Option<&mut String>) -> Result<()> {
         log::info!("→ Installing {} (cask)...", name);
 
-        let status = self
-            .brew_command()
-            .args(["install", "--cask", name])
-            .status()
-            .context(format!("Failed to install cask: {}", name))?;
+        let success = utils::run_status(
+            utils::command_for(&Self::describe_cask_install(name))
+                .env("HOMEBREW_NO_AUTO_UPDATE", "1"),
+            output,
+        )
+        .context(format!("Failed to install cask: {}", name))?;
 
-        if !status.success() {
+        if !success {
             anyhow::bail!("brew install --cask {} failed", name);
         }
 
@@ -137,17 +376,100 @@ impl BrewManager {
         Ok(())
     }
 
-    /// Add a tap
-    pub fn add_tap(&self, name: &str) -> Result<()> {
+    /// Installed formulae that depend on `name` (`brew uses --installed`)
+    pub fn installed_dependents(&self, name: &str) -> Result<Vec<String>> {
+        let output = self
+            .brew_command()
+            .args(["uses", "--installed", name])
+            .output()
+            .context(format!("Failed to run brew uses {}", name))?;
+
+        if !output.status.success() {
+            anyhow::bail!("brew uses --installed {} failed", name);
+        }
+
+        let dependents = String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .map(|s| s.to_string())
+            .collect();
+
+        Ok(dependents)
+    }
+
+    /// Uninstall a cask
+    pub fn uninstall_cask(&self, name: &str) -> Result<()> {
+        log::info!("→ Uninstalling {} (cask)...", name);
+
+        let success = utils::run_status(
+            self.brew_command().args(["uninstall", "--cask", name]),
+            None,
+        )
+        .context(format!("Failed to uninstall cask: {}", name))?;
+
+        if !success {
+            anyhow::bail!("brew uninstall --cask {} failed", name);
+        }
+
+        log::info!("✓ {} uninstalled", name);
+        Ok(())
+    }
+
+    /// Command upgrading an installed cask
+    pub fn describe_cask_upgrade(&self, name: &str) -> Vec<String> {
+        vec![
+            "brew".to_string(),
+            "upgrade".to_string(),
+            "--cask".to_string(),
+            name.to_string(),
+        ]
+    }
+
+    /// Upgrade a cask to its latest version
+    /// With `output`, brew's output is captured there instead of printed
+    pub fn upgrade_cask(&self, name: &str, output: Option<&mut String>) -> Result<()> {
+        let argv = self.describe_cask_upgrade(name);
+        let success = utils::run_status(
+            utils::command_for(&argv).env("HOMEBREW_NO_AUTO_UPDATE", "1"),
+            output,
+        )
+        .context(format!("Failed to upgrade cask: {}", name))?;
+
+        if !success {
+            anyhow::bail!("{} failed", argv.join(" "));
+        }
+        Ok(())
+    }
+
+    /// Uninstall casks one at a time; `success` lists what was removed
+    pub fn uninstall_casks(&self, casks: &[String]) -> InstallResult {
+        let mut result = InstallResult::default();
+        for cask in casks {
+            match self.uninstall_cask(cask) {
+                Ok(()) => result.success.push(cask.clone()),
+                Err(e) => {
+                    let kind = FailureKind::classify(&e, "");
+                    result.failed.push((cask.clone(), e.to_string(), kind));
+                }
+            }
+        }
+        result
+    }
+
+    /// Add a tap, cloning from its custom URL when one is configured
+    pub fn add_tap(&self, tap: &TapEntry) -> Result<()> {
+        let name = tap.name();
         log::info!("→ Adding tap {}...", name);
 
-        let status = self
-            .brew_command()
-            .args(["tap", name])
-            .status()
-            .context(format!("Failed to add tap: {}", name))?;
+        let mut cmd = self.brew_command();
+        cmd.args(["tap", name]);
+        if let Some(url) = tap.url() {
+            cmd.arg(url);
+        }
 
-        if !status.success() {
+        let success =
+            utils::run_status(&mut cmd, None).context(format!("Failed to add tap: {}", name))?;
+
+        if !success {
             anyhow::bail!("brew tap {} failed", name);
         }
 
@@ -155,8 +477,178 @@ impl BrewManager {
         Ok(())
     }
 
+    /// List pinned formulae (short names)
+    pub fn list_pinned(&self) -> Result<HashSet<String>> {
+        let output = self
+            .brew_command()
+            .args(["list", "--pinned"])
+            .output()
+            .context("Failed to list pinned brew formulae")?;
+
+        if !output.status.success() {
+            anyhow::bail!("brew list --pinned failed");
+        }
+
+        let pinned = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        Ok(pinned)
+    }
+
+    /// Compare `brew.pin` with what brew has pinned
+    /// Only declared formulae are unpinned - pins made outside macup are left alone
+    pub fn pin_changes(&self, pin: &[String], formulae: &[String]) -> Result<PinChanges> {
+        let pinned = self.list_pinned()?;
+        let wanted: HashSet<&str> = pin.iter().map(|p| Self::short_name(p.trim())).collect();
+
+        let to_pin = pin
+            .iter()
+            .filter(|p| !pinned.contains(Self::short_name(p.trim())))
+            .cloned()
+            .collect();
+
+        let to_unpin = formulae
+            .iter()
+            .map(|spec| Self::parse_package_name(spec).0)
+            .filter(|name| {
+                let short = Self::short_name(name);
+                pinned.contains(short) && !wanted.contains(short)
+            })
+            .map(|name| name.to_string())
+            .collect();
+
+        Ok(PinChanges { to_pin, to_unpin })
+    }
+
+    /// `brew pin` / `brew unpin` a formula
+    pub fn set_pinned(&self, name: &str, pinned: bool) -> Result<()> {
+        let action = if pinned { "pin" } else { "unpin" };
+
+        let success = utils::run_status(self.brew_command().args([action, name]), None)
+            .context(format!("Failed to {} {}", action, name))?;
+
+        if !success {
+            anyhow::bail!("brew {} {} failed", action, name);
+        }
+
+        Ok(())
+    }
+
+    /// Render the [brew] config section as a Brewfile
+    /// "package:binary" entries only keep the package name
+    pub fn brewfile_contents(config: &BrewConfig) -> String {
+        let mut lines = Vec::new();
+
+        for tap in &config.taps {
+            match tap.url() {
+                Some(url) => lines.push(format!("tap \"{}\", \"{}\"", tap.name(), url)),
+                None => lines.push(format!("tap \"{}\"", tap.name())),
+            }
+        }
+        for formula in &config.formulae {
+            let (pkg_name, _binary_name) = Self::parse_package_name(formula);
+            lines.push(format!("brew \"{}\"", pkg_name));
+        }
+        for cask in &config.casks {
+            lines.push(format!("cask \"{}\"", cask));
+        }
+
+        let mut contents = lines.join("\n");
+        contents.push('\n');
+        contents
+    }
+
+    /// Write a Brewfile for the config to a temp file
+    fn write_temp_brewfile(config: &BrewConfig) -> Result<PathBuf> {
+        let path = std::env::temp_dir().join(format!("macup-Brewfile-{}", std::process::id()));
+        fs::write(&path, Self::brewfile_contents(config))
+            .context(format!("Failed to write Brewfile: {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Install everything in the [brew] section with `brew bundle`
+    pub fn bundle_install(&self, config: &BrewConfig) -> Result<()> {
+        let brewfile = Self::write_temp_brewfile(config)?;
+
+        log::info!("→ Running brew bundle ({})...", brewfile.display());
+
+        let success = utils::run_status(
+            self.brew_command()
+                .args(["bundle", "--file"])
+                .arg(&brewfile),
+            None,
+        )
+        .context("Failed to run brew bundle");
+
+        let _ = fs::remove_file(&brewfile);
+
+        if !success? {
+            anyhow::bail!("brew bundle failed");
+        }
+
+        log::info!("✓ brew bundle completed");
+        Ok(())
+    }
+
+    /// Run `brew cleanup`
+    /// Returns the reclaimed space brew reports (e.g. "1.2GB"), if any
+    pub fn cleanup(&self) -> Result<Option<String>> {
+        log::info!("→ Running brew cleanup...");
+
+        let output = self
+            .brew_command()
+            .arg("cleanup")
+            .output()
+            .context("Failed to run brew cleanup")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "brew cleanup failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        // "This operation has freed approximately 1.2GB of disk space."
+        let freed = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find_map(|line| line.split_once("freed approximately "))
+            .and_then(|(_, rest)| rest.split_whitespace().next())
+            .map(|size| size.to_string());
+
+        Ok(freed)
+    }
+
+    /// Run `brew bundle check` for the [brew] section
+    /// Returns the names of entries that still need to be installed
+    pub fn bundle_check(&self, config: &BrewConfig) -> Result<HashSet<String>> {
+        let brewfile = Self::write_temp_brewfile(config)?;
+
+        let output = self
+            .brew_command()
+            .args(["bundle", "check", "--verbose", "--file"])
+            .arg(&brewfile)
+            .output()
+            .context("Failed to run brew bundle check");
+
+        let _ = fs::remove_file(&brewfile);
+        let output = output?;
+
+        // Lines look like: "→ Formula ripgrep needs to be installed or updated."
+        let missing = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| line.contains("needs to be"))
+            .filter_map(|line| line.split_whitespace().nth(2))
+            .map(|s| s.to_string())
+            .collect();
+
+        Ok(missing)
+    }
+
     /// Install formulae with idempotency
-    /// Uses binary checking for faster detection
+    /// Installed ones are found with a single (cached) `brew list`, like `macup diff`
     pub fn install_formulae(&self, formulae: &[String]) -> Result<InstallResult> {
         if formulae.is_empty() {
             return Ok(InstallResult::default());
@@ -164,25 +656,16 @@ impl BrewManager {
 
         log::info!("Checking {} formulae...", formulae.len());
 
-        // Check which formulae are already installed by checking their binaries
-        let to_install: Vec<_> = formulae
+        let installed = self.installed_set(formulae).unwrap_or_default();
+        let (skipped, to_install): (Vec<_>, Vec<_>) = formulae
             .iter()
-            .filter(|pkg| {
-                let (_pkg_name, binary_name) = Self::parse_package_name(pkg);
-                !utils::command_exists(binary_name)
-            })
             .cloned()
-            .collect();
+            .partition(|pkg| installed.contains(pkg));
 
-        let mut result = InstallResult::default();
-        result.skipped = formulae
-            .iter()
-            .filter(|pkg| {
-                let (_pkg_name, binary_name) = Self::parse_package_name(pkg);
-                utils::command_exists(binary_name)
-            })
-            .cloned()
-            .collect();
+        let mut result = InstallResult {
+            skipped,
+            ..Default::default()
+        };
 
         if !result.skipped.is_empty() {
             log::info!("✓ {} formulae already installed", result.skipped.len());
@@ -195,20 +678,25 @@ impl BrewManager {
         log::info!("Installing {} formulae...", to_install.len());
 
         // Parallel install
-        let results: Vec<_> = rayon::ThreadPoolBuilder::new()
-            .num_threads(self.max_parallel)
-            .build()?
-            .install(|| {
-                to_install
-                    .par_iter()
-                    .map(|pkg| (pkg.clone(), self.install_formula(pkg)))
-                    .collect()
-            });
-
-        for (pkg, res) in results {
+        let results: Vec<_> = utils::install_pool(self.max_parallel)?.install(|| {
+            to_install
+                .par_iter()
+                .map(|pkg| {
+                    let mut output = String::new();
+                    let res = self.install_formula(pkg, Some(&mut output));
+                    (pkg.clone(), res, output)
+                })
+                .collect()
+        });
+
+        for (pkg, res, output) in results {
+            utils::flush_output(&pkg, &output, res.is_err());
             match res {
                 Ok(_) => result.success.push(pkg),
-                Err(e) => result.failed.push((pkg, e.to_string())),
+                Err(e) => {
+                    let kind = FailureKind::classify(&e, &output);
+                    result.failed.push((pkg, e.to_string(), kind))
+                }
             }
         }
 
@@ -224,19 +712,16 @@ impl BrewManager {
         log::info!("Checking {} casks...", casks.len());
 
         let installed = self.list_casks()?;
+        let to_install = self.missing_casks(casks, &installed);
 
-        let to_install: Vec<_> = casks
-            .iter()
-            .filter(|pkg| !installed.contains(pkg.as_str()))
-            .cloned()
-            .collect();
-
-        let mut result = InstallResult::default();
-        result.skipped = casks
-            .iter()
-            .filter(|pkg| installed.contains(pkg.as_str()))
-            .cloned()
-            .collect();
+        let mut result = InstallResult {
+            skipped: casks
+                .iter()
+                .filter(|pkg| !to_install.contains(pkg))
+                .cloned()
+                .collect(),
+            ..Default::default()
+        };
 
         if !result.skipped.is_empty() {
             log::info!("✓ {} casks already installed", result.skipped.len());
@@ -248,20 +733,25 @@ impl BrewManager {
 
         log::info!("Installing {} casks...", to_install.len());
 
-        let results: Vec<_> = rayon::ThreadPoolBuilder::new()
-            .num_threads(self.max_parallel)
-            .build()?
-            .install(|| {
-                to_install
-                    .par_iter()
-                    .map(|pkg| (pkg.clone(), self.install_cask(pkg)))
-                    .collect()
-            });
-
-        for (pkg, res) in results {
+        let results: Vec<_> = utils::install_pool(self.max_parallel)?.install(|| {
+            to_install
+                .par_iter()
+                .map(|pkg| {
+                    let mut output = String::new();
+                    let res = self.install_cask(pkg, Some(&mut output));
+                    (pkg.clone(), res, output)
+                })
+                .collect()
+        });
+
+        for (pkg, res, output) in results {
+            utils::flush_output(&pkg, &output, res.is_err());
             match res {
                 Ok(_) => result.success.push(pkg),
-                Err(e) => result.failed.push((pkg, e.to_string())),
+                Err(e) => {
+                    let kind = FailureKind::classify(&e, &output);
+                    result.failed.push((pkg, e.to_string(), kind))
+                }
             }
         }
 
@@ -269,7 +759,7 @@ impl BrewManager {
     }
 
     /// Add taps
-    pub fn add_taps(&self, taps: &[String]) -> Result<InstallResult> {
+    pub fn add_taps(&self, taps: &[TapEntry]) -> Result<InstallResult> {
         if taps.is_empty() {
             return Ok(InstallResult::default());
         }
@@ -278,18 +768,14 @@ impl BrewManager {
 
         let installed = self.list_taps()?;
 
-        let to_add: Vec<_> = taps
+        let (skipped, to_add): (Vec<_>, Vec<_>) = taps
             .iter()
-            .filter(|tap| !installed.contains(tap.as_str()))
-            .cloned()
-            .collect();
+            .partition(|tap| Self::has_tap(&installed, tap.name()));
 
-        let mut result = InstallResult::default();
-        result.skipped = taps
-            .iter()
-            .filter(|tap| installed.contains(tap.as_str()))
-            .cloned()
-            .collect();
+        let mut result = InstallResult {
+            skipped: skipped.iter().map(|tap| tap.name().to_string()).collect(),
+            ..Default::default()
+        };
 
         if !result.skipped.is_empty() {
             log::info!("✓ {} taps already added", result.skipped.len());
@@ -301,9 +787,13 @@ impl BrewManager {
 
         // Taps are added sequentially (safer)
         for tap in to_add {
-            match self.add_tap(&tap) {
-                Ok(_) => result.success.push(tap),
-                Err(e) => result.failed.push((tap, e.to_string())),
+            let name = tap.name().to_string();
+            match self.add_tap(tap) {
+                Ok(_) => result.success.push(name),
+                Err(e) => {
+                    let kind = FailureKind::classify(&e, "");
+                    result.failed.push((name, e.to_string(), kind))
+                }
             }
         }
 
@@ -330,9 +820,27 @@ impl Manager for BrewManager {
     }
 
     fn is_package_installed(&self, package: &str) -> Result<bool> {
-        // Parse package:binary format and check if binary exists
-        let (_pkg_name, binary_name) = Self::parse_package_name(package);
-        Ok(utils::command_exists(binary_name))
+        // By `brew list`, not the binary: a system git doesn't make brew's installed
+        Ok(Self::has_formula(&self.list_formulae()?, package))
+    }
+
+    fn installed_set(&self, candidates: &[String]) -> Result<HashSet<String>> {
+        // One `brew list --formula --versions` covers every candidate
+        let listed = self.list_formulae()?;
+        Ok(candidates
+            .iter()
+            .filter(|formula| Self::has_formula(&listed, formula))
+            .cloned()
+            .collect())
+    }
+
+    fn describe_install(&self, package: &str) -> Vec<String> {
+        let (pkg_name, _) = Self::parse_package_name(package);
+        vec![
+            "brew".to_string(),
+            "install".to_string(),
+            pkg_name.to_string(),
+        ]
     }
 
     fn install_package(&self, package: &str) -> Result<()> {
@@ -342,10 +850,293 @@ impl Manager for BrewManager {
             return Ok(());
         }
 
-        self.install_formula(package)
+        self.install_formula(package, None)
     }
 
     fn install_packages(&self, packages: &[String]) -> Result<InstallResult> {
         self.install_formulae(packages)
     }
+
+    fn describe_upgrade(&self, package: &str) -> Option<Vec<String>> {
+        let (pkg_name, _) = Self::parse_package_name(package);
+        Some(vec![
+            "brew".to_string(),
+            "upgrade".to_string(),
+            pkg_name.to_string(),
+        ])
+    }
+
+    fn upgrade_package(&self, package: &str, output: Option<&mut String>) -> Result<()> {
+        let argv = self
+            .describe_upgrade(package)
+            .expect("brew upgrades any formula");
+        let success = utils::run_status(
+            utils::command_for(&argv).env("HOMEBREW_NO_AUTO_UPDATE", "1"),
+            output,
+        )
+        .context(format!("Failed to upgrade formula: {}", package))?;
+
+        if !success {
+            anyhow::bail!("{} failed", argv.join(" "));
+        }
+        self.forget_formulae();
+        Ok(())
+    }
+
+    /// Refuses while other installed formulae still depend on it
+    fn uninstall_package(&self, package: &str) -> Result<()> {
+        let (pkg_name, _) = Self::parse_package_name(package);
+
+        let dependents = self.installed_dependents(pkg_name)?;
+        if !dependents.is_empty() {
+            anyhow::bail!(
+                "{} is required by {} - not uninstalling",
+                pkg_name,
+                dependents.join(", ")
+            );
+        }
+
+        log::info!("→ Uninstalling {} (formula)...", pkg_name);
+
+        let success = utils::run_status(self.brew_command().args(["uninstall", pkg_name]), None)
+            .context(format!("Failed to uninstall formula: {}", pkg_name))?;
+
+        if !success {
+            anyhow::bail!("brew uninstall {} failed", pkg_name);
+        }
+
+        self.forget_formulae();
+        log::info!("✓ {} uninstalled", pkg_name);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::FakeEnv;
+
+    #[test]
+    fn filtering_formulae_lists_brew_once() {
+        let env = FakeEnv::new("brew-installed-set");
+        env.bin(
+            "brew",
+            r#"[ "$1" = list ] && printf 'git 2.43.0\nwget 1.21.4\nripgrep 14.1.0\n'
+exit 0"#,
+        );
+
+        let brew = BrewManager::new(1);
+        let candidates: Vec<String> = ["git", "homebrew/core/wget", "ripgrep:rg", "jq"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let installed = brew.installed_set(&candidates).unwrap();
+        assert_eq!(
+            installed,
+            HashSet::from([
+                "git".to_string(),
+                "homebrew/core/wget".to_string(),
+                "ripgrep:rg".to_string()
+            ])
+        );
+        // Checking again reuses the manager's list
+        brew.installed_set(&candidates).unwrap();
+        assert_eq!(env.calls(), ["brew list --formula --versions"]);
+    }
+
+    #[test]
+    fn parses_brew_list_versions_output() {
+        let versions = BrewManager::parse_versions(
+            "git 2.43.0\npython@3.12 3.12.1 3.12.2\nopenssl@3 3.2.0_1\n\n",
+        );
+
+        assert_eq!(versions.len(), 3);
+        assert_eq!(versions["git"], "2.43.0");
+        // Several kept versions: the newest (last) wins
+        assert_eq!(versions["python@3.12"], "3.12.2");
+        assert_eq!(versions["openssl@3"], "3.2.0_1");
+    }
+
+    #[test]
+    fn formula_with_an_installed_dependent_is_not_uninstalled() {
+        let env = FakeEnv::new("brew-dependents");
+        env.bin(
+            "brew",
+            r#"[ "$1" = uses ] && [ "$3" = openssl@3 ] && echo "python@3.12 wget"
+exit 0"#,
+        );
+
+        let brew = BrewManager::new(1);
+        let packages = ["openssl@3".to_string(), "jq".to_string()];
+        let result = brew.uninstall_packages(&packages).unwrap();
+
+        assert_eq!(result.success, ["jq"]);
+        assert_eq!(result.failed.len(), 1);
+        assert_eq!(
+            result.failed[0].1,
+            "openssl@3 is required by python@3.12, wget - not uninstalling"
+        );
+        let calls = env.calls();
+        assert!(calls.contains(&"brew uninstall jq".to_string()));
+        assert!(!calls.contains(&"brew uninstall openssl@3".to_string()));
+    }
+
+    fn brew_config(toml: &str) -> BrewConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn brewfile_lists_taps_formulae_and_casks() {
+        let config = brew_config(
+            r#"
+            taps = ["homebrew/cask-fonts", { name = "acme/tools", url = "https://git.example.com/tools" }]
+            formulae = ["git", "ripgrep:rg"]
+            casks = ["firefox"]
+            "#,
+        );
+
+        assert_eq!(
+            BrewManager::brewfile_contents(&config),
+            "tap \"homebrew/cask-fonts\"\n\
+             tap \"acme/tools\", \"https://git.example.com/tools\"\n\
+             brew \"git\"\n\
+             brew \"ripgrep\"\n\
+             cask \"firefox\"\n"
+        );
+    }
+
+    #[test]
+    fn bundle_install_runs_brew_bundle_on_the_generated_brewfile() {
+        let env = FakeEnv::new("brew-bundle");
+        // Keep a copy: macup removes its temp Brewfile afterwards
+        env.bin(
+            "brew",
+            r#"[ "$1" = bundle ] && cp "$3" "$HOME/Brewfile"
+exit 0"#,
+        );
+
+        let config = brew_config("formulae = [\"git\"]\ncasks = [\"firefox\"]");
+        BrewManager::new(1).bundle_install(&config).unwrap();
+
+        let calls = env.calls();
+        assert_eq!(calls.len(), 1);
+        assert!(calls[0].starts_with("brew bundle --file "));
+        let home = std::env::var("HOME").unwrap();
+        assert_eq!(
+            fs::read_to_string(format!("{}/Brewfile", home)).unwrap(),
+            "brew \"git\"\ncask \"firefox\"\n"
+        );
+    }
+
+    #[test]
+    fn recognizes_brews_cask_suggestion() {
+        let stderr = "Warning: No available formula with the name \"docker\". Did you mean docker-completion?\n\
+                      ==> Searching for similarly named formulae and casks...\n\
+                      To install docker, run:\n  brew install --cask docker\n";
+
+        assert!(BrewManager::output_suggests_cask(stderr, "docker"));
+        assert!(!BrewManager::output_suggests_cask(stderr, "dockerd"));
+        assert!(!BrewManager::output_suggests_cask(
+            "Error: No available formula with the name \"zz-nope\".",
+            "zz-nope"
+        ));
+    }
+
+    #[test]
+    fn failed_formula_that_is_a_cask_suggests_moving_it() {
+        let env = FakeEnv::new("brew-cask-formula");
+        env.bin(
+            "brew",
+            r#"[ "$1" = install ] || exit 0
+echo "Warning: No available formula with the name \"docker\"." >&2
+echo "  brew install --cask $2" >&2
+exit 1"#,
+        );
+
+        let mut output = String::new();
+        let err = BrewManager::new(1)
+            .install_formula("docker", Some(&mut output))
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "docker is a cask, not a formula - move it to brew.casks"
+        );
+        assert_eq!(FailureKind::classify(&err, &output), FailureKind::Cask);
+    }
+
+    #[test]
+    fn tap_qualified_names_are_detected_after_install() {
+        let env = FakeEnv::new("brew-tap-qualified");
+        // Installs by full name, lists by short name, like brew
+        env.bin(
+            "brew",
+            r#"list=formulae; for arg; do [ "$arg" = --cask ] && list=casks; done
+touch "$HOME/formulae" "$HOME/casks"
+case "$1" in
+  install) for arg; do name="$arg"; done; echo "${name##*/} 1.0" >> "$HOME/$list" ;;
+  list) [ "$list" = casks ] && cut -d' ' -f1 "$HOME/casks" || cat "$HOME/formulae" ;;
+esac
+exit 0"#,
+        );
+
+        let brew = BrewManager::new(1);
+        let formulae = vec!["acme/tools/zz-tool".to_string()];
+        let casks = vec!["homebrew/cask-versions/firefox@beta".to_string()];
+
+        assert_eq!(brew.install_formulae(&formulae).unwrap().success, formulae);
+        assert_eq!(brew.install_casks(&casks).unwrap().success, casks);
+        let calls = env.calls();
+        assert!(calls.contains(&"brew install acme/tools/zz-tool".to_string()));
+        assert!(
+            calls.contains(&"brew install --cask homebrew/cask-versions/firefox@beta".to_string())
+        );
+
+        env.clear_calls();
+        assert_eq!(brew.install_formulae(&formulae).unwrap().skipped, formulae);
+        assert_eq!(brew.install_casks(&casks).unwrap().skipped, casks);
+        assert!(!env
+            .calls()
+            .iter()
+            .any(|call| call.starts_with("brew install")));
+    }
+
+    #[test]
+    fn tap_url_is_passed_only_when_given() {
+        let env = FakeEnv::new("brew-tap-url");
+        env.bin(
+            "brew",
+            r#"[ "$*" = tap ] && echo acme/present
+exit 0"#,
+        );
+
+        let config = brew_config(
+            r#"
+            taps = [
+                "homebrew/cask-fonts",
+                { name = "acme/private", url = "git@git.example.com:acme/homebrew-private.git" },
+                { name = "acme/plain" },
+                { name = "acme/present", url = "https://example.com/present" },
+            ]
+            "#,
+        );
+        let result = BrewManager::new(1).add_taps(&config.taps).unwrap();
+
+        assert_eq!(result.skipped, ["acme/present"]);
+        let mut taps: Vec<_> = env
+            .calls()
+            .into_iter()
+            .filter(|call| call.starts_with("brew tap "))
+            .collect();
+        taps.sort();
+        assert_eq!(
+            taps,
+            [
+                "brew tap acme/plain",
+                "brew tap acme/private git@git.example.com:acme/homebrew-private.git",
+                "brew tap homebrew/cask-fonts",
+            ]
+        );
+    }
 }
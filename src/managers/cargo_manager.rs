@@ -1,4 +1,4 @@
-use crate::managers::{InstallResult, Manager};
+use crate::managers::{FailureKind, InstallResult, Manager};
 use crate::utils;
 use anyhow::{Context, Result};
 use rayon::prelude::*;
@@ -50,18 +50,24 @@ impl CargoManager {
 
     /// Install a cargo package
     /// Accepts "package:binary" format but only uses package name for installation
-    pub fn install_package_impl(&self, package_spec: &str) -> Result<()> {
+    /// With `output`, cargo's output is captured there instead of printed
+    pub fn install_package_impl(
+        &self,
+        package_spec: &str,
+        output: Option<&mut String>,
+    ) -> Result<()> {
         // Parse package:binary format - install using package name only
         let (pkg_name, _binary_name) = Self::parse_package_name(package_spec);
 
         log::info!("→ Installing {} (cargo)...", pkg_name);
 
-        let status = Command::new("cargo")
-            .args(["install", pkg_name])
-            .status()
-            .context(format!("Failed to install cargo package: {}", pkg_name))?;
+        let success = utils::run_status(
+            &mut utils::command_for(&self.describe_install(package_spec)),
+            output,
+        )
+        .context(format!("Failed to install cargo package: {}", pkg_name))?;
 
-        if !status.success() {
+        if !success {
             anyhow::bail!("cargo install {} failed", pkg_name);
         }
 
@@ -93,6 +99,15 @@ impl Manager for CargoManager {
         Ok(utils::command_exists(binary_name))
     }
 
+    fn describe_install(&self, package: &str) -> Vec<String> {
+        let (pkg_name, _) = Self::parse_package_name(package);
+        vec![
+            "cargo".to_string(),
+            "install".to_string(),
+            pkg_name.to_string(),
+        ]
+    }
+
     fn install_package(&self, package: &str) -> Result<()> {
         if self.is_package_installed(package)? {
             let (pkg_name, _) = Self::parse_package_name(package);
@@ -100,7 +115,33 @@ impl Manager for CargoManager {
             return Ok(());
         }
 
-        self.install_package_impl(package)
+        self.install_package_impl(package, None)
+    }
+
+    fn describe_upgrade(&self, package: &str) -> Option<Vec<String>> {
+        let (pkg_name, _) = Self::parse_package_name(package);
+        Some(vec![
+            "cargo".to_string(),
+            "install".to_string(),
+            "--force".to_string(),
+            pkg_name.to_string(),
+        ])
+    }
+
+    fn uninstall_package(&self, package: &str) -> Result<()> {
+        let (pkg_name, _) = Self::parse_package_name(package);
+
+        log::info!("→ Uninstalling {} (cargo)...", pkg_name);
+
+        let success = utils::run_status(Command::new("cargo").args(["uninstall", pkg_name]), None)
+            .context(format!("Failed to uninstall cargo package: {}", pkg_name))?;
+
+        if !success {
+            anyhow::bail!("cargo uninstall {} failed", pkg_name);
+        }
+
+        log::info!("✓ {} uninstalled", pkg_name);
+        Ok(())
     }
 
     fn install_packages(&self, packages: &[String]) -> Result<InstallResult> {
@@ -118,15 +159,17 @@ impl Manager for CargoManager {
             .cloned()
             .collect();
 
-        let mut result = InstallResult::default();
-        result.skipped = packages
-            .iter()
-            .filter(|pkg| {
-                let (_pkg_name, binary_name) = Self::parse_package_name(pkg);
-                utils::command_exists(binary_name)
-            })
-            .cloned()
-            .collect();
+        let mut result = InstallResult {
+            skipped: packages
+                .iter()
+                .filter(|pkg| {
+                    let (_pkg_name, binary_name) = Self::parse_package_name(pkg);
+                    utils::command_exists(binary_name)
+                })
+                .cloned()
+                .collect(),
+            ..Default::default()
+        };
 
         if !result.skipped.is_empty() {
             log::info!(
@@ -141,23 +184,61 @@ impl Manager for CargoManager {
 
         log::info!("Installing {} cargo packages...", to_install.len());
 
-        let results: Vec<_> = rayon::ThreadPoolBuilder::new()
-            .num_threads(self.max_parallel)
-            .build()?
-            .install(|| {
-                to_install
-                    .par_iter()
-                    .map(|pkg| (pkg.clone(), self.install_package_impl(pkg)))
-                    .collect()
-            });
-
-        for (pkg, res) in results {
+        let results: Vec<_> = utils::install_pool(self.max_parallel)?.install(|| {
+            to_install
+                .par_iter()
+                .map(|pkg| {
+                    let mut output = String::new();
+                    let res = self.install_package_impl(pkg, Some(&mut output));
+                    (pkg.clone(), res, output)
+                })
+                .collect()
+        });
+
+        for (pkg, res, output) in results {
+            utils::flush_output(&pkg, &output, res.is_err());
             match res {
                 Ok(_) => result.success.push(pkg),
-                Err(e) => result.failed.push((pkg, e.to_string())),
+                Err(e) => {
+                    let kind = FailureKind::classify(&e, &output);
+                    result.failed.push((pkg, e.to_string(), kind))
+                }
             }
         }
 
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{stderr_of, FakeEnv};
+
+    #[test]
+    fn parallel_install_output_is_grouped_by_package() {
+        let Some(stderr) = stderr_of(
+            "managers::cargo_manager::tests::parallel_install_output_is_grouped_by_package",
+        ) else {
+            let env = FakeEnv::new("cargo-output");
+            // Both installs write while the other is still running
+            env.bin(
+                "cargo",
+                r#"[ "$1" = install ] || exit 0
+[ "$2" = zz-b ] && sleep 0.1
+echo "$2 line 1"
+sleep 0.2
+echo "$2 line 2"
+exit 1"#,
+            );
+
+            let packages = ["zz-a".to_string(), "zz-b".to_string()];
+            let result = CargoManager::new(2).install_packages(&packages).unwrap();
+            assert_eq!(result.failed.len(), 2);
+            return;
+        };
+
+        assert!(stderr.contains("  ── zz-a ──\n    zz-a line 1\n    zz-a line 2\n"));
+        assert!(stderr.contains("  ── zz-b ──\n    zz-b line 1\n    zz-b line 2\n"));
+    }
+}
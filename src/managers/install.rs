@@ -1,17 +1,36 @@
-use crate::config::InstallScript;
-use crate::utils::command::command_exists;
-use anyhow::Result;
+use crate::config::{InstallScript, OnFailure};
+use crate::utils::command::{command_exists, flush_output, run_status};
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
 use std::process::Command;
 
-pub struct InstallManager;
+pub struct InstallManager {
+    /// Scripts forced to run even if installed (`apply --rerun`)
+    rerun: HashSet<String>,
+}
 
 impl InstallManager {
     pub fn new() -> Self {
-        Self
+        Self {
+            rerun: HashSet::new(),
+        }
+    }
+
+    pub fn with_rerun(rerun: &[String]) -> Self {
+        Self {
+            rerun: rerun.iter().cloned().collect(),
+        }
+    }
+
+    /// Check if script has to run: not installed, or forced with --rerun
+    pub fn needs_run(&self, script: &InstallScript) -> Result<bool> {
+        Ok(self.rerun.contains(&script.name) || !self.is_installed(script)?)
     }
 
     /// Check if script is already installed
-    /// Priority: binary > check command
+    /// Priority: binary > check command > `once` marker
     pub fn is_installed(&self, script: &InstallScript) -> Result<bool> {
         // First, check binary if provided
         if let Some(binary) = &script.binary {
@@ -27,29 +46,54 @@ impl InstallManager {
             return Ok(check.status.success());
         }
 
+        // `once` scripts are installed once their marker is recorded
+        if script.once {
+            return Ok(marker_path(script).is_some_and(|path| path.exists()));
+        }
+
         // If neither binary nor check provided, consider not installed
         Ok(false)
     }
 
-    pub fn apply_script(&self, script: &InstallScript) -> Result<()> {
+    /// Run a script unless it's installed; false when its command failed
+    /// (after any retries), whatever the script's `on_failure` policy
+    pub fn apply_script(&self, script: &InstallScript) -> Result<bool> {
         // Check if already installed
-        if self.is_installed(script)? {
+        if !self.needs_run(script)? {
             log::info!("✓ {} already installed", script.name);
-            return Ok(());
+            return Ok(true);
         }
 
         // Run install command
         log::info!("→ Installing {}...", script.name);
 
-        let result = Command::new("sh").arg("-c").arg(&script.command).status()?;
+        let retries = match script.failure_policy() {
+            OnFailure::Retry => script.retries,
+            OnFailure::Abort | OnFailure::Skip => 0,
+        };
+
+        // --quiet lowers the log level to warnings, which quiets every script
+        let quiet = script.quiet || !log::log_enabled!(log::Level::Info);
+
+        let ran = crate::utils::retry_with_backoff(retries, &script.name, || {
+            let mut output = String::new();
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c").arg(&script.command);
 
-        if !result.success() {
-            if script.required {
+            let success = run_status(&mut cmd, quiet.then_some(&mut output))?;
+            if !success {
+                flush_output(&script.name, &output, true);
                 anyhow::bail!("Failed to install {}", script.name);
-            } else {
-                log::warn!("Failed to install {} (optional)", script.name);
-                return Ok(());
             }
+            Ok(())
+        });
+
+        if ran.is_err() {
+            return Ok(false);
+        }
+
+        if script.once {
+            record_marker(script)?;
         }
 
         // Verify installation
@@ -58,19 +102,377 @@ impl InstallManager {
         }
 
         log::info!("✓ {} installed", script.name);
-        Ok(())
+        Ok(true)
     }
 
     pub fn apply_scripts(&self, scripts: &[InstallScript]) -> Result<()> {
-        for script in scripts {
-            if let Err(e) = self.apply_script(script) {
-                if script.required {
-                    return Err(e);
-                } else {
-                    log::warn!("Skipping optional script {}: {}", script.name, e);
+        let scripts: Vec<_> = scripts.iter().collect();
+        let mut failed = HashSet::new();
+
+        for script in Self::order_scripts(&scripts)? {
+            // Don't run a script whose dependency just failed
+            if let Some(dep) = script.depends_on.iter().find(|dep| failed.contains(*dep)) {
+                let e = anyhow::anyhow!("dependency '{}' failed", dep);
+                if script.aborts_on_failure() {
+                    return Err(e.context(format!("Failed to install {}", script.name)));
                 }
+                log::warn!("Skipping optional script {}: {}", script.name, e);
+                failed.insert(script.name.clone());
+                continue;
             }
+
+            let e = match self.apply_script(script) {
+                Ok(true) => continue,
+                Ok(false) => anyhow::anyhow!("Failed to install {}", script.name),
+                Err(e) => e,
+            };
+            if script.aborts_on_failure() {
+                return Err(e);
+            }
+            log::warn!("Skipping optional script {}: {}", script.name, e);
+            failed.insert(script.name.clone());
         }
         Ok(())
     }
+
+    /// Order scripts so each runs after the scripts named in its `depends_on`
+    /// Keeps config order otherwise; dependencies outside `scripts` (e.g.
+    /// already installed) count as satisfied
+    pub fn order_scripts<'a>(scripts: &[&'a InstallScript]) -> Result<Vec<&'a InstallScript>> {
+        let names: HashSet<_> = scripts.iter().map(|s| s.name.as_str()).collect();
+        let mut done = HashSet::new();
+        let mut ordered = Vec::with_capacity(scripts.len());
+
+        while ordered.len() < scripts.len() {
+            let next = scripts.iter().find(|script| {
+                !done.contains(script.name.as_str())
+                    && script
+                        .depends_on
+                        .iter()
+                        .all(|dep| !names.contains(dep.as_str()) || done.contains(dep.as_str()))
+            });
+
+            match next {
+                Some(script) => {
+                    done.insert(script.name.as_str());
+                    ordered.push(*script);
+                }
+                None => {
+                    let stuck: Vec<_> = scripts
+                        .iter()
+                        .filter(|s| !done.contains(s.name.as_str()))
+                        .map(|s| s.name.as_str())
+                        .collect();
+                    anyhow::bail!(
+                        "Dependency cycle detected between install scripts: {}",
+                        stuck.join(", ")
+                    );
+                }
+            }
+        }
+
+        Ok(ordered)
+    }
+}
+
+/// Marker recorded after a `once` script succeeds:
+/// ~/.cache/macup/installed/<hash of name + command>
+/// Changing the command yields a new marker, so the script runs again
+fn marker_path(script: &InstallScript) -> Option<PathBuf> {
+    // FNV-1a: stable across Rust versions, unlike DefaultHasher
+    let hash = format!("{}\0{}", script.name, script.command)
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+
+    dirs::home_dir().map(|home| home.join(format!(".cache/macup/installed/{:016x}", hash)))
+}
+
+fn record_marker(script: &InstallScript) -> Result<()> {
+    let path = marker_path(script).context("Could not determine home directory")?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).context(format!(
+            "Failed to create marker directory: {}",
+            dir.display()
+        ))?;
+    }
+
+    fs::write(&path, format!("{}\n", script.name))
+        .context(format!("Failed to record marker for {}", script.name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::InstallConfig;
+    use crate::utils::testing::output_of;
+
+    fn scripts(toml: &str) -> Vec<InstallScript> {
+        toml::from_str::<InstallConfig>(toml).unwrap().scripts
+    }
+
+    fn names(scripts: &[&InstallScript]) -> Vec<String> {
+        scripts.iter().map(|s| s.name.clone()).collect()
+    }
+
+    #[test]
+    fn orders_scripts_after_their_dependencies() {
+        let scripts = scripts(
+            r#"
+            [[scripts]]
+            name = "c"
+            command = "true"
+            depends_on = ["b"]
+
+            [[scripts]]
+            name = "b"
+            command = "true"
+            depends_on = ["a", "installed-elsewhere"]
+
+            [[scripts]]
+            name = "a"
+            command = "true"
+
+            [[scripts]]
+            name = "d"
+            command = "true"
+            "#,
+        );
+        let refs: Vec<_> = scripts.iter().collect();
+
+        let ordered = InstallManager::order_scripts(&refs).unwrap();
+        assert_eq!(names(&ordered), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn rejects_dependency_cycles() {
+        let scripts = scripts(
+            r#"
+            [[scripts]]
+            name = "a"
+            command = "true"
+            depends_on = ["b"]
+
+            [[scripts]]
+            name = "b"
+            command = "true"
+            depends_on = ["a"]
+
+            [[scripts]]
+            name = "c"
+            command = "true"
+            "#,
+        );
+        let refs: Vec<_> = scripts.iter().collect();
+
+        let err = InstallManager::order_scripts(&refs).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Dependency cycle detected between install scripts: a, b"
+        );
+    }
+
+    #[test]
+    fn failed_optional_script_skips_its_dependents() {
+        let marker =
+            std::env::temp_dir().join(format!("macup-test-dependent-{}", std::process::id()));
+        let _ = fs::remove_file(&marker);
+
+        let scripts = scripts(&format!(
+            r#"
+            [[scripts]]
+            name = "base"
+            command = "exit 1"
+            on_failure = "skip"
+
+            [[scripts]]
+            name = "dependent"
+            command = "touch {}"
+            depends_on = ["base"]
+            required = false
+            "#,
+            marker.display()
+        ));
+
+        let install_mgr = InstallManager::new();
+        assert!(!install_mgr.apply_script(&scripts[0]).unwrap());
+        install_mgr.apply_scripts(&scripts).unwrap();
+        assert!(!marker.exists());
+    }
+
+    #[test]
+    fn script_whose_binary_exists_is_installed() {
+        let env = crate::utils::testing::FakeEnv::new("script-binary");
+        env.bin("zz-tool", "exit 0");
+
+        let scripts = scripts(
+            r#"
+            [[scripts]]
+            name = "present"
+            command = "true"
+            binary = "zz-tool"
+
+            [[scripts]]
+            name = "absent"
+            command = "true"
+            binary = "zz-absent"
+            "#,
+        );
+
+        let install_mgr = InstallManager::new();
+        assert!(install_mgr.is_installed(&scripts[0]).unwrap());
+        assert!(!install_mgr.is_installed(&scripts[1]).unwrap());
+        // Checking runs nothing
+        assert!(env.calls().is_empty());
+    }
+
+    #[test]
+    fn once_script_runs_once_unless_rerun() {
+        let env = crate::utils::testing::FakeEnv::new("script-once");
+        env.bin("zz-setup", "exit 0");
+
+        let scripts = scripts(
+            r#"
+            [[scripts]]
+            name = "setup"
+            command = "zz-setup"
+            once = true
+            "#,
+        );
+
+        // The second apply finds the marker the first recorded
+        InstallManager::new().apply_scripts(&scripts).unwrap();
+        InstallManager::new().apply_scripts(&scripts).unwrap();
+        assert_eq!(env.calls(), ["zz-setup "]);
+
+        // --rerun forces it
+        InstallManager::with_rerun(&["setup".to_string()])
+            .apply_scripts(&scripts)
+            .unwrap();
+        assert_eq!(env.calls().len(), 2);
+    }
+
+    #[test]
+    fn failure_policies() {
+        let env = crate::utils::testing::FakeEnv::new("script-policies");
+        // Fails twice, then succeeds
+        env.bin(
+            "zz-flaky",
+            r#"echo x >> "$HOME/attempts"
+[ "$(wc -l < "$HOME/attempts")" -ge 3 ]"#,
+        );
+
+        let scripts = scripts(
+            r#"
+            [[scripts]]
+            name = "abort"
+            command = "exit 1"
+            on_failure = "abort"
+
+            [[scripts]]
+            name = "skip"
+            command = "exit 1"
+            on_failure = "skip"
+
+            [[scripts]]
+            name = "legacy-optional"
+            command = "exit 1"
+            required = false
+
+            [[scripts]]
+            name = "flaky"
+            command = "zz-flaky"
+            on_failure = "retry"
+            once = true
+
+            [[scripts]]
+            name = "retry-then-abort"
+            command = "exit 1"
+            on_failure = "retry"
+            retries = 1
+
+            [[scripts]]
+            name = "retry-then-skip"
+            command = "exit 1"
+            on_failure = "retry"
+            retries = 1
+            required = false
+            "#,
+        );
+        let install_mgr = InstallManager::new();
+        let apply = |idx: usize| install_mgr.apply_scripts(&scripts[idx..=idx]);
+
+        assert_eq!(scripts[0].failure_policy(), OnFailure::Abort);
+        assert!(apply(0).is_err());
+        assert!(apply(1).is_ok());
+        assert_eq!(scripts[2].failure_policy(), OnFailure::Skip);
+        assert!(apply(2).is_ok());
+
+        // Succeeds on the third attempt (default retries = 2)
+        assert!(apply(3).is_ok());
+        assert_eq!(env.calls().len(), 3);
+
+        // Out of retries: aborts unless the script is optional
+        assert!(apply(4).is_err());
+        assert!(apply(5).is_ok());
+    }
+
+    #[test]
+    fn quiet_script_output_shows_only_on_failure() {
+        let Some(output) =
+            output_of("managers::install::tests::quiet_script_output_shows_only_on_failure")
+        else {
+            // Logging as main sets it up, so only `quiet` captures output
+            env_logger::Builder::new()
+                .filter_level(log::LevelFilter::Info)
+                .init();
+            let _env = crate::utils::testing::FakeEnv::new("script-quiet");
+            let scripts = scripts(
+                r#"
+                [[scripts]]
+                name = "chatty-success"
+                command = "echo zz-success-output"
+                once = true
+                quiet = true
+
+                [[scripts]]
+                name = "chatty-failure"
+                command = "echo zz-failure-output; exit 1"
+                once = true
+                quiet = true
+
+                [[scripts]]
+                name = "streamed"
+                command = "echo zz-streamed-output"
+                once = true
+
+                [[scripts]]
+                name = "globally-quiet"
+                command = "echo zz-globally-quiet-output"
+                once = true
+                "#,
+            );
+
+            let install_mgr = InstallManager::new();
+            assert!(install_mgr.apply_script(&scripts[0]).unwrap());
+            assert!(!install_mgr.apply_script(&scripts[1]).unwrap());
+            assert!(install_mgr.apply_script(&scripts[2]).unwrap());
+
+            // --quiet
+            log::set_max_level(log::LevelFilter::Warn);
+            assert!(install_mgr.apply_script(&scripts[3]).unwrap());
+            return;
+        };
+
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!stdout.contains("zz-success-output"));
+        assert!(!stderr.contains("zz-success-output"));
+        assert!(stderr.contains("  ── chatty-failure ──\n    zz-failure-output\n"));
+        assert!(stdout.contains("zz-streamed-output"));
+        assert!(!stdout.contains("zz-globally-quiet-output"));
+    }
 }
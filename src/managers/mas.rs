@@ -1,7 +1,8 @@
-use crate::managers::{InstallResult, Manager};
+use crate::managers::{FailureKind, InstallResult, Manager};
 use crate::utils;
 use anyhow::{Context, Result};
 use rayon::prelude::*;
+use serde::Serialize;
 use std::collections::HashSet;
 use std::process::Command;
 
@@ -9,6 +10,15 @@ pub struct MasManager {
     max_parallel: usize,
 }
 
+/// An installed app with an App Store update (`mas outdated`)
+#[derive(Debug, Clone, Serialize)]
+pub struct OutdatedApp {
+    pub id: String,
+    pub name: String,
+    pub installed: String,
+    pub available: String,
+}
+
 impl MasManager {
     pub fn new(max_parallel: usize) -> Self {
         Self { max_parallel }
@@ -35,15 +45,116 @@ impl MasManager {
         Ok(apps)
     }
 
-    pub fn install_app(&self, id: &str) -> Result<()> {
+    /// App name of an App Store id, from `mas info`
+    /// First line format: "Xcode 15.1 [Free]" (name, version, price)
+    pub fn app_name(&self, id: &str) -> Result<String> {
+        let output = Command::new("mas")
+            .args(["info", id])
+            .output()
+            .context("Failed to run mas info")?;
+
+        if !output.status.success() {
+            anyhow::bail!("mas info {} failed - no App Store app with that id?", id);
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let first = stdout.lines().next().unwrap_or("").trim();
+        let without_price = first.rsplit_once(" [").map_or(first, |(head, _)| head);
+        let name = without_price
+            .rsplit_once(' ')
+            .map_or(without_price, |(name, _version)| name)
+            .trim();
+
+        if name.is_empty() {
+            anyhow::bail!("mas info {} printed no app name", id);
+        }
+        Ok(name.to_string())
+    }
+
+    /// Apps with an update available
+    /// mas can't pin versions, so this is the only kind of drift it has
+    pub fn list_outdated(&self) -> Result<Vec<OutdatedApp>> {
+        let output = Command::new("mas")
+            .arg("outdated")
+            .output()
+            .context("Failed to run mas outdated")?;
+
+        if !output.status.success() {
+            anyhow::bail!("mas outdated failed");
+        }
+
+        let apps = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(Self::parse_outdated_line)
+            .collect();
+
+        Ok(apps)
+    }
+
+    /// Format: "497799835 Xcode (15.0 -> 15.1)"
+    fn parse_outdated_line(line: &str) -> Option<OutdatedApp> {
+        let (id, rest) = line.trim().split_once(char::is_whitespace)?;
+        let (name, versions) = rest.rsplit_once(" (")?;
+        let (installed, available) = versions.trim_end_matches(')').split_once(" -> ")?;
+
+        Some(OutdatedApp {
+            id: id.to_string(),
+            name: name.trim().to_string(),
+            installed: installed.trim().to_string(),
+            available: available.trim().to_string(),
+        })
+    }
+
+    /// Pre-flight check before installing: `mas install` fails opaquely per app
+    /// when nobody is signed in to the App Store
+    pub fn ensure_signed_in(&self) -> Result<()> {
+        if self.is_signed_out() {
+            anyhow::bail!(
+                "Not signed in to the App Store. Sign in to the App Store first, then re-run."
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether `mas account` clearly reports that nobody is signed in
+    /// `mas account` is unsupported on newer macOS, so anything inconclusive is `false`
+    pub fn is_signed_out(&self) -> bool {
+        let output = match Command::new("mas").arg("account").output() {
+            Ok(output) => output,
+            Err(e) => {
+                log::debug!("Failed to run mas account: {}", e);
+                return false;
+            }
+        };
+
+        if output.status.success() {
+            return false;
+        }
+
+        let message = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        if message.to_lowercase().contains("not signed in") {
+            return true;
+        }
+
+        log::debug!("mas account check inconclusive: {}", message.trim());
+        false
+    }
+
+    /// Install an app by id
+    /// With `output`, mas's output is captured there instead of printed
+    pub fn install_app(&self, id: &str, output: Option<&mut String>) -> Result<()> {
         log::info!("→ Installing app {}...", id);
 
-        let status = Command::new("mas")
-            .args(["install", id])
-            .status()
-            .context(format!("Failed to install app: {}", id))?;
+        let success =
+            utils::run_status(&mut utils::command_for(&self.describe_install(id)), output)
+                .context(format!("Failed to install app: {}", id))?;
 
-        if !status.success() {
+        if !success {
             anyhow::bail!("mas install {} failed", id);
         }
 
@@ -63,10 +174,12 @@ impl Manager for MasManager {
 
     fn install_self(&self) -> Result<()> {
         log::info!("Installing mas-cli via Homebrew...");
-        Command::new("brew")
-            .env("HOMEBREW_NO_AUTO_UPDATE", "1")
-            .args(["install", "mas"])
-            .status()?;
+        utils::run_status(
+            Command::new("brew")
+                .env("HOMEBREW_NO_AUTO_UPDATE", "1")
+                .args(["install", "mas"]),
+            None,
+        )?;
         Ok(())
     }
 
@@ -74,13 +187,54 @@ impl Manager for MasManager {
         self.list_apps()
     }
 
+    fn installed_set(&self, candidates: &[String]) -> Result<HashSet<String>> {
+        let apps = self.list_apps()?;
+        Ok(candidates
+            .iter()
+            .filter(|id| apps.contains(id.as_str()))
+            .cloned()
+            .collect())
+    }
+
+    fn describe_install(&self, package: &str) -> Vec<String> {
+        vec![
+            "mas".to_string(),
+            "install".to_string(),
+            package.to_string(),
+        ]
+    }
+
     fn install_package(&self, package: &str) -> Result<()> {
         if self.is_package_installed(package)? {
             log::info!("✓ App {} already installed", package);
             return Ok(());
         }
 
-        self.install_app(package)
+        self.ensure_signed_in()?;
+        self.install_app(package, None)
+    }
+
+    fn describe_upgrade(&self, package: &str) -> Option<Vec<String>> {
+        Some(vec![
+            "mas".to_string(),
+            "upgrade".to_string(),
+            package.to_string(),
+        ])
+    }
+
+    fn uninstall_package(&self, package: &str) -> Result<()> {
+        log::info!("→ Uninstalling app {}...", package);
+
+        let success = utils::run_status(Command::new("mas").args(["uninstall", package]), None)
+            .context(format!("Failed to uninstall app: {}", package))?;
+
+        // mas moves the app to the Trash, which needs root
+        if !success {
+            anyhow::bail!("mas uninstall {} failed (run macup with sudo)", package);
+        }
+
+        log::info!("✓ App {} uninstalled", package);
+        Ok(())
     }
 
     fn install_packages(&self, packages: &[String]) -> Result<InstallResult> {
@@ -95,12 +249,14 @@ impl Manager for MasManager {
             .cloned()
             .collect();
 
-        let mut result = InstallResult::default();
-        result.skipped = packages
-            .iter()
-            .filter(|pkg| installed.contains(pkg.as_str()))
-            .cloned()
-            .collect();
+        let mut result = InstallResult {
+            skipped: packages
+                .iter()
+                .filter(|pkg| installed.contains(pkg.as_str()))
+                .cloned()
+                .collect(),
+            ..Default::default()
+        };
 
         if !result.skipped.is_empty() {
             log::info!("✓ {} apps already installed", result.skipped.len());
@@ -110,25 +266,113 @@ impl Manager for MasManager {
             return Ok(result);
         }
 
+        self.ensure_signed_in()?;
+
         log::info!("Installing {} apps...", to_install.len());
 
-        let results: Vec<_> = rayon::ThreadPoolBuilder::new()
-            .num_threads(self.max_parallel)
-            .build()?
-            .install(|| {
-                to_install
-                    .par_iter()
-                    .map(|pkg| (pkg.clone(), self.install_app(pkg)))
-                    .collect()
-            });
-
-        for (pkg, res) in results {
+        let results: Vec<_> = utils::install_pool(self.max_parallel)?.install(|| {
+            to_install
+                .par_iter()
+                .map(|pkg| {
+                    let mut output = String::new();
+                    let res = self.install_app(pkg, Some(&mut output));
+                    (pkg.clone(), res, output)
+                })
+                .collect()
+        });
+
+        for (pkg, res, output) in results {
+            utils::flush_output(&pkg, &output, res.is_err());
             match res {
                 Ok(_) => result.success.push(pkg),
-                Err(e) => result.failed.push((pkg, e.to_string())),
+                Err(e) => {
+                    let kind = FailureKind::classify(&e, &output);
+                    result.failed.push((pkg, e.to_string(), kind))
+                }
             }
         }
 
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::FakeEnv;
+
+    /// `mas` as it behaves with nobody signed in to the App Store
+    const SIGNED_OUT: &str = r#"case "$1" in
+  list) exit 0 ;;
+  account) echo "Not signed in"; exit 1 ;;
+esac
+exit 0"#;
+
+    #[test]
+    fn signed_out_install_fails_before_any_mas_install() {
+        let env = FakeEnv::new("mas-signed-out");
+        env.bin("mas", SIGNED_OUT);
+
+        let err = MasManager::new(1)
+            .install_packages(&["497799835".to_string()])
+            .unwrap_err();
+
+        assert!(err.to_string().contains("Sign in to the App Store first"));
+        assert!(!env
+            .calls()
+            .iter()
+            .any(|call| call.starts_with("mas install")));
+    }
+
+    #[test]
+    fn inconclusive_account_check_still_installs() {
+        // `mas account` is unsupported on newer macOS
+        let env = FakeEnv::new("mas-no-account");
+        env.bin(
+            "mas",
+            r#"[ "$1" = account ] && { echo "unsupported"; exit 1; }
+exit 0"#,
+        );
+
+        let result = MasManager::new(1)
+            .install_packages(&["497799835".to_string()])
+            .unwrap();
+
+        assert_eq!(result.success, ["497799835"]);
+        assert!(env.calls().contains(&"mas install 497799835".to_string()));
+    }
+
+    #[test]
+    fn parses_mas_outdated_output() {
+        let env = FakeEnv::new("mas-outdated");
+        env.bin(
+            "mas",
+            r#"cat <<'OUT'
+497799835 Xcode (15.0 -> 15.1)
+ 1295203466  Microsoft Remote Desktop (10.9.4 -> 10.9.5)
+Warning: something unrelated
+OUT"#,
+        );
+
+        let outdated: Vec<_> = MasManager::new(1)
+            .list_outdated()
+            .unwrap()
+            .into_iter()
+            .map(|app| (app.id, app.name, app.installed, app.available))
+            .collect();
+
+        let expected = [
+            ("497799835", "Xcode", "15.0", "15.1"),
+            ("1295203466", "Microsoft Remote Desktop", "10.9.4", "10.9.5"),
+        ]
+        .map(|(id, name, installed, available)| {
+            (
+                id.to_string(),
+                name.to_string(),
+                installed.to_string(),
+                available.to_string(),
+            )
+        });
+        assert_eq!(outdated, expected);
+    }
+}
@@ -1,30 +1,204 @@
+// CODEGEN_START[asdf]: module
+pub mod asdf;
+// CODEGEN_END[asdf]: module
 pub mod brew;
 // CODEGEN_START[cargo]: module
 pub mod cargo_manager;
 // CODEGEN_END[cargo]: module
+// CODEGEN_START[pnpm]: module
+pub mod pnpm;
+// CODEGEN_END[pnpm]: module
+// CODEGEN_START[yarn]: module
+pub mod yarn;
+// CODEGEN_END[yarn]: module
+// CODEGEN_START[pip]: module
+pub mod pip;
+// CODEGEN_END[pip]: module
 // CODEGEN_MARKER: insert_module_declaration_here
 pub mod install;
 // CODEGEN_START[mas]: module
 pub mod mas;
 // CODEGEN_END[mas]: module
+pub mod node_globals;
 // CODEGEN_START[npm]: module
 pub mod npm;
 // CODEGEN_END[npm]: module
 pub mod registry;
 
-use anyhow::Result;
+use crate::utils;
+use anyhow::{Context, Result};
 use std::collections::HashSet;
+use std::fmt;
 
 pub use registry::{ManagerMetadata, PACKAGE_MANAGERS};
 
+/// Key package names are compared by, so "Ripgrep" and "ripgrep" are one package
+/// brew, npm, cargo and asdf registries don't distinguish names by case; mas
+/// ids are numeric and only trimmed. Configs keep the casing the user wrote
+pub fn normalize_package_name(manager: &str, name: &str) -> String {
+    match manager {
+        "mas" => name.trim().to_string(),
+        _ => name.trim().to_lowercase(),
+    }
+}
+
+/// Whether two config entries ("package" or "package:binary") install the same package
+pub fn same_package(manager: &str, a: &str, b: &str) -> bool {
+    let name = |spec: &str| normalize_package_name(manager, spec.split(':').next().unwrap_or(spec));
+    name(a) == name(b)
+}
+
 /// Result of installing packages
 #[derive(Debug, Default)]
 pub struct InstallResult {
     pub success: Vec<String>,
-    pub failed: Vec<(String, String)>, // (package, error)
+    pub failed: Vec<(String, String, FailureKind)>, // (package, error, kind)
     pub skipped: Vec<String>,
 }
 
+impl InstallResult {
+    /// Combine per-category results (e.g. brew taps + formulae + casks) into one
+    pub fn aggregate(results: &[InstallResult]) -> Self {
+        let mut combined = Self::default();
+        for result in results {
+            combined.success.extend(result.success.iter().cloned());
+            combined.failed.extend(result.failed.iter().cloned());
+            combined.skipped.extend(result.skipped.iter().cloned());
+        }
+        combined
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.success.is_empty() && self.failed.is_empty() && self.skipped.is_empty()
+    }
+
+    /// Multi-line report: installed names, skipped count, failures with their errors
+    pub fn summary(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for InstallResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.success.is_empty() {
+            writeln!(
+                f,
+                "  ✓ {} installed: {}",
+                self.success.len(),
+                self.success.join(", ")
+            )?;
+        }
+        if !self.skipped.is_empty() {
+            writeln!(f, "  ⊘ {} skipped (already installed)", self.skipped.len())?;
+        }
+        if !self.failed.is_empty() {
+            writeln!(f, "  ✗ {} failed:", self.failed.len())?;
+            for (pkg, err, _) in &self.failed {
+                writeln!(f, "    - {}: {}", pkg, err)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Broad category of an install failure, used to group the apply summary
+/// and suggest a fix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FailureKind {
+    Network,
+    NotFound,
+    Permission,
+    Timeout,
+    /// A cask listed under `brew.formulae`
+    Cask,
+    Other,
+}
+
+impl FailureKind {
+    /// Infer the kind from an install error and the command's captured output
+    pub fn classify(error: &anyhow::Error, output: &str) -> Self {
+        Self::from_text(&format!("{:#}\n{}", error, output))
+    }
+
+    /// Match well-known brew/npm/cargo/mas/asdf error messages
+    /// Checked in order: a DNS failure (npm's ENOTFOUND) is a network error, not NotFound
+    pub fn from_text(text: &str) -> Self {
+        let text = text.to_lowercase();
+        let has = |patterns: &[&str]| patterns.iter().any(|p| text.contains(p));
+
+        // Raised by BrewManager::install_formula itself, so it's unambiguous
+        if has(&["is a cask, not a formula"]) {
+            FailureKind::Cask
+        } else if has(&["timed out", "timeout", "etimedout"]) {
+            FailureKind::Timeout
+        } else if has(&[
+            "could not resolve host",
+            "enotfound",
+            "econnrefused",
+            "econnreset",
+            "connection refused",
+            "connection reset",
+            "network is unreachable",
+            "failed to download",
+            "curl: (",
+            "spurious network error",
+        ]) {
+            FailureKind::Network
+        } else if has(&[
+            "permission denied",
+            "eacces",
+            "eperm",
+            "operation not permitted",
+        ]) {
+            FailureKind::Permission
+        } else if has(&[
+            "no available formula",
+            "no casks found",
+            "no formulae or casks found",
+            "e404",
+            "404 not found",
+            "could not find",
+            "no app with",
+            "no results found",
+            "unknown plugin",
+            "not found",
+        ]) {
+            FailureKind::NotFound
+        } else {
+            FailureKind::Other
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FailureKind::Network => "Network errors",
+            FailureKind::NotFound => "Not found",
+            FailureKind::Permission => "Permission denied",
+            FailureKind::Timeout => "Timed out",
+            FailureKind::Cask => "Casks listed as formulae",
+            FailureKind::Other => "Other failures",
+        }
+    }
+
+    /// Suggested fix shown under each group in the summary
+    pub fn remediation(&self) -> Option<&'static str> {
+        match self {
+            FailureKind::Network => {
+                Some("Check your connection and re-run - these are usually transient")
+            }
+            FailureKind::NotFound => Some("Check the package name (or add the missing tap)"),
+            FailureKind::Permission => {
+                Some("Fix ownership of the install prefix instead of using sudo")
+            }
+            FailureKind::Timeout => Some("Re-run, or lower max_parallel for this manager"),
+            FailureKind::Cask => {
+                Some("Move them from brew.formulae to brew.casks (or re-run with --auto-fix)")
+            }
+            FailureKind::Other => None,
+        }
+    }
+}
+
 /// Trait for package managers
 pub trait Manager {
     /// Manager name (brew, mas, npm, cargo)
@@ -45,9 +219,215 @@ pub trait Manager {
         Ok(self.list_installed()?.contains(package))
     }
 
+    /// Which of `candidates` are installed
+    /// Managers whose check spawns a process override this with a single listing
+    fn installed_set(&self, candidates: &[String]) -> Result<HashSet<String>> {
+        let mut installed = HashSet::new();
+        for package in candidates {
+            if self.is_package_installed(package)? {
+                installed.insert(package.clone());
+            }
+        }
+        Ok(installed)
+    }
+
+    /// Exact command (program first) installing `package`, without running it
+    /// The real install is built from this, so dry runs show what would run
+    fn describe_install(&self, package: &str) -> Vec<String>;
+
     /// Install a single package (with idempotency check)
     fn install_package(&self, package: &str) -> Result<()>;
 
     /// Install multiple packages (batch check + parallel install)
     fn install_packages(&self, packages: &[String]) -> Result<InstallResult>;
+
+    /// Exact command (program first) upgrading an installed `package`
+    /// `None` when the manager can't upgrade it (the default)
+    fn describe_upgrade(&self, _package: &str) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Upgrade an installed package to its latest version
+    /// With `output`, the command's output is captured there instead of printed
+    fn upgrade_package(&self, package: &str, output: Option<&mut String>) -> Result<()> {
+        let Some(argv) = self.describe_upgrade(package) else {
+            anyhow::bail!("{} can't upgrade {}", self.name(), package);
+        };
+
+        let success = utils::run_status(&mut utils::command_for(&argv), output)
+            .context(format!("Failed to upgrade {}", package))?;
+        if !success {
+            anyhow::bail!("{} failed", argv.join(" "));
+        }
+        Ok(())
+    }
+
+    /// Uninstall a single package ("package:binary" entries uninstall the package)
+    /// Managers that can't uninstall keep this default
+    fn uninstall_package(&self, package: &str) -> Result<()> {
+        anyhow::bail!(
+            "{} does not support uninstalling packages ({})",
+            self.name(),
+            package
+        );
+    }
+
+    /// Uninstall packages one at a time; `success` lists what was removed
+    /// Sequential on purpose, unlike `install_packages`: uninstalls are quick,
+    /// and most managers can't run them concurrently anyway - brew, npm, pnpm
+    /// and yarn lock their prefix, cargo its install metadata, and pip has no
+    /// lock at all on the site-packages it rewrites
+    fn uninstall_packages(&self, packages: &[String]) -> Result<InstallResult> {
+        let mut result = InstallResult::default();
+        for package in packages {
+            match self.uninstall_package(package) {
+                Ok(()) => result.success.push(package.clone()),
+                Err(e) => {
+                    let kind = FailureKind::classify(&e, "");
+                    result.failed.push((package.clone(), e.to_string(), kind));
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_representative_install_errors() {
+        let cases = [
+            (
+                "curl: (6) Could not resolve host: ghcr.io",
+                FailureKind::Network,
+            ),
+            (
+                "npm ERR! code ENOTFOUND\nnpm ERR! getaddrinfo ENOTFOUND registry.npmjs.org",
+                FailureKind::Network,
+            ),
+            (
+                "warning: spurious network error (2 tries remaining)",
+                FailureKind::Network,
+            ),
+            (
+                "Error: No available formula with the name \"ripgerp\".",
+                FailureKind::NotFound,
+            ),
+            (
+                "npm ERR! 404 Not Found - GET https://registry.npmjs.org/zz-nope",
+                FailureKind::NotFound,
+            ),
+            (
+                "error: could not find `zz-nope` in registry `crates-io`",
+                FailureKind::NotFound,
+            ),
+            (
+                "npm ERR! Error: EACCES: permission denied, mkdir '/usr/local/lib'",
+                FailureKind::Permission,
+            ),
+            (
+                "Error: Operation not permitted @ dir_s_mkdir",
+                FailureKind::Permission,
+            ),
+            ("npm ERR! code ETIMEDOUT", FailureKind::Timeout),
+            (
+                "brew install zz failed: firefox is a cask, not a formula",
+                FailureKind::Cask,
+            ),
+            (
+                "error[E0599]: no method named `foo` found",
+                FailureKind::Other,
+            ),
+        ];
+
+        for (text, kind) in cases {
+            assert_eq!(FailureKind::from_text(text), kind, "{}", text);
+        }
+    }
+
+    #[test]
+    fn classify_reads_the_error_and_the_output() {
+        let error = anyhow::anyhow!("npm install zz-pkg failed");
+        assert_eq!(FailureKind::classify(&error, ""), FailureKind::Other);
+        assert_eq!(
+            FailureKind::classify(&error, "npm ERR! code ECONNRESET"),
+            FailureKind::Network
+        );
+    }
+
+    #[test]
+    fn summary_lists_installed_names_and_failures() {
+        let taps = InstallResult {
+            skipped: vec!["homebrew/cask-fonts".to_string()],
+            ..Default::default()
+        };
+        let formulae = InstallResult {
+            success: vec!["git".to_string(), "wget".to_string()],
+            skipped: vec!["jq".to_string()],
+            ..Default::default()
+        };
+        let casks = InstallResult {
+            success: vec!["slack".to_string()],
+            failed: vec![(
+                "zz-app".to_string(),
+                "brew install --cask zz-app failed".to_string(),
+                FailureKind::Other,
+            )],
+            ..Default::default()
+        };
+
+        let brew = InstallResult::aggregate(&[taps, formulae, casks]);
+        assert_eq!(
+            brew.summary(),
+            concat!(
+                "  ✓ 3 installed: git, wget, slack\n",
+                "  ⊘ 2 skipped (already installed)\n",
+                "  ✗ 1 failed:\n",
+                "    - zz-app: brew install --cask zz-app failed\n",
+            )
+        );
+        assert!(InstallResult::aggregate(&[]).is_empty());
+        assert_eq!(InstallResult::default().summary(), "");
+    }
+
+    #[test]
+    fn installs_run_exactly_the_described_command() {
+        let env = crate::utils::testing::FakeEnv::new("describe-install");
+        for bin in [
+            "brew", "cargo", "mas", "npm", "pnpm", "yarn", "pip3", "asdf",
+        ] {
+            // Lists are empty (`npm ls -g <pkg>` reports by status), installs succeed
+            env.bin(bin, r#"[ "$1" = ls ] && exit 1; exit 0"#);
+        }
+
+        let cases: Vec<(Box<dyn Manager>, &str)> = vec![
+            (Box::new(brew::BrewManager::new(1)), "httpie:http"),
+            (Box::new(cargo_manager::CargoManager::new(1)), "ripgrep:rg"),
+            (Box::new(mas::MasManager::new(1)), "497799835"),
+            (
+                Box::new(npm::NpmManager::new(1)),
+                "prettier@3.2.5:zz-prettier",
+            ),
+            (Box::new(pnpm::PnpmManager::new(1)), "zz-pnpm-tool"),
+            (Box::new(yarn::YarnManager::new(1)), "zz-yarn-tool"),
+            (Box::new(pip::PipManager::new(1)), "zz-pip-tool"),
+            (Box::new(asdf::AsdfManager::new(1)), "nodejs@20.11.0"),
+        ];
+
+        for (manager, package) in cases {
+            env.clear_calls();
+            let described = manager.describe_install(package).join(" ");
+            let _ = manager.install_package(package);
+
+            assert!(
+                env.calls().contains(&described),
+                "{} ran {:?}, described {:?}",
+                package,
+                env.calls(),
+                described
+            );
+        }
+    }
 }
@@ -0,0 +1,286 @@
+use crate::utils;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::process::Command;
+
+/// CLI used to manage Node.js global packages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeCli {
+    Npm,
+    Pnpm,
+    /// Yarn classic (1.x) - yarn 2+ dropped `yarn global`
+    Yarn,
+}
+
+impl NodeCli {
+    pub fn program(&self) -> &'static str {
+        match self {
+            NodeCli::Npm => "npm",
+            NodeCli::Pnpm => "pnpm",
+            NodeCli::Yarn => "yarn",
+        }
+    }
+
+    /// Arguments installing `package` globally
+    pub fn global_add_args<'a>(&self, package: &'a str) -> Vec<&'a str> {
+        match self {
+            NodeCli::Npm => vec!["install", "-g", package],
+            NodeCli::Pnpm => vec!["add", "-g", package],
+            NodeCli::Yarn => vec!["global", "add", package],
+        }
+    }
+
+    /// Arguments uninstalling a global `package`
+    pub fn global_remove_args<'a>(&self, package: &'a str) -> Vec<&'a str> {
+        match self {
+            NodeCli::Npm => vec!["uninstall", "-g", package],
+            NodeCli::Pnpm => vec!["remove", "-g", package],
+            NodeCli::Yarn => vec!["global", "remove", package],
+        }
+    }
+
+    /// List globally installed package names
+    pub fn list_globals(&self) -> Result<HashSet<String>> {
+        let args: &[&str] = match self {
+            NodeCli::Npm | NodeCli::Pnpm => &["list", "-g", "--depth=0", "--parseable"],
+            NodeCli::Yarn => &["global", "list"],
+        };
+
+        let output = utils::without_update_checks(&mut Command::new(self.program()))
+            .args(args)
+            .output()
+            .context(format!("Failed to list {} global packages", self.program()))?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        let packages = match self {
+            // One install path per line - the package is what follows node_modules/
+            // ("@scope/name" for scoped packages); the prefix line has none
+            NodeCli::Npm | NodeCli::Pnpm => stdout
+                .lines()
+                .filter_map(|line| line.rsplit_once("node_modules/"))
+                .map(|(_, name)| name.to_string())
+                .collect(),
+            // Format: info "typescript@5.4.5" has binaries:
+            NodeCli::Yarn => stdout
+                .lines()
+                .filter_map(|line| line.strip_prefix("info \""))
+                .filter_map(|rest| rest.split('"').next())
+                .map(|spec| strip_version(spec).to_string())
+                .collect(),
+        };
+
+        Ok(packages)
+    }
+
+    /// Command `install_global` runs for `package_spec` (program first)
+    pub fn describe_install(&self, package_spec: &str) -> Vec<String> {
+        let (pkg_name, _binary_name) = parse_package_name(package_spec);
+        std::iter::once(self.program())
+            .chain(self.global_add_args(pkg_name))
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    /// Install a global package
+    /// Accepts "package:binary" format but only uses package name for installation
+    /// With `output`, the CLI's output is captured there instead of printed
+    pub fn install_global(&self, package_spec: &str, output: Option<&mut String>) -> Result<()> {
+        let (pkg_name, _binary_name) = parse_package_name(package_spec);
+        let program = self.program();
+
+        log::info!("→ Installing {} ({} global)...", pkg_name, program);
+
+        let success = utils::run_status(
+            utils::without_update_checks(&mut utils::command_for(
+                &self.describe_install(package_spec),
+            )),
+            output,
+        )
+        .context(format!(
+            "Failed to install {} package: {}",
+            program, pkg_name
+        ))?;
+
+        if !success {
+            anyhow::bail!(
+                "{} {} failed",
+                program,
+                self.global_add_args(pkg_name).join(" ")
+            );
+        }
+
+        log::info!("✓ {} installed", pkg_name);
+        Ok(())
+    }
+
+    /// Command reinstalling `package_spec` at its latest version
+    /// `None` for a pinned version ("prettier@3.2.5"), which stays as declared
+    pub fn describe_upgrade(&self, package_spec: &str) -> Option<Vec<String>> {
+        let (pkg_name, _binary_name) = parse_package_name(package_spec);
+        if strip_version(pkg_name) != pkg_name {
+            return None;
+        }
+
+        let latest = format!("{}@latest", pkg_name);
+        Some(
+            std::iter::once(self.program())
+                .chain(self.global_add_args(&latest))
+                .map(|s| s.to_string())
+                .collect(),
+        )
+    }
+
+    /// Upgrade a global package to its latest version
+    /// With `output`, the CLI's output is captured there instead of printed
+    pub fn upgrade_global(&self, package_spec: &str, output: Option<&mut String>) -> Result<()> {
+        let Some(argv) = self.describe_upgrade(package_spec) else {
+            anyhow::bail!("{} is pinned to a version - not upgrading", package_spec);
+        };
+
+        let success = utils::run_status(
+            utils::without_update_checks(&mut utils::command_for(&argv)),
+            output,
+        )
+        .context(format!(
+            "Failed to upgrade {} package: {}",
+            self.program(),
+            package_spec
+        ))?;
+
+        if !success {
+            anyhow::bail!("{} failed", argv.join(" "));
+        }
+        Ok(())
+    }
+
+    /// Uninstall a global package ("package:binary" and versions are stripped)
+    pub fn uninstall_global(&self, package_spec: &str) -> Result<()> {
+        let pkg_name = strip_version(parse_package_name(package_spec).0);
+        let program = self.program();
+
+        log::info!("→ Uninstalling {} ({} global)...", pkg_name, program);
+
+        let success = utils::run_status(
+            utils::without_update_checks(&mut Command::new(program))
+                .args(self.global_remove_args(pkg_name)),
+            None,
+        )
+        .context(format!(
+            "Failed to uninstall {} package: {}",
+            program, pkg_name
+        ))?;
+
+        if !success {
+            anyhow::bail!(
+                "{} {} failed",
+                program,
+                self.global_remove_args(pkg_name).join(" ")
+            );
+        }
+
+        log::info!("✓ {} uninstalled", pkg_name);
+        Ok(())
+    }
+}
+
+/// Parse package name with optional binary mapping
+/// Format: "package:binary" or just "package", package may carry a version
+/// Examples:
+///   - "typescript:tsc" -> install "typescript", check binary "tsc"
+///   - "prettier" -> install "prettier", check binary "prettier"
+///   - "prettier@3.2.5" -> install "prettier@3.2.5", check binary "prettier"
+pub fn parse_package_name(input: &str) -> (&str, &str) {
+    if let Some((pkg, bin)) = input.split_once(':') {
+        (pkg.trim(), bin.trim())
+    } else {
+        (input.trim(), strip_version(input.trim()))
+    }
+}
+
+/// Name a config entry is listed under by `list_globals`:
+/// "typescript@5.4.5:tsc" -> "typescript", "@scope/name@1.2.3" -> "@scope/name"
+pub fn package_name(input: &str) -> &str {
+    strip_version(parse_package_name(input).0)
+}
+
+/// "name@1.2.3" -> "name", "@scope/name@1.2.3" -> "@scope/name"
+fn strip_version(spec: &str) -> &str {
+    match spec.rfind('@') {
+        Some(idx) if idx > 0 => &spec[..idx],
+        _ => spec,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::FakeEnv;
+
+    #[test]
+    fn builds_each_clis_global_add_command() {
+        assert_eq!(
+            NodeCli::Npm.describe_install("typescript:tsc"),
+            ["npm", "install", "-g", "typescript"]
+        );
+        assert_eq!(
+            NodeCli::Pnpm.describe_install("typescript:tsc"),
+            ["pnpm", "add", "-g", "typescript"]
+        );
+        assert_eq!(
+            NodeCli::Yarn.describe_install("prettier@3.2.5"),
+            ["yarn", "global", "add", "prettier@3.2.5"]
+        );
+    }
+
+    #[test]
+    fn installs_and_lists_with_the_right_cli() {
+        let env = FakeEnv::new("node-globals");
+        env.bin("pnpm", "exit 0").bin(
+            "yarn",
+            r#"[ "$2" = list ] && printf 'yarn global v1.22.19\ninfo "typescript@5.4.5" has binaries:\n   - tsc\ninfo "@vue/cli@5.0.8" has binaries:\n'
+exit 0"#,
+        );
+
+        NodeCli::Pnpm
+            .install_global("typescript:tsc", None)
+            .unwrap();
+        assert_eq!(
+            NodeCli::Yarn.list_globals().unwrap(),
+            HashSet::from(["typescript".to_string(), "@vue/cli".to_string()])
+        );
+        assert_eq!(env.calls(), ["pnpm add -g typescript", "yarn global list"]);
+    }
+
+    #[test]
+    fn update_notices_are_suppressed_unless_turned_off() {
+        let env = FakeEnv::new("node-update-checks");
+        env.bin(
+            "npm",
+            r#"echo "notifier=$NPM_CONFIG_UPDATE_NOTIFIER fund=$NPM_CONFIG_FUND" >> "$HOME/npm-env""#,
+        );
+        let seen = || std::fs::read_to_string(dirs::home_dir().unwrap().join("npm-env")).unwrap();
+
+        NodeCli::Npm.install_global("typescript", None).unwrap();
+        assert_eq!(seen(), "notifier=false fund=false\n");
+
+        // settings.suppress_update_checks = false
+        utils::set_suppress_update_checks(false);
+        let result = NodeCli::Npm.install_global("typescript", None);
+        utils::set_suppress_update_checks(true);
+        result.unwrap();
+        assert_eq!(seen(), "notifier=false fund=false\nnotifier= fund=\n");
+        assert_eq!(env.calls().len(), 2);
+    }
+
+    #[test]
+    fn parses_binaries_and_versions() {
+        assert_eq!(parse_package_name("typescript:tsc"), ("typescript", "tsc"));
+        assert_eq!(
+            parse_package_name("prettier@3.2.5"),
+            ("prettier@3.2.5", "prettier")
+        );
+        assert_eq!(package_name("@scope/name@1.2.3"), "@scope/name");
+        assert_eq!(package_name("typescript@5.4.5:tsc"), "typescript");
+    }
+}
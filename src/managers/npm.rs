@@ -1,9 +1,10 @@
-use crate::managers::{InstallResult, Manager};
+use crate::managers::node_globals::{package_name, parse_package_name, NodeCli};
+use crate::managers::{normalize_package_name, FailureKind, InstallResult, Manager};
 use crate::utils;
-use anyhow::{Context, Result};
+use anyhow::Result;
 use rayon::prelude::*;
 use std::collections::HashSet;
-use std::process::Command;
+use std::process::{Command, Stdio};
 
 pub struct NpmManager {
     max_parallel: usize,
@@ -14,55 +15,43 @@ impl NpmManager {
         Self { max_parallel }
     }
 
-    /// Parse package name with optional binary mapping
-    /// Format: "package:binary" or just "package"
-    /// Examples:
-    ///   - "typescript:tsc" -> install "typescript", check binary "tsc"
-    ///   - "prettier" -> install "prettier", check binary "prettier"
-    fn parse_package_name(input: &str) -> (&str, &str) {
-        if let Some((pkg, bin)) = input.split_once(':') {
-            (pkg.trim(), bin.trim())
-        } else {
-            (input.trim(), input.trim())
-        }
+    pub fn list_global_packages(&self) -> Result<HashSet<String>> {
+        NodeCli::Npm.list_globals()
     }
 
-    pub fn list_global_packages(&self) -> Result<HashSet<String>> {
-        let output = Command::new("npm")
-            .args(["list", "-g", "--depth=0", "--parseable"])
-            .output()
-            .context("Failed to list npm global packages")?;
-
-        let packages = String::from_utf8_lossy(&output.stdout)
-            .lines()
-            .filter_map(|line| {
-                // Extract package name from path
-                line.split('/').last().map(|s| s.to_string())
-            })
-            .collect();
+    /// Check a single global package without listing all of them
+    /// `npm ls -g <pkg>` exits non-zero when the package isn't installed
+    pub fn has_global_package(&self, pkg_name: &str) -> bool {
+        Command::new("npm")
+            .args(["ls", "-g", pkg_name, "--depth=0"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
 
-        Ok(packages)
+    /// Which of `candidates` are installed, given the `list_global_packages`
+    /// result: their binary is on PATH or their versionless name is listed
+    pub fn installed_among(candidates: &[String], globals: &HashSet<String>) -> HashSet<String> {
+        candidates
+            .iter()
+            .filter(|pkg| {
+                utils::command_exists(parse_package_name(pkg).1)
+                    || globals.contains(&normalize_package_name("npm", package_name(pkg)))
+            })
+            .cloned()
+            .collect()
     }
 
     /// Install a global npm package
     /// Accepts "package:binary" format but only uses package name for installation
-    pub fn install_global_package(&self, package_spec: &str) -> Result<()> {
-        // Parse package:binary format - install using package name only
-        let (pkg_name, _binary_name) = Self::parse_package_name(package_spec);
-
-        log::info!("→ Installing {} (npm -g)...", pkg_name);
-
-        let status = Command::new("npm")
-            .args(["install", "-g", pkg_name])
-            .status()
-            .context(format!("Failed to install npm package: {}", pkg_name))?;
-
-        if !status.success() {
-            anyhow::bail!("npm install -g {} failed", pkg_name);
-        }
-
-        log::info!("✓ {} installed", pkg_name);
-        Ok(())
+    /// With `output`, npm's output is captured there instead of printed
+    pub fn install_global_package(
+        &self,
+        package_spec: &str,
+        output: Option<&mut String>,
+    ) -> Result<()> {
+        NodeCli::Npm.install_global(package_spec, output)
     }
 }
 
@@ -85,18 +74,53 @@ impl Manager for NpmManager {
 
     fn is_package_installed(&self, package: &str) -> Result<bool> {
         // Parse package:binary format and check if binary exists
-        let (_pkg_name, binary_name) = Self::parse_package_name(package);
-        Ok(utils::command_exists(binary_name))
+        let (pkg_name, binary_name) = parse_package_name(package);
+        if utils::command_exists(binary_name) {
+            return Ok(true);
+        }
+
+        // Packages without a matching binary: ask npm about just this one
+        // (the full `list_installed` is kept for whole-section diffs)
+        Ok(self.has_global_package(pkg_name))
+    }
+
+    fn installed_set(&self, candidates: &[String]) -> Result<HashSet<String>> {
+        // Binaries first; one `npm list -g` covers the rest
+        let needs_list = candidates
+            .iter()
+            .any(|pkg| !utils::command_exists(parse_package_name(pkg).1));
+        let globals = if needs_list {
+            self.list_global_packages()?
+        } else {
+            HashSet::new()
+        };
+        Ok(Self::installed_among(candidates, &globals))
+    }
+
+    fn describe_install(&self, package: &str) -> Vec<String> {
+        NodeCli::Npm.describe_install(package)
     }
 
     fn install_package(&self, package: &str) -> Result<()> {
         if self.is_package_installed(package)? {
-            let (pkg_name, _) = Self::parse_package_name(package);
+            let (pkg_name, _) = parse_package_name(package);
             log::info!("✓ {} already installed", pkg_name);
             return Ok(());
         }
 
-        self.install_global_package(package)
+        self.install_global_package(package, None)
+    }
+
+    fn uninstall_package(&self, package: &str) -> Result<()> {
+        NodeCli::Npm.uninstall_global(package)
+    }
+
+    fn describe_upgrade(&self, package: &str) -> Option<Vec<String>> {
+        NodeCli::Npm.describe_upgrade(package)
+    }
+
+    fn upgrade_package(&self, package: &str, output: Option<&mut String>) -> Result<()> {
+        NodeCli::Npm.upgrade_global(package, output)
     }
 
     fn install_packages(&self, packages: &[String]) -> Result<InstallResult> {
@@ -108,21 +132,23 @@ impl Manager for NpmManager {
         let to_install: Vec<_> = packages
             .iter()
             .filter(|pkg| {
-                let (_pkg_name, binary_name) = Self::parse_package_name(pkg);
+                let (_pkg_name, binary_name) = parse_package_name(pkg);
                 !utils::command_exists(binary_name)
             })
             .cloned()
             .collect();
 
-        let mut result = InstallResult::default();
-        result.skipped = packages
-            .iter()
-            .filter(|pkg| {
-                let (_pkg_name, binary_name) = Self::parse_package_name(pkg);
-                utils::command_exists(binary_name)
-            })
-            .cloned()
-            .collect();
+        let mut result = InstallResult {
+            skipped: packages
+                .iter()
+                .filter(|pkg| {
+                    let (_pkg_name, binary_name) = parse_package_name(pkg);
+                    utils::command_exists(binary_name)
+                })
+                .cloned()
+                .collect(),
+            ..Default::default()
+        };
 
         if !result.skipped.is_empty() {
             log::info!("✓ {} npm packages already installed", result.skipped.len());
@@ -134,23 +160,83 @@ impl Manager for NpmManager {
 
         log::info!("Installing {} npm packages...", to_install.len());
 
-        let results: Vec<_> = rayon::ThreadPoolBuilder::new()
-            .num_threads(self.max_parallel)
-            .build()?
-            .install(|| {
-                to_install
-                    .par_iter()
-                    .map(|pkg| (pkg.clone(), self.install_global_package(pkg)))
-                    .collect()
-            });
-
-        for (pkg, res) in results {
+        let results: Vec<_> = utils::install_pool(self.max_parallel)?.install(|| {
+            to_install
+                .par_iter()
+                .map(|pkg| {
+                    let mut output = String::new();
+                    let res = self.install_global_package(pkg, Some(&mut output));
+                    (pkg.clone(), res, output)
+                })
+                .collect()
+        });
+
+        for (pkg, res, output) in results {
+            utils::flush_output(&pkg, &output, res.is_err());
             match res {
                 Ok(_) => result.success.push(pkg),
-                Err(e) => result.failed.push((pkg, e.to_string())),
+                Err(e) => {
+                    let kind = FailureKind::classify(&e, &output);
+                    result.failed.push((pkg, e.to_string(), kind))
+                }
             }
         }
 
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::FakeEnv;
+
+    #[test]
+    fn installed_set_matches_scoped_and_versioned_entries() {
+        let env = FakeEnv::new("npm-installed-set");
+        env.bin(
+            "npm",
+            r#"[ "$1" = list ] && printf '/usr/lib\n/usr/lib/node_modules/@scope/tool\n/usr/lib/node_modules/prettier\n'
+exit 0"#,
+        );
+
+        let candidates: Vec<String> = ["@scope/tool@1.2.0", "prettier@3.2.5:zz-none", "zz-absent"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let installed = NpmManager::new(1).installed_set(&candidates).unwrap();
+
+        assert_eq!(
+            installed,
+            HashSet::from([
+                "@scope/tool@1.2.0".to_string(),
+                "prettier@3.2.5:zz-none".to_string()
+            ])
+        );
+        assert_eq!(env.calls(), ["npm list -g --depth=0 --parseable"]);
+    }
+
+    #[test]
+    fn single_package_check_avoids_the_full_list() {
+        let env = FakeEnv::new("npm-single-check");
+        env.bin(
+            "npm",
+            r#"[ "$1" = ls ] && [ "$3" = zz-present ] && exit 0
+exit 1"#,
+        );
+        env.bin("present-bin", "exit 0");
+
+        let npm = NpmManager::new(1);
+        assert!(npm.is_package_installed("zz-present").unwrap());
+        assert!(!npm.is_package_installed("zz-absent").unwrap());
+        assert!(npm.is_package_installed("some-pkg:present-bin").unwrap());
+
+        assert_eq!(
+            env.calls(),
+            [
+                "npm ls -g zz-present --depth=0",
+                "npm ls -g zz-absent --depth=0",
+            ]
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use super::{FailureKind, InstallResult, Manager};
+use crate::utils;
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::process::Command;
+
+/// Manager for `pip3 install --user` packages
+/// Libraries often have no binary, so installs are checked against
+/// `pip3 list` by package name rather than with `which`
+pub struct PipManager {
+    max_parallel: usize,
+}
+
+impl PipManager {
+    pub fn new(max_parallel: usize) -> Self {
+        Self { max_parallel }
+    }
+
+    /// Package name of a requirement spec, normalized like pip does (PEP 503)
+    /// Examples:
+    ///   - "black==24.1.0" -> "black"
+    ///   - "Django>=4" -> "django"
+    ///   - "ruamel.yaml" -> "ruamel-yaml"
+    pub fn package_name(spec: &str) -> String {
+        let name = spec
+            .split(|c: char| "=<>!~;[ @".contains(c))
+            .next()
+            .unwrap_or(spec)
+            .trim();
+
+        name.to_lowercase().replace(['_', '.'], "-")
+    }
+
+    /// Parse `pip3 list --format=freeze` output into normalized package names
+    /// Lines look like "requests==2.31.0" or "mypkg @ file:///..."; editable
+    /// installs ("-e ...") and comments are skipped
+    pub fn parse_freeze(output: &str) -> HashSet<String> {
+        output
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('-') && !line.starts_with('#'))
+            .map(Self::package_name)
+            .collect()
+    }
+
+    /// Names of every package pip can see (user site included)
+    pub fn list_packages(&self) -> Result<HashSet<String>> {
+        let output = Command::new("pip3")
+            .args(["list", "--format=freeze"])
+            .output()
+            .context("Failed to list pip packages")?;
+
+        if !output.status.success() {
+            anyhow::bail!("pip3 list failed");
+        }
+
+        Ok(Self::parse_freeze(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    /// Install a single package into the user site
+    /// With `output`, pip's output is captured there instead of printed
+    fn install_one(&self, package: &str, output: Option<&mut String>) -> Result<()> {
+        log::info!("→ Installing {} (pip)...", package);
+
+        let success = utils::run_status(
+            &mut utils::command_for(&self.describe_install(package)),
+            output,
+        )
+        .context(format!("Failed to install pip package: {}", package))?;
+
+        if !success {
+            anyhow::bail!("pip3 install --user {} failed", package);
+        }
+
+        log::info!("✓ {} installed", package);
+        Ok(())
+    }
+}
+
+impl Manager for PipManager {
+    fn name(&self) -> &str {
+        "pip"
+    }
+
+    fn is_installed(&self) -> bool {
+        utils::command_exists("pip3")
+    }
+
+    fn install_self(&self) -> Result<()> {
+        // Runtime is installed via brew in apply phase
+        Ok(())
+    }
+
+    fn list_installed(&self) -> Result<HashSet<String>> {
+        self.list_packages()
+    }
+
+    fn is_package_installed(&self, package: &str) -> Result<bool> {
+        Ok(self.list_packages()?.contains(&Self::package_name(package)))
+    }
+
+    fn installed_set(&self, candidates: &[String]) -> Result<HashSet<String>> {
+        // One `pip3 list` covers every candidate
+        let listed = self.list_packages()?;
+        Ok(candidates
+            .iter()
+            .filter(|spec| listed.contains(&Self::package_name(spec)))
+            .cloned()
+            .collect())
+    }
+
+    fn describe_install(&self, package: &str) -> Vec<String> {
+        vec![
+            "pip3".to_string(),
+            "install".to_string(),
+            "--user".to_string(),
+            package.to_string(),
+        ]
+    }
+
+    fn install_package(&self, package: &str) -> Result<()> {
+        if self.is_package_installed(package)? {
+            log::info!("✓ {} already installed", package);
+            return Ok(());
+        }
+
+        self.install_one(package, None)
+    }
+
+    fn uninstall_package(&self, package: &str) -> Result<()> {
+        let pkg_name = Self::package_name(package);
+
+        log::info!("→ Uninstalling {} (pip)...", pkg_name);
+
+        let success = utils::run_status(
+            Command::new("pip3").args(["uninstall", "-y", &pkg_name]),
+            None,
+        )
+        .context(format!("Failed to uninstall pip package: {}", pkg_name))?;
+
+        if !success {
+            anyhow::bail!("pip3 uninstall {} failed", pkg_name);
+        }
+
+        log::info!("✓ {} uninstalled", pkg_name);
+        Ok(())
+    }
+
+    fn install_packages(&self, packages: &[String]) -> Result<InstallResult> {
+        if packages.is_empty() {
+            return Ok(InstallResult::default());
+        }
+
+        let installed = self.installed_set(packages).unwrap_or_default();
+        let (already_installed, to_install): (Vec<_>, Vec<_>) = packages
+            .iter()
+            .cloned()
+            .partition(|pkg| installed.contains(pkg));
+
+        let mut result = InstallResult {
+            skipped: already_installed,
+            ..Default::default()
+        };
+
+        if !result.skipped.is_empty() {
+            log::info!("✓ {} pip packages already installed", result.skipped.len());
+        }
+
+        if to_install.is_empty() {
+            return Ok(result);
+        }
+
+        log::info!("Installing {} pip packages...", to_install.len());
+
+        let results: Vec<_> = utils::install_pool(self.max_parallel)?.install(|| {
+            to_install
+                .par_iter()
+                .map(|pkg| {
+                    let mut output = String::new();
+                    let res = self.install_one(pkg, Some(&mut output));
+                    (pkg.clone(), res, output)
+                })
+                .collect()
+        });
+
+        for (pkg, res, output) in results {
+            utils::flush_output(&pkg, &output, res.is_err());
+            match res {
+                Ok(_) => result.success.push(pkg),
+                Err(e) => {
+                    let kind = FailureKind::classify(&e, &output);
+                    result.failed.push((pkg, e.to_string(), kind))
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::FakeEnv;
+
+    #[test]
+    fn parses_freeze_output_into_normalized_names() {
+        let output = "# Editable install with no version control (mypkg==0.1)\n\
+                      -e /Users/me/src/mypkg\n\
+                      Django==4.2.7\n\
+                      ruamel.yaml==0.18.5\n\
+                      typing_extensions==4.9.0\n\
+                      localtool @ file:///Users/me/src/localtool\n\n";
+
+        assert_eq!(
+            PipManager::parse_freeze(output),
+            HashSet::from(
+                ["django", "ruamel-yaml", "typing-extensions", "localtool"].map(String::from)
+            )
+        );
+        assert_eq!(PipManager::package_name("Black[d]>=24.1"), "black");
+    }
+
+    #[test]
+    fn installs_missing_packages_into_the_user_site() {
+        let env = FakeEnv::new("pip-install");
+        env.bin(
+            "pip3",
+            r#"[ "$1" = list ] && echo "requests==2.31.0"
+exit 0"#,
+        );
+
+        let packages = ["Requests>=2".to_string(), "black==24.1.0".to_string()];
+        let result = PipManager::new(1).install_packages(&packages).unwrap();
+
+        assert_eq!(result.skipped, ["Requests>=2"]);
+        assert_eq!(result.success, ["black==24.1.0"]);
+        assert!(env
+            .calls()
+            .contains(&"pip3 install --user black==24.1.0".to_string()));
+        assert!(!env
+            .calls()
+            .iter()
+            .any(|call| call.contains("install --user Requests")));
+    }
+}
@@ -60,6 +60,50 @@ pub static PACKAGE_MANAGERS: &[ManagerMetadata] = &[
         section_type: SectionType::Cargo,
     },
     // CODEGEN_END: cargo
+    // CODEGEN_START: asdf
+    ManagerMetadata {
+        name: "asdf",
+        display_name: "asdf runtimes",
+        icon: "🧰",
+        runtime_command: "asdf",
+        runtime_name: "asdf",
+        brew_formula: "asdf",
+        section_type: SectionType::Asdf,
+    },
+    // CODEGEN_END: asdf
+    // CODEGEN_START: pnpm
+    ManagerMetadata {
+        name: "pnpm",
+        display_name: "pnpm packages",
+        icon: "📦",
+        runtime_command: "pnpm",
+        runtime_name: "pnpm",
+        brew_formula: "pnpm",
+        section_type: SectionType::Pnpm,
+    },
+    // CODEGEN_END: pnpm
+    // CODEGEN_START: yarn
+    ManagerMetadata {
+        name: "yarn",
+        display_name: "yarn packages",
+        icon: "🧶",
+        runtime_command: "yarn",
+        runtime_name: "yarn",
+        brew_formula: "yarn",
+        section_type: SectionType::Yarn,
+    },
+    // CODEGEN_END: yarn
+    // CODEGEN_START: pip
+    ManagerMetadata {
+        name: "pip",
+        display_name: "pip packages",
+        icon: "🐍",
+        runtime_command: "pip3",
+        runtime_name: "python",
+        brew_formula: "python",
+        section_type: SectionType::Pip,
+    },
+    // CODEGEN_END: pip
     // CODEGEN_MARKER: insert_manager_metadata_here
 ];
 
@@ -70,7 +114,6 @@ impl ManagerMetadata {
     }
 
     /// Get manager metadata by section type
-    #[allow(dead_code)]
     pub fn get_by_section_type(section_type: &SectionType) -> Option<&'static ManagerMetadata> {
         PACKAGE_MANAGERS
             .iter()
@@ -78,7 +121,6 @@ impl ManagerMetadata {
     }
 
     /// Get all manager names
-    #[allow(dead_code)]
     pub fn all_names() -> Vec<&'static str> {
         PACKAGE_MANAGERS.iter().map(|m| m.name).collect()
     }
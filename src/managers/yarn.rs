@@ -0,0 +1,144 @@
+use crate::managers::node_globals::{parse_package_name, NodeCli};
+use crate::managers::{FailureKind, InstallResult, Manager};
+use crate::utils;
+use anyhow::Result;
+use rayon::prelude::*;
+use std::collections::HashSet;
+
+pub struct YarnManager {
+    max_parallel: usize,
+}
+
+impl YarnManager {
+    pub fn new(max_parallel: usize) -> Self {
+        Self { max_parallel }
+    }
+
+    pub fn list_global_packages(&self) -> Result<HashSet<String>> {
+        NodeCli::Yarn.list_globals()
+    }
+
+    /// Install a global yarn package
+    /// Accepts "package:binary" format but only uses package name for installation
+    /// With `output`, yarn's output is captured there instead of printed
+    pub fn install_global_package(
+        &self,
+        package_spec: &str,
+        output: Option<&mut String>,
+    ) -> Result<()> {
+        NodeCli::Yarn.install_global(package_spec, output)
+    }
+}
+
+impl Manager for YarnManager {
+    fn name(&self) -> &str {
+        "yarn"
+    }
+
+    fn is_installed(&self) -> bool {
+        utils::command_exists("yarn")
+    }
+
+    fn install_self(&self) -> Result<()> {
+        // Runtime is installed via brew in apply phase
+        Ok(())
+    }
+
+    fn list_installed(&self) -> Result<HashSet<String>> {
+        self.list_global_packages()
+    }
+
+    fn is_package_installed(&self, package: &str) -> Result<bool> {
+        // Parse package:binary format and check if binary exists
+        let (_pkg_name, binary_name) = parse_package_name(package);
+        Ok(utils::command_exists(binary_name))
+    }
+
+    fn describe_install(&self, package: &str) -> Vec<String> {
+        NodeCli::Yarn.describe_install(package)
+    }
+
+    fn install_package(&self, package: &str) -> Result<()> {
+        if self.is_package_installed(package)? {
+            let (pkg_name, _) = parse_package_name(package);
+            log::info!("✓ {} already installed", pkg_name);
+            return Ok(());
+        }
+
+        self.install_global_package(package, None)
+    }
+
+    fn uninstall_package(&self, package: &str) -> Result<()> {
+        NodeCli::Yarn.uninstall_global(package)
+    }
+
+    fn describe_upgrade(&self, package: &str) -> Option<Vec<String>> {
+        NodeCli::Yarn.describe_upgrade(package)
+    }
+
+    fn upgrade_package(&self, package: &str, output: Option<&mut String>) -> Result<()> {
+        NodeCli::Yarn.upgrade_global(package, output)
+    }
+
+    fn install_packages(&self, packages: &[String]) -> Result<InstallResult> {
+        if packages.is_empty() {
+            return Ok(InstallResult::default());
+        }
+
+        // Check which packages are already installed by checking their binaries
+        let to_install: Vec<_> = packages
+            .iter()
+            .filter(|pkg| {
+                let (_pkg_name, binary_name) = parse_package_name(pkg);
+                !utils::command_exists(binary_name)
+            })
+            .cloned()
+            .collect();
+
+        let mut result = InstallResult {
+            skipped: packages
+                .iter()
+                .filter(|pkg| {
+                    let (_pkg_name, binary_name) = parse_package_name(pkg);
+                    utils::command_exists(binary_name)
+                })
+                .cloned()
+                .collect(),
+            ..Default::default()
+        };
+
+        if !result.skipped.is_empty() {
+            log::info!("✓ {} yarn packages already installed", result.skipped.len());
+        }
+
+        if to_install.is_empty() {
+            return Ok(result);
+        }
+
+        log::info!("Installing {} yarn packages...", to_install.len());
+
+        let results: Vec<_> = utils::install_pool(self.max_parallel)?.install(|| {
+            to_install
+                .par_iter()
+                .map(|pkg| {
+                    let mut output = String::new();
+                    let res = self.install_global_package(pkg, Some(&mut output));
+                    (pkg.clone(), res, output)
+                })
+                .collect()
+        });
+
+        for (pkg, res, output) in results {
+            utils::flush_output(&pkg, &output, res.is_err());
+            match res {
+                Ok(_) => result.success.push(pkg),
+                Err(e) => {
+                    let kind = FailureKind::classify(&e, &output);
+                    result.failed.push((pkg, e.to_string(), kind))
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
@@ -1,5 +1,12 @@
 use anyhow::Result;
-use std::process::{Command, Output};
+use std::fs;
+use std::io::Read;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// Execute a command and return output
 #[allow(dead_code)]
@@ -28,7 +35,162 @@ pub fn execute_shell(command: &str) -> Result<Output> {
     Ok(output)
 }
 
+/// Run a command and collect its output, killing it after `timeout`
+/// stdout/stderr are drained on separate threads so a chatty command
+/// can't block on a full pipe while we wait
+pub fn output_with_timeout(cmd: &mut Command, timeout: Duration) -> Result<Output> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!("timed out after {}s", timeout.as_secs());
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Read a child's pipe to the end on a background thread
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// `settings.suppress_update_checks` (on unless a config turns it off)
+static SUPPRESS_UPDATE_CHECKS: AtomicBool = AtomicBool::new(true);
+
+/// Turn the update/funding notices of spawned package managers on or off
+pub fn set_suppress_update_checks(suppress: bool) {
+    SUPPRESS_UPDATE_CHECKS.store(suppress, Ordering::Relaxed);
+}
+
+/// Silence npm's update notifier and funding messages on `cmd` (pnpm reads
+/// the same settings), unless `settings.suppress_update_checks = false`
+pub fn without_update_checks(cmd: &mut Command) -> &mut Command {
+    if SUPPRESS_UPDATE_CHECKS.load(Ordering::Relaxed) {
+        cmd.env("NPM_CONFIG_UPDATE_NOTIFIER", "false")
+            .env("NPM_CONFIG_FUND", "false");
+    }
+    cmd
+}
+
+/// Build a `Command` from an argv whose first element is the program
+/// (the form `Manager::describe_install` returns)
+pub fn command_for(argv: &[String]) -> Command {
+    let (program, args) = argv
+        .split_first()
+        .expect("argv must contain at least the program");
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    cmd
+}
+
+/// Run a command to completion and report whether it succeeded
+/// With a buffer, stdout/stderr are captured into it instead of going to the
+/// terminal, so parallel installs don't interleave their output
+/// Waits for a slot under `settings.global_max_parallel` first
+pub fn run_status(cmd: &mut Command, buffer: Option<&mut String>) -> std::io::Result<bool> {
+    let _permit = super::acquire_install_slot();
+
+    match buffer {
+        None => Ok(cmd.status()?.success()),
+        Some(buffer) => {
+            let output = cmd.output()?;
+            buffer.push_str(&String::from_utf8_lossy(&output.stdout));
+            buffer.push_str(&String::from_utf8_lossy(&output.stderr));
+            Ok(output.status.success())
+        }
+    }
+}
+
+/// Print one package's captured output as a single block (to stderr)
+/// Output of failed installs is always shown, successful ones only with --verbose
+pub fn flush_output(package: &str, output: &str, failed: bool) {
+    if output.trim().is_empty() || !(failed || log::log_enabled!(log::Level::Debug)) {
+        return;
+    }
+
+    eprintln!("  ── {} ──", package);
+    for line in output.lines() {
+        eprintln!("    {}", line);
+    }
+}
+
 /// Check if a command exists in PATH
 pub fn command_exists(command: &str) -> bool {
     which::which(command).is_ok()
 }
+
+/// Result of resolving a command's binary on PATH
+#[derive(Debug)]
+pub enum BinaryStatus {
+    /// Resolves to an existing executable file
+    Found,
+    /// An entry exists on PATH but is a dangling symlink or not executable
+    Broken(PathBuf),
+    /// No entry on PATH
+    Missing,
+}
+
+/// Resolve a command on PATH and verify the binary actually runs
+/// Unlike `command_exists`, a dangling symlink (e.g. after a toolchain
+/// removal) is reported as `Broken` rather than silently skipped
+pub fn check_binary(command: &str) -> BinaryStatus {
+    let path_var = std::env::var_os("PATH").unwrap_or_default();
+
+    for dir in std::env::split_paths(&path_var) {
+        let candidate = dir.join(command);
+
+        // symlink_metadata succeeds for dangling links too
+        if fs::symlink_metadata(&candidate).is_err() {
+            continue;
+        }
+
+        return match fs::metadata(&candidate) {
+            Ok(meta) if meta.is_file() && meta.permissions().mode() & 0o111 != 0 => {
+                BinaryStatus::Found
+            }
+            _ => BinaryStatus::Broken(candidate),
+        };
+    }
+
+    BinaryStatus::Missing
+}
+
+/// Fail early with a readable error when a prompt would run without a terminal
+/// (piped stdin, CI); inquire's own error doesn't say what to do instead
+pub fn require_terminal(what: &str, instead: &str) -> Result<()> {
+    use std::io::IsTerminal;
+
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!(
+            "{} needs a terminal (stdin is not a TTY) - {}",
+            what,
+            instead
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use anyhow::{Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// File the log stream is copied to (`settings.log_file` / `--log-file`)
+static LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+
+/// Logger target: writes every record to stderr, and to the log file once one is set
+pub struct TeeWriter;
+
+impl Write for TeeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Ok(mut file) = LOG_FILE.lock() {
+            if let Some(file) = file.as_mut() {
+                // A log file that went away must not abort the run
+                let _ = file.write_all(buf);
+            }
+        }
+
+        io::stderr().write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+/// Start appending log records to `path`, creating it (and its directory) if needed
+pub fn set_log_file(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).context(format!(
+            "Failed to create log directory: {}",
+            parent.display()
+        ))?;
+    }
+
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .context(format!("Failed to open log file: {}", path.display()))?;
+
+    if let Ok(mut log_file) = LOG_FILE.lock() {
+        *log_file = Some(file);
+    }
+
+    Ok(())
+}
+
+/// Whether a log file was already set (`--log-file` wins over `settings.log_file`)
+pub fn log_file_is_set() -> bool {
+    LOG_FILE.lock().is_ok_and(|file| file.is_some())
+}
+
+/// Expand a leading `~` to the home directory
+pub fn expand_tilde(path: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match dirs::home_dir() {
+            Some(home) => home.join(rest.trim_start_matches('/')),
+            None => PathBuf::from(path),
+        },
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::FakeEnv;
+
+    #[test]
+    fn log_records_are_copied_to_the_log_file() {
+        let _env = FakeEnv::new("log-file");
+        let home = dirs::home_dir().unwrap();
+        let path = expand_tilde("~/logs/macup.log");
+        assert_eq!(path, home.join("logs/macup.log"));
+
+        set_log_file(&path).unwrap();
+        assert!(log_file_is_set());
+        TeeWriter
+            .write_all(b"[INFO] Loaded config from: macup.toml\n")
+            .unwrap();
+        *LOG_FILE.lock().unwrap() = None;
+
+        let logged = fs::read_to_string(&path).unwrap();
+        assert_eq!(logged, "[INFO] Loaded config from: macup.toml\n");
+    }
+
+    #[test]
+    fn only_a_leading_tilde_is_expanded() {
+        assert_eq!(
+            expand_tilde("/tmp/~macup.log"),
+            PathBuf::from("/tmp/~macup.log")
+        );
+        assert_eq!(
+            expand_tilde("~other/macup.log"),
+            PathBuf::from("~other/macup.log")
+        );
+    }
+}
@@ -1,3 +1,15 @@
 pub mod command;
+pub mod log_file;
+pub mod pool;
+pub mod retry;
+pub mod semaphore;
+pub mod suggest;
+#[cfg(test)]
+pub mod testing;
 
 pub use command::*;
+pub use log_file::*;
+pub use pool::*;
+pub use retry::*;
+pub use semaphore::*;
+pub use suggest::*;
@@ -0,0 +1,24 @@
+use anyhow::Result;
+use rayon::ThreadPool;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Install pools by thread count, built on first use and kept for the run
+static POOLS: Mutex<BTreeMap<usize, Arc<ThreadPool>>> = Mutex::new(BTreeMap::new());
+
+/// Thread pool running `num_threads` installs at once
+/// Managers with the same parallelism share it instead of building one per call
+pub fn install_pool(num_threads: usize) -> Result<Arc<ThreadPool>> {
+    let mut pools = POOLS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(pool) = pools.get(&num_threads) {
+        return Ok(Arc::clone(pool));
+    }
+
+    let pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()?,
+    );
+    pools.insert(num_threads, Arc::clone(&pool));
+    Ok(pool)
+}
@@ -0,0 +1,82 @@
+use anyhow::Result;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Delay before the first retry, doubled for each further attempt
+/// (tests don't wait)
+#[cfg(not(test))]
+const BASE_DELAY: Duration = Duration::from_secs(2);
+#[cfg(test)]
+const BASE_DELAY: Duration = Duration::from_millis(1);
+
+/// Run `op`, retrying up to `retries` more times on failure
+/// Attempts are spaced with exponential backoff plus up to 1s of jitter, so
+/// parallel retries don't all hit the network at the same moment
+pub fn retry_with_backoff<T>(
+    retries: u32,
+    label: &str,
+    mut op: impl FnMut() -> Result<T>,
+) -> Result<T> {
+    let mut attempt = 0;
+
+    loop {
+        match op() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < retries => {
+                attempt += 1;
+                let delay = backoff_delay(attempt);
+                log::warn!(
+                    "{} failed ({}), retrying in {:.1}s ({}/{})",
+                    label,
+                    e,
+                    delay.as_secs_f32(),
+                    attempt,
+                    retries
+                );
+                thread::sleep(delay);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn backoff_delay(attempt: u32) -> Duration {
+    // No rand dependency - sub-second clock noise is jitter enough
+    // (up to half the base delay: 1s)
+    let jitter = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_millis())
+        .unwrap_or(0) as f64
+        / 2000.0;
+
+    BASE_DELAY * 2u32.saturating_pow(attempt - 1) + BASE_DELAY.mul_f64(jitter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_until_success_or_the_limit() {
+        let mut attempts = 0;
+        let value = retry_with_backoff(3, "flaky", || {
+            attempts += 1;
+            if attempts < 3 {
+                anyhow::bail!("attempt {} failed", attempts);
+            }
+            Ok(attempts)
+        })
+        .unwrap();
+        assert_eq!(value, 3);
+
+        let mut attempts = 0;
+        let err = retry_with_backoff(2, "broken", || -> Result<()> {
+            attempts += 1;
+            anyhow::bail!("attempt {} failed", attempts)
+        })
+        .unwrap_err();
+        // One attempt plus two retries, returning the last error
+        assert_eq!(attempts, 3);
+        assert_eq!(err.to_string(), "attempt 3 failed");
+    }
+}
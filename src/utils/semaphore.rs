@@ -0,0 +1,71 @@
+use std::sync::{Condvar, Mutex};
+
+/// Installs running right now, and the cap across every manager
+/// (`settings.global_max_parallel`, 0 = unbounded)
+struct Slots {
+    in_flight: usize,
+    limit: usize,
+}
+
+static SLOTS: Mutex<Slots> = Mutex::new(Slots {
+    in_flight: 0,
+    limit: 0,
+});
+static FREED: Condvar = Condvar::new();
+
+/// Cap concurrent installs across all managers (0 = unbounded)
+pub fn set_install_limit(limit: usize) {
+    SLOTS.lock().unwrap_or_else(|e| e.into_inner()).limit = limit;
+    FREED.notify_all();
+}
+
+/// A running install; frees its slot when dropped
+pub struct InstallPermit;
+
+/// Wait for a free install slot
+/// Per-manager parallelism still applies - this only bounds the total
+pub fn acquire_install_slot() -> InstallPermit {
+    let mut slots = SLOTS.lock().unwrap_or_else(|e| e.into_inner());
+    while slots.limit > 0 && slots.in_flight >= slots.limit {
+        slots = FREED.wait(slots).unwrap_or_else(|e| e.into_inner());
+    }
+    slots.in_flight += 1;
+    InstallPermit
+}
+
+impl Drop for InstallPermit {
+    fn drop(&mut self) {
+        let mut slots = SLOTS.lock().unwrap_or_else(|e| e.into_inner());
+        slots.in_flight -= 1;
+        FREED.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn in_flight_installs_never_exceed_the_limit() {
+        set_install_limit(2);
+
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let _permit = acquire_install_slot();
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(20));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        set_install_limit(0);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,47 @@
+/// Closest candidate to a mistyped name ("bre" -> "brew", "crago" -> "cargo"),
+/// compared case-insensitively by edit distance (a swap counts as one edit)
+/// Returns None when nothing is close enough to be a plausible typo
+pub fn suggest<'a>(input: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let input = input.to_lowercase();
+    // Allow roughly one edit per three characters, at least one
+    let max_distance = (input.chars().count() / 3).max(1);
+
+    candidates
+        .iter()
+        .map(|candidate| {
+            let distance = strsim::osa_distance(&input, &candidate.to_lowercase());
+            (distance, *candidate)
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// " - did you mean 'brew'?" for an error message, or "" without a close match
+pub fn did_you_mean(input: &str, candidates: &[&str]) -> String {
+    suggest(input, candidates)
+        .map(|candidate| format!(" - did you mean '{}'?", candidate))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANAGERS: &[&str] = &["brew", "cask", "mas", "npm", "cargo", "pnpm", "yarn", "pip"];
+
+    #[test]
+    fn typos_map_to_the_closest_candidate() {
+        assert_eq!(suggest("bre", MANAGERS), Some("brew"));
+        assert_eq!(suggest("crago", MANAGERS), Some("cargo"));
+        assert_eq!(suggest("Yarm", MANAGERS), Some("yarn"));
+        assert_eq!(suggest("pipp", MANAGERS), Some("pip"));
+        assert_eq!(suggest("terraform", MANAGERS), None);
+    }
+
+    #[test]
+    fn did_you_mean_is_empty_without_a_close_match() {
+        assert_eq!(did_you_mean("bre", MANAGERS), " - did you mean 'brew'?");
+        assert_eq!(did_you_mean("xyz", MANAGERS), "");
+    }
+}
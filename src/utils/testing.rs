@@ -0,0 +1,139 @@
+//! Test helpers: a throwaway HOME and PATH of fake package manager binaries
+
+use std::ffi::OsString;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::sync::{Mutex, MutexGuard};
+
+/// Set in the child process `stdout_of` / `stderr_of` re-run a test in
+const CHILD_VAR: &str = "MACUP_TEST_CHILD";
+
+/// Re-run the test at `path` (e.g. "executor::apply::tests::name") in a child
+/// process and return what it printed to stdout. Inside that child this
+/// returns `None`, and the test does the printing instead.
+pub fn stdout_of(path: &str) -> Option<String> {
+    run_in_child(path).map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Like `stdout_of`, for what the test printed to stderr
+pub fn stderr_of(path: &str) -> Option<String> {
+    run_in_child(path).map(|output| String::from_utf8_lossy(&output.stderr).into_owned())
+}
+
+/// Like `stdout_of`, for a test expected to exit on its own (e.g. through
+/// `std::process::exit`): the child's output is returned whatever its status
+pub fn output_of(path: &str) -> Option<Output> {
+    if std::env::var_os(CHILD_VAR).is_some() {
+        return None;
+    }
+
+    Some(
+        Command::new(std::env::current_exe().unwrap())
+            .args([path, "--exact", "--nocapture", "--test-threads=1"])
+            .env(CHILD_VAR, "1")
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap(),
+    )
+}
+
+fn run_in_child(path: &str) -> Option<Output> {
+    let output = output_of(path)?;
+    assert!(
+        output.status.success(),
+        "{} failed in the child process:\n{}{}",
+        path,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Some(output)
+}
+
+/// PATH and HOME are process-wide: tests that change them run one at a time
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+/// HOME and PATH pointing into a temporary directory, restored on drop
+/// Every fake binary appends its argv to `calls.log` before running its body
+pub struct FakeEnv {
+    root: PathBuf,
+    old_path: Option<OsString>,
+    old_home: Option<OsString>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl FakeEnv {
+    pub fn new(name: &str) -> Self {
+        let lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let root = std::env::temp_dir().join(format!("macup-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("bin")).unwrap();
+        fs::create_dir_all(root.join("home")).unwrap();
+
+        let old_path = std::env::var_os("PATH");
+        let old_home = std::env::var_os("HOME");
+        std::env::set_var(
+            "PATH",
+            format!("{}:/usr/bin:/bin", root.join("bin").display()),
+        );
+        std::env::set_var("HOME", root.join("home"));
+
+        Self {
+            root,
+            old_path,
+            old_home,
+            _lock: lock,
+        }
+    }
+
+    /// Add a `sh` script named `name` to PATH
+    pub fn bin(&self, name: &str, body: &str) -> &Self {
+        let path = self.root.join("bin").join(name);
+        let script = format!(
+            "#!/bin/sh\necho \"{} $*\" >> \"{}\"\n{}\n",
+            name,
+            self.log_path().display(),
+            body
+        );
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        self
+    }
+
+    /// Add a symlink named `name` to PATH pointing at `target` (which may not exist)
+    pub fn link(&self, name: &str, target: &str) -> &Self {
+        std::os::unix::fs::symlink(target, self.root.join("bin").join(name)).unwrap();
+        self
+    }
+
+    /// Fake binary invocations so far, one "name args..." per line
+    pub fn calls(&self) -> Vec<String> {
+        fs::read_to_string(self.log_path())
+            .unwrap_or_default()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    pub fn clear_calls(&self) {
+        let _ = fs::remove_file(self.log_path());
+    }
+
+    fn log_path(&self) -> PathBuf {
+        self.root.join("calls.log")
+    }
+}
+
+impl Drop for FakeEnv {
+    fn drop(&mut self) {
+        for (key, value) in [("PATH", &self.old_path), ("HOME", &self.old_home)] {
+            match value {
+                Some(value) => std::env::set_var(key, value),
+                None => std::env::remove_var(key),
+            }
+        }
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}